@@ -78,6 +78,82 @@ binary_benchmark_group!(
     benchmarks = [bench_eval_nano, bench_eval_tantivy]
 );
 
+// dataset queries are sentences, so each of them is a top-10 query with many
+// words joined with OR. counting all matching documents makes nano engine
+// score every match and also collect the count, so it's the cost of counting
+// total, not of pruning alone
+fn create_eval_pruning_command(
+    engine: &str,
+    count_total: bool,
+) -> gungraun::Command {
+    let mut command = gungraun::Command::new(env!("CARGO_BIN_EXE_nano_search"));
+    command
+        .arg(format!("--engines={engine}"))
+        .arg(format!("--dataset={DATASET}"))
+        .arg("--parent-index-dir=/tmp")
+        .arg("eval");
+    if count_total {
+        command.arg("--count-total");
+    }
+    command.build()
+}
+
+#[binary_benchmark]
+#[bench::eval_top_10(args = ("nano"), setup = setup_eval)]
+fn bench_eval_nano_pruned(engine: &str) -> gungraun::Command {
+    create_eval_pruning_command(engine, false)
+}
+
+#[binary_benchmark]
+#[bench::eval_top_10(args = ("nano"), setup = setup_eval)]
+fn bench_eval_nano_exhaustive(engine: &str) -> gungraun::Command {
+    create_eval_pruning_command(engine, true)
+}
+
+binary_benchmark_group!(
+    name = bench_pruning_group,
+    compare_by_id = true,
+    benchmarks = [bench_eval_nano_pruned, bench_eval_nano_exhaustive]
+);
+
+// short queries of first few words of dataset ones, e.g. like typed in a
+// search box. both sides collect only top-10 hits with the same collector, so
+// the only difference is whether non-competitive documents are skipped
+fn create_eval_short_queries_command(
+    engine: &str,
+    pruning: bool,
+) -> gungraun::Command {
+    let mut command = gungraun::Command::new(env!("CARGO_BIN_EXE_nano_search"));
+    command
+        .arg(format!("--engines={engine}"))
+        .arg(format!("--dataset={DATASET}"))
+        .arg("--parent-index-dir=/tmp")
+        .arg("eval")
+        .arg("--max-query-words=3");
+    if !pruning {
+        command.arg("--no-pruning");
+    }
+    command.build()
+}
+
+#[binary_benchmark]
+#[bench::eval_short_top_10(args = ("nano"), setup = setup_eval)]
+fn bench_eval_nano_short_pruned(engine: &str) -> gungraun::Command {
+    create_eval_short_queries_command(engine, true)
+}
+
+#[binary_benchmark]
+#[bench::eval_short_top_10(args = ("nano"), setup = setup_eval)]
+fn bench_eval_nano_short_unpruned(engine: &str) -> gungraun::Command {
+    create_eval_short_queries_command(engine, false)
+}
+
+binary_benchmark_group!(
+    name = bench_short_queries_pruning_group,
+    compare_by_id = true,
+    benchmarks = [bench_eval_nano_short_pruned, bench_eval_nano_short_unpruned]
+);
+
 main!(
     config = BinaryBenchmarkConfig::default()
         // collect and show CPU metrics
@@ -91,5 +167,10 @@ main!(
                 // function, multi-thread channels and disk IO
                 .tolerance(1.0)
         ),
-    binary_benchmark_groups = [bench_index_group, bench_eval_group]
+    binary_benchmark_groups = [
+        bench_index_group,
+        bench_eval_group,
+        bench_pruning_group,
+        bench_short_queries_pruning_group
+    ]
 );
@@ -1,7 +1,12 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
 use clap::builder::PossibleValuesParser;
 use clap::{Parser, Subcommand};
 
+use crate::engines::nano::search::{Scorer, parse_scorer};
 use crate::engines::utils::get_all_engine_names;
+use crate::model::search::{FacetRequest, SortBy};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -50,10 +55,132 @@ pub enum Command {
     },
 
     #[command(about = "Evaluate search quality")]
-    Eval,
+    Eval {
+        #[arg(
+            long,
+            default_value = "bm25",
+            value_parser = parse_scorer,
+            help = SCORER_HELP
+        )]
+        scorer: Arc<dyn Scorer>,
+
+        #[arg(long, default_value = None, help = SEARCH_THREADS_HELP)]
+        search_threads: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Count all documents matching each query. Makes engines \
+                    visit every matching document, instead of skipping ones \
+                    that cannot get into top hits, e.g. to compare search \
+                    speed with and without skipping."
+        )]
+        count_total: bool,
+
+        #[arg(
+            long,
+            help = "Score every document matching each query, instead of \
+                    skipping ones that cannot get into top hits, while still \
+                    collecting only top hits. Only affects nano engine."
+        )]
+        no_pruning: bool,
+
+        #[arg(
+            long,
+            default_value = None,
+            help = "Keep only first words of each query, e.g. to evaluate \
+                    short queries on dataset of long ones."
+        )]
+        max_query_words: Option<usize>,
+    },
 
     #[command(about = "Perform a search with a single query")]
-    Search,
+    Search {
+        #[arg(
+            long,
+            default_value = "bm25",
+            value_parser = parse_scorer,
+            help = SCORER_HELP
+        )]
+        scorer: Arc<dyn Scorer>,
+
+        #[arg(long, default_value = None, help = SEARCH_THREADS_HELP)]
+        search_threads: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Explain how each found document is scored, i.e. which \
+                    query terms matched it and how their scores were \
+                    combined. Only supported by nano engine."
+        )]
+        explain: bool,
+
+        #[arg(long, default_value_t = 10, help = "Max number of hits to show")]
+        limit: u64,
+
+        #[arg(
+            long,
+            default_value_t = 0,
+            help = "Number of best hits to skip, e.g. to show the second page"
+        )]
+        offset: u64,
+
+        #[arg(
+            long,
+            default_value = None,
+            help = "Order hits by document value instead of relevance, with \
+                    optional order: field[:asc|desc]. Only supported by nano \
+                    engine."
+        )]
+        sort_by: Option<SortBy>,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Keyword fields to count matching documents per value of, \
+                    e.g. category,author. Only supported by nano engine."
+        )]
+        term_facets: Vec<String>,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = parse_histogram_facet,
+            help = "Number or date fields to count matching documents per \
+                    interval of values of: field:year|month|width, e.g. \
+                    timestamp:year. Only supported by nano engine."
+        )]
+        histogram_facets: Vec<FacetRequest>,
+
+        #[arg(
+            long = "filter",
+            help = "Query that hits should also match, without affecting \
+                    their scores, e.g. \"NOT draft\" or \"year:[2000 TO *]\". \
+                    Can be repeated. Only supported by nano engine."
+        )]
+        filters: Vec<String>,
+    },
+}
+
+const SCORER_HELP: &str =
+    "Scoring model of nano engine with optional parameters: \
+     bm25[:k1=1.2,b=0.75], bm25plus[:k1=1.2,b=0.75,delta=1], tfidf, \
+     dirichlet[:mu=2000], dfr[:c=1]. Index does not depend on it, so it can \
+     be changed without reindexing.";
+
+const SEARCH_THREADS_HELP: &str = "Number of threads to search index segments \
+                                   of nano engine in parallel. By default \
+                                   equals to number of available CPU cores.";
+
+/// Parses histogram facet of a field, e.g. "timestamp:year".
+fn parse_histogram_facet(spec: &str) -> Result<FacetRequest> {
+    let (field, interval) = spec
+        .split_once(':')
+        .context("histogram facet should have form \"field:interval\"")?;
+
+    Ok(FacetRequest::Histogram {
+        field: field.to_string(),
+        interval: interval.parse()?,
+    })
 }
 
 const fn get_examples_string() -> &'static str {
@@ -65,6 +192,17 @@ const fn get_examples_string() -> &'static str {
     # complex
     ./nano_search --engines=nano,tantivy --dataset=cisi index --threads=1
     ./nano_search --engines=nano,tantivy --dataset=cisi eval
+    ./nano_search --engines=nano --dataset=cisi eval --scorer=bm25:k1=1.5,b=0.6
+    ./nano_search --engines=nano --dataset=cisi eval --count-total
+    ./nano_search --engines=nano --dataset=cisi search --explain
+    ./nano_search --engines=nano,tantivy --dataset=cisi search --limit=5 \
+     --offset=5
+    ./nano_search --engines=nano --dataset=simplewiki search \
+     --sort-by=timestamp:desc
+    ./nano_search --engines=nano --dataset=simplewiki search \
+     --histogram-facets=timestamp:year
+    ./nano_search --engines=nano --dataset=simplewiki search \
+     --filter='timestamp:[2020-01-01 TO *]'
 
     # with 'cargo run' (notice app options go after double-dash '--' separator)
     cargo run -- --engines=nano,tantivy --dataset=cisi index"
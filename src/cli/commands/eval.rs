@@ -2,39 +2,78 @@ use std::time::Instant;
 
 use anyhow::Result;
 use colored::Colorize;
+use itertools::Itertools;
 
 use crate::dataset_readers::utils::init_dataset_by_name;
+use crate::engines::nano::search::SearchOptions;
 use crate::engines::utils::engine_open_from_disk_by_names;
-use crate::eval::evaluate_search_quality;
+use crate::eval::evaluate_search_quality_with_request;
 use crate::eval::model::{Query, SearchQuality};
 use crate::model::engine::SearchEngine;
+use crate::model::search::SearchRequest;
 use crate::utils::GetPercentile;
 
 pub fn eval(
     engines: &[String],
     dataset: &str,
     parent_index_dir: &str,
+    nano_search_options: &SearchOptions,
+    count_total: bool,
+    max_query_words: Option<usize>,
 ) -> Result<()> {
     println!("initializing search engines: {}", engines.join(","));
     println!("initializing dataset '{dataset}'");
+    println!("nano scorer: {:?}", nano_search_options.scorer);
 
-    let engines = engine_open_from_disk_by_names(engines, parent_index_dir)?;
+    let engines = engine_open_from_disk_by_names(
+        engines,
+        parent_index_dir,
+        nano_search_options,
+    )?;
     let dataset = init_dataset_by_name(dataset)?;
 
+    let request = SearchRequest::builder()
+        .query(String::new())
+        .limit(10)
+        .count_total(count_total)
+        .build();
+
     for engine in engines {
-        evaluate(engine.as_ref(), &mut dataset.queries()?)?;
+        let mut queries = dataset.queries()?.map(|query| {
+            query.map(|query| truncate_query(query, max_query_words))
+        });
+        evaluate(engine.as_ref(), &mut queries, &request)?;
     }
 
     Ok(())
 }
 
+/// Keeps only first words of the query, if max number of words is set.
+///
+/// Punctuation is dropped and words are lowercased, so a cut doesn't leave
+/// unclosed parenthesis or operator without operand, e.g. `(a OR`.
+fn truncate_query(mut query: Query, max_words: Option<usize>) -> Query {
+    if let Some(max_words) = max_words {
+        query.text = query
+            .text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(max_words)
+            .map(str::to_lowercase)
+            .join(" ");
+    }
+    query
+}
+
 fn evaluate(
     engine: &dyn SearchEngine,
     queries: &mut dyn Iterator<Item = Result<Query>>,
+    request: &SearchRequest,
 ) -> Result<()> {
     let now = Instant::now();
     println!("evaluating {} engine...", engine.get_name().red());
-    let quality = evaluate_search_quality(queries, engine, 10)?;
+    let quality =
+        evaluate_search_quality_with_request(queries, engine, request)?;
     println!(
         "evaluating {} engine... done in {:.1} seconds",
         engine.get_name(),
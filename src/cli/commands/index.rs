@@ -71,7 +71,7 @@ pub fn log_progress(
         docs_processed += 1;
         docs_processed_in_period += 1;
 
-        let doc_len = doc.as_ref().map_or(0, |doc| doc.text.len());
+        let doc_len = doc.as_ref().map_or(0, Doc::get_text_len);
 
         bytes_total += doc_len;
         bytes_in_period += doc_len;
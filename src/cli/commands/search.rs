@@ -1,21 +1,44 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
+use itertools::Itertools;
 
 use crate::dataset_readers::utils::init_dataset_by_name;
+use crate::engines::nano::search::SearchOptions;
 use crate::engines::utils::engine_open_from_disk_by_names;
 use crate::eval::evaluate_search_quality_for_query;
+use crate::model::doc::Doc;
+use crate::model::highlight::{Fragment, HighlightOptions};
+use crate::model::search::{Facet, FacetRequest, SearchRequest, SortBy};
 
-const SEARCH_LIMIT: u64 = 10;
+/// Max number of chars of each field to print for found documents.
+const FIELD_PREVIEW_CHARS: usize = 100;
 
+/// Max number of values to print for each term facet.
+const FACET_VALUES_LIMIT: usize = 10;
+
+#[expect(clippy::too_many_arguments)]
 pub fn search(
     engines: &[String],
     dataset: &str,
     parent_index_dir: &str,
+    nano_search_options: &SearchOptions,
+    explain: bool,
+    limit: u64,
+    offset: u64,
+    sort_by: Option<&SortBy>,
+    term_facets: &[String],
+    histogram_facets: &[FacetRequest],
+    filters: &[String],
 ) -> Result<()> {
     println!("initializing search engines: {}", engines.join(","));
     println!("initializing dataset '{dataset}'");
+    println!("nano scorer: {:?}", nano_search_options.scorer);
 
-    let engines = engine_open_from_disk_by_names(engines, parent_index_dir)?;
+    let engines = engine_open_from_disk_by_names(
+        engines,
+        parent_index_dir,
+        nano_search_options,
+    )?;
     let dataset = init_dataset_by_name(dataset)?;
 
     let query = dataset
@@ -27,19 +50,81 @@ pub fn search(
     println!("query (id={}): {}", query.id, query.text);
     println!("relevant docs: {:?}", query.relevant_docs);
 
+    let request = SearchRequest::builder()
+        .query(&query.text)
+        .limit(limit)
+        .offset(offset)
+        .count_total(true)
+        .maybe_sort_by(sort_by.cloned())
+        .facets(
+            term_facets
+                .iter()
+                .map(|field| FacetRequest::Terms {
+                    field: field.clone(),
+                    limit: FACET_VALUES_LIMIT,
+                })
+                .chain(histogram_facets.iter().cloned())
+                .collect(),
+        )
+        .filters(filters.to_vec())
+        .build();
+
+    let highlight_options = HighlightOptions::default();
+
     for engine in engines {
         println!("searching with {} engine", engine.get_name().red());
-        let found_docids = engine.search(&query.text, SEARCH_LIMIT)?;
+        let response = engine.search_with_request(&request)?;
+
+        if let Some(total_count) = response.total_count {
+            println!("total matching docs: {total_count}");
+        }
+        println!("found docs (offset={offset}, limit={limit}):");
+
+        for hit in &response.hits {
+            let relevance = if query.relevant_docs.contains_key(&hit.docid) {
+                " (relevant)".green()
+            } else {
+                "".normal()
+            };
+            println!("  docid={} score={:.4}{relevance}", hit.docid, hit.score);
+
+            // fetched once for sort value, snippet and preview
+            let doc = engine.get_document(hit.docid)?;
 
-        println!("found docids: {found_docids:?}");
+            if let Some(sort_by) = sort_by {
+                print_sort_value(doc.as_ref(), &sort_by.field);
+            }
+
+            if let Some(doc) = &doc {
+                let fragments = engine
+                    .highlight(&query.text, doc, &highlight_options)?
+                    .unwrap_or_default();
+
+                if fragments.is_empty() {
+                    print_document_preview(doc);
+                } else {
+                    print_snippet(&fragments);
+                }
+            }
+
+            if explain
+                && let Some(explanation) =
+                    engine.explain(&query.text, hit.docid)?
+            {
+                println!("{explanation}");
+            }
+        }
+
+        for facet in &response.facets {
+            print_facet(facet);
+        }
 
         let quality = evaluate_search_quality_for_query(
-            &found_docids,
+            &response.get_docids(),
             &query.relevant_docs,
-            SEARCH_LIMIT,
+            limit,
         )?;
 
-        println!("search limit: {SEARCH_LIMIT}");
         println!("precision: {:.1}%", quality.precision * 100.0);
         println!("recall   : {:.1}%", quality.recall * 100.0);
         println!("NDCG     : {:.1}%", quality.ndcg * 100.0);
@@ -47,3 +132,47 @@ pub fn search(
 
     Ok(())
 }
+
+fn print_snippet(fragments: &[Fragment]) {
+    for fragment in fragments {
+        let text = fragment.mark(|word| word.yellow().bold().to_string());
+        println!(
+            "    {}: {}",
+            fragment.field,
+            text.split_whitespace().join(" ")
+        );
+    }
+}
+
+/// Prints values of the facet with numbers of matching documents.
+fn print_facet(facet: &Facet) {
+    println!("facet {}:", facet.field);
+    for bucket in &facet.buckets {
+        println!("  {}: {}", bucket.key, bucket.count);
+    }
+}
+
+/// Prints value of the document, which hits are sorted by.
+fn print_sort_value(doc: Option<&Doc>, field: &str) {
+    let value = doc.and_then(|doc| {
+        doc.values
+            .iter()
+            .find(|value| value.name == field)
+            .map(|value| value.value.to_string())
+    });
+    println!("    {field}={}", value.as_deref().unwrap_or("(missing)"));
+}
+
+/// Prints beginning of each field, for documents without snippet.
+fn print_document_preview(doc: &Doc) {
+    for field in &doc.fields {
+        let text = field.text.split_whitespace().join(" ");
+        let preview: String = text.chars().take(FIELD_PREVIEW_CHARS).collect();
+        let ellipsis = if preview.len() < text.len() {
+            "..."
+        } else {
+            ""
+        };
+        println!("    {}: {preview}{ellipsis}", field.name);
+    }
+}
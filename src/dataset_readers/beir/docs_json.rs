@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use serde_json::{Map, Value};
 
 use super::utils::{extract_string_from_json, parse_id};
-use crate::model::doc::{Doc, DocsSource};
+use crate::model::doc::{Doc, DocField, DocsSource};
 use crate::utils::get_file_lines;
 
 pub struct BeirDocsJsonReader {
@@ -52,6 +52,10 @@ fn parse_doc(line: &str) -> Result<Doc> {
 
     Ok(Doc {
         id,
-        text: format!("{title} {text}"),
+        fields: vec![
+            DocField::new("title", title),
+            DocField::new("text", text),
+        ],
+        values: Vec::new(),
     })
 }
@@ -4,7 +4,7 @@ use anyhow::{Context, Result, bail};
 use parquet::record::Row;
 
 use super::utils::{extract_string_from_parquet, parse_id};
-use crate::model::doc::{Doc, DocsSource};
+use crate::model::doc::{Doc, DocField, DocsSource};
 use crate::utils::get_parquet_rows;
 
 pub struct BeirDocsParquetReader {
@@ -63,6 +63,10 @@ pub fn parse_doc(row: &Row) -> Result<Doc> {
 
     Ok(Doc {
         id,
-        text: format!("{title} {text}"),
+        fields: vec![
+            DocField::new("title", title),
+            DocField::new("text", text),
+        ],
+        values: Vec::new(),
     })
 }
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result, bail};
 
 use super::model::CisiDatasetReader;
-use crate::model::doc::{Doc, DocsSource};
+use crate::model::doc::{Doc, DocField, DocsSource};
 use crate::utils::get_file_lines;
 
 pub struct CisiDocsIterator {
@@ -34,6 +34,10 @@ impl Iterator for CisiDocsIterator {
     }
 }
 
+const TITLE_FIELD: &str = "title";
+const AUTHOR_FIELD: &str = "author";
+const TEXT_FIELD: &str = "text";
+
 #[derive(Clone, Copy)]
 enum ESectionType {
     DocId,
@@ -85,7 +89,12 @@ fn read_next_doc(it: &mut CisiDocsIterator) -> Result<Option<Doc>> {
 
                         it.current_doc = Some(Doc {
                             id: docid,
-                            text: String::new(),
+                            fields: vec![
+                                DocField::new(TITLE_FIELD, ""),
+                                DocField::new(AUTHOR_FIELD, ""),
+                                DocField::new(TEXT_FIELD, ""),
+                            ],
+                            values: Vec::new(),
                         });
 
                         if prev_doc.is_some() {
@@ -98,17 +107,24 @@ fn read_next_doc(it: &mut CisiDocsIterator) -> Result<Option<Doc>> {
                 }
             }
             ELineType::SectionContent(ref section_type) => {
-                match section_type {
-                    ESectionType::Refs => {
-                        // skip
-                    }
-                    _default => {
-                        it.current_doc
-                            .as_mut()
-                            .context("doc should be initialized")?
-                            .text += &line;
-                    }
+                let field_idx = match section_type {
+                    ESectionType::Title => 0,
+                    ESectionType::Author => 1,
+                    ESectionType::Text => 2,
+                    ESectionType::DocId | ESectionType::Refs => continue,
+                };
+
+                let field = &mut it
+                    .current_doc
+                    .as_mut()
+                    .context("doc should be initialized")?
+                    .fields[field_idx];
+
+                // separate lines, so words on line edges do not stick together
+                if !field.text.is_empty() {
+                    field.text += " ";
                 }
+                field.text += &line;
             }
         }
     }
@@ -6,7 +6,7 @@ use anyhow::{Context, Result};
 use serde_json::{Map, Value};
 
 use super::model::JsonDatasetReader;
-use crate::model::doc::{Doc, DocsSource, ExternalDocId};
+use crate::model::doc::{Doc, DocField, DocsSource, ExternalDocId};
 
 pub struct JsonDocsIterator {
     lines: Lines<BufReader<File>>,
@@ -57,6 +57,7 @@ fn parse_doc(json: &str, docid: ExternalDocId) -> Option<Doc> {
 
     Some(Doc {
         id: docid,
-        text: body,
+        fields: vec![DocField::new("text", body)],
+        values: Vec::new(),
     })
 }
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 
 use super::model::WikiDatasetReader;
-use crate::model::doc::{Doc, DocsSource};
+use crate::model::doc::{Doc, DocField, DocValue, DocValueField, DocsSource};
 use crate::utils::wikidump::WikiPagesIterator;
 
 pub struct WikiDocsIterator {
@@ -29,14 +29,28 @@ impl Iterator for WikiDocsIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         let doc = self.it.next().map(|page| {
+            let mut page = page?;
+            let revision = page
+                .revisions
+                // last revision here means latest revision by timestamp
+                .pop()
+                .context("should get latest revision")?;
+
+            let timestamp =
+                chrono::DateTime::parse_from_rfc3339(&revision.timestamp)
+                    .context("revision timestamp should be valid")?
+                    .timestamp();
+
             Ok(Doc {
                 id: self.docid,
-                text: page?
-                    .revisions
-                    // last revision here means latest revision by timestamp
-                    .pop()
-                    .context("should get latest revision")?
-                    .text,
+                fields: vec![
+                    DocField::new("title", page.title),
+                    DocField::new("text", revision.text),
+                ],
+                values: vec![DocValueField::new(
+                    "timestamp",
+                    DocValue::Date(timestamp),
+                )],
             })
         });
 
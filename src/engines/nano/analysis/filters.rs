@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use super::stop_words::STOP_WORDS;
+use super::{Token, TokenFilter};
+
+/// Removes non-alphabetic characters from the token, e.g. punctuation and
+/// digits, and removes the token if nothing is left.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AlphabeticFilter;
+
+impl TokenFilter for AlphabeticFilter {
+    fn filter(&self, mut token: Token) -> Option<Token> {
+        token.text.retain(char::is_alphabetic);
+        (!token.text.is_empty()).then_some(token)
+    }
+}
+
+/// Makes the token lowercase.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowercaseFilter;
+
+impl TokenFilter for LowercaseFilter {
+    fn filter(&self, mut token: Token) -> Option<Token> {
+        token.text = token.text.to_lowercase();
+        Some(token)
+    }
+}
+
+/// Removes stop words, e.g. "the" or "of". Words are compared as is, so it
+/// should go after [`LowercaseFilter`].
+///
+/// Removed stop words do not take positions, so the same filter should remove
+/// them from documents and queries, and phrase "retrieval of information"
+/// matches "retrieval" right before "information".
+#[derive(Debug, Clone)]
+pub struct StopWordsFilter {
+    stop_words: HashSet<String>,
+}
+
+impl Default for StopWordsFilter {
+    /// Removes same English stop words as Lucene does by default.
+    fn default() -> Self {
+        Self {
+            stop_words: STOP_WORDS.clone(),
+        }
+    }
+}
+
+impl StopWordsFilter {
+    #[must_use]
+    pub fn new(
+        stop_words: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            stop_words: stop_words.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TokenFilter for StopWordsFilter {
+    fn filter(&self, token: Token) -> Option<Token> {
+        (!self.stop_words.contains(&token.text)).then_some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn filter(filter: &dyn TokenFilter, text: &str) -> Option<String> {
+        filter
+            .filter(Token::new(text, 0..text.len()))
+            .map(|token| token.text)
+    }
+
+    #[test]
+    fn test_alphabetic_filter() {
+        assert_eq!(
+            filter(&AlphabeticFilter, "(Wom-an's)").as_deref(),
+            Some("Womans")
+        );
+        assert_eq!(filter(&AlphabeticFilter, "1980,"), None);
+    }
+
+    #[test]
+    fn test_lowercase_filter() {
+        assert_eq!(filter(&LowercaseFilter, "CaT").as_deref(), Some("cat"));
+    }
+
+    #[test]
+    fn test_stop_words_filter() {
+        let default_filter = StopWordsFilter::default();
+        assert_eq!(filter(&default_filter, "the"), None);
+        assert_eq!(filter(&default_filter, "cat").as_deref(), Some("cat"));
+
+        let custom_filter = StopWordsFilter::new(["cat"]);
+        assert_eq!(filter(&custom_filter, "cat"), None);
+        assert_eq!(filter(&custom_filter, "the").as_deref(), Some("the"));
+    }
+}
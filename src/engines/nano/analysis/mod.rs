@@ -0,0 +1,240 @@
+//! Analysis module turns text into terms, both when documents are indexed and
+//! when query words are searched.
+//!
+//! Text is split into tokens by a [`Tokenizer`], and then each token passes
+//! through a chain of [`TokenFilter`]s, which may change or remove it. Index
+//! should be searched with the same [`Analyzer`] it was built with, otherwise
+//! query terms would not match terms of documents.
+
+mod filters;
+mod stop_words;
+mod tokenizers;
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, ensure};
+pub use filters::{AlphabeticFilter, LowercaseFilter, StopWordsFilter};
+pub use stop_words::STOP_WORDS;
+pub use tokenizers::{UnicodeTokenizer, WhitespaceTokenizer};
+
+use crate::engines::nano::index::model::Term;
+
+/// Piece of analyzed text, that becomes a term of the index or query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Text of the token, as changed by filters so far
+    pub text: String,
+
+    /// Bytes of the token in original text, e.g. to highlight it
+    pub bytes: Range<usize>,
+}
+
+impl Token {
+    #[must_use]
+    pub fn new(text: &str, bytes: Range<usize>) -> Self {
+        Self {
+            text: text.to_string(),
+            bytes,
+        }
+    }
+}
+
+/// Splits text into tokens.
+pub trait Tokenizer: Debug + Send + Sync {
+    /// Splits text into tokens, in order they go in text.
+    fn tokenize(&self, text: &str) -> Vec<Token>;
+}
+
+/// Changes or removes tokens produced by tokenizer, e.g. lowercases them or
+/// removes stop words.
+pub trait TokenFilter: Debug + Send + Sync {
+    /// Changes the token, or returns `None` to remove it.
+    fn filter(&self, token: Token) -> Option<Token>;
+}
+
+/// Pipeline of tokenizer and token filters, that turns text into terms.
+///
+/// It's called `Analyzer` in Lucene and `TextAnalyzer` in Tantivy.
+#[derive(Debug, Clone)]
+pub struct Analyzer {
+    tokenizer: Arc<dyn Tokenizer>,
+    filters: Vec<Arc<dyn TokenFilter>>,
+}
+
+impl Default for Analyzer {
+    /// Splits text by Unicode word boundaries, lowercases words and removes
+    /// English stop words.
+    fn default() -> Self {
+        Self::new(UnicodeTokenizer)
+            .with_filter(LowercaseFilter)
+            .with_filter(StopWordsFilter::default())
+    }
+}
+
+impl Analyzer {
+    #[must_use]
+    pub fn new(tokenizer: impl Tokenizer + 'static) -> Self {
+        Self {
+            tokenizer: Arc::new(tokenizer),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Adds filter to the end of the chain.
+    #[must_use]
+    pub fn with_filter(mut self, filter: impl TokenFilter + 'static) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Splits text into tokens and passes each of them through filters, in
+    /// order filters were added.
+    ///
+    /// Tokens removed by filters do not take positions, so words around
+    /// removed stop word go next to each other in phrases.
+    #[must_use]
+    pub fn analyze(&self, text: &str) -> Vec<Token> {
+        self.analyze_with_removed(text)
+            .into_iter()
+            .filter_map(|(bytes, term)| term.map(|text| Token { text, bytes }))
+            .collect()
+    }
+
+    /// Splits text into tokens and passes each of them through filters, but
+    /// keeps tokens removed by filters, e.g. so highlighted fragments can
+    /// start with stop words. Returns bytes of each token in text, along with
+    /// its term, or `None` if filters removed it.
+    #[must_use]
+    pub fn analyze_with_removed(
+        &self,
+        text: &str,
+    ) -> Vec<(Range<usize>, Option<Term>)> {
+        self.tokenizer
+            .tokenize(text)
+            .into_iter()
+            .map(|token| {
+                let bytes = token.bytes.clone();
+                let term = self
+                    .filters
+                    .iter()
+                    .try_fold(token, |token, filter| filter.filter(token))
+                    .map(|token| token.text);
+                (bytes, term)
+            })
+            .collect()
+    }
+
+    /// Passes part of a word through filters as single token, without
+    /// splitting it with tokenizer, e.g. literal parts of wildcard patterns,
+    /// which tokenizer would split by wildcards.
+    ///
+    /// Filters removing the part are skipped, since they remove whole words,
+    /// e.g. stop words, while "the" of "the*" is only the start of a word.
+    #[must_use]
+    pub fn filter_word_part(&self, part: &str) -> String {
+        self.filters
+            .iter()
+            .fold(Token::new(part, 0..part.len()), |token, filter| {
+                filter.filter(token.clone()).unwrap_or(token)
+            })
+            .text
+    }
+
+    /// Analyzes query word into its term.
+    ///
+    /// Query normalizer removes words without terms, and splits words with
+    /// several terms, so each word left in normalized query has single term.
+    pub fn analyze_word(&self, word: &str) -> Result<Term> {
+        let mut tokens = self.analyze(word);
+
+        ensure!(
+            tokens.len() == 1,
+            "word '{word}' should be analyzed into single term"
+        );
+
+        Ok(tokens.pop().context("should exist")?.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn analyze(analyzer: &Analyzer, text: &str) -> Vec<String> {
+        analyzer
+            .analyze(text)
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_analyzer() {
+        let analyzer = Analyzer::default();
+
+        assert_eq!(
+            analyzer.analyze("The (Cat) and 42 mice"),
+            vec![
+                Token::new("cat", 5..8),
+                Token::new("42", 14..16),
+                Token::new("mice", 17..21),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_with_removed() {
+        assert_eq!(
+            Analyzer::default().analyze_with_removed("The Cat"),
+            vec![(0..3, None), (4..7, Some("cat".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_filters_chain() {
+        let analyzer = Analyzer::new(WhitespaceTokenizer)
+            .with_filter(LowercaseFilter)
+            .with_filter(StopWordsFilter::default());
+
+        assert_eq!(
+            analyze(&analyzer, "The Cat of the House"),
+            ["cat", "house"]
+        );
+    }
+
+    #[test]
+    fn test_analyzer_without_filters() {
+        let analyzer = Analyzer::new(WhitespaceTokenizer);
+
+        assert_eq!(analyze(&analyzer, "The  Cat, "), ["The", "Cat,"]);
+    }
+
+    #[test]
+    fn test_filter_word_part() {
+        let analyzer = Analyzer::default();
+
+        // tokenizer would split the part and drop the parentheses
+        assert_eq!(analyzer.filter_word_part("(Cat-Dog)"), "(cat-dog)");
+        assert_eq!(analyzer.filter_word_part("The"), "the");
+        assert_eq!(
+            Analyzer::new(WhitespaceTokenizer).filter_word_part("Cat"),
+            "Cat"
+        );
+    }
+
+    #[test]
+    fn test_analyze_word() -> Result<()> {
+        let analyzer = Analyzer::default();
+
+        assert_eq!(analyzer.analyze_word("(Cat)")?, "cat");
+        assert_eq!(analyzer.analyze_word("42")?, "42");
+        assert!(analyzer.analyze_word("--").is_err());
+        assert!(analyzer.analyze_word("cat mouse").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Token, Tokenizer};
+
+/// Splits text into words by Unicode word boundaries ([UAX #29]), so
+/// punctuation splits words, and keeps words with letters or digits.
+///
+/// - Hyphenated compounds split into parts, e.g. "state-of-the-art" turns into
+///   "state", "of", "the" and "art", and "covid-19" into "covid" and "19"
+/// - Apostrophes inside words are removed, so "don't", "don’t" and "dont" all
+///   turn into "dont"
+/// - Dots and commas between letters or digits are kept, e.g. "3.14", "1,000"
+///   and "U.S" (of "U.S.") stay single tokens
+///
+/// [UAX #29]: https://www.unicode.org/reports/tr29/#Word_Boundaries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeTokenizer;
+
+impl Tokenizer for UnicodeTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        text.split_word_bound_indices()
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map(|(start_byte, word)| {
+                let mut token =
+                    Token::new(word, start_byte..start_byte + word.len());
+                token.text.retain(|ch| !is_apostrophe(ch));
+                token
+            })
+            .collect()
+    }
+}
+
+const fn is_apostrophe(ch: char) -> bool {
+    matches!(ch, '\'' | '\u{2019}')
+}
+
+/// Splits text by whitespace, so punctuation stays attached to words, e.g.
+/// "cat," or "(mouse)".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut word_start: Option<usize> = None;
+
+        let char_indices = text
+            .char_indices()
+            .chain(std::iter::once((text.len(), ' ')));
+
+        for (byte_idx, ch) in char_indices {
+            match (word_start, ch.is_whitespace()) {
+                (None, false) => word_start = Some(byte_idx),
+                (Some(start_byte), true) => {
+                    word_start = None;
+                    tokens.push(Token::new(
+                        &text[start_byte..byte_idx],
+                        start_byte..byte_idx,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_whitespace_tokenizer() {
+        assert_eq!(
+            WhitespaceTokenizer.tokenize(" cat,\tmöuse\n(dog) "),
+            vec![
+                Token::new("cat,", 1..5),
+                Token::new("möuse", 6..12),
+                Token::new("(dog)", 13..18),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_whitespace_tokenizer_empty() {
+        assert!(WhitespaceTokenizer.tokenize("").is_empty());
+        assert!(WhitespaceTokenizer.tokenize(" \n ").is_empty());
+    }
+
+    fn unicode_tokens(text: &str) -> Vec<String> {
+        UnicodeTokenizer
+            .tokenize(text)
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_unicode_tokenizer() {
+        assert_eq!(
+            UnicodeTokenizer.tokenize(" cat,\tmöuse\n(dog) 42 "),
+            vec![
+                Token::new("cat", 1..4),
+                Token::new("möuse", 6..12),
+                Token::new("dog", 14..17),
+                Token::new("42", 19..21),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unicode_tokenizer_punctuation() {
+        assert_eq!(
+            unicode_tokens("state-of-the-art covid-19 (U.S.) 3.14 1,000"),
+            [
+                "state", "of", "the", "art", "covid", "19", "U.S", "3.14",
+                "1,000"
+            ]
+        );
+        assert_eq!(
+            unicode_tokens("cat & mouse -- dog!"),
+            ["cat", "mouse", "dog"]
+        );
+    }
+
+    #[test]
+    fn test_unicode_tokenizer_apostrophes() {
+        assert_eq!(
+            UnicodeTokenizer.tokenize("don't 'cat’s'"),
+            vec![Token::new("dont", 0..5), Token::new("cats", 7..14)]
+        );
+    }
+
+    #[test]
+    fn test_unicode_tokenizer_non_latin() {
+        assert_eq!(
+            unicode_tokens("Привет, мир! Γειά σου"),
+            ["Привет", "мир", "Γειά", "σου"]
+        );
+    }
+
+    #[test]
+    fn test_unicode_tokenizer_empty() {
+        assert!(UnicodeTokenizer.tokenize("").is_empty());
+        assert!(UnicodeTokenizer.tokenize(" -- !? ").is_empty());
+    }
+}
@@ -1,14 +1,77 @@
+use std::sync::OnceLock;
+
 use anyhow::{Context, Result};
 
+use super::analysis::Analyzer;
 use super::index::model::{Index, IndexMedium};
-use super::index::{DiskIndexOptions, build_index, open_index};
-use super::search::search;
+use super::index::{DiskIndexOptions, build_index_with_analyzer, open_index};
+use super::search::{
+    DocLookup, FilterCache, SearchOptions, SearchPool, explain, get_document,
+    highlight, search_with_request,
+};
 use crate::model::doc::{Doc, ExternalDocId};
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::highlight::{Fragment, HighlightOptions};
+use crate::model::search::{SearchRequest, SearchResponse};
+use crate::utils::TreeNode;
 
 pub struct NanoSearchEngine {
     index_medium: IndexMedium,
     index: Option<Box<dyn Index>>,
+    analyzer: Analyzer,
+    search_options: SearchOptions,
+
+    /// Documents matching filters of previous searches in the current index
+    filter_cache: FilterCache,
+
+    /// Threads searching segments, sized by `search_options`
+    search_pool: SearchPool,
+
+    /// Segments of documents of the current index, built on first lookup
+    doc_lookup: OnceLock<DocLookup>,
+}
+
+impl NanoSearchEngine {
+    /// Sets analyzer, that turns text of documents and query words into
+    /// terms. Index is built and searched with the same analyzer, so it should
+    /// be set before indexing documents or right after opening the index.
+    pub fn set_analyzer(&mut self, analyzer: Analyzer) {
+        self.analyzer = analyzer;
+        // cached documents matched filters analyzed with previous analyzer
+        self.filter_cache = FilterCache::default();
+    }
+
+    /// Gets lookup of documents of the index, building it on first call.
+    fn get_doc_lookup(&self, index: &dyn Index) -> Result<&DocLookup> {
+        if let Some(doc_lookup) = self.doc_lookup.get() {
+            return Ok(doc_lookup);
+        }
+        let doc_lookup = DocLookup::build(index)
+            .context("lookup of documents should be built")?;
+        Ok(self.doc_lookup.get_or_init(|| doc_lookup))
+    }
+
+    /// Sets options for subsequent searches, e.g. scorer to rank documents
+    /// with. Options do not affect the index, so they can be changed any time.
+    ///
+    /// Search threads are restarted if their number changes.
+    pub fn set_search_options(
+        &mut self,
+        search_options: SearchOptions,
+    ) -> Result<()> {
+        let threads_count = search_options.get_search_threads();
+        if threads_count != self.search_pool.get_threads_count() {
+            self.search_pool = SearchPool::new(threads_count)
+                .context("search threads should be started")?;
+        }
+        self.search_options = search_options;
+        Ok(())
+    }
+}
+
+fn create_default_search_pool() -> Result<SearchPool> {
+    SearchPool::new(SearchOptions::default().get_search_threads())
+        .context("search threads should be started")
 }
 
 impl SearchEngine for NanoSearchEngine {
@@ -27,6 +90,11 @@ impl SearchEngine for NanoSearchEngine {
         Ok(Self {
             index_medium: IndexMedium::Memory,
             index: None,
+            analyzer: Analyzer::default(),
+            search_options: SearchOptions::default(),
+            filter_cache: FilterCache::default(),
+            search_pool: create_default_search_pool()?,
+            doc_lookup: OnceLock::new(),
         })
     }
 
@@ -48,6 +116,11 @@ impl SearchEngine for NanoSearchEngine {
         Ok(Self {
             index_medium,
             index: None,
+            analyzer: Analyzer::default(),
+            search_options: SearchOptions::default(),
+            filter_cache: FilterCache::default(),
+            search_pool: create_default_search_pool()?,
+            doc_lookup: OnceLock::new(),
         })
     }
 
@@ -63,6 +136,11 @@ impl SearchEngine for NanoSearchEngine {
         Ok(Self {
             index_medium,
             index: Some(index),
+            analyzer: Analyzer::default(),
+            search_options: SearchOptions::default(),
+            filter_cache: FilterCache::default(),
+            search_pool: create_default_search_pool()?,
+            doc_lookup: OnceLock::new(),
         })
     }
 
@@ -71,18 +149,74 @@ impl SearchEngine for NanoSearchEngine {
         docs: &mut dyn Iterator<Item = Result<Doc>>,
     ) -> Result<()> {
         self.index = Some(
-            build_index(&self.index_medium, docs)
+            build_index_with_analyzer(&self.index_medium, docs, &self.analyzer)
                 .context("index should be built")?,
         );
+        // cached documents belong to segments of the previous index
+        self.filter_cache.clear()?;
+        self.doc_lookup = OnceLock::new();
         Ok(())
     }
 
-    fn search(&self, query: &str, limit: u64) -> Result<Vec<ExternalDocId>> {
+    fn search_with_request(
+        &self,
+        request: &SearchRequest,
+    ) -> Result<SearchResponse> {
         let index = self
             .index
             .as_ref()
             .context("index should be initialized before search")?;
 
-        search(query, index.as_ref(), limit)
+        search_with_request(
+            request,
+            index.as_ref(),
+            &self.analyzer,
+            &self.search_options,
+            &self.filter_cache,
+            &self.search_pool,
+        )
+    }
+
+    fn explain(
+        &self,
+        query: &str,
+        docid: ExternalDocId,
+    ) -> Result<Option<TreeNode>> {
+        let index = self
+            .index
+            .as_ref()
+            .context("index should be initialized before explain")?;
+
+        explain(
+            query,
+            index.as_ref(),
+            docid,
+            &self.analyzer,
+            &self.search_options,
+            self.get_doc_lookup(index.as_ref())?,
+        )
+        .map(Some)
+    }
+
+    fn get_document(&self, docid: ExternalDocId) -> Result<Option<Doc>> {
+        let index = self
+            .index
+            .as_ref()
+            .context("index should be initialized before getting documents")?;
+
+        get_document(
+            index.as_ref(),
+            docid,
+            self.get_doc_lookup(index.as_ref())?,
+        )
+    }
+
+    fn highlight(
+        &self,
+        query: &str,
+        doc: &Doc,
+        options: &HighlightOptions,
+    ) -> Result<Option<Vec<Fragment>>> {
+        highlight(query, doc, &self.analyzer, options).map(Some)
     }
 }
@@ -0,0 +1,112 @@
+use std::sync::LazyLock;
+
+use anyhow::{Result, ensure};
+use levenshtein_automata::{
+    DFA, Distance, LevenshteinAutomatonBuilder, SINK_STATE,
+};
+
+/// Largest supported edit distance. Automata for larger distances get too
+/// big, and match too many unrelated terms anyway.
+pub const MAX_EDIT_DISTANCE: u8 = 2;
+
+// building automaton builder takes few milliseconds, so do it once for each
+// distance
+static BUILDERS: LazyLock<Vec<LevenshteinAutomatonBuilder>> =
+    LazyLock::new(|| {
+        (0..=MAX_EDIT_DISTANCE)
+            .map(|distance| LevenshteinAutomatonBuilder::new(distance, true))
+            .collect()
+    });
+
+/// Automaton, that accepts terms within Levenshtein distance from a word.
+///
+/// Transposition of two adjacent characters counts as single edit, since it's
+/// a common typo (e.g. "retreival").
+///
+/// It reads term byte by byte, and tells as soon as possible if term cannot
+/// get close enough to the word, so term dictionary can skip all terms with
+/// the same prefix without reading them.
+pub struct LevenshteinAutomaton {
+    dfa: DFA,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(word: &str, max_distance: u8) -> Result<Self> {
+        ensure!(
+            max_distance <= MAX_EDIT_DISTANCE,
+            "edit distance should be at most {MAX_EDIT_DISTANCE}"
+        );
+
+        Ok(Self {
+            dfa: BUILDERS[max_distance as usize].build_dfa(word),
+        })
+    }
+
+    /// Gets edit distance between the word and `term`, or `None` if it
+    /// exceeds max distance.
+    #[must_use]
+    pub fn get_distance(&self, term: &str) -> Option<u8> {
+        match self.dfa.eval(term) {
+            Distance::Exact(distance) => Some(distance),
+            Distance::AtLeast(_) => None,
+        }
+    }
+}
+
+impl fst::Automaton for LevenshteinAutomaton {
+    type State = u32;
+
+    fn start(&self) -> Self::State {
+        self.dfa.initial_state()
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        matches!(self.dfa.distance(*state), Distance::Exact(_))
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        *state != SINK_STATE
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        self.dfa.transition(*state, byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_distance() -> Result<()> {
+        let automaton = LevenshteinAutomaton::new("retrieval", 2)?;
+
+        assert_eq!(automaton.get_distance("retrieval"), Some(0));
+        assert_eq!(automaton.get_distance("retreival"), Some(1));
+        assert_eq!(automaton.get_distance("retrieve"), Some(2));
+        assert_eq!(automaton.get_distance("retrieved"), Some(2));
+        assert_eq!(automaton.get_distance("retina"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_ascii() -> Result<()> {
+        let automaton = LevenshteinAutomaton::new("café", 1)?;
+
+        assert_eq!(automaton.get_distance("cafe"), Some(1));
+        assert_eq!(automaton.get_distance("cafés"), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_distance() {
+        assert_eq!(
+            LevenshteinAutomaton::new("word", 3)
+                .err()
+                .map(|e| e.to_string()),
+            Some("edit distance should be at most 2".to_string())
+        );
+    }
+}
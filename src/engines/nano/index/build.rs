@@ -3,16 +3,29 @@ use anyhow::{Result, bail};
 use super::disk::{build_disk_index, open_disk_index};
 use super::memory::build_memory_index;
 use super::model::{Index, IndexMedium};
+use crate::engines::nano::analysis::Analyzer;
 use crate::model::doc::Doc;
 
 pub fn build_index(
     index_medium: &IndexMedium,
     docs: &mut dyn Iterator<Item = Result<Doc>>,
+) -> Result<Box<dyn Index>> {
+    build_index_with_analyzer(index_medium, docs, &Analyzer::default())
+}
+
+/// Builds index with terms produced by `analyzer`. Index should then be
+/// searched with the same analyzer.
+pub fn build_index_with_analyzer(
+    index_medium: &IndexMedium,
+    docs: &mut dyn Iterator<Item = Result<Doc>>,
+    analyzer: &Analyzer,
 ) -> Result<Box<dyn Index>> {
     match index_medium {
-        IndexMedium::Memory => Ok(Box::new(build_memory_index(docs)?)),
+        IndexMedium::Memory => {
+            Ok(Box::new(build_memory_index(docs, analyzer, true, true)?))
+        }
         IndexMedium::Disk(options) => {
-            Ok(Box::new(build_disk_index(docs, options)?))
+            Ok(Box::new(build_disk_index(docs, options, analyzer)?))
         }
     }
 }
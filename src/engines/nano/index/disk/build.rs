@@ -1,26 +1,49 @@
-use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::thread::JoinHandle;
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, ensure};
 use crossbeam_channel::Receiver;
 use itertools::Itertools;
 use memmap2::Mmap;
+use zerocopy::IntoBytes;
 
 use super::DiskIndexOptions;
 use super::model::{
     DiskIndex, DiskIndexSegment, IndexFile, TermPostingListFileAddress,
 };
-use super::serializer::{BinarySerializable, PostingsSerializer};
-use crate::engines::nano::index::memory::{MemoryIndex, build_memory_index};
-use crate::engines::nano::index::model::IndexSegmentStats;
+use super::serializer::{
+    BinarySerializable, PostingsSerializer, serialize_stored_fields,
+};
+use super::terms::{TermDictionary, TermDictionaryWriter};
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::doc_values::DocValuesColumnInfo;
+use crate::engines::nano::index::memory::{
+    MemoryIndex, build_memory_index, get_term_freq_total,
+};
+use crate::engines::nano::index::model::{
+    IndexSegmentStats, split_field_term_key,
+};
 use crate::model::doc::Doc;
 use crate::utils::CountingWriter;
 
 const SEGMENT_DIR_PREFIX: &str = "segment-";
 
+/// Version of on-disk segment layout, written at the start of Stats file.
+///
+/// Must be bumped on any incompatible change of segment files, so segments
+/// written in old layout are rejected on open, instead of being misread.
+/// Version 1 is the first versioned layout, and covers all of:
+/// - skip data after posting list blocks, instead of per-block headers
+/// - positions of terms in documents, for phrase and proximity queries
+/// - FST dictionary of terms
+/// - field of each term, stored as a prefix of term key
+/// - total frequency of each term in term info
+/// - stored fields of documents
+/// - doc values, for filtering, sorting and facets
+pub const SEGMENT_FORMAT_VERSION: u32 = 1;
+
 // limit number of index threads to not create too much segments
 const MAX_INDEX_THREADS: usize = 10;
 const DOCS_CHANNEL_CAPACITY: usize = 10_000;
@@ -28,6 +51,7 @@ const DOCS_CHANNEL_CAPACITY: usize = 10_000;
 pub fn build_disk_index(
     docs: &mut dyn Iterator<Item = Result<Doc>>,
     opts: &DiskIndexOptions,
+    analyzer: &Analyzer,
 ) -> Result<DiskIndex> {
     let (docs_sender, docs_receiver) =
         crossbeam_channel::bounded(DOCS_CHANNEL_CAPACITY);
@@ -43,7 +67,10 @@ pub fn build_disk_index(
         let handle = spawn_indexer_thread(
             thread_idx,
             docs_receiver.clone(),
+            analyzer.clone(),
             opts.max_segment_docs,
+            opts.record_positions,
+            opts.store_fields,
             opts.index_dir.clone(),
         )?;
         thread_handles.push(handle);
@@ -71,7 +98,10 @@ pub fn build_disk_index(
 fn spawn_indexer_thread(
     thread_idx: usize,
     docs_receiver: Receiver<Result<Doc>>,
+    analyzer: Analyzer,
     max_segment_docs: usize,
+    record_positions: bool,
+    store_fields: bool,
     index_dir: PathBuf,
 ) -> Result<JoinHandle<Result<Vec<DiskIndexSegment>>>> {
     let handle = std::thread::Builder::new()
@@ -83,7 +113,12 @@ fn spawn_indexer_thread(
                 docs_receiver.into_iter().chunks(max_segment_docs);
 
             for docs_chunk in &docs_chunks {
-                let mem_idx = build_memory_index(&mut docs_chunk.into_iter())?;
+                let mem_idx = build_memory_index(
+                    &mut docs_chunk.into_iter(),
+                    &analyzer,
+                    record_positions,
+                    store_fields,
+                )?;
                 let segment = build_disk_index_segment(mem_idx, &index_dir)?;
                 segments.push(segment);
             }
@@ -98,38 +133,67 @@ fn build_disk_index_segment(
     memory_index: MemoryIndex,
     index_dir: impl AsRef<Path>,
 ) -> Result<DiskIndexSegment> {
-    let segment_id = uuid::Uuid::new_v4().as_simple().to_string();
-    let segment_dir_name = SEGMENT_DIR_PREFIX.to_string() + &segment_id;
+    let segment_dir_name = SEGMENT_DIR_PREFIX.to_string() + &memory_index.id;
     let segment_dir = index_dir.as_ref().join(segment_dir_name);
     fs::create_dir(&segment_dir)?;
 
-    let mut terms_file = create_writer(&segment_dir, IndexFile::Terms)?;
+    let terms_file = create_writer(&segment_dir, IndexFile::Terms)?;
+    let term_addresses_file =
+        create_writer(&segment_dir, IndexFile::TermAddresses)?;
     let mut postings_file = create_writer(&segment_dir, IndexFile::Postings)?;
-    let mut doc_term_counts_file =
-        create_writer(&segment_dir, IndexFile::DocLen)?;
-    let mut docs_file = create_writer(&segment_dir, IndexFile::Docs)?;
+    let positions_file = if memory_index.has_positions {
+        Some(create_writer(&segment_dir, IndexFile::Positions)?)
+    } else {
+        None
+    };
     let mut stats_file = create_writer(&segment_dir, IndexFile::Stats)?;
 
-    let mut terms = HashMap::new();
+    // memory index keeps terms sorted, as term dictionary requires
+    let mut terms_writer =
+        TermDictionaryWriter::new(terms_file, term_addresses_file)?;
 
     let mut postings_writer = CountingWriter::new(postings_file);
-    let mut postings_serializer = PostingsSerializer::new(&mut postings_writer);
+    let mut positions_writer = positions_file.map(CountingWriter::new);
+    let mut postings_serializer = PostingsSerializer::new(
+        &mut postings_writer,
+        positions_writer.as_mut(),
+    );
+
+    for (key, posting_list) in &memory_index.terms {
+        let (field, _) = split_field_term_key(key)
+            .context("term key should contain field name")?;
+        let (field_ordinal, _) = memory_index
+            .stats
+            .get_field(field)
+            .context("field should exist in segment")?;
+        let field_doc_term_counts =
+            &memory_index.doc_term_counts[field_ordinal];
 
-    for (term, posting_list) in memory_index.terms {
         let start_byte = postings_serializer.get_written_bytes();
-        for posting in posting_list.values() {
-            postings_serializer.write_posting(posting)?;
+        let positions_start_byte =
+            postings_serializer.get_positions_written_bytes();
+        for entry in posting_list.values() {
+            let doc_terms_count = field_doc_term_counts
+                .get(entry.posting.docid as usize)
+                .context("doc with such ID should exist in segment")?;
+            postings_serializer.write_posting(
+                &entry.posting,
+                *doc_terms_count,
+                &entry.positions,
+            )?;
         }
         postings_serializer.flush()?;
         let end_byte = postings_serializer.get_written_bytes();
 
         let address = TermPostingListFileAddress {
             postings_count: posting_list.len(),
+            term_freq_total: get_term_freq_total(posting_list),
             start_byte,
             end_byte,
+            positions_start_byte,
         };
 
-        terms.insert(term, address);
+        terms_writer.insert(key, address)?;
     }
 
     drop(postings_serializer);
@@ -138,22 +202,22 @@ fn build_disk_index_segment(
     postings_file.flush()?;
     drop(postings_file);
 
-    terms
-        .serialize(&mut terms_file)
-        .context("terms should be serialized to file")?;
-
-    memory_index
-        .doc_term_counts
-        .serialize(&mut doc_term_counts_file)?;
-
-    doc_term_counts_file.flush()?;
-    drop(doc_term_counts_file);
+    if let Some(positions_writer) = positions_writer {
+        let mut positions_file = positions_writer.into_inner();
+        positions_file.flush()?;
+    }
 
-    memory_index.docs.serialize(&mut docs_file)?;
+    terms_writer
+        .finish()
+        .context("terms should be written to file")?;
 
-    docs_file.flush()?;
-    drop(docs_file);
+    write_doc_term_counts_file(&memory_index, &segment_dir)?;
+    write_docs_file(&memory_index, &segment_dir)?;
+    write_doc_values_file(&memory_index, &segment_dir)?;
 
+    SEGMENT_FORMAT_VERSION
+        .serialize(&mut stats_file)
+        .context("format version should be serialized to file")?;
     memory_index
         .stats
         .serialize(&mut stats_file)
@@ -163,17 +227,103 @@ fn build_disk_index_segment(
         mmap_file(segment_dir.join(IndexFile::DocLen.name()))?;
     let postings_file =
         mmap_file(segment_dir.join(IndexFile::Postings.name()))?;
+    let positions_file = if memory_index.has_positions {
+        Some(mmap_file(segment_dir.join(IndexFile::Positions.name()))?)
+    } else {
+        None
+    };
     let docs_file = mmap_file(segment_dir.join(IndexFile::Docs.name()))?;
+    let doc_values_file =
+        mmap_file(segment_dir.join(IndexFile::DocValues.name()))?;
+    let doc_values = open_doc_values(&doc_values_file, &memory_index.stats)?;
+    let terms = open_term_dictionary(&segment_dir)?;
 
     Ok(DiskIndexSegment {
+        id: memory_index.id,
         terms,
         postings_file,
+        positions_file,
         doc_term_counts_file,
         docs_file,
+        doc_values_file,
+        doc_values,
         stats: memory_index.stats,
     })
 }
 
+fn write_doc_term_counts_file(
+    memory_index: &MemoryIndex,
+    segment_dir: impl AsRef<Path>,
+) -> Result<()> {
+    let mut doc_term_counts_file =
+        create_writer(segment_dir, IndexFile::DocLen)?;
+
+    get_doc_major_term_counts(memory_index)
+        .serialize(&mut doc_term_counts_file)?;
+
+    doc_term_counts_file.flush()?;
+    Ok(())
+}
+
+/// Writes stored docs of the segment, followed by their stored fields if any.
+fn write_docs_file(
+    memory_index: &MemoryIndex,
+    segment_dir: impl AsRef<Path>,
+) -> Result<()> {
+    let docs_file = create_writer(segment_dir, IndexFile::Docs)?;
+    let mut docs_writer = CountingWriter::new(docs_file);
+
+    memory_index.docs.serialize(&mut docs_writer)?;
+    if let Some(stored_fields) = &memory_index.stored_fields {
+        serialize_stored_fields(stored_fields, &mut docs_writer)?;
+    }
+
+    docs_writer.into_inner().flush()?;
+    Ok(())
+}
+
+/// Writes descriptions of doc values columns of the segment, followed by codes
+/// of values of each column.
+fn write_doc_values_file(
+    memory_index: &MemoryIndex,
+    segment_dir: impl AsRef<Path>,
+) -> Result<()> {
+    let mut doc_values_file = create_writer(segment_dir, IndexFile::DocValues)?;
+
+    memory_index
+        .doc_values
+        .len()
+        .serialize(&mut doc_values_file)?;
+    for (info, _) in &memory_index.doc_values {
+        info.serialize(&mut doc_values_file)?;
+    }
+    for (_, codes) in &memory_index.doc_values {
+        doc_values_file.write_all(codes.as_bytes())?;
+    }
+
+    doc_values_file.flush()?;
+    Ok(())
+}
+
+/// Reads descriptions of doc values columns, and finds start bytes of codes
+/// of each column.
+fn open_doc_values(
+    doc_values_file: &Mmap,
+    stats: &IndexSegmentStats,
+) -> Result<Vec<(DocValuesColumnInfo, usize)>> {
+    let mut data = &doc_values_file[..];
+    let infos = Vec::<DocValuesColumnInfo>::deserialize_from_slice(&mut data)?;
+
+    let codes_start_byte = doc_values_file.len() - data.len();
+    let codes_len = size_of::<u64>() * stats.indexed_docs_count as usize;
+
+    Ok(infos
+        .into_iter()
+        .enumerate()
+        .map(|(idx, info)| (info, codes_start_byte + idx * codes_len))
+        .collect())
+}
+
 pub fn open_disk_index(options: &DiskIndexOptions) -> Result<DiskIndex> {
     let mut segments = Vec::new();
 
@@ -189,29 +339,64 @@ pub fn open_disk_index(options: &DiskIndexOptions) -> Result<DiskIndex> {
 }
 
 fn open_disk_index_segment(segment_dir: &Path) -> Result<DiskIndexSegment> {
-    let mut terms_file = open_reader(segment_dir, IndexFile::Terms)?;
     let mut stats_file = open_reader(segment_dir, IndexFile::Stats)?;
     let doc_term_counts_file_name = segment_dir.join(IndexFile::DocLen.name());
     let postings_file_name = segment_dir.join(IndexFile::Postings.name());
+    let positions_file_name = segment_dir.join(IndexFile::Positions.name());
     let docs_file_name = segment_dir.join(IndexFile::Docs.name());
 
-    let terms = HashMap::<String, TermPostingListFileAddress>::deserialize(
-        &mut terms_file,
-    )?;
+    let format_version = u32::deserialize(&mut stats_file)?;
+    ensure!(
+        format_version == SEGMENT_FORMAT_VERSION,
+        "segment {} has format version {format_version}, but only version \
+         {SEGMENT_FORMAT_VERSION} is supported, index should be rebuilt",
+        segment_dir.display()
+    );
+
+    let terms = open_term_dictionary(segment_dir)?;
     let stats = IndexSegmentStats::deserialize(&mut stats_file)?;
     let postings_file = mmap_file(postings_file_name)?;
+    let positions_file = if positions_file_name.exists() {
+        Some(mmap_file(positions_file_name)?)
+    } else {
+        None
+    };
     let doc_term_counts_file = mmap_file(doc_term_counts_file_name)?;
     let docs_file = mmap_file(docs_file_name)?;
+    let doc_values_file =
+        mmap_file(segment_dir.join(IndexFile::DocValues.name()))?;
+    let doc_values = open_doc_values(&doc_values_file, &stats)?;
+
+    let id = segment_dir
+        .file_name()
+        .and_then(|name| name.to_str()?.strip_prefix(SEGMENT_DIR_PREFIX))
+        .with_context(|| {
+            format!(
+                "segment dir {} should be named with segment id",
+                segment_dir.display()
+            )
+        })?;
 
     Ok(DiskIndexSegment {
+        id: id.to_string(),
         terms,
         postings_file,
+        positions_file,
         doc_term_counts_file,
         docs_file,
+        doc_values_file,
+        doc_values,
         stats,
     })
 }
 
+fn open_term_dictionary(segment_dir: &Path) -> Result<TermDictionary> {
+    TermDictionary::open(
+        mmap_file(segment_dir.join(IndexFile::Terms.name()))?,
+        mmap_file(segment_dir.join(IndexFile::TermAddresses.name()))?,
+    )
+}
+
 fn create_writer(
     dir: impl AsRef<Path>,
     file: IndexFile,
@@ -237,3 +422,16 @@ fn mmap_file(file_path: impl AsRef<Path>) -> Result<Mmap> {
     let mmap = unsafe { Mmap::map(&file)? };
     Ok(mmap)
 }
+
+/// Gets lengths of all fields of each document put together, since scoring a
+/// document may require lengths of several fields.
+fn get_doc_major_term_counts(memory_index: &MemoryIndex) -> Vec<u16> {
+    (0..memory_index.docs.len())
+        .flat_map(|docid| {
+            memory_index
+                .doc_term_counts
+                .iter()
+                .map(move |field_doc_term_counts| field_doc_term_counts[docid])
+        })
+        .collect()
+}
@@ -1,11 +1,14 @@
 use std::borrow::Cow;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use memmap2::Mmap;
 
 use super::model::TermPostingListFileAddress;
 use super::serializer::PostingsDeserializer;
-use crate::engines::nano::index::model::DocPosting;
+use crate::engines::nano::index::model::{
+    DocPosting, DocPostingsBlockMeta, DocPostingsIterator, SegmentDocId,
+    TermPosition,
+};
 
 pub struct DiskDocPostingsIterator<'a> {
     deserializer: PostingsDeserializer<'a>,
@@ -14,14 +17,23 @@ pub struct DiskDocPostingsIterator<'a> {
 impl<'a> DiskDocPostingsIterator<'a> {
     pub fn new(
         postings_file: &'a Mmap,
+        positions_file: Option<&'a Mmap>,
         address: &TermPostingListFileAddress,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let positions = positions_file
+            .map(|file| {
+                file.get(address.positions_start_byte..)
+                    .context("positions should be in bounds of positions file")
+            })
+            .transpose()?;
+
+        Ok(Self {
             deserializer: PostingsDeserializer::new(
                 &postings_file[address.start_byte..address.end_byte],
                 address.postings_count,
-            ),
-        }
+                positions,
+            )?,
+        })
     }
 }
 
@@ -32,3 +44,24 @@ impl<'a> Iterator for DiskDocPostingsIterator<'a> {
         self.deserializer.next().map(|res| res.map(Cow::Owned))
     }
 }
+
+impl<'a> DocPostingsIterator<'a> for DiskDocPostingsIterator<'a> {
+    fn skip_to(&mut self, target: SegmentDocId) -> Result<()> {
+        self.deserializer.skip_to(target)
+    }
+
+    fn peek_block_meta(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<Option<DocPostingsBlockMeta>> {
+        self.deserializer.peek_block_meta(target)
+    }
+
+    fn skip_term_freqs(&mut self) {
+        self.deserializer.skip_term_freqs();
+    }
+
+    fn read_positions(&mut self, output: &mut Vec<TermPosition>) -> Result<()> {
+        self.deserializer.read_positions(output)
+    }
+}
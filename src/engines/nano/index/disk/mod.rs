@@ -2,6 +2,7 @@ mod build;
 mod iterator;
 mod model;
 mod serializer;
+mod terms;
 
 pub use build::{build_disk_index, open_disk_index};
 pub use model::DiskIndexOptions;
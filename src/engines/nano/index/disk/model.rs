@@ -1,16 +1,23 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use memmap2::Mmap;
 
 use super::iterator::DiskDocPostingsIterator;
-use crate::engines::nano::index::disk::serializer::deserialize_vec_item;
+use super::terms::TermDictionary;
+use crate::engines::nano::index::automaton::LevenshteinAutomaton;
+use crate::engines::nano::index::disk::serializer::{
+    deserialize_stored_fields, deserialize_vec_item,
+};
+use crate::engines::nano::index::doc_values::{
+    DocValuesColumn, DocValuesColumnInfo,
+};
 use crate::engines::nano::index::model::{
     DocPostingsForTerm, Index, IndexSegment, IndexSegmentStats, SegmentDocId,
-    StoredDoc, Term,
+    StoredDoc, TermBounds, TermsIterator,
 };
+use crate::model::doc::DocField;
 
 #[derive(bon::Builder)]
 pub struct DiskIndexOptions {
@@ -23,10 +30,10 @@ pub struct DiskIndexOptions {
     /// Each thread builds its own segments.
     ///
     /// Input documents are distributed randomly across threads and segments,
-    /// introducing non-determinism to the indexing and search processes.
-    /// Since a segment's specific document set determines its internal
-    /// statistics - such as term IDF and average document length - this
-    /// distribution directly impacts final relevance scoring.
+    /// introducing non-determinism to the indexing process. It does not affect
+    /// relevance scoring though, since search aggregates statistics of all
+    /// segments - such as term IDF and average document length - into
+    /// index-wide ones.
     pub index_threads: Option<usize>,
 
     /// Maximum number of documents new segment is allowed to collect in memory
@@ -34,6 +41,17 @@ pub struct DiskIndexOptions {
     /// higher the memory consumption by indexer
     #[builder(default = 25_000)]
     pub max_segment_docs: usize,
+
+    /// Whether to record positions of terms in documents. Positions are
+    /// required for phrase queries, but make index noticeably bigger
+    #[builder(default = true)]
+    pub record_positions: bool,
+
+    /// Whether to store original fields of documents in the index, so search
+    /// results can show what was actually found. Fields are compressed, but
+    /// still make index bigger
+    #[builder(default = true)]
+    pub store_fields: bool,
 }
 
 pub struct DiskIndex {
@@ -41,24 +59,43 @@ pub struct DiskIndex {
 }
 
 pub struct DiskIndexSegment {
-    pub terms: HashMap<Term, TermPostingListFileAddress>,
+    /// Unique ID of the segment, which is a part of segment dir name
+    pub id: String,
+    pub terms: TermDictionary,
     // use file mmap instead of open/seek/read to avoid "Too many opened files"
     // OS error on big indices with lots of segments
     pub postings_file: Mmap,
+    /// `None` if positions were not recorded for the segment
+    pub positions_file: Option<Mmap>,
     pub doc_term_counts_file: Mmap,
     pub docs_file: Mmap,
+    pub doc_values_file: Mmap,
+    /// Columns of doc values along with start bytes of their codes in
+    /// `doc_values_file`
+    pub doc_values: Vec<(DocValuesColumnInfo, usize)>,
     pub stats: IndexSegmentStats,
 }
 
 #[derive(Copy, Clone)]
 pub enum IndexFile {
-    /// Maps terms to offsets of corresponding posting lists in Postings file
+    /// Sorted term dictionary in form of FST, that maps terms to their
+    /// ordinal numbers in `TermAddresses` file
     Terms,
 
+    /// Addresses of posting lists in Postings file for terms from Terms file,
+    /// along with total frequencies of the terms
+    TermAddresses,
+
     /// Posting lists for terms from Terms file
     Postings,
 
-    /// Count of terms in each document (a.k.a. document lengths)
+    /// Positions of terms in documents for postings from Postings file.
+    /// Optional, exists only if positions were recorded
+    Positions,
+
+    /// Count of terms in each field of each document (a.k.a. document
+    /// lengths). Lengths of all fields of a document go together, in order of
+    /// field ordinals
     ///
     /// Note:
     /// For example, Tantivy/Lucene store document lengths in '.fieldnorm' file.
@@ -67,9 +104,15 @@ pub enum IndexFile {
     /// See <https://github.com/quickwit-oss/tantivy/blob/5a2fe42c248a45635cbf4a37f1c85136ffe7bb16/src/fieldnorm/mod.rs>
     DocLen,
 
-    /// Stored documents
+    /// Stored documents, followed by their original fields compressed in
+    /// blocks of several documents. Fields are optional, exist only if they
+    /// were stored
     Docs,
 
+    /// Columns of typed values of documents: descriptions of all columns,
+    /// followed by codes of values of each column, in order of descriptions
+    DocValues,
+
     /// Statistics gathered while building index, which is used later by search
     /// routine (e.g. for candidates scoring) and debugging
     Stats,
@@ -79,9 +122,12 @@ impl IndexFile {
     pub const fn name(self) -> &'static str {
         match self {
             Self::Terms => "terms",
+            Self::TermAddresses => "termaddrs",
             Self::Postings => "postings",
+            Self::Positions => "positions",
             Self::DocLen => "doclen",
             Self::Docs => "docs",
+            Self::DocValues => "docvalues",
             Self::Stats => "stats",
         }
     }
@@ -90,8 +136,12 @@ impl IndexFile {
 #[derive(Clone)]
 pub struct TermPostingListFileAddress {
     pub postings_count: usize,
+    /// Total number of occurrences of the term in all documents of the segment
+    pub term_freq_total: u64,
     pub start_byte: usize,
     pub end_byte: usize,
+    /// Start of posting list positions in Positions file
+    pub positions_start_byte: usize,
 }
 
 impl Index for DiskIndex {
@@ -107,27 +157,55 @@ impl Index for DiskIndex {
 impl IndexSegment for DiskIndexSegment {
     fn get_doc_postings_for_term<'a>(
         &'a self,
+        field: &str,
         term: &str,
     ) -> Result<Option<DocPostingsForTerm<'a>>> {
-        let term_posting_list_addr = self.terms.get(term);
-
-        term_posting_list_addr.map_or_else(
-            || Ok(None),
-            |addr| {
-                Ok(Some(DocPostingsForTerm {
-                    count: addr.postings_count,
-                    // TODO: avoid heap-allocation on hot path - try enum
-                    iterator: Box::new(DiskDocPostingsIterator::new(
-                        &self.postings_file,
-                        addr,
-                    )),
-                }))
-            },
-        )
+        let Some(addr) = self.terms.get(field, term)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(DocPostingsForTerm {
+            count: addr.postings_count,
+            term_freq_total: addr.term_freq_total,
+            // TODO: avoid heap-allocation on hot path - try enum
+            iterator: Box::new(DiskDocPostingsIterator::new(
+                &self.postings_file,
+                self.positions_file.as_ref(),
+                &addr,
+            )?),
+        }))
     }
 
-    fn get_doc_terms_count(&self, docid: SegmentDocId) -> Result<Cow<'_, u16>> {
-        deserialize_vec_item::<u16>(&self.doc_term_counts_file, docid as usize)
+    fn get_terms_in_range(
+        &self,
+        field: &str,
+        bounds: TermBounds,
+    ) -> TermsIterator<'_> {
+        self.terms.get_terms_in_range(field, bounds)
+    }
+
+    fn get_terms_matching<'a>(
+        &'a self,
+        field: &str,
+        automaton: &'a LevenshteinAutomaton,
+    ) -> TermsIterator<'a> {
+        self.terms.get_terms_matching(field, automaton)
+    }
+
+    fn get_doc_terms_count(
+        &self,
+        field: &str,
+        docid: SegmentDocId,
+    ) -> Result<Cow<'_, u16>> {
+        let (field_ordinal, _) = self
+            .stats
+            .get_field(field)
+            .context("field should exist in segment")?;
+
+        // lengths of all fields of a document go together
+        let idx = docid as usize * self.stats.fields.len() + field_ordinal;
+
+        deserialize_vec_item::<u16>(&self.doc_term_counts_file, idx)
     }
 
     fn get_stored_doc(
@@ -137,7 +215,38 @@ impl IndexSegment for DiskIndexSegment {
         deserialize_vec_item::<StoredDoc>(&self.docs_file, docid as usize)
     }
 
+    fn get_stored_fields(
+        &self,
+        docid: SegmentDocId,
+    ) -> Result<Option<Vec<DocField>>> {
+        deserialize_stored_fields(&self.docs_file, docid)
+    }
+
+    fn get_doc_values_columns(&self) -> Result<Vec<DocValuesColumn<'_>>> {
+        let codes_len =
+            size_of::<u64>() * self.stats.indexed_docs_count as usize;
+
+        self.doc_values
+            .iter()
+            .map(|(info, start_byte)| {
+                let codes = self
+                    .doc_values_file
+                    .get(*start_byte..start_byte + codes_len)
+                    .context("doc values should be in file bounds")?;
+                DocValuesColumn::from_bytes(info, codes)
+            })
+            .collect()
+    }
+
     fn get_stats(&self) -> &IndexSegmentStats {
         &self.stats
     }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_positions(&self) -> bool {
+        self.positions_file.is_some()
+    }
 }
@@ -5,9 +5,13 @@ use std::io::{Read, Write};
 use anyhow::{Context, Result, ensure};
 
 use crate::engines::nano::index::disk::model::TermPostingListFileAddress;
+use crate::engines::nano::index::doc_values::{
+    DocValueType, DocValuesColumnInfo,
+};
 use crate::engines::nano::index::model::{
-    DocPosting, IndexSegmentStats, StoredDoc,
+    DocPosting, DocPostingsBlockMeta, FieldStats, IndexSegmentStats, StoredDoc,
 };
+use crate::model::doc::DocField;
 
 pub trait BinarySerializable: Sized {
     fn serialize(&self, write: &mut dyn Write) -> Result<()>;
@@ -173,21 +177,52 @@ impl BinarySerializable for IndexSegmentStats {
     fn serialize(&self, write: &mut dyn Write) -> Result<()> {
         self.indexed_docs_count.serialize(write)?;
         self.max_posting_list_size.serialize(write)?;
-        self.terms_count_per_doc_avg.serialize(write)?;
+        self.fields.serialize(write)?;
         Ok(())
     }
     fn deserialize(read: &mut dyn Read) -> Result<Self> {
         Ok(Self {
             indexed_docs_count: u64::deserialize(read)?,
             max_posting_list_size: u64::deserialize(read)?,
-            terms_count_per_doc_avg: f64::deserialize(read)?,
+            fields: Vec::<FieldStats>::deserialize(read)?,
         })
     }
     fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
         Ok(Self {
             indexed_docs_count: u64::deserialize_from_slice(data)?,
             max_posting_list_size: u64::deserialize_from_slice(data)?,
+            fields: Vec::<FieldStats>::deserialize_from_slice(data)?,
+        })
+    }
+}
+
+impl BinarySerializable for FieldStats {
+    fn serialize(&self, write: &mut dyn Write) -> Result<()> {
+        self.name.serialize(write)?;
+        self.terms_count_total.serialize(write)?;
+        self.terms_count_per_doc_avg.serialize(write)?;
+        self.unique_terms_count.serialize(write)?;
+        self.doc_terms_count_histogram.serialize(write)?;
+        Ok(())
+    }
+    fn deserialize(read: &mut dyn Read) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize(read)?,
+            terms_count_total: u64::deserialize(read)?,
+            terms_count_per_doc_avg: f64::deserialize(read)?,
+            unique_terms_count: u64::deserialize(read)?,
+            doc_terms_count_histogram: Vec::<u64>::deserialize(read)?,
+        })
+    }
+    fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize_from_slice(data)?,
+            terms_count_total: u64::deserialize_from_slice(data)?,
             terms_count_per_doc_avg: f64::deserialize_from_slice(data)?,
+            unique_terms_count: u64::deserialize_from_slice(data)?,
+            doc_terms_count_histogram: Vec::<u64>::deserialize_from_slice(
+                data,
+            )?,
         })
     }
 }
@@ -212,25 +247,54 @@ impl BinarySerializable for DocPosting {
     }
 }
 
+impl BinarySerializable for DocPostingsBlockMeta {
+    fn serialize(&self, write: &mut dyn Write) -> Result<()> {
+        self.last_docid.serialize(write)?;
+        self.max_term_freq.serialize(write)?;
+        self.min_doc_terms_count.serialize(write)?;
+        Ok(())
+    }
+    fn deserialize(read: &mut dyn Read) -> Result<Self> {
+        Ok(Self {
+            last_docid: u32::deserialize(read)?,
+            max_term_freq: u32::deserialize(read)?,
+            min_doc_terms_count: u16::deserialize(read)?,
+        })
+    }
+    fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            last_docid: u32::deserialize_from_slice(data)?,
+            max_term_freq: u32::deserialize_from_slice(data)?,
+            min_doc_terms_count: u16::deserialize_from_slice(data)?,
+        })
+    }
+}
+
 impl BinarySerializable for TermPostingListFileAddress {
     fn serialize(&self, write: &mut dyn Write) -> Result<()> {
         self.postings_count.serialize(write)?;
+        self.term_freq_total.serialize(write)?;
         self.start_byte.serialize(write)?;
         self.end_byte.serialize(write)?;
+        self.positions_start_byte.serialize(write)?;
         Ok(())
     }
     fn deserialize(read: &mut dyn Read) -> Result<Self> {
         Ok(Self {
             postings_count: usize::deserialize(read)?,
+            term_freq_total: u64::deserialize(read)?,
             start_byte: usize::deserialize(read)?,
             end_byte: usize::deserialize(read)?,
+            positions_start_byte: usize::deserialize(read)?,
         })
     }
     fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
         Ok(Self {
             postings_count: usize::deserialize_from_slice(data)?,
+            term_freq_total: u64::deserialize_from_slice(data)?,
             start_byte: usize::deserialize_from_slice(data)?,
             end_byte: usize::deserialize_from_slice(data)?,
+            positions_start_byte: usize::deserialize_from_slice(data)?,
         })
     }
 }
@@ -251,3 +315,48 @@ impl BinarySerializable for StoredDoc {
         })
     }
 }
+
+impl BinarySerializable for DocField {
+    fn serialize(&self, write: &mut dyn Write) -> Result<()> {
+        self.name.serialize(write)?;
+        self.text.serialize(write)?;
+        Ok(())
+    }
+    fn deserialize(read: &mut dyn Read) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize(read)?,
+            text: String::deserialize(read)?,
+        })
+    }
+    fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize_from_slice(data)?,
+            text: String::deserialize_from_slice(data)?,
+        })
+    }
+}
+
+impl BinarySerializable for DocValuesColumnInfo {
+    fn serialize(&self, write: &mut dyn Write) -> Result<()> {
+        self.name.serialize(write)?;
+        self.value_type.to_u8().serialize(write)?;
+        self.keywords.serialize(write)?;
+        Ok(())
+    }
+    fn deserialize(read: &mut dyn Read) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize(read)?,
+            value_type: DocValueType::from_u8(u8::deserialize(read)?)?,
+            keywords: Vec::<String>::deserialize(read)?,
+        })
+    }
+    fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            name: String::deserialize_from_slice(data)?,
+            value_type: DocValueType::from_u8(u8::deserialize_from_slice(
+                data,
+            )?)?,
+            keywords: Vec::<String>::deserialize_from_slice(data)?,
+        })
+    }
+}
@@ -0,0 +1,115 @@
+use std::io::Write;
+
+use anyhow::{Context, Result, ensure};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use super::binary::{BinarySerializable, deserialize_vec_item};
+use crate::engines::nano::index::model::{SegmentDocId, StoredDoc};
+use crate::model::doc::DocField;
+use crate::utils::CountingWriter;
+
+/// Number of documents compressed together into one block of stored fields.
+///
+/// Texts of neighbouring documents share a lot of words, so bigger blocks
+/// compress better, but reading fields of a single document requires
+/// decompressing its whole block.
+const DOCS_PER_BLOCK: usize = 16;
+
+/// Writes stored fields of the documents after the vector of stored docs in
+/// Docs file.
+///
+/// Fields of each [`DOCS_PER_BLOCK`] documents are serialized and compressed
+/// together into a block. Blocks are followed by the vector of their start
+/// bytes (plus end byte of the last block), number of docs per block, and the
+/// start byte of that vector in the end of file, so the block of a document
+/// can be found without reading the whole file.
+///
+/// Note: Tantivy/Lucene compress stored fields the same way, see
+/// <https://github.com/quickwit-oss/tantivy/blob/5a2fe42c248a45635cbf4a37f1c85136ffe7bb16/src/store/mod.rs>
+pub fn serialize_stored_fields<W: Write>(
+    docs_fields: &[Vec<DocField>],
+    write: &mut CountingWriter<W>,
+) -> Result<()> {
+    let mut block_start_bytes = Vec::new();
+
+    for block in docs_fields.chunks(DOCS_PER_BLOCK) {
+        block_start_bytes.push(write.get_written_bytes());
+
+        let mut encoder = ZlibEncoder::new(&mut *write, Compression::default());
+        block.len().serialize(&mut encoder)?;
+        for fields in block {
+            fields.serialize(&mut encoder)?;
+        }
+        encoder.finish().context("block should be compressed")?;
+    }
+
+    let block_start_bytes_start = write.get_written_bytes();
+    block_start_bytes.push(block_start_bytes_start);
+
+    block_start_bytes.serialize(write)?;
+    DOCS_PER_BLOCK.serialize(write)?;
+    block_start_bytes_start.serialize(write)?;
+
+    Ok(())
+}
+
+/// Reads stored fields of the document from Docs file, written with
+/// [`serialize_stored_fields`].
+///
+/// Returns `None` if the file has no stored fields.
+pub fn deserialize_stored_fields(
+    data: &[u8],
+    docid: SegmentDocId,
+) -> Result<Option<Vec<DocField>>> {
+    let docs_count = usize::deserialize_from_slice(&mut &data[..])?;
+    let stored_fields_start =
+        size_of::<usize>() + size_of::<StoredDoc>() * docs_count;
+
+    if data.len() <= stored_fields_start {
+        return Ok(None);
+    }
+
+    let docid = docid as usize;
+    ensure!(
+        docid < docs_count,
+        "doc with such ID should exist in segment"
+    );
+
+    let footer_start = data
+        .len()
+        .checked_sub(2 * size_of::<usize>())
+        .context("stored fields footer should be in file bounds")?;
+    let mut footer = &data[footer_start..];
+    let docs_per_block = usize::deserialize_from_slice(&mut footer)?;
+    let block_start_bytes_start = usize::deserialize_from_slice(&mut footer)?;
+
+    let block_start_bytes = data
+        .get(block_start_bytes_start..)
+        .context("block start bytes should be in file bounds")?;
+    let block_idx = docid / docs_per_block;
+    let start_byte =
+        *deserialize_vec_item::<usize>(block_start_bytes, block_idx)?;
+    let end_byte =
+        *deserialize_vec_item::<usize>(block_start_bytes, block_idx + 1)?;
+
+    let block = data
+        .get(start_byte..end_byte)
+        .context("block should be in file bounds")?;
+    let mut decoder = ZlibDecoder::new(block);
+
+    let block_docs_count = usize::deserialize(&mut decoder)?;
+    let idx_in_block = docid % docs_per_block;
+    ensure!(
+        idx_in_block < block_docs_count,
+        "doc should exist in stored fields block"
+    );
+
+    // fields of preceding docs in the block have to be decompressed anyway
+    for _ in 0..idx_in_block {
+        Vec::<DocField>::deserialize(&mut decoder)?;
+    }
+
+    Ok(Some(Vec::<DocField>::deserialize(&mut decoder)?))
+}
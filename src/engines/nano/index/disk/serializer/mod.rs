@@ -1,6 +1,8 @@
 mod binary;
 mod compression;
+mod doc_store;
 mod postings;
 
 pub use binary::{BinarySerializable, deserialize_vec_item};
+pub use doc_store::{deserialize_stored_fields, serialize_stored_fields};
 pub use postings::{PostingsDeserializer, PostingsSerializer};
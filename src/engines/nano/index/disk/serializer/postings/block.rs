@@ -5,9 +5,11 @@ use anyhow::Result;
 use crate::engines::nano::index::disk::serializer::compression::{
     decode_sorted, decode_unsorted, encode_sorted, encode_unsorted,
 };
-use crate::engines::nano::index::model::{DocPosting, SegmentDocId};
+use crate::engines::nano::index::model::{
+    DocPosting, DocPostingsBlockMeta, SegmentDocId,
+};
 
-const BLOCK_CAPACITY: usize = 128;
+pub const BLOCK_CAPACITY: usize = 128;
 
 /// Group of [`DocPosting`]-s serialized together as single block.
 ///
@@ -20,6 +22,14 @@ pub struct DocPostingsBlock {
     docids: [SegmentDocId; BLOCK_CAPACITY],
     term_freqs: [u32; BLOCK_CAPACITY],
     len: usize,
+
+    /// Largest term frequency among postings of the block.
+    /// Tracked only while serializing
+    max_term_freq: u32,
+
+    /// Smallest number of terms among documents of the block.
+    /// Tracked only while serializing
+    min_doc_terms_count: u16,
 }
 
 impl DocPostingsBlock {
@@ -28,15 +38,11 @@ impl DocPostingsBlock {
             docids: [0; BLOCK_CAPACITY],
             term_freqs: [0; BLOCK_CAPACITY],
             len: 0,
+            max_term_freq: 0,
+            min_doc_terms_count: u16::MAX,
         }
     }
 
-    #[inline]
-    #[allow(clippy::unused_self)]
-    pub const fn capacity(&self) -> usize {
-        BLOCK_CAPACITY
-    }
-
     pub const fn len(&self) -> usize {
         self.len
     }
@@ -51,9 +57,11 @@ impl DocPostingsBlock {
 
     pub const fn clear(&mut self) {
         self.len = 0;
+        self.max_term_freq = 0;
+        self.min_doc_terms_count = u16::MAX;
     }
 
-    pub fn add_posting(&mut self, posting: &DocPosting) {
+    pub fn add_posting(&mut self, posting: &DocPosting, doc_terms_count: u16) {
         debug_assert!(
             !self.is_full(),
             "postings should not exceed block capacity"
@@ -61,6 +69,9 @@ impl DocPostingsBlock {
 
         self.docids[self.len] = posting.docid;
         self.term_freqs[self.len] = posting.term_freq;
+        self.max_term_freq = self.max_term_freq.max(posting.term_freq);
+        self.min_doc_terms_count =
+            self.min_doc_terms_count.min(doc_terms_count);
 
         self.len += 1;
     }
@@ -77,6 +88,21 @@ impl DocPostingsBlock {
         }
     }
 
+    pub fn get_last_docid(&self) -> SegmentDocId {
+        debug_assert!(!self.is_empty(), "block should not be empty");
+        self.docids[self.len - 1]
+    }
+
+    pub fn get_meta(&self) -> DocPostingsBlockMeta {
+        debug_assert!(!self.is_empty(), "block should not be empty");
+
+        DocPostingsBlockMeta {
+            last_docid: self.get_last_docid(),
+            max_term_freq: self.max_term_freq,
+            min_doc_terms_count: self.min_doc_terms_count,
+        }
+    }
+
     pub fn serialize(&self, output: &mut dyn Write) -> Result<()> {
         encode_sorted(&self.docids[..self.len], output)?;
         encode_unsorted(&self.term_freqs[..self.len], output)?;
@@ -84,10 +110,13 @@ impl DocPostingsBlock {
         Ok(())
     }
 
+    /// Decodes block of `len` postings. If `with_term_freqs` is not set, only
+    /// docids are decoded, and postings get zero term frequencies.
     pub fn deserialize_from_slice(
         &mut self,
         input: &mut &[u8],
         len: usize,
+        with_term_freqs: bool,
     ) -> Result<()> {
         debug_assert!(
             len > 0 && len <= BLOCK_CAPACITY,
@@ -97,7 +126,12 @@ impl DocPostingsBlock {
         self.len = len;
 
         decode_sorted(input, &mut self.docids[..self.len])?;
-        decode_unsorted(input, &mut self.term_freqs[..self.len])?;
+
+        if with_term_freqs {
+            decode_unsorted(input, &mut self.term_freqs[..self.len])?;
+        } else {
+            self.term_freqs[..self.len].fill(0);
+        }
 
         Ok(())
     }
@@ -1,5 +1,6 @@
 mod block;
 mod serializer;
+mod skip;
 
 pub use serializer::{PostingsDeserializer, PostingsSerializer};
 
@@ -1,52 +1,131 @@
 use std::io::Write;
 
-use anyhow::Result;
+use anyhow::{Context, Result, ensure};
 
-use super::block::DocPostingsBlock;
-use crate::engines::nano::index::model::DocPosting;
+use super::block::{BLOCK_CAPACITY, DocPostingsBlock};
+use super::skip::{SKIP_ENTRY_SIZE, SkipEntry};
+use crate::engines::nano::index::disk::serializer::BinarySerializable;
+use crate::engines::nano::index::disk::serializer::compression::{
+    decode_unsorted, encode_unsorted,
+};
+use crate::engines::nano::index::model::{
+    DocPosting, DocPostingsBlockMeta, SegmentDocId, TermPosition,
+};
 use crate::utils::CountingWriter;
 
 /// Serializer for [`DocPosting`]-lists.
 ///
 /// Data Layout:
 ///
-/// [Block 1 [docids][freqs]] [Block 2 [docids][freqs]] ...
+/// [Block 1 [docids][freqs]] [Block 2 [docids][freqs]] ... [skip data]
 ///
-/// - `[docids]` - Delta-encoded and bit-packed series of sorted document IDs.
-/// - `[freqs]`  - Bit-packed series of unsorted term frequencies.
+/// - `[docids]`    - Delta-encoded and bit-packed series of sorted document
+///   IDs.
+/// - `[freqs]`     - Bit-packed series of unsorted term frequencies.
+/// - `[skip data]` - Series of fixed size [`SkipEntry`]-s, one for each block.
 ///
 /// Document IDs and frequencies are interleaved block-by-block using a
 /// Structure of Arrays (SOA) layout. This maximizes spatial locality because
-/// posting lists are currently always read sequentially from start to finish,
-/// and IDs are always processed alongside with corresponding frequencies.
+/// posting lists are mostly read sequentially from start to finish, and IDs are
+/// always processed alongside with corresponding frequencies.
 ///
-/// Future Evolution:
+/// Skip data allows to jump straight to the block containing target docid with
+/// binary search, without touching blocks in front of it. E.g. when rare term
+/// is intersected with very common one, or when blocks are skipped because
+/// their documents cannot score high enough to get into search results.
 ///
-/// When implementing complex search queries that require intersecting posting
-/// lists (i.e. `AND` queries) this interleaved layout remains optimal. But it
-/// can be improved by adding skip lists, to support fast jumping and avoid
-/// reading unnecessary blocks during intersections.
+/// Skip data goes after the blocks, since it's only known after all blocks are
+/// written. Its size is derived from number of postings, so it's located from
+/// the end of posting list and doesn't need separate address in terms file.
 ///
-/// Lucene and Tantivy use same IDs+freqs interleaved block layout + skip lists.
+/// Lucene and Tantivy use same IDs+freqs interleaved block layout + skip data.
+/// Lucene uses multi-level skip lists, which are read sequentially from file.
+/// Here single level is enough, since mmap-ed skip data can be binary searched.
+///
+/// Positions (if recorded) are written to separate output, since they are
+/// much bigger than IDs and freqs, and only needed for some queries (e.g.
+/// phrases). Positions Data Layout:
+///
+/// [Block 1 [positions]] [Block 2 [positions]] ...
+///
+/// - `[positions]` - Bit-packed series of positions of all postings of the
+///   block. Positions of each posting are delta-encoded, and their count equals
+///   to term frequency of the posting.
+///
+/// Positions of a block are located with offset stored in its skip entry, same
+/// as Lucene stores pointers into `.pos` file in its skip data.
 pub struct PostingsSerializer<'a, W: Write> {
     buffer: DocPostingsBlock,
     output: &'a mut CountingWriter<W>,
+
+    /// Skip entries of blocks written for current posting list
+    skip_entries: Vec<SkipEntry>,
+
+    /// Offset of current posting list start in output
+    list_start_byte: usize,
+
+    /// Output for positions, or `None` if positions are not recorded
+    positions_output: Option<&'a mut CountingWriter<W>>,
+
+    /// Delta-encoded positions of postings in the buffer
+    positions_buffer: Vec<TermPosition>,
+
+    /// Offset of current posting list start in positions output
+    positions_start_byte: usize,
 }
 
 impl<'a, W: Write> PostingsSerializer<'a, W> {
-    pub const fn new(output: &'a mut CountingWriter<W>) -> Self {
+    pub fn new(
+        output: &'a mut CountingWriter<W>,
+        positions_output: Option<&'a mut CountingWriter<W>>,
+    ) -> Self {
+        let list_start_byte = output.get_written_bytes();
+        let positions_start_byte = positions_output
+            .as_ref()
+            .map_or(0, |output| output.get_written_bytes());
+
         Self {
             buffer: DocPostingsBlock::new(),
             output,
+            skip_entries: Vec::new(),
+            list_start_byte,
+            positions_output,
+            positions_buffer: Vec::new(),
+            positions_start_byte,
         }
     }
 
-    pub fn write_posting(&mut self, posting: &DocPosting) -> Result<()> {
-        self.buffer.add_posting(posting);
+    /// Writes posting with sorted `positions` of the term in the document.
+    ///
+    /// Positions are ignored if serializer was created without positions
+    /// output.
+    pub fn write_posting(
+        &mut self,
+        posting: &DocPosting,
+        doc_terms_count: u16,
+        positions: &[TermPosition],
+    ) -> Result<()> {
+        if self.positions_output.is_some() {
+            ensure!(
+                positions.len() == posting.term_freq as usize,
+                "positions count should be equal to term frequency"
+            );
+
+            let mut prev_position = 0;
+            for &position in positions {
+                self.positions_buffer.push(
+                    position
+                        .checked_sub(prev_position)
+                        .context("positions should be sorted")?,
+                );
+                prev_position = position;
+            }
+        }
+
+        self.buffer.add_posting(posting, doc_terms_count);
 
         if self.buffer.is_full() {
-            self.buffer.serialize(self.output)?;
-            self.buffer.clear();
+            self.write_block()?;
         }
 
         Ok(())
@@ -56,16 +135,58 @@ impl<'a, W: Write> PostingsSerializer<'a, W> {
         self.output.get_written_bytes()
     }
 
-    /// Flushes internal buffer to output.
+    pub fn get_positions_written_bytes(&self) -> usize {
+        self.positions_output
+            .as_ref()
+            .map_or(0, |output| output.get_written_bytes())
+    }
+
+    fn write_block(&mut self) -> Result<()> {
+        self.buffer.serialize(self.output)?;
+
+        let end_offset = self.output.get_written_bytes() - self.list_start_byte;
+
+        let positions_end_offset =
+            if let Some(positions_output) = &mut self.positions_output {
+                encode_unsorted(&self.positions_buffer, *positions_output)?;
+                self.positions_buffer.clear();
+                positions_output.get_written_bytes() - self.positions_start_byte
+            } else {
+                0
+            };
+
+        self.skip_entries.push(SkipEntry {
+            meta: self.buffer.get_meta(),
+            end_offset: u32::try_from(end_offset)
+                .context("posting list should not exceed 4GB")?,
+            positions_end_offset: u32::try_from(positions_end_offset)
+                .context("positions of posting list should not exceed 4GB")?,
+        });
+
+        self.buffer.clear();
+
+        Ok(())
+    }
+
+    /// Flushes internal buffer and skip data of current posting list to
+    /// output. Postings written after that go to the next posting list.
     ///
     /// It is required to flush changes explicitly before [`drop()`], otherwise
     /// serializer will panic. This allows to propogate errors if something goes
     /// wrong while flushing and not to forget some postings inside buffer.
     pub fn flush(&mut self) -> Result<()> {
         if !self.buffer.is_empty() {
-            self.buffer.serialize(self.output)?;
-            self.buffer.clear();
+            self.write_block()?;
+        }
+
+        for entry in &self.skip_entries {
+            entry.serialize(self.output)?;
         }
+
+        self.skip_entries.clear();
+        self.list_start_byte = self.output.get_written_bytes();
+        self.positions_start_byte = self.get_positions_written_bytes();
+
         Ok(())
     }
 }
@@ -73,7 +194,7 @@ impl<'a, W: Write> PostingsSerializer<'a, W> {
 impl<W: Write> Drop for PostingsSerializer<'_, W> {
     fn drop(&mut self) {
         assert!(
-            self.buffer.is_empty(),
+            self.buffer.is_empty() && self.skip_entries.is_empty(),
             "serializer should be explicitly flushed before drop"
         );
     }
@@ -82,32 +203,253 @@ impl<W: Write> Drop for PostingsSerializer<'_, W> {
 pub struct PostingsDeserializer<'a> {
     buffer: DocPostingsBlock,
     buffer_pos: usize,
-    input: &'a [u8],
-    input_left: usize,
+
+    /// Metadata of the block in buffer
+    buffer_meta: Option<DocPostingsBlockMeta>,
+
+    /// Serialized blocks
+    blocks: &'a [u8],
+
+    /// Serialized skip entries, one for each block
+    skip_entries: &'a [u8],
+
+    postings_count: usize,
+
+    /// Index of the block in buffer
+    buffer_block_idx: usize,
+
+    /// Index of the block to be decoded next
+    next_block_idx: usize,
+
+    /// Serialized positions starting from the first block, or `None` if
+    /// positions are not recorded
+    positions: Option<&'a [u8]>,
+
+    /// Delta-encoded positions of the block in buffer
+    positions_buffer: Vec<TermPosition>,
+
+    /// Whether positions of the block in buffer are decoded already
+    positions_decoded: bool,
+
+    /// Whether term frequencies of next blocks should be decoded
+    with_term_freqs: bool,
 }
 
 impl<'a> PostingsDeserializer<'a> {
-    pub const fn new(input: &'a [u8], postings_count: usize) -> Self {
-        Self {
+    pub fn new(
+        input: &'a [u8],
+        postings_count: usize,
+        positions: Option<&'a [u8]>,
+    ) -> Result<Self> {
+        let skip_data_size =
+            postings_count.div_ceil(BLOCK_CAPACITY) * SKIP_ENTRY_SIZE;
+
+        ensure!(
+            input.len() >= skip_data_size,
+            "posting list should contain skip data"
+        );
+
+        let (blocks, skip_entries) =
+            input.split_at(input.len() - skip_data_size);
+
+        Ok(Self {
             buffer: DocPostingsBlock::new(),
             buffer_pos: 0,
-            input,
-            input_left: postings_count,
-        }
+            buffer_meta: None,
+            blocks,
+            skip_entries,
+            postings_count,
+            buffer_block_idx: 0,
+            next_block_idx: 0,
+            positions,
+            positions_buffer: Vec::new(),
+            positions_decoded: false,
+            with_term_freqs: true,
+        })
+    }
+
+    const fn get_blocks_count(&self) -> usize {
+        self.skip_entries.len() / SKIP_ENTRY_SIZE
+    }
+
+    fn get_skip_entry(&self, block_idx: usize) -> Result<SkipEntry> {
+        SkipEntry::deserialize_from_slice(
+            &mut &self.skip_entries[block_idx * SKIP_ENTRY_SIZE..],
+        )
     }
 
     fn read_next_block(&mut self) -> Result<()> {
         self.buffer_pos = 0;
 
-        if self.input_left == 0 {
-            debug_assert!(self.input.is_empty());
+        let block_idx = self.next_block_idx;
+
+        if block_idx >= self.get_blocks_count() {
             self.buffer.clear();
+            self.buffer_meta = None;
+            return Ok(());
+        }
+
+        let start_offset = if block_idx == 0 {
+            0
         } else {
-            let len = self.buffer.capacity().min(self.input_left);
-            self.input_left -= len;
-            self.buffer.deserialize_from_slice(&mut self.input, len)?;
+            self.get_skip_entry(block_idx - 1)?.end_offset as usize
+        };
+        let entry = self.get_skip_entry(block_idx)?;
+
+        let mut input = self
+            .blocks
+            .get(start_offset..entry.end_offset as usize)
+            .context("block should be in bounds of posting list")?;
+
+        let len = BLOCK_CAPACITY
+            .min(self.postings_count - block_idx * BLOCK_CAPACITY);
+
+        self.buffer.deserialize_from_slice(
+            &mut input,
+            len,
+            self.with_term_freqs,
+        )?;
+        self.buffer_meta = Some(entry.meta);
+        self.buffer_block_idx = block_idx;
+        self.next_block_idx += 1;
+        self.positions_decoded = false;
+
+        Ok(())
+    }
+
+    /// Checks if current block has postings, that were not read yet, and
+    /// which may be equal to or greater than `target`.
+    fn is_target_in_current_block(&self, target: SegmentDocId) -> bool {
+        self.buffer_pos < self.buffer.len()
+            && self.buffer.get_last_docid() >= target
+    }
+
+    /// Finds index of the first not yet decoded block, that may contain
+    /// `target`, by binary search through skip entries.
+    ///
+    /// Returns blocks count if there is no such block.
+    fn find_block(&self, target: SegmentDocId) -> Result<usize> {
+        let mut low = self.next_block_idx;
+        let mut high = self.get_blocks_count();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.get_skip_entry(mid)?.meta.last_docid < target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
         }
 
+        Ok(low)
+    }
+
+    /// Skips whole blocks with docids lower than `target` without decoding
+    /// them.
+    pub fn skip_to(&mut self, target: SegmentDocId) -> Result<()> {
+        if self.is_target_in_current_block(target) {
+            return Ok(());
+        }
+
+        // drop the rest of current block
+        self.buffer_pos = self.buffer.len();
+
+        self.next_block_idx = self.find_block(target)?;
+
+        Ok(())
+    }
+
+    /// Gets metadata of the block, that may contain `target` docid, without
+    /// moving the deserializer.
+    pub fn peek_block_meta(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<Option<DocPostingsBlockMeta>> {
+        // posting returned last counts too, even if it ends current block
+        if let Some(meta) = self.buffer_meta
+            && meta.last_docid >= target
+        {
+            return Ok(Some(meta));
+        }
+
+        let block_idx = self.find_block(target)?;
+
+        if block_idx < self.get_blocks_count() {
+            Ok(Some(self.get_skip_entry(block_idx)?.meta))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Stops decoding term frequencies of next blocks, when only docids are
+    /// needed. Positions can't be read after that.
+    pub const fn skip_term_freqs(&mut self) {
+        self.with_term_freqs = false;
+    }
+
+    /// Reads positions of the posting, that was returned last, into `output`.
+    pub fn read_positions(
+        &mut self,
+        output: &mut Vec<TermPosition>,
+    ) -> Result<()> {
+        ensure!(
+            self.buffer_pos > 0 && self.buffer_pos <= self.buffer.len(),
+            "posting should be read before its positions"
+        );
+        ensure!(
+            self.with_term_freqs,
+            "term frequencies should be decoded to read positions"
+        );
+
+        if !self.positions_decoded {
+            self.decode_positions()?;
+        }
+
+        // positions of the block go in the same order as its postings
+        let posting_idx = self.buffer_pos - 1;
+        let start: usize = (0..posting_idx)
+            .map(|idx| self.buffer.get_posting(idx).term_freq as usize)
+            .sum();
+        let count = self.buffer.get_posting(posting_idx).term_freq as usize;
+
+        output.clear();
+
+        let mut position = 0;
+        for &delta in &self.positions_buffer[start..start + count] {
+            position += delta;
+            output.push(position);
+        }
+
+        Ok(())
+    }
+
+    fn decode_positions(&mut self) -> Result<()> {
+        let positions = self
+            .positions
+            .context("index should contain term positions")?;
+
+        let block_idx = self.buffer_block_idx;
+
+        let start_offset = if block_idx == 0 {
+            0
+        } else {
+            self.get_skip_entry(block_idx - 1)?.positions_end_offset as usize
+        };
+        let end_offset =
+            self.get_skip_entry(block_idx)?.positions_end_offset as usize;
+
+        let mut input = positions
+            .get(start_offset..end_offset)
+            .context("block positions should be in bounds of positions file")?;
+
+        let count = (0..self.buffer.len())
+            .map(|idx| self.buffer.get_posting(idx).term_freq as usize)
+            .sum();
+
+        self.positions_buffer.resize(count, 0);
+        decode_unsorted(&mut input, &mut self.positions_buffer)?;
+        self.positions_decoded = true;
+
         Ok(())
     }
 }
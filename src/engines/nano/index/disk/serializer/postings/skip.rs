@@ -0,0 +1,50 @@
+use std::io::{Read, Write};
+
+use anyhow::Result;
+
+use crate::engines::nano::index::disk::serializer::BinarySerializable;
+use crate::engines::nano::index::model::DocPostingsBlockMeta;
+
+/// Serialized size of [`SkipEntry`] in bytes.
+pub const SKIP_ENTRY_SIZE: usize = 18;
+
+/// Entry of posting list skip data, which describes single block of postings.
+///
+/// Skip data has one entry per block, so it's cheap to binary search through
+/// and to find the block containing target docid without touching any blocks
+/// in front of it.
+#[derive(Clone, Copy, Debug)]
+pub struct SkipEntry {
+    pub meta: DocPostingsBlockMeta,
+
+    /// Offset of the byte after the end of the block, relative to the start
+    /// of posting list. It's also the start offset of the next block
+    pub end_offset: u32,
+
+    /// Offset of the byte after the end of block positions, relative to the
+    /// start of posting list positions. Zero if positions are not recorded
+    pub positions_end_offset: u32,
+}
+
+impl BinarySerializable for SkipEntry {
+    fn serialize(&self, write: &mut dyn Write) -> Result<()> {
+        self.meta.serialize(write)?;
+        self.end_offset.serialize(write)?;
+        self.positions_end_offset.serialize(write)?;
+        Ok(())
+    }
+    fn deserialize(read: &mut dyn Read) -> Result<Self> {
+        Ok(Self {
+            meta: DocPostingsBlockMeta::deserialize(read)?,
+            end_offset: u32::deserialize(read)?,
+            positions_end_offset: u32::deserialize(read)?,
+        })
+    }
+    fn deserialize_from_slice(data: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            meta: DocPostingsBlockMeta::deserialize_from_slice(data)?,
+            end_offset: u32::deserialize_from_slice(data)?,
+            positions_end_offset: u32::deserialize_from_slice(data)?,
+        })
+    }
+}
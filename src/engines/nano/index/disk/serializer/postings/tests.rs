@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 
 use super::serializer::{PostingsDeserializer, PostingsSerializer};
-use crate::engines::nano::index::model::DocPosting;
+use crate::engines::nano::index::model::{DocPosting, DocPostingsBlockMeta};
 use crate::utils::CountingWriter;
 
 #[test]
@@ -13,29 +13,38 @@ fn test_postings_serializer() -> Result<()> {
     Ok(())
 }
 
-fn assert_postings_serializer(postings_count: usize) -> Result<()> {
+/// Serializes postings with docids `idx * 2`, term freqs `idx * 3` and doc
+/// lengths `1000 - idx % 1000`.
+fn serialize_postings(postings_count: usize) -> Result<Vec<u8>> {
     let storage = Vec::<u8>::new();
     let mut storage_writer = CountingWriter::new(storage);
 
-    // serialize
-    {
-        let mut serializer = PostingsSerializer::new(&mut storage_writer);
+    let mut serializer = PostingsSerializer::new(&mut storage_writer, None);
 
-        for idx in 0..postings_count {
-            serializer.write_posting(&DocPosting {
+    for idx in 0..postings_count {
+        serializer.write_posting(
+            &DocPosting {
                 docid: (idx * 2) as u32,
                 term_freq: (idx * 3) as u32,
-            })?;
-        }
-
-        serializer.flush()?;
+            },
+            (1000 - idx % 1000) as u16,
+            &[],
+        )?;
     }
 
+    serializer.flush()?;
+    drop(serializer);
+
+    Ok(storage_writer.into_inner())
+}
+
+fn assert_postings_serializer(postings_count: usize) -> Result<()> {
+    let storage = serialize_postings(postings_count)?;
+
     // deserialize
     {
-        let storage = storage_writer.into_inner();
         let deserializer =
-            PostingsDeserializer::new(&storage[..], postings_count);
+            PostingsDeserializer::new(&storage[..], postings_count, None)?;
 
         let mut actual_postings_count = 0;
 
@@ -53,3 +62,245 @@ fn assert_postings_serializer(postings_count: usize) -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_postings_deserializer_skip_to() -> Result<()> {
+    let storage = serialize_postings(300)?;
+    let mut deserializer = PostingsDeserializer::new(&storage[..], 300, None)?;
+
+    // skips 1st block, and stops at the start of 2nd block (postings 128-255),
+    // which contains the target
+    deserializer.skip_to(300)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 256);
+
+    // target is in current block, so nothing is skipped
+    deserializer.skip_to(400)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 258);
+
+    // skips rest of current block
+    deserializer.skip_to(520)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 512);
+
+    // skips everything
+    deserializer.skip_to(1000)?;
+    assert!(deserializer.next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_skip_to_far_block() -> Result<()> {
+    let storage = serialize_postings(10_000)?;
+    let mut deserializer =
+        PostingsDeserializer::new(&storage[..], 10_000, None)?;
+
+    // jumps straight to the start of 59th block (postings 7424-7551), which
+    // contains the target
+    deserializer.skip_to(15_000)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 14_848);
+    assert_eq!(posting.term_freq, 7_424 * 3);
+
+    // jumps to the start of last block (postings 9984-9999)
+    deserializer.skip_to(19_998)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 19_968);
+    assert_eq!(deserializer.count(), 15);
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_missing_skip_data() -> Result<()> {
+    let storage = serialize_postings(300)?;
+
+    // skip data of 3 blocks does not fit into 10 bytes
+    assert!(PostingsDeserializer::new(&storage[..10], 300, None).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_peek_block_meta() -> Result<()> {
+    let storage = serialize_postings(300)?;
+    let mut deserializer = PostingsDeserializer::new(&storage[..], 300, None)?;
+
+    let first_block = DocPostingsBlockMeta {
+        last_docid: 254,
+        max_term_freq: 381,
+        min_doc_terms_count: 873,
+    };
+    let second_block = DocPostingsBlockMeta {
+        last_docid: 510,
+        max_term_freq: 765,
+        min_doc_terms_count: 745,
+    };
+    let third_block = DocPostingsBlockMeta {
+        last_docid: 598,
+        max_term_freq: 897,
+        min_doc_terms_count: 701,
+    };
+
+    // nothing is decoded yet
+    assert_eq!(deserializer.peek_block_meta(0)?, Some(first_block));
+    assert_eq!(deserializer.peek_block_meta(300)?, Some(second_block));
+    assert_eq!(deserializer.peek_block_meta(598)?, Some(third_block));
+    assert_eq!(deserializer.peek_block_meta(599)?, None);
+
+    // peeking does not move deserializer
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 0);
+
+    // meta of decoded block
+    assert_eq!(deserializer.peek_block_meta(0)?, Some(first_block));
+    assert_eq!(deserializer.peek_block_meta(300)?, Some(second_block));
+
+    // skipping continues from peeked block
+    deserializer.skip_to(300)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 256);
+
+    Ok(())
+}
+
+/// Serializes postings with docids `idx * 2`, term freqs `idx % 4 + 1` and
+/// positions `idx`, `idx + 10`, `idx + 20`, etc.
+fn serialize_postings_with_positions(
+    postings_count: usize,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut storage_writer = CountingWriter::new(Vec::<u8>::new());
+    let mut positions_writer = CountingWriter::new(Vec::<u8>::new());
+
+    let mut serializer = PostingsSerializer::new(
+        &mut storage_writer,
+        Some(&mut positions_writer),
+    );
+
+    for idx in 0..postings_count {
+        let term_freq = (idx % 4 + 1) as u32;
+        let positions = (0..term_freq)
+            .map(|n| idx as u32 + n * 10)
+            .collect::<Vec<_>>();
+
+        serializer.write_posting(
+            &DocPosting {
+                docid: (idx * 2) as u32,
+                term_freq,
+            },
+            100,
+            &positions,
+        )?;
+    }
+
+    serializer.flush()?;
+    drop(serializer);
+
+    Ok((storage_writer.into_inner(), positions_writer.into_inner()))
+}
+
+#[test]
+fn test_postings_deserializer_read_positions() -> Result<()> {
+    let (storage, positions) = serialize_postings_with_positions(300)?;
+    let mut deserializer =
+        PostingsDeserializer::new(&storage[..], 300, Some(&positions[..]))?;
+
+    let mut actual_positions = Vec::new();
+
+    for idx in 0..300_u32 {
+        let posting = deserializer.next().context("should exist")??;
+        assert_eq!(posting.docid, idx * 2);
+
+        deserializer.read_positions(&mut actual_positions)?;
+
+        let term_freq = idx % 4 + 1;
+        let expected_positions =
+            (0..term_freq).map(|n| idx + n * 10).collect::<Vec<_>>();
+
+        assert_eq!(actual_positions, expected_positions);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_read_positions_after_skip_to() -> Result<()> {
+    let (storage, positions) = serialize_postings_with_positions(300)?;
+    let mut deserializer =
+        PostingsDeserializer::new(&storage[..], 300, Some(&positions[..]))?;
+    let mut actual_positions = Vec::new();
+
+    // read positions from the 1st block
+    deserializer.next().context("should exist")??;
+    deserializer.read_positions(&mut actual_positions)?;
+    assert_eq!(actual_positions, &[0]);
+
+    // skip to the 3rd block (postings 256-299)
+    deserializer.skip_to(520)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 512);
+
+    deserializer.read_positions(&mut actual_positions)?;
+    assert_eq!(actual_positions, &[256]);
+
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 514);
+
+    deserializer.read_positions(&mut actual_positions)?;
+    assert_eq!(actual_positions, &[257, 267]);
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_read_positions_errors() -> Result<()> {
+    let (storage, positions) = serialize_postings_with_positions(10)?;
+    let mut actual_positions = Vec::new();
+
+    // positions are not available before reading posting
+    let mut deserializer =
+        PostingsDeserializer::new(&storage[..], 10, Some(&positions[..]))?;
+    assert!(deserializer.read_positions(&mut actual_positions).is_err());
+
+    // positions are not recorded
+    let mut deserializer = PostingsDeserializer::new(&storage[..], 10, None)?;
+    deserializer.next().context("should exist")??;
+    assert!(deserializer.read_positions(&mut actual_positions).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_postings_deserializer_skip_term_freqs() -> Result<()> {
+    let (storage, positions) = serialize_postings_with_positions(300)?;
+    let mut deserializer =
+        PostingsDeserializer::new(&storage[..], 300, Some(&positions[..]))?;
+
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.term_freq, 1);
+
+    // block in buffer is decoded already, so next blocks go without term freqs
+    deserializer.skip_term_freqs();
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.term_freq, 2);
+
+    deserializer.skip_to(300)?;
+    let posting = deserializer.next().context("should exist")??;
+    assert_eq!(posting.docid, 256);
+    assert_eq!(posting.term_freq, 0);
+
+    // block metadata is stored separately, so it's still available
+    assert_eq!(
+        deserializer
+            .peek_block_meta(256)?
+            .map(|meta| meta.max_term_freq),
+        Some(4)
+    );
+
+    let mut actual_positions = Vec::new();
+    assert!(deserializer.read_positions(&mut actual_positions).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,399 @@
+use std::io::Write;
+use std::ops::Bound;
+
+use anyhow::{Context, Result};
+use fst::{Automaton, IntoStreamer, Map, MapBuilder, Streamer};
+use memmap2::Mmap;
+
+use super::model::TermPostingListFileAddress;
+use super::serializer::{BinarySerializable, deserialize_vec_item};
+use crate::engines::nano::index::automaton::LevenshteinAutomaton;
+use crate::engines::nano::index::model::{
+    Term, TermBounds, TermsIterator, make_field_term_key,
+    make_field_term_key_bounds, split_field_term_key,
+};
+
+/// Sorted dictionary, that maps terms of a segment to addresses of their
+/// posting lists.
+///
+/// Terms of all fields are stored together, keyed with [`make_field_term_key`].
+///
+/// Terms are stored in [FST](https://burntsushi.net/transducers/), which maps
+/// each term to its ordinal number. FST shares both prefixes and suffixes of
+/// terms, so it takes much less space than plain list of terms, and can be
+/// searched right from memory-mapped file without loading it into the heap.
+///
+/// Addresses of posting lists have fixed size, so they are stored in separate
+/// file as a vector, and found by term ordinal.
+///
+/// Lucene and Tantivy use FST as term index too, though they point it to
+/// blocks of terms, rather than to each term.
+pub struct TermDictionary {
+    fst: Map<Mmap>,
+    addresses_file: Mmap,
+}
+
+impl TermDictionary {
+    pub fn open(terms_file: Mmap, addresses_file: Mmap) -> Result<Self> {
+        Ok(Self {
+            fst: Map::new(terms_file).context("terms file should be FST")?,
+            addresses_file,
+        })
+    }
+
+    pub fn get(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<Option<TermPostingListFileAddress>> {
+        self.fst
+            .get(make_field_term_key(field, term))
+            .map(|ordinal| {
+                Ok(deserialize_vec_item::<TermPostingListFileAddress>(
+                    &self.addresses_file,
+                    ordinal as usize,
+                )?
+                .into_owned())
+            })
+            .transpose()
+    }
+
+    pub fn get_terms_in_range(
+        &self,
+        field: &str,
+        bounds: TermBounds,
+    ) -> TermsIterator<'_> {
+        let (lower, upper) = make_field_term_key_bounds(field, bounds);
+        let mut range = self.fst.range();
+
+        range = match lower {
+            Bound::Included(term) => range.ge(term),
+            Bound::Excluded(term) => range.gt(term),
+            Bound::Unbounded => range,
+        };
+        range = match upper {
+            Bound::Included(term) => range.le(term),
+            Bound::Excluded(term) => range.lt(term),
+            Bound::Unbounded => range,
+        };
+
+        Box::new(FstTermsIterator {
+            stream: range.into_stream(),
+        })
+    }
+
+    /// Walks only FST branches, which automaton can accept, so terms far from
+    /// automaton word are mostly skipped without reading.
+    pub fn get_terms_matching<'a>(
+        &'a self,
+        field: &str,
+        automaton: &'a LevenshteinAutomaton,
+    ) -> TermsIterator<'a> {
+        let automaton = PrefixedAutomaton {
+            prefix: make_field_term_key(field, "").into_bytes(),
+            inner: automaton,
+        };
+
+        Box::new(FstTermsIterator {
+            stream: self.fst.search(automaton).into_stream(),
+        })
+    }
+}
+
+struct FstTermsIterator<'a, A: Automaton = fst::automaton::AlwaysMatch> {
+    stream: fst::map::Stream<'a, A>,
+}
+
+impl<A: Automaton> Iterator for FstTermsIterator<'_, A> {
+    type Item = Result<Term>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.next().map(|(key, _)| {
+            let key =
+                std::str::from_utf8(key).context("term should be UTF-8")?;
+            let (_, term) = split_field_term_key(key)
+                .context("term key should contain field name")?;
+            Ok(term.to_string())
+        })
+    }
+}
+
+/// Automaton, that accepts keys made of `prefix` followed by anything `inner`
+/// automaton accepts. Used to search terms of single field.
+struct PrefixedAutomaton<A> {
+    prefix: Vec<u8>,
+    inner: A,
+}
+
+#[derive(Clone)]
+enum PrefixedState<S> {
+    /// Number of prefix bytes matched so far
+    Prefix(usize),
+    Inner(S),
+    Mismatch,
+}
+
+impl<A: Automaton> Automaton for PrefixedAutomaton<A> {
+    type State = PrefixedState<A::State>;
+
+    fn start(&self) -> Self::State {
+        if self.prefix.is_empty() {
+            PrefixedState::Inner(self.inner.start())
+        } else {
+            PrefixedState::Prefix(0)
+        }
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixedState::Inner(state) => self.inner.is_match(state),
+            PrefixedState::Prefix(_) | PrefixedState::Mismatch => false,
+        }
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        match state {
+            PrefixedState::Prefix(_) => true,
+            PrefixedState::Inner(state) => self.inner.can_match(state),
+            PrefixedState::Mismatch => false,
+        }
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        match state {
+            PrefixedState::Prefix(matched) if self.prefix[*matched] == byte => {
+                if matched + 1 == self.prefix.len() {
+                    PrefixedState::Inner(self.inner.start())
+                } else {
+                    PrefixedState::Prefix(matched + 1)
+                }
+            }
+            PrefixedState::Inner(state) => {
+                PrefixedState::Inner(self.inner.accept(state, byte))
+            }
+            PrefixedState::Prefix(_) | PrefixedState::Mismatch => {
+                PrefixedState::Mismatch
+            }
+        }
+    }
+}
+
+/// Writes term dictionary of a segment, see [`TermDictionary`].
+///
+/// Term keys should be inserted in lexicographic order.
+pub struct TermDictionaryWriter<W: Write> {
+    fst: MapBuilder<W>,
+    addresses: Vec<TermPostingListFileAddress>,
+    addresses_output: W,
+}
+
+impl<W: Write> TermDictionaryWriter<W> {
+    pub fn new(terms_output: W, addresses_output: W) -> Result<Self> {
+        Ok(Self {
+            fst: MapBuilder::new(terms_output)?,
+            addresses: Vec::new(),
+            addresses_output,
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        key: &str,
+        address: TermPostingListFileAddress,
+    ) -> Result<()> {
+        self.fst
+            .insert(key, self.addresses.len() as u64)
+            .context("terms should be inserted in lexicographic order")?;
+        self.addresses.push(address);
+        Ok(())
+    }
+
+    /// Finishes writing and flushes outputs.
+    pub fn finish(mut self) -> Result<()> {
+        self.fst.into_inner()?.flush()?;
+        self.addresses.serialize(&mut self.addresses_output)?;
+        self.addresses_output.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn address(postings_count: usize) -> TermPostingListFileAddress {
+        TermPostingListFileAddress {
+            postings_count,
+            term_freq_total: postings_count as u64 * 3,
+            start_byte: postings_count * 10,
+            end_byte: postings_count * 10 + 5,
+            positions_start_byte: postings_count * 20,
+        }
+    }
+
+    fn build_dictionary(
+        terms: &[(&str, &str)],
+    ) -> Result<(TermDictionary, TempDir)> {
+        let dir = TempDir::new()?;
+        let terms_path = dir.path().join("terms");
+        let addresses_path = dir.path().join("addresses");
+
+        let mut writer = TermDictionaryWriter::new(
+            File::create(&terms_path)?,
+            File::create(&addresses_path)?,
+        )?;
+        for (idx, (field, term)) in terms.iter().enumerate() {
+            writer
+                .insert(&make_field_term_key(field, term), address(idx + 1))?;
+        }
+        writer.finish()?;
+
+        let dictionary = TermDictionary::open(
+            unsafe { Mmap::map(&File::open(terms_path)?)? },
+            unsafe { Mmap::map(&File::open(addresses_path)?)? },
+        )?;
+
+        Ok((dictionary, dir))
+    }
+
+    fn collect(terms: TermsIterator) -> Result<Vec<Term>> {
+        terms.collect()
+    }
+
+    #[test]
+    fn test_get() -> Result<()> {
+        let (dictionary, _dir) = build_dictionary(&[
+            ("text", "cat"),
+            ("text", "dog"),
+            ("text", "mouse"),
+            ("title", "dog"),
+        ])?;
+
+        let addr = dictionary
+            .get("text", "dog")?
+            .context("dog should be found")?;
+        assert_eq!(addr.postings_count, 2);
+        assert_eq!(addr.term_freq_total, 6);
+        assert_eq!(addr.start_byte, 20);
+        assert_eq!(addr.end_byte, 25);
+        assert_eq!(addr.positions_start_byte, 40);
+
+        let addr = dictionary
+            .get("title", "dog")?
+            .context("dog should be found in title")?;
+        assert_eq!(addr.postings_count, 4);
+
+        assert!(dictionary.get("text", "do")?.is_none());
+        assert!(dictionary.get("text", "dogs")?.is_none());
+        assert!(dictionary.get("title", "cat")?.is_none());
+        assert!(dictionary.get("author", "dog")?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_terms_in_range() -> Result<()> {
+        let (dictionary, _dir) = build_dictionary(&[
+            ("text", "cat"),
+            ("text", "catalog"),
+            ("text", "dog"),
+            ("text", "mouse"),
+            ("title", "ant"),
+            ("title", "zebra"),
+        ])?;
+
+        assert_eq!(
+            collect(
+                dictionary.get_terms_in_range("text", (Unbounded, Unbounded))
+            )?,
+            &["cat", "catalog", "dog", "mouse"]
+        );
+        assert_eq!(
+            collect(
+                dictionary.get_terms_in_range("title", (Unbounded, Unbounded))
+            )?,
+            &["ant", "zebra"]
+        );
+        assert_eq!(
+            collect(dictionary.get_terms_in_range(
+                "text",
+                (Excluded("cat"), Included("dog"))
+            ))?,
+            &["catalog", "dog"]
+        );
+        assert_eq!(
+            collect(dictionary.get_terms_in_range(
+                "text",
+                (Included("d"), Excluded("mouse"))
+            ))?,
+            &["dog"]
+        );
+        assert!(
+            collect(
+                dictionary
+                    .get_terms_in_range("text", (Included("x"), Unbounded))
+            )?
+            .is_empty()
+        );
+        assert!(
+            collect(
+                dictionary.get_terms_in_range("tex", (Unbounded, Unbounded))
+            )?
+            .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_terms_matching() -> Result<()> {
+        let (dictionary, _dir) = build_dictionary(&[
+            ("text", "cat"),
+            ("text", "cats"),
+            ("text", "coat"),
+            ("text", "dog"),
+            ("text", "scat"),
+            ("title", "bat"),
+        ])?;
+        let automaton = LevenshteinAutomaton::new("cat", 1)?;
+
+        assert_eq!(
+            collect(dictionary.get_terms_matching("text", &automaton))?,
+            &["cat", "cats", "coat", "scat"]
+        );
+        assert_eq!(
+            collect(dictionary.get_terms_matching("title", &automaton))?,
+            &["bat"]
+        );
+        assert!(
+            collect(dictionary.get_terms_matching("author", &automaton))?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_unsorted() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut writer = TermDictionaryWriter::new(
+            File::create(dir.path().join("terms"))?,
+            File::create(dir.path().join("addresses"))?,
+        )?;
+
+        writer.insert("dog", address(1))?;
+        let err = writer.insert("cat", address(2)).err();
+
+        assert_eq!(
+            err.map(|err| err.to_string()).as_deref(),
+            Some("terms should be inserted in lexicographic order")
+        );
+
+        Ok(())
+    }
+}
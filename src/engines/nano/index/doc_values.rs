@@ -0,0 +1,463 @@
+use std::ops::{Bound, Range};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use zerocopy::FromBytes;
+use zerocopy::little_endian::U64;
+
+use super::model::SegmentDocId;
+use crate::model::doc::DocValue;
+
+/// Code of a document without value in a column.
+///
+/// Values with the same code can't be stored: `u64::MAX` numbers, `i64::MAX`
+/// dates and the largest `NaN` floats.
+pub const MISSING_CODE: u64 = u64::MAX;
+
+const SIGN_BIT: u64 = 1 << 63;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocValueType {
+    U64,
+    F64,
+    Date,
+    Keyword,
+}
+
+impl DocValueType {
+    #[must_use]
+    pub const fn of(value: &DocValue) -> Self {
+        match value {
+            DocValue::U64(_) => Self::U64,
+            DocValue::F64(_) => Self::F64,
+            DocValue::Date(_) => Self::Date,
+            DocValue::Keyword(_) => Self::Keyword,
+        }
+    }
+
+    #[must_use]
+    pub const fn to_u8(self) -> u8 {
+        match self {
+            Self::U64 => 0,
+            Self::F64 => 1,
+            Self::Date => 2,
+            Self::Keyword => 3,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => Self::U64,
+            1 => Self::F64,
+            2 => Self::Date,
+            3 => Self::Keyword,
+            _ => bail!("doc value type should be known, got {value}"),
+        })
+    }
+
+    /// Parses value of the type from query text. Dates are parsed either from
+    /// RFC 3339, e.g. "2024-01-31T12:00:00Z", or from calendar date, e.g.
+    /// "2024-01-31", which means midnight UTC.
+    pub fn parse_value(self, text: &str) -> Result<DocValue> {
+        Ok(match self {
+            Self::U64 => DocValue::U64(
+                text.parse()
+                    .with_context(|| format!("'{text}' should be a number"))?,
+            ),
+            Self::F64 => DocValue::F64(
+                text.parse()
+                    .with_context(|| format!("'{text}' should be a number"))?,
+            ),
+            Self::Date => DocValue::Date(
+                parse_date(text)
+                    .with_context(|| format!("'{text}' should be a date"))?,
+            ),
+            Self::Keyword => DocValue::Keyword(text.to_string()),
+        })
+    }
+}
+
+fn parse_date(text: &str) -> Result<i64> {
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Ok(date.timestamp());
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp())
+}
+
+/// Description of a column of doc values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocValuesColumnInfo {
+    pub name: String,
+    pub value_type: DocValueType,
+
+    /// Sorted unique keywords of keyword column, which codes of values are
+    /// ordinals of. Empty for other types
+    pub keywords: Vec<String>,
+}
+
+/// Column of values of a field for each document of a segment, that can be
+/// indexed with [`SegmentDocId`].
+///
+/// Values are kept as `u64` codes, which go in the same order as values
+/// themselves, so documents can be sorted and compared by codes without
+/// decoding values.
+///
+/// Note: Tantivy/Lucene call such columns "fast fields" and "doc values".
+/// They compress codes with bit packing and other codecs, while codes here
+/// take full 8 bytes, so they can be read from file in place.
+#[derive(Clone, Copy)]
+pub struct DocValuesColumn<'a> {
+    pub info: &'a DocValuesColumnInfo,
+    codes: &'a [U64],
+}
+
+impl<'a> DocValuesColumn<'a> {
+    #[must_use]
+    pub const fn new(info: &'a DocValuesColumnInfo, codes: &'a [U64]) -> Self {
+        Self { info, codes }
+    }
+
+    /// Reads column codes from serialized little-endian numbers.
+    pub fn from_bytes(
+        info: &'a DocValuesColumnInfo,
+        bytes: &'a [u8],
+    ) -> Result<Self> {
+        let codes = <[U64]>::ref_from_bytes(bytes)
+            .map_err(|_| anyhow!("doc values should be whole u64 numbers"))?;
+        Ok(Self::new(info, codes))
+    }
+
+    #[must_use]
+    pub const fn get_docs_count(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Gets code of value of the document, or `None` if it has no value.
+    pub fn get_code(&self, docid: SegmentDocId) -> Result<Option<u64>> {
+        let code = self
+            .codes
+            .get(docid as usize)
+            .context("doc with such ID should exist in column")?
+            .get();
+        Ok((code != MISSING_CODE).then_some(code))
+    }
+
+    /// Gets value of the document, or `None` if it has no value.
+    pub fn get_value(&self, docid: SegmentDocId) -> Result<Option<DocValue>> {
+        self.get_code(docid)?
+            .map(|code| self.decode(code))
+            .transpose()
+    }
+
+    /// Gets range of codes of values within bounds. Range may be empty, e.g.
+    /// if keyword column has no values within bounds.
+    pub fn get_code_range(
+        &self,
+        lower: Bound<&DocValue>,
+        upper: Bound<&DocValue>,
+    ) -> Result<Range<u64>> {
+        for value in [lower, upper] {
+            if let Bound::Included(value) | Bound::Excluded(value) = value {
+                ensure!(
+                    DocValueType::of(value) == self.info.value_type,
+                    "range bounds of field '{}' should have type of its values",
+                    self.info.name
+                );
+            }
+        }
+
+        let start = match lower {
+            Bound::Unbounded => 0,
+            Bound::Included(value) => self.get_insertion_code(value, false)?,
+            Bound::Excluded(value) => self.get_insertion_code(value, true)?,
+        };
+        let end = match upper {
+            Bound::Unbounded => MISSING_CODE,
+            Bound::Included(value) => self.get_insertion_code(value, true)?,
+            Bound::Excluded(value) => self.get_insertion_code(value, false)?,
+        };
+
+        Ok(start..end)
+    }
+
+    /// Gets first code, which goes after all values less than `value`, or
+    /// less than or equal to it if `after_equal` is set.
+    fn get_insertion_code(
+        &self,
+        value: &DocValue,
+        after_equal: bool,
+    ) -> Result<u64> {
+        if let DocValue::Keyword(keyword) = value {
+            let keywords = &self.info.keywords;
+            let idx = if after_equal {
+                keywords.partition_point(|existing| existing <= keyword)
+            } else {
+                keywords.partition_point(|existing| existing < keyword)
+            };
+            return Ok(idx as u64);
+        }
+
+        let code = encode_number(value)?;
+        Ok(if after_equal {
+            code.saturating_add(1)
+        } else {
+            code
+        })
+    }
+
+    pub fn decode(&self, code: u64) -> Result<DocValue> {
+        Ok(match self.info.value_type {
+            DocValueType::U64 => DocValue::U64(code),
+            DocValueType::F64 => DocValue::F64(decode_f64(code)),
+            DocValueType::Date => DocValue::Date(decode_i64(code)),
+            DocValueType::Keyword => DocValue::Keyword(
+                self.info
+                    .keywords
+                    .get(code as usize)
+                    .context("keyword ordinal should be in column")?
+                    .clone(),
+            ),
+        })
+    }
+}
+
+/// Collects values of a field from documents going one by one, to build its
+/// column.
+pub struct DocValuesColumnBuilder {
+    name: String,
+    value_type: DocValueType,
+    values: Vec<Option<DocValue>>,
+}
+
+impl DocValuesColumnBuilder {
+    pub fn new(name: impl Into<String>, value_type: DocValueType) -> Self {
+        Self {
+            name: name.into(),
+            value_type,
+            values: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn add(&mut self, docid: SegmentDocId, value: DocValue) -> Result<()> {
+        ensure!(
+            DocValueType::of(&value) == self.value_type,
+            "doc values of field '{}' should have the same type",
+            self.name
+        );
+        ensure!(
+            self.values.len() <= docid as usize,
+            "doc values should have unique names"
+        );
+
+        // field may have no values in previous documents
+        self.values.resize(docid as usize, None);
+        self.values.push(Some(value));
+
+        Ok(())
+    }
+
+    /// Builds column info and codes of values of all `docs_count` documents.
+    pub fn build(
+        self,
+        docs_count: usize,
+    ) -> Result<(DocValuesColumnInfo, Vec<U64>)> {
+        let mut keywords: Vec<String> = self
+            .values
+            .iter()
+            .filter_map(|value| match value {
+                Some(DocValue::Keyword(keyword)) => Some(keyword.clone()),
+                _ => None,
+            })
+            .collect();
+        keywords.sort_unstable();
+        keywords.dedup();
+
+        let mut codes = Vec::with_capacity(docs_count);
+        for value in &self.values {
+            let code = match value {
+                None => MISSING_CODE,
+                Some(DocValue::Keyword(keyword)) => keywords
+                    .binary_search(keyword)
+                    .map_err(|_| anyhow!("keyword should be in column"))?
+                    as u64,
+                Some(value) => {
+                    let code = encode_number(value)?;
+                    ensure!(
+                        code != MISSING_CODE,
+                        "doc value of field '{}' should not be reserved for \
+                         missing values",
+                        self.name
+                    );
+                    code
+                }
+            };
+            codes.push(U64::new(code));
+        }
+        codes.resize(docs_count, U64::new(MISSING_CODE));
+
+        let info = DocValuesColumnInfo {
+            name: self.name,
+            value_type: self.value_type,
+            keywords,
+        };
+
+        Ok((info, codes))
+    }
+}
+
+/// Encodes number or date into code, which goes in the same order as values
+/// of its type.
+pub fn encode_number(value: &DocValue) -> Result<u64> {
+    Ok(match value {
+        DocValue::U64(value) => *value,
+        DocValue::F64(value) => encode_f64(*value),
+        DocValue::Date(value) => encode_i64(*value),
+        DocValue::Keyword(_) => {
+            bail!("keyword should not be encoded as number")
+        }
+    })
+}
+
+const fn encode_i64(value: i64) -> u64 {
+    // flipping sign bit moves negative numbers before positive ones
+    value.cast_unsigned() ^ SIGN_BIT
+}
+
+const fn decode_i64(code: u64) -> i64 {
+    (code ^ SIGN_BIT).cast_signed()
+}
+
+const fn encode_f64(value: f64) -> u64 {
+    // same order as `f64::total_cmp`: bits of negative numbers are inverted,
+    // so larger magnitude goes first, and sign bit of positive ones is set, so
+    // they go after negative ones
+    let bits = value.to_bits();
+    if bits & SIGN_BIT == 0 {
+        bits | SIGN_BIT
+    } else {
+        !bits
+    }
+}
+
+const fn decode_f64(code: u64) -> f64 {
+    let bits = if code & SIGN_BIT == 0 {
+        !code
+    } else {
+        code & !SIGN_BIT
+    };
+    f64::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_numbers_in_order() {
+        let floats = [f64::NEG_INFINITY, -2.5, -0.0, 0.0, 1e-10, 3.0];
+        for pair in floats.windows(2) {
+            assert!(encode_f64(pair[0]) < encode_f64(pair[1]));
+        }
+        for value in floats {
+            assert_eq!(
+                decode_f64(encode_f64(value)).to_bits(),
+                value.to_bits()
+            );
+        }
+
+        let dates = [i64::MIN, -1, 0, 1_700_000_000];
+        for pair in dates.windows(2) {
+            assert!(encode_i64(pair[0]) < encode_i64(pair[1]));
+        }
+        for value in dates {
+            assert_eq!(decode_i64(encode_i64(value)), value);
+        }
+
+        assert!(encode_number(&DocValue::Keyword("a".into())).is_err());
+    }
+
+    #[test]
+    fn test_parse_value() -> Result<()> {
+        assert_eq!(DocValueType::U64.parse_value("42")?, DocValue::U64(42));
+        assert_eq!(DocValueType::F64.parse_value("-0.5")?, DocValue::F64(-0.5));
+        assert_eq!(
+            DocValueType::Date.parse_value("1970-01-02")?,
+            DocValue::Date(86_400)
+        );
+        assert_eq!(
+            DocValueType::Date.parse_value("1970-01-01T01:00:00+01:00")?,
+            DocValue::Date(0)
+        );
+        assert_eq!(
+            DocValueType::Keyword.parse_value("cat")?,
+            DocValue::Keyword("cat".into())
+        );
+
+        assert!(DocValueType::U64.parse_value("-1").is_err());
+        assert!(DocValueType::Date.parse_value("yesterday").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_column() -> Result<()> {
+        let mut builder =
+            DocValuesColumnBuilder::new("tag", DocValueType::Keyword);
+        builder.add(1, DocValue::Keyword("mouse".into()))?;
+        builder.add(2, DocValue::Keyword("cat".into()))?;
+        builder.add(3, DocValue::Keyword("mouse".into()))?;
+
+        assert!(builder.add(3, DocValue::Keyword("dog".into())).is_err());
+        assert!(builder.add(4, DocValue::U64(1)).is_err());
+
+        let (info, codes) = builder.build(5)?;
+        let column = DocValuesColumn::new(&info, &codes);
+
+        assert_eq!(info.keywords, &["cat", "mouse"]);
+        assert_eq!(
+            (0..5)
+                .map(|docid| column.get_code(docid))
+                .collect::<Result<Vec<_>>>()?,
+            &[None, Some(1), Some(0), Some(1), None]
+        );
+        assert_eq!(column.get_value(2)?, Some(DocValue::Keyword("cat".into())));
+        assert!(column.get_code(5).is_err());
+
+        let keyword = |value: &str| DocValue::Keyword(value.into());
+        assert_eq!(
+            column.get_code_range(
+                Bound::Included(&keyword("bird")),
+                Bound::Included(&keyword("mouse"))
+            )?,
+            0..2
+        );
+        assert_eq!(
+            column.get_code_range(
+                Bound::Excluded(&keyword("cat")),
+                Bound::Unbounded
+            )?,
+            1..MISSING_CODE
+        );
+        assert!(
+            column
+                .get_code_range(
+                    Bound::Included(&DocValue::U64(1)),
+                    Bound::Unbounded
+                )
+                .is_err()
+        );
+
+        let mut builder =
+            DocValuesColumnBuilder::new("count", DocValueType::U64);
+        builder.add(0, DocValue::U64(MISSING_CODE))?;
+        assert!(builder.build(1).is_err());
+
+        Ok(())
+    }
+}
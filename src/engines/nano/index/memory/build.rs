@@ -1,55 +1,181 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, ensure};
 
-use super::model::{MemoryIndex, TermPostingList};
-use crate::engines::nano::index::model::{DocPosting, SegmentDocId, StoredDoc};
-use crate::model::doc::Doc;
+use super::model::{MemoryDocPosting, MemoryIndex};
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::doc_values::{
+    DocValueType, DocValuesColumnBuilder,
+};
+use crate::engines::nano::index::model::{
+    DocPosting, FieldStats, SegmentDocId, StoredDoc,
+    get_doc_terms_count_bucket, make_field_term_key, split_field_term_key,
+};
+use crate::model::doc::{Doc, DocValueField};
 
+/// Builds index segment in memory.
+///
+/// Each field of documents gets its own terms and document lengths. Fields
+/// get ordinals in order they first appear in documents. Text of fields is
+/// turned into terms with `analyzer`.
+///
+/// If `record_positions` is set, postings also get positions of the term in
+/// the field, which are required for phrase queries, but take extra space.
+///
+/// If `store_fields` is set, original fields of documents are kept as well, so
+/// they can be shown in search results.
+///
+/// Typed values of documents go into columns, one for each value field.
 pub fn build_memory_index(
     docs: &mut dyn Iterator<Item = Result<Doc>>,
+    analyzer: &Analyzer,
+    record_positions: bool,
+    store_fields: bool,
 ) -> Result<MemoryIndex> {
-    let mut index = MemoryIndex::default();
+    let mut index = MemoryIndex {
+        id: uuid::Uuid::new_v4().as_simple().to_string(),
+        has_positions: record_positions,
+        stored_fields: store_fields.then(Vec::new),
+        ..MemoryIndex::default()
+    };
 
-    let mut terms_total: u64 = 0;
+    let mut doc_values_builders: Vec<DocValuesColumnBuilder> = Vec::new();
 
     for (idx, doc) in docs.enumerate() {
         let docid = idx as SegmentDocId;
 
         let doc = doc.context("doc should be valid")?;
-        let words = doc.text.split_whitespace();
 
-        let terms = words.filter_map(|word| {
-            let term = crate::utils::normalize_word(word);
-            if term.is_empty() { None } else { Some(term) }
-        });
+        for field in &doc.fields {
+            let field_ordinal = get_or_add_field(&mut index, &field.name);
 
-        let mut doc_terms_count = 0_u16;
+            ensure!(
+                index.doc_term_counts[field_ordinal].len() <= docid as usize,
+                "doc fields should have unique names"
+            );
 
-        for term in terms {
-            let posting_list =
-                index.terms.entry(term).or_insert_with(TermPostingList::new);
+            let tokens = analyzer.analyze(&field.text);
 
-            let posting =
-                posting_list.entry(docid).or_insert_with(|| DocPosting {
-                    docid,
-                    term_freq: 0,
+            let mut doc_terms_count = 0_u16;
+
+            for token in tokens {
+                let posting_list = index
+                    .terms
+                    .entry(make_field_term_key(&field.name, &token.text))
+                    .or_default();
+
+                let entry = posting_list.entry(docid).or_insert_with(|| {
+                    MemoryDocPosting {
+                        posting: DocPosting {
+                            docid,
+                            term_freq: 0,
+                        },
+                        positions: Vec::new(),
+                    }
                 });
 
-            posting.term_freq += 1;
-            doc_terms_count += 1;
+                entry.posting.term_freq += 1;
+
+                if record_positions {
+                    entry.positions.push(doc_terms_count.into());
+                }
+
+                doc_terms_count += 1;
+
+                index.stats.max_posting_list_size = (posting_list.len() as u64)
+                    .max(index.stats.max_posting_list_size);
+            }
+
+            // fields may appear in later documents only, so lengths of the
+            // field in previous documents are filled with zeros
+            let doc_term_counts = &mut index.doc_term_counts[field_ordinal];
+            doc_term_counts.resize(docid as usize, 0);
+            doc_term_counts.push(doc_terms_count);
 
-            index.stats.max_posting_list_size = (posting_list.len() as u64)
-                .max(index.stats.max_posting_list_size);
+            index.stats.fields[field_ordinal].terms_count_total +=
+                u64::from(doc_terms_count);
         }
 
-        index.doc_term_counts.push(doc_terms_count);
+        add_doc_values(&mut doc_values_builders, docid, doc.values)?;
         index.docs.push(StoredDoc { docid: doc.id });
+        if let Some(stored_fields) = &mut index.stored_fields {
+            stored_fields.push(doc.fields);
+        }
         index.stats.indexed_docs_count += 1;
+    }
+
+    for (field_stats, doc_term_counts) in index
+        .stats
+        .fields
+        .iter_mut()
+        .zip(&mut index.doc_term_counts)
+    {
+        doc_term_counts.resize(index.docs.len(), 0);
 
-        terms_total += doc_terms_count as u64;
+        field_stats.terms_count_per_doc_avg = field_stats.terms_count_total
+            as f64
+            / index.stats.indexed_docs_count as f64;
+
+        for doc_terms_count in doc_term_counts.iter() {
+            let bucket = get_doc_terms_count_bucket(*doc_terms_count);
+            let histogram = &mut field_stats.doc_terms_count_histogram;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
     }
 
-    index.stats.terms_count_per_doc_avg =
-        terms_total as f64 / index.stats.indexed_docs_count as f64;
+    for builder in doc_values_builders {
+        index.doc_values.push(builder.build(index.docs.len())?);
+    }
+
+    for key in index.terms.keys() {
+        let (field, _) = split_field_term_key(key)
+            .context("term key should contain field name")?;
+        let (field_ordinal, _) = index
+            .stats
+            .get_field(field)
+            .context("field should exist in segment")?;
+        index.stats.fields[field_ordinal].unique_terms_count += 1;
+    }
 
     Ok(index)
 }
+
+/// Gets ordinal of the field, registering the field if it's new.
+fn get_or_add_field(index: &mut MemoryIndex, field: &str) -> usize {
+    if let Some((field_ordinal, _)) = index.stats.get_field(field) {
+        return field_ordinal;
+    }
+
+    index.stats.fields.push(FieldStats {
+        name: field.to_string(),
+        ..FieldStats::default()
+    });
+    index.doc_term_counts.push(Vec::new());
+
+    index.stats.fields.len() - 1
+}
+
+/// Adds values of the document to columns of their fields, registering
+/// columns for new fields.
+fn add_doc_values(
+    builders: &mut Vec<DocValuesColumnBuilder>,
+    docid: SegmentDocId,
+    values: Vec<DocValueField>,
+) -> Result<()> {
+    for field in values {
+        let builder_idx = builders
+            .iter()
+            .position(|builder| builder.get_name() == field.name)
+            .unwrap_or_else(|| {
+                builders.push(DocValuesColumnBuilder::new(
+                    &field.name,
+                    DocValueType::of(&field.value),
+                ));
+                builders.len() - 1
+            });
+        builders[builder_idx].add(docid, field.value)?;
+    }
+
+    Ok(())
+}
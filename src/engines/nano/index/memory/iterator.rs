@@ -1,19 +1,29 @@
 use std::borrow::Cow;
 use std::collections::btree_map;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use crate::engines::nano::index::model::{DocPosting, SegmentDocId};
+use super::model::{MemoryDocPosting, TermPostingList};
+use crate::engines::nano::index::model::{
+    DocPosting, DocPostingsBlockMeta, DocPostingsIterator, SegmentDocId,
+    TermPosition,
+};
 
 pub struct MemoryDocPostingsIterator<'a> {
-    postings: btree_map::Values<'a, SegmentDocId, DocPosting>,
+    list: &'a TermPostingList,
+    postings: btree_map::Range<'a, SegmentDocId, MemoryDocPosting>,
+
+    /// Posting returned last
+    current: Option<&'a MemoryDocPosting>,
 }
 
 impl<'a> MemoryDocPostingsIterator<'a> {
-    pub const fn new(
-        postings: btree_map::Values<'a, SegmentDocId, DocPosting>,
-    ) -> Self {
-        Self { postings }
+    pub fn new(list: &'a TermPostingList) -> Self {
+        Self {
+            list,
+            postings: list.range(..),
+            current: None,
+        }
     }
 }
 
@@ -21,8 +31,53 @@ impl<'a> Iterator for MemoryDocPostingsIterator<'a> {
     type Item = Result<Cow<'a, DocPosting>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.postings
-            .next()
-            .map(|posting| Ok(Cow::Borrowed(posting)))
+        self.current = self.postings.next().map(|(_, entry)| entry);
+        self.current.map(|entry| Ok(Cow::Borrowed(&entry.posting)))
+    }
+}
+
+impl<'a> DocPostingsIterator<'a> for MemoryDocPostingsIterator<'a> {
+    fn skip_to(&mut self, target: SegmentDocId) -> Result<()> {
+        // tree lookup is cheap, so jump straight to target. only move forward,
+        // in case iterator is already past the target
+        let next_docid = self.postings.clone().next().map(|(&docid, _)| docid);
+        if next_docid.is_some_and(|docid| docid < target) {
+            self.postings = self.list.range(target..);
+        }
+        Ok(())
+    }
+
+    fn peek_block_meta(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<Option<DocPostingsBlockMeta>> {
+        // memory index does not track block metadata, so whole posting list is
+        // reported as single block with the loosest possible bounds. this still
+        // gives finite upper bound of score, since BM25 saturates as term
+        // frequency grows
+        Ok(self
+            .list
+            .last_key_value()
+            .filter(|(last_docid, _)| **last_docid >= target)
+            .map(|(&last_docid, _)| DocPostingsBlockMeta {
+                last_docid,
+                max_term_freq: u32::MAX,
+                min_doc_terms_count: 0,
+            }))
+    }
+
+    fn skip_term_freqs(&mut self) {
+        // postings are not encoded in memory, so there is nothing to skip
+    }
+
+    fn read_positions(&mut self, output: &mut Vec<TermPosition>) -> Result<()> {
+        let entry = self
+            .current
+            .context("posting should be read before its positions")?;
+
+        output.clear();
+        output.extend_from_slice(&entry.positions);
+
+        Ok(())
     }
 }
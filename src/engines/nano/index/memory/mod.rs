@@ -3,4 +3,4 @@ mod iterator;
 mod model;
 
 pub use build::build_memory_index;
-pub use model::MemoryIndex;
+pub use model::{MemoryIndex, get_term_freq_total};
@@ -1,27 +1,64 @@
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
+use std::ops::Bound;
 
 use anyhow::{Context, Result};
+use zerocopy::little_endian::U64;
 
 use super::iterator::MemoryDocPostingsIterator;
+use crate::engines::nano::index::automaton::LevenshteinAutomaton;
+use crate::engines::nano::index::doc_values::{
+    DocValuesColumn, DocValuesColumnInfo,
+};
 use crate::engines::nano::index::model::{
     DocPosting, DocPostingsForTerm, Index, IndexSegment, IndexSegmentStats,
-    SegmentDocId, StoredDoc, Term,
+    SegmentDocId, StoredDoc, TermBounds, TermPosition, TermsIterator,
+    make_field_term_key, make_field_term_key_bounds, split_field_term_key,
 };
+use crate::model::doc::DocField;
 
 #[derive(Default)]
 pub struct MemoryIndex {
-    pub terms: HashMap<Term, TermPostingList>,
-    /// Count of terms for each document, in form of vector that can be indexed
-    /// with [`SegmentDocId`].
-    pub doc_term_counts: Vec<u16>,
+    /// Unique ID of the segment, which disk segment built from it keeps too
+    pub id: String,
+    /// Terms of all fields keyed with [`make_field_term_key`] and sorted
+    /// lexicographically, so they can be iterated in ranges and dumped into
+    /// sorted term dictionary of disk segment as is
+    pub terms: BTreeMap<String, TermPostingList>,
+    /// Count of terms in each field of each document, in form of vectors for
+    /// each field ordinal, that can be indexed with [`SegmentDocId`].
+    pub doc_term_counts: Vec<Vec<u16>>,
     /// Stored documents, in form of vector that can be indexed with
     /// [`SegmentDocId`].
     pub docs: Vec<StoredDoc>,
+    /// Original fields of documents, in form of vector that can be indexed
+    /// with [`SegmentDocId`]. `None` if fields are not stored
+    pub stored_fields: Option<Vec<Vec<DocField>>>,
+    /// Columns of typed values of documents, with codes of values that can be
+    /// indexed with [`SegmentDocId`]
+    pub doc_values: Vec<(DocValuesColumnInfo, Vec<U64>)>,
     pub stats: IndexSegmentStats,
+    /// Whether term positions are recorded in postings
+    pub has_positions: bool,
+}
+
+pub type TermPostingList = BTreeMap<SegmentDocId, MemoryDocPosting>;
+
+/// Sums up frequencies of the term in all postings of its posting list.
+pub fn get_term_freq_total(posting_list: &TermPostingList) -> u64 {
+    posting_list
+        .values()
+        .map(|entry| u64::from(entry.posting.term_freq))
+        .sum()
 }
 
-pub type TermPostingList = BTreeMap<SegmentDocId, DocPosting>;
+pub struct MemoryDocPosting {
+    pub posting: DocPosting,
+
+    /// Sorted positions of the term in the document.
+    /// Empty if positions are not recorded
+    pub positions: Vec<TermPosition>,
+}
 
 impl Index for MemoryIndex {
     fn get_segments(&self) -> Vec<&dyn IndexSegment> {
@@ -32,26 +69,66 @@ impl Index for MemoryIndex {
 impl IndexSegment for MemoryIndex {
     fn get_doc_postings_for_term<'a>(
         &'a self,
+        field: &str,
         term: &str,
     ) -> Result<Option<DocPostingsForTerm<'a>>> {
-        let term_posting_list = self.terms.get(term);
+        let term_posting_list =
+            self.terms.get(&make_field_term_key(field, term));
 
         term_posting_list.map_or_else(
             || Ok(None),
             |list| {
                 Ok(Some(DocPostingsForTerm {
                     count: list.len(),
-                    iterator: Box::new(MemoryDocPostingsIterator::new(
-                        list.values(),
-                    )),
+                    // memory segments are small, so summing up frequencies
+                    // on each request is fine here
+                    term_freq_total: get_term_freq_total(list),
+                    iterator: Box::new(MemoryDocPostingsIterator::new(list)),
                 }))
             },
         )
     }
 
-    fn get_doc_terms_count(&self, docid: SegmentDocId) -> Result<Cow<'_, u16>> {
-        let count = self
-            .doc_term_counts
+    fn get_terms_in_range(
+        &self,
+        field: &str,
+        bounds: TermBounds,
+    ) -> TermsIterator<'_> {
+        Box::new(
+            self.terms
+                .range(make_field_term_key_bounds(field, bounds))
+                .map(|(key, _)| get_term(key)),
+        )
+    }
+
+    fn get_terms_matching<'a>(
+        &'a self,
+        field: &str,
+        automaton: &'a LevenshteinAutomaton,
+    ) -> TermsIterator<'a> {
+        // memory segments are small, so checking each term is fine here
+        Box::new(
+            self.get_terms_in_range(
+                field,
+                (Bound::Unbounded, Bound::Unbounded),
+            )
+            .filter(|term| {
+                term.as_ref()
+                    .map_or(true, |term| automaton.get_distance(term).is_some())
+            }),
+        )
+    }
+
+    fn get_doc_terms_count(
+        &self,
+        field: &str,
+        docid: SegmentDocId,
+    ) -> Result<Cow<'_, u16>> {
+        let (field_ordinal, _) = self
+            .stats
+            .get_field(field)
+            .context("field should exist in segment")?;
+        let count = self.doc_term_counts[field_ordinal]
             .get(docid as usize)
             .context("doc with such ID should exist in segment")?;
         Ok(Cow::Borrowed(count))
@@ -68,7 +145,42 @@ impl IndexSegment for MemoryIndex {
         Ok(Cow::Borrowed(doc))
     }
 
+    fn get_stored_fields(
+        &self,
+        docid: SegmentDocId,
+    ) -> Result<Option<Vec<DocField>>> {
+        let Some(stored_fields) = &self.stored_fields else {
+            return Ok(None);
+        };
+        let fields = stored_fields
+            .get(docid as usize)
+            .context("doc with such ID should exist in segment")?;
+        Ok(Some(fields.clone()))
+    }
+
+    fn get_doc_values_columns(&self) -> Result<Vec<DocValuesColumn<'_>>> {
+        Ok(self
+            .doc_values
+            .iter()
+            .map(|(info, codes)| DocValuesColumn::new(info, codes))
+            .collect())
+    }
+
     fn get_stats(&self) -> &IndexSegmentStats {
         &self.stats
     }
+
+    fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_positions(&self) -> bool {
+        self.has_positions
+    }
+}
+
+fn get_term(key: &str) -> Result<String> {
+    let (_, term) = split_field_term_key(key)
+        .context("term key should contain field name")?;
+    Ok(term.to_string())
 }
@@ -1,9 +1,11 @@
+pub mod automaton;
 mod build;
 mod disk;
+pub mod doc_values;
 mod memory;
 
 pub mod model;
-pub use build::{build_index, open_index};
+pub use build::{build_index, build_index_with_analyzer, open_index};
 pub use disk::DiskIndexOptions;
 pub use memory::MemoryIndex;
 
@@ -1,12 +1,87 @@
 use std::borrow::Cow;
+use std::ops::Bound;
 
 use anyhow::Result;
 
+use super::automaton::LevenshteinAutomaton;
 use super::disk::DiskIndexOptions;
-use crate::model::doc::ExternalDocId;
+use super::doc_values::DocValuesColumn;
+use crate::model::doc::{DocField, ExternalDocId};
 
 pub type Term = String;
 
+/// Lower and upper bounds of terms range, e.g. `(Included("a"), Excluded("c"))`
+/// for all terms starting with "a" or "b".
+pub type TermBounds<'a> = (Bound<&'a str>, Bound<&'a str>);
+
+/// Iterator over terms of a segment in lexicographic order.
+pub type TermsIterator<'a> = Box<dyn Iterator<Item = Result<Term>> + 'a>;
+
+/// Separates field name from term in keys of term dictionary.
+///
+/// Terms never contain zero byte, and it goes before any other character, so
+/// terms of a field go in a row, and in the same order as without the field.
+const FIELD_TERM_SEPARATOR: char = '\0';
+
+/// Character right after the separator, so keys of all terms of a field are
+/// lower than field name followed by it.
+const FIELD_TERMS_END: char = '\x01';
+
+/// Makes key of the term in term dictionary of a segment, e.g. "title\0cat".
+///
+/// Segment keeps terms of all fields in single sorted dictionary, where terms
+/// of each field are prefixed with field name. Tantivy builds its `Term` the
+/// same way, with field ID in front of the value.
+#[must_use]
+pub fn make_field_term_key(field: &str, term: &str) -> String {
+    let mut key = String::with_capacity(field.len() + 1 + term.len());
+    key.push_str(field);
+    key.push(FIELD_TERM_SEPARATOR);
+    key.push_str(term);
+    key
+}
+
+/// Splits key of term dictionary into field name and term.
+#[must_use]
+pub fn split_field_term_key(key: &str) -> Option<(&str, &str)> {
+    key.split_once(FIELD_TERM_SEPARATOR)
+}
+
+/// Converts bounds of terms of a field into bounds of term dictionary keys.
+#[must_use]
+pub fn make_field_term_key_bounds(
+    field: &str,
+    bounds: TermBounds,
+) -> (Bound<String>, Bound<String>) {
+    let (lower, upper) = bounds;
+
+    let lower = match lower {
+        Bound::Included(term) => {
+            Bound::Included(make_field_term_key(field, term))
+        }
+        Bound::Excluded(term) => {
+            Bound::Excluded(make_field_term_key(field, term))
+        }
+        Bound::Unbounded => Bound::Included(make_field_term_key(field, "")),
+    };
+
+    let upper = match upper {
+        Bound::Included(term) => {
+            Bound::Included(make_field_term_key(field, term))
+        }
+        Bound::Excluded(term) => {
+            Bound::Excluded(make_field_term_key(field, term))
+        }
+        Bound::Unbounded => {
+            let mut key = field.to_string();
+            key.push(FIELD_TERMS_END);
+            Bound::Excluded(key)
+        }
+    };
+
+    (lower, upper)
+}
+
 /// Unique identifier of a document inside an index segment.
 ///
 /// This is a simple sequential index, in the order doc was supplied to the
@@ -17,6 +92,10 @@ pub type Term = String;
 /// And a sequence of small, sorted numbers can be compressed much better.
 pub type SegmentDocId = u32;
 
+/// Position of a term inside a document field, i.e. number of indexed terms of
+/// the field in front of it.
+pub type TermPosition = u32;
+
 pub enum IndexMedium {
     /// Index built and used entirely in RAM.
     /// - Suitable for small indices that fit in memory
@@ -28,7 +107,7 @@ pub enum IndexMedium {
     /// Index persisted to the file system on the disk.
     /// - Supports large indices by splitting into segments
     /// - Total size limited mainly by disk space
-    /// - Most data is on disk; term dictionary is memory-resident
+    /// - Data is memory-mapped from disk, including term dictionary
     Disk(DiskIndexOptions),
 }
 
@@ -41,35 +120,112 @@ pub trait Index {
 /// Segments can be built and searched independently from each other.
 ///
 /// This trait is abstraction for in-memory and on-disk index implementations.
-// TODO: fix cross-segment relevance skew for better ranking.
-// - the problem: each segment maintains unique statistics that impact relevance
-//   scoring. e.g., searching a single 10-document segment may yield different
-//   results than searching ten 1-document segments. while document balancing
-//   typically prevents significant skew, relevance remains inconsistent in
-//   smaller, final segments
-// - use approach from lucene/tantivy: maintain local segment stats in memory,
-//   compute global stats for query terms on the fly, and pass it along with the
-//   query to segments, so all segments score their local results using global
-//   term statistics
-pub trait IndexSegment {
+///
+/// Each text field of documents is indexed separately, i.e. it has its own
+/// terms, postings and document lengths, so all of them are requested for
+/// specific field.
+///
+/// Note: statistics of a segment describe only documents of this segment, so
+/// they should not be used for scoring directly. Otherwise the same document
+/// would get different scores depending on which segment it lands in. Search
+/// routine aggregates them into index-wide statistics instead.
+///
+/// Segments are searched in parallel, so they should be shareable between
+/// threads.
+pub trait IndexSegment: Sync {
     fn get_doc_postings_for_term<'a>(
         &'a self,
+        field: &str,
         term: &str,
     ) -> Result<Option<DocPostingsForTerm<'a>>>;
 
-    fn get_doc_terms_count(&self, docid: SegmentDocId) -> Result<Cow<'_, u16>>;
+    /// Iterates terms of the field within `bounds`, in lexicographic order.
+    ///
+    /// This is the basis for queries that expand into many terms, like prefix
+    /// or fuzzy ones.
+    fn get_terms_in_range(
+        &self,
+        field: &str,
+        bounds: TermBounds,
+    ) -> TermsIterator<'_>;
+
+    /// Iterates terms of the field starting with `prefix`, in lexicographic
+    /// order.
+    fn get_terms_with_prefix(
+        &self,
+        field: &str,
+        prefix: &str,
+    ) -> TermsIterator<'_> {
+        let prefix = prefix.to_string();
+        let terms = self.get_terms_in_range(
+            field,
+            (Bound::Included(&prefix), Bound::Unbounded),
+        );
+
+        // terms with the same prefix go in a row, so stop at the first one
+        // without it
+        Box::new(terms.take_while(move |term| {
+            term.as_ref().map_or(true, |term| term.starts_with(&prefix))
+        }))
+    }
+
+    /// Iterates terms of the field accepted by `automaton`, i.e. close enough
+    /// to its word, in lexicographic order.
+    fn get_terms_matching<'a>(
+        &'a self,
+        field: &str,
+        automaton: &'a LevenshteinAutomaton,
+    ) -> TermsIterator<'a>;
+
+    /// Gets number of terms in the field of the document.
+    fn get_doc_terms_count(
+        &self,
+        field: &str,
+        docid: SegmentDocId,
+    ) -> Result<Cow<'_, u16>>;
 
     fn get_stored_doc(&self, docid: SegmentDocId)
     -> Result<Cow<'_, StoredDoc>>;
 
+    /// Gets original fields of the document, or `None` if fields were not
+    /// stored while building the segment.
+    fn get_stored_fields(
+        &self,
+        docid: SegmentDocId,
+    ) -> Result<Option<Vec<DocField>>>;
+
+    /// Gets columns of typed values of documents, in order their fields first
+    /// appear in documents.
+    fn get_doc_values_columns(&self) -> Result<Vec<DocValuesColumn<'_>>>;
+
+    /// Gets column of typed values of the field, or `None` if no document of
+    /// the segment has values of such field.
+    fn get_doc_values(
+        &self,
+        field: &str,
+    ) -> Result<Option<DocValuesColumn<'_>>> {
+        Ok(self
+            .get_doc_values_columns()?
+            .into_iter()
+            .find(|column| column.info.name == field))
+    }
+
     fn get_stats(&self) -> &IndexSegmentStats;
+
+    /// Gets ID of the segment, which is unique across all indices and stays
+    /// the same when index is opened again, e.g. to cache data of the segment.
+    fn get_id(&self) -> &str;
+
+    /// Checks if term positions were recorded while building the segment.
+    /// Without them phrase queries cannot be answered.
+    fn has_positions(&self) -> bool;
 }
 
-/// Doc fields stored inside index.
+/// Doc fields stored inside index, that are needed to answer search queries.
 ///
-/// Currently just bare minimum is stored - external doc IDs to answer
-/// search queries with. Do not need to store doc text, since we do not support
-/// snippets or any other feature that requires source doc text yet.
+/// It has fixed size, so it can be read by [`SegmentDocId`] fast while
+/// collecting search results. Original text of the document is stored
+/// separately, see [`IndexSegment::get_stored_fields`].
 #[derive(Clone, Debug)]
 pub struct StoredDoc {
     pub docid: ExternalDocId,
@@ -84,8 +240,86 @@ pub struct IndexSegmentStats {
     /// Largest posting list size in this segment
     pub max_posting_list_size: u64,
 
-    /// Average number of terms per document
+    /// Statistics of each text field, in order fields go in documents.
+    ///
+    /// Position of the field in this list is the field ordinal inside the
+    /// segment.
+    pub fields: Vec<FieldStats>,
+}
+
+impl IndexSegmentStats {
+    /// Gets ordinal and statistics of the field, or `None` if segment does not
+    /// have such field.
+    #[must_use]
+    pub fn get_field(&self, field: &str) -> Option<(usize, &FieldStats)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, stats)| stats.name == field)
+    }
+
+    /// Total number of terms in all fields of all documents of the segment
+    #[must_use]
+    pub fn get_terms_count_total(&self) -> u64 {
+        self.fields
+            .iter()
+            .map(|field| field.terms_count_total)
+            .sum()
+    }
+
+    /// Number of distinct terms in the segment. Same word in different fields
+    /// counts as different terms
+    #[must_use]
+    pub fn get_unique_terms_count(&self) -> u64 {
+        self.fields
+            .iter()
+            .map(|field| field.unique_terms_count)
+            .sum()
+    }
+}
+
+/// Statistics of a text field in a segment.
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct FieldStats {
+    pub name: String,
+
+    /// Total number of terms in this field of all documents of the segment
+    pub terms_count_total: u64,
+
+    /// Average number of terms in this field per document
     pub terms_count_per_doc_avg: f64,
+
+    /// Number of distinct terms in this field of all documents of the segment
+    pub unique_terms_count: u64,
+
+    /// Number of documents by count of terms in this field (i.e. document
+    /// length), in log-scaled buckets, see [`get_doc_terms_count_bucket`].
+    ///
+    /// Documents without the field fall into the first bucket. Buckets after
+    /// the last non-empty one are omitted.
+    pub doc_terms_count_histogram: Vec<u64>,
+}
+
+/// Gets bucket of document length histogram, that document with
+/// `doc_terms_count` terms falls into.
+///
+/// First bucket holds empty documents, and each next one holds documents twice
+/// as long as previous one: 1, 2-3, 4-7, 8-15, etc.
+#[must_use]
+pub const fn get_doc_terms_count_bucket(doc_terms_count: u16) -> usize {
+    (u16::BITS - doc_terms_count.leading_zeros()) as usize
+}
+
+/// Gets lowest and highest document length, that fall into bucket of document
+/// length histogram, see [`get_doc_terms_count_bucket`].
+#[must_use]
+pub const fn get_doc_terms_count_bucket_bounds(bucket: usize) -> (u16, u16) {
+    if bucket == 0 {
+        (0, 0)
+    } else {
+        let lower = 1_u32 << (bucket - 1);
+        (lower as u16, (lower * 2 - 1) as u16)
+    }
 }
 
 /// Posting list (inverted list): references to documents containing a term.
@@ -96,12 +330,76 @@ pub struct DocPostingsForTerm<'a> {
     /// Total number of postings, that can be read through the iterator
     pub count: usize,
 
+    /// Total number of occurrences of the term in all documents of the
+    /// segment (a.k.a. collection term frequency), i.e. sum of term
+    /// frequencies of all postings
+    pub term_freq_total: u64,
+
     /// Iterator over postings
-    pub iterator: DocPostingsIterator<'a>,
+    pub iterator: Box<dyn DocPostingsIterator<'a> + 'a>,
+}
+
+/// Iterator over postings of a term, which are grouped into blocks.
+///
+/// Besides reading postings one by one, it allows to look at block metadata
+/// and to skip whole blocks without decoding them. This is what makes dynamic
+/// pruning possible, i.e. skipping documents which cannot get into top results
+/// without scoring them (see [`DocPostingsBlockMeta`]).
+pub trait DocPostingsIterator<'a>:
+    Iterator<Item = Result<Cow<'a, DocPosting>>>
+{
+    /// Skips whole blocks of postings with docids lower than `target`.
+    ///
+    /// Postings lower than `target` still can be returned after that, but
+    /// only from the block which contains `target`, so caller should keep
+    /// reading until it reaches the target.
+    fn skip_to(&mut self, target: SegmentDocId) -> Result<()>;
+
+    /// Gets metadata of the block, that may contain `target` docid, without
+    /// moving the iterator. Block of the posting, that was returned last, is
+    /// taken into account too.
+    ///
+    /// Returns `None` if there are no postings with docids equal to or greater
+    /// than `target` left.
+    fn peek_block_meta(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<Option<DocPostingsBlockMeta>>;
+
+    /// Stops reading term frequencies of next postings, when only docids are
+    /// needed, e.g. for filtering. Postings may get zero term frequencies
+    /// after that, and their positions may not be read.
+    fn skip_term_freqs(&mut self);
+
+    /// Reads sorted positions of the term in the document of the posting, that
+    /// was returned last, into `output`.
+    ///
+    /// Positions are decoded lazily, so only documents which need them (e.g.
+    /// candidates for phrase match) pay for reading.
+    fn read_positions(&mut self, output: &mut Vec<TermPosition>) -> Result<()>;
 }
 
-type DocPostingsIterator<'a> =
-    Box<dyn Iterator<Item = Result<Cow<'a, DocPosting>>> + 'a>;
+/// Summary of postings in a block, that allows to estimate upper bound of
+/// scores of its documents without reading postings themselves.
+///
+/// Max term frequency and min document length are stored instead of max
+/// score, since score depends on index-wide statistics, which change as index
+/// gets more segments. Since BM25 grows with term frequency and decreases with
+/// document length, scoring such imaginary posting gives upper bound for any
+/// real posting in the block.
+///
+/// Lucene and Tantivy store similar pairs as "impacts" and "block WAND" data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DocPostingsBlockMeta {
+    /// Largest document ID in the block
+    pub last_docid: SegmentDocId,
+
+    /// Largest term frequency among postings in the block
+    pub max_term_freq: u32,
+
+    /// Smallest number of terms among documents of the block
+    pub min_doc_terms_count: u16,
+}
 
 /// Reference to document containing specific term.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::ops::Bound::{Excluded, Included, Unbounded};
 
 use anyhow::{Context, Result};
 use tempfile::TempDir;
@@ -7,10 +8,16 @@ use super::disk::DiskIndexOptions;
 use super::model::IndexMedium;
 use super::*;
 use crate::engines::nano::index::model::{
-    DocPosting, Index, IndexSegment, IndexSegmentStats,
+    DocPosting, FieldStats, Index, IndexSegment, IndexSegmentStats,
+    SegmentDocId, Term, TermPosition, TermsIterator,
+    get_doc_terms_count_bucket, get_doc_terms_count_bucket_bounds,
 };
+use crate::model::doc::{Doc, DocField, DocValue, DocValueField};
 use crate::utils::test_docs::create_cat_mouse_docs_iterator;
 use crate::utils::test_docs::docs::*;
+use crate::utils::test_docs_iterator::TestDocsIterator;
+
+const TEXT: &str = "text";
 
 #[test]
 fn test_build_memory_index() -> Result<()> {
@@ -70,10 +77,38 @@ fn assert_one_segment_index(index: &dyn Index) -> Result<()> {
 
     // assert no postings for unknown term
     {
-        let res = segment.get_doc_postings_for_term("xxx")?;
+        let res = segment.get_doc_postings_for_term(TEXT, "xxx")?;
         assert!(res.is_none(), "postings for term 'xxx' should not be found");
     }
 
+    // assert terms are iterated in lexicographic order
+    assert_eq!(
+        collect_terms(
+            segment.get_terms_in_range(TEXT, (Unbounded, Unbounded))
+        )?,
+        &["cat", "dog", "mouse"]
+    );
+    assert_eq!(
+        collect_terms(
+            segment
+                .get_terms_in_range(TEXT, (Excluded("cat"), Included("mouse")))
+        )?,
+        &["dog", "mouse"]
+    );
+    assert_eq!(
+        collect_terms(
+            segment.get_terms_in_range(TEXT, (Included("d"), Excluded("e")))
+        )?,
+        &["dog"]
+    );
+    assert_eq!(
+        collect_terms(segment.get_terms_with_prefix(TEXT, "mo"))?,
+        &["mouse"]
+    );
+    assert!(
+        collect_terms(segment.get_terms_with_prefix(TEXT, "x"))?.is_empty()
+    );
+
     // assert correct postings for known term
     assert_postings_for_term(
         segment,
@@ -98,14 +133,37 @@ fn assert_one_segment_index(index: &dyn Index) -> Result<()> {
         ],
     )?;
 
+    // assert correct term positions
+    assert!(segment.has_positions());
+    assert_positions_for_term(
+        segment,
+        "mouse",
+        &[
+            (MOUSE.index, vec![0]),
+            (DOG_MOUSE.index, vec![1]),
+            (CAT_MOUSE.index, vec![1]),
+            (CAT_MOUSE_CAT.index, vec![1]),
+        ],
+    )?;
+    assert_positions_for_term(
+        segment,
+        "cat",
+        &[
+            (CAT.index, vec![0]),
+            (CAT_DOG.index, vec![0]),
+            (CAT_MOUSE.index, vec![0]),
+            (CAT_MOUSE_CAT.index, vec![0, 2]),
+        ],
+    )?;
+
     // assert correct doc terms counts
-    assert_eq!(*segment.get_doc_terms_count(CAT.index)?, 1);
-    assert_eq!(*segment.get_doc_terms_count(DOG.index)?, 1);
-    assert_eq!(*segment.get_doc_terms_count(MOUSE.index)?, 1);
-    assert_eq!(*segment.get_doc_terms_count(CAT_DOG.index)?, 2);
-    assert_eq!(*segment.get_doc_terms_count(DOG_MOUSE.index)?, 2);
-    assert_eq!(*segment.get_doc_terms_count(CAT_MOUSE.index)?, 2);
-    assert_eq!(*segment.get_doc_terms_count(CAT_MOUSE_CAT.index)?, 3);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, CAT.index)?, 1);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, DOG.index)?, 1);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, MOUSE.index)?, 1);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, CAT_DOG.index)?, 2);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, DOG_MOUSE.index)?, 2);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, CAT_MOUSE.index)?, 2);
+    assert_eq!(*segment.get_doc_terms_count(TEXT, CAT_MOUSE_CAT.index)?, 3);
 
     // assert correct index statistics
     assert_eq!(
@@ -113,9 +171,50 @@ fn assert_one_segment_index(index: &dyn Index) -> Result<()> {
         &IndexSegmentStats {
             indexed_docs_count: 7,
             max_posting_list_size: 4, // docs with "cat" term
-            terms_count_per_doc_avg: 12.0 / 7.0,
+            fields: vec![FieldStats {
+                name: TEXT.to_string(),
+                terms_count_total: 12,
+                terms_count_per_doc_avg: 12.0 / 7.0,
+                unique_terms_count: 3,
+                doc_terms_count_histogram: vec![0, 3, 4],
+            }],
         }
     );
+    assert_eq!(segment.get_stats().get_terms_count_total(), 12);
+    assert_eq!(segment.get_stats().get_unique_terms_count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_build_disk_index_without_positions_and_open() -> Result<()> {
+    // setup
+    let mut docs_it = create_cat_mouse_docs_iterator();
+    let dir = TempDir::new()?;
+    let medium = IndexMedium::Disk(
+        DiskIndexOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .record_positions(false)
+            .build(),
+    );
+
+    // execute
+    build_index(&medium, &mut docs_it)?;
+    let index = open_index(&medium)?;
+
+    // assert
+    let segments = index.get_segments();
+    assert_eq!(segments.len(), 1);
+    assert!(!segments[0].has_positions());
+
+    let mut postings = segments[0]
+        .get_doc_postings_for_term(TEXT, "cat")?
+        .context("postings for term 'cat' should be found")?;
+    postings.iterator.next().context("posting should exist")??;
+
+    let mut positions = Vec::new();
+    assert!(postings.iterator.read_positions(&mut positions).is_err());
 
     Ok(())
 }
@@ -161,6 +260,42 @@ fn test_build_disk_index_with_multiple_segments_and_open() -> Result<()> {
     assert_multiple_segments_index(index.as_ref())
 }
 
+#[test]
+fn test_open_disk_index_with_other_format_version_is_error() -> Result<()> {
+    // setup
+    let mut docs_it = create_cat_mouse_docs_iterator();
+    let dir = TempDir::new()?;
+    let medium = IndexMedium::Disk(
+        DiskIndexOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .build(),
+    );
+    build_index(&medium, &mut docs_it)?;
+
+    // segment of any other layout, either unversioned or of future version,
+    // has some other value where Stats file starts with format version
+    let segment_dir = std::fs::read_dir(dir.path())?
+        .next()
+        .context("segment dir should exist")??
+        .path();
+    let stats_path = segment_dir.join("stats");
+    let mut stats = std::fs::read(&stats_path)?;
+    stats[..4].copy_from_slice(&7_u32.to_le_bytes());
+    std::fs::write(&stats_path, stats)?;
+
+    // execute
+    let err = open_index(&medium).err().context("open should fail")?;
+
+    // assert
+    assert!(
+        err.to_string()
+            .contains("has format version 7, but only version 1 is supported"),
+        "{err}"
+    );
+    Ok(())
+}
+
 fn assert_multiple_segments_index(index: &dyn Index) -> Result<()> {
     let segments = index.get_segments();
 
@@ -196,17 +331,23 @@ fn assert_multiple_segments_index(index: &dyn Index) -> Result<()> {
             ],
         )?;
 
-        assert_eq!(*first_segment.get_doc_terms_count(CAT.index)?, 1);
-        assert_eq!(*first_segment.get_doc_terms_count(DOG.index)?, 1);
-        assert_eq!(*first_segment.get_doc_terms_count(MOUSE.index)?, 1);
-        assert_eq!(*first_segment.get_doc_terms_count(CAT_DOG.index)?, 2);
+        assert_eq!(*first_segment.get_doc_terms_count(TEXT, CAT.index)?, 1);
+        assert_eq!(*first_segment.get_doc_terms_count(TEXT, DOG.index)?, 1);
+        assert_eq!(*first_segment.get_doc_terms_count(TEXT, MOUSE.index)?, 1);
+        assert_eq!(*first_segment.get_doc_terms_count(TEXT, CAT_DOG.index)?, 2);
 
         assert_eq!(
             first_segment.get_stats(),
             &IndexSegmentStats {
                 indexed_docs_count: 4,
                 max_posting_list_size: 2,
-                terms_count_per_doc_avg: 5.0 / 4.0
+                fields: vec![FieldStats {
+                    name: TEXT.to_string(),
+                    terms_count_total: 5,
+                    terms_count_per_doc_avg: 5.0 / 4.0,
+                    unique_terms_count: 3,
+                    doc_terms_count_histogram: vec![0, 3, 1],
+                }],
             }
         );
     }
@@ -233,16 +374,26 @@ fn assert_multiple_segments_index(index: &dyn Index) -> Result<()> {
             ],
         )?;
 
+        assert_positions_for_term(
+            second_segment,
+            "cat",
+            &[
+                (sh(CAT_MOUSE.index), vec![0]),
+                (sh(CAT_MOUSE_CAT.index), vec![0, 2]),
+            ],
+        )?;
+
         assert_eq!(
-            *second_segment.get_doc_terms_count(sh(DOG_MOUSE.index))?,
+            *second_segment.get_doc_terms_count(TEXT, sh(DOG_MOUSE.index))?,
             2
         );
         assert_eq!(
-            *second_segment.get_doc_terms_count(sh(CAT_MOUSE.index))?,
+            *second_segment.get_doc_terms_count(TEXT, sh(CAT_MOUSE.index))?,
             2
         );
         assert_eq!(
-            *second_segment.get_doc_terms_count(sh(CAT_MOUSE_CAT.index))?,
+            *second_segment
+                .get_doc_terms_count(TEXT, sh(CAT_MOUSE_CAT.index))?,
             3
         );
 
@@ -251,7 +402,13 @@ fn assert_multiple_segments_index(index: &dyn Index) -> Result<()> {
             &IndexSegmentStats {
                 indexed_docs_count: 3,
                 max_posting_list_size: 3,
-                terms_count_per_doc_avg: 7.0 / 3.0
+                fields: vec![FieldStats {
+                    name: TEXT.to_string(),
+                    terms_count_total: 7,
+                    terms_count_per_doc_avg: 7.0 / 3.0,
+                    unique_terms_count: 3,
+                    doc_terms_count_histogram: vec![0, 0, 3],
+                }],
             }
         );
     }
@@ -259,13 +416,290 @@ fn assert_multiple_segments_index(index: &dyn Index) -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_build_index_with_multiple_fields() -> Result<()> {
+    // setup
+    let dir = TempDir::new()?;
+    let mediums = [
+        IndexMedium::Memory,
+        IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .build(),
+        ),
+    ];
+
+    for medium in &mediums {
+        // second doc has no title, so its title length should be zero
+        let mut docs = TestDocsIterator::from_enumerated_fields(vec![
+            vec![("title", "cat"), (TEXT, "dog")],
+            vec![(TEXT, "cat mouse")],
+        ]);
+
+        // execute
+        let index = build_index(medium, &mut docs)?;
+
+        // assert
+        let segments = index.get_segments();
+        assert_eq!(segments.len(), 1);
+        let segment = segments[0];
+
+        assert_eq!(
+            collect_terms(
+                segment.get_terms_in_range("title", (Unbounded, Unbounded))
+            )?,
+            &["cat"]
+        );
+        assert_eq!(
+            collect_terms(
+                segment.get_terms_in_range(TEXT, (Unbounded, Unbounded))
+            )?,
+            &["cat", "dog", "mouse"]
+        );
+        assert!(segment.get_doc_postings_for_term("title", "dog")?.is_none());
+        assert!(
+            segment
+                .get_doc_postings_for_term("author", "cat")?
+                .is_none()
+        );
+
+        assert_eq!(*segment.get_doc_terms_count("title", 0)?, 1);
+        assert_eq!(*segment.get_doc_terms_count("title", 1)?, 0);
+        assert_eq!(*segment.get_doc_terms_count(TEXT, 0)?, 1);
+        assert_eq!(*segment.get_doc_terms_count(TEXT, 1)?, 2);
+        assert!(segment.get_doc_terms_count("author", 0).is_err());
+
+        assert_eq!(
+            segment.get_stats(),
+            &IndexSegmentStats {
+                indexed_docs_count: 2,
+                max_posting_list_size: 1,
+                fields: vec![
+                    FieldStats {
+                        name: "title".to_string(),
+                        terms_count_total: 1,
+                        terms_count_per_doc_avg: 0.5,
+                        unique_terms_count: 1,
+                        doc_terms_count_histogram: vec![1, 1],
+                    },
+                    FieldStats {
+                        name: TEXT.to_string(),
+                        terms_count_total: 3,
+                        terms_count_per_doc_avg: 1.5,
+                        unique_terms_count: 3,
+                        doc_terms_count_histogram: vec![0, 1, 1],
+                    },
+                ],
+            }
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_build_index_with_stored_fields() -> Result<()> {
+    // setup
+    let dir = TempDir::new()?;
+    let medium = IndexMedium::Disk(
+        DiskIndexOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .max_segment_docs(30)
+            .build(),
+    );
+
+    // enough docs for multiple compressed blocks in each segment
+    let docs_fields = (0..50)
+        .map(|id| {
+            vec![
+                DocField::new("title", format!("title {id}")),
+                DocField::new(TEXT, "cat ".repeat(id)),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        docs_fields.iter().map(|fields| {
+            fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.text.as_str()))
+        }),
+    );
+
+    // execute
+    build_index(&medium, &mut docs)?;
+    let index = open_index(&medium)?;
+
+    // assert
+    let segments = index.get_segments();
+    assert_eq!(segments.len(), 2);
+
+    for segment in segments {
+        for docid in 0..segment.get_stats().indexed_docs_count {
+            let docid = docid as SegmentDocId;
+            let external_docid = segment.get_stored_doc(docid)?.docid;
+
+            assert_eq!(
+                segment.get_stored_fields(docid)?.as_ref(),
+                Some(&docs_fields[external_docid as usize])
+            );
+        }
+
+        let docs_count = segment.get_stats().indexed_docs_count;
+        assert!(
+            segment
+                .get_stored_fields(docs_count as SegmentDocId)
+                .is_err()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_build_index_without_stored_fields() -> Result<()> {
+    // setup
+    let mut docs_it = create_cat_mouse_docs_iterator();
+    let dir = TempDir::new()?;
+    let medium = IndexMedium::Disk(
+        DiskIndexOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .store_fields(false)
+            .build(),
+    );
+
+    // execute
+    build_index(&medium, &mut docs_it)?;
+    let index = open_index(&medium)?;
+
+    // assert
+    let segment = index.get_segments()[0];
+    assert_eq!(segment.get_stored_doc(0)?.docid, 0);
+    assert!(segment.get_stored_fields(0)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_build_index_with_doc_values() -> Result<()> {
+    let dir = TempDir::new()?;
+    let disk_medium = IndexMedium::Disk(
+        DiskIndexOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .max_segment_docs(30)
+            .build(),
+    );
+
+    // setup
+    // ratings are missing in odd docs, so some columns have missing values
+    let docs_values = (0..50_u64)
+        .map(|id| {
+            let mut values = vec![
+                DocValueField::new("count", DocValue::U64(id * 10)),
+                DocValueField::new(
+                    "date",
+                    DocValue::Date(id.cast_signed() - 25),
+                ),
+                DocValueField::new(
+                    "tag",
+                    DocValue::Keyword(
+                        ["mouse", "cat", "dog"][id as usize % 3].into(),
+                    ),
+                ),
+            ];
+            if id % 2 == 0 {
+                values.push(DocValueField::new(
+                    "rating",
+                    DocValue::F64(id as f64 / -4.0),
+                ));
+            }
+            values.sort_by(|a, b| a.name.cmp(&b.name));
+            values
+        })
+        .collect::<Vec<_>>();
+
+    for medium in [IndexMedium::Memory, disk_medium] {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            docs_values.iter().map(|_| [(TEXT, "cat")]),
+        )
+        .with_values(docs_values.clone());
+
+        // execute
+        let index = build_index(&medium, &mut docs)?;
+        let index = match medium {
+            IndexMedium::Memory => index,
+            IndexMedium::Disk(_) => open_index(&medium)?,
+        };
+
+        // assert
+        for segment in index.get_segments() {
+            for docid in 0..segment.get_stats().indexed_docs_count {
+                let docid = docid as SegmentDocId;
+                let external_docid = segment.get_stored_doc(docid)?.docid;
+
+                let mut values = Vec::new();
+                for column in segment.get_doc_values_columns()? {
+                    if let Some(value) = column.get_value(docid)? {
+                        values
+                            .push(DocValueField::new(&column.info.name, value));
+                    }
+                }
+                values.sort_by(|a, b| a.name.cmp(&b.name));
+
+                assert_eq!(values, docs_values[external_docid as usize]);
+            }
+
+            assert!(segment.get_doc_values("tag")?.is_some());
+            assert!(segment.get_doc_values("unknown")?.is_none());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_build_index_with_mismatched_doc_value_types() {
+    let mut docs =
+        TestDocsIterator::from_enumerated_fields([[(TEXT, "cat")]; 2])
+            .with_values(
+                [DocValue::U64(1), DocValue::Date(1)]
+                    .map(|value| vec![DocValueField::new("count", value)]),
+            );
+
+    let res = build_index(&IndexMedium::Memory, &mut docs);
+
+    assert_eq!(
+        res.err().map(|err| err.to_string()).as_deref(),
+        Some("doc values of field 'count' should have the same type")
+    );
+}
+
+#[test]
+fn test_build_index_with_duplicate_fields() {
+    let mut docs = std::iter::once(Ok(Doc {
+        id: 0,
+        fields: vec![DocField::new(TEXT, "cat"), DocField::new(TEXT, "dog")],
+        values: Vec::new(),
+    }));
+
+    let res = build_index(&IndexMedium::Memory, &mut docs);
+
+    assert_eq!(
+        res.err().map(|err| err.to_string()).as_deref(),
+        Some("doc fields should have unique names")
+    );
+}
+
 fn assert_postings_for_term(
     segment: &dyn IndexSegment,
     term: &str,
     expected_postings: &[DocPosting],
 ) -> Result<()> {
     let postings_it = segment
-        .get_doc_postings_for_term(term)?
+        .get_doc_postings_for_term(TEXT, term)?
         .context(format!("postings for term '{term}' should be found"))?;
     let postings = postings_it
         .iterator
@@ -277,8 +711,53 @@ fn assert_postings_for_term(
         .collect::<Vec<Cow<'_, DocPosting>>>();
 
     assert_eq!(postings_it.count, postings.len());
+    assert_eq!(
+        postings_it.term_freq_total,
+        postings.iter().map(|p| u64::from(p.term_freq)).sum::<u64>()
+    );
     assert!(postings.iter().map(|p| p.docid).is_sorted());
     assert_eq!(postings, expected_postings);
 
     Ok(())
 }
+
+fn assert_positions_for_term(
+    segment: &dyn IndexSegment,
+    term: &str,
+    expected_positions: &[(SegmentDocId, Vec<TermPosition>)],
+) -> Result<()> {
+    let mut postings = segment
+        .get_doc_postings_for_term(TEXT, term)?
+        .context(format!("postings for term '{term}' should be found"))?;
+
+    let mut actual_positions = Vec::new();
+
+    while let Some(posting) = postings.iterator.next().transpose()? {
+        let docid = posting.docid;
+        let mut positions = Vec::new();
+        postings.iterator.read_positions(&mut positions)?;
+        actual_positions.push((docid, positions));
+    }
+
+    assert_eq!(actual_positions, expected_positions);
+
+    Ok(())
+}
+
+fn collect_terms(terms: TermsIterator) -> Result<Vec<Term>> {
+    terms.collect()
+}
+
+#[test]
+fn test_doc_terms_count_histogram_buckets() {
+    let buckets: Vec<usize> = [0, 1, 2, 3, 4, 7, 8, u16::MAX]
+        .into_iter()
+        .map(get_doc_terms_count_bucket)
+        .collect();
+    assert_eq!(buckets, &[0, 1, 2, 2, 3, 3, 4, 16]);
+
+    assert_eq!(get_doc_terms_count_bucket_bounds(0), (0, 0));
+    assert_eq!(get_doc_terms_count_bucket_bounds(1), (1, 1));
+    assert_eq!(get_doc_terms_count_bucket_bounds(3), (4, 7));
+    assert_eq!(get_doc_terms_count_bucket_bounds(16), (32_768, u16::MAX));
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+
+use super::histogram_facet::{
+    HistogramFacetCollector, HistogramFacetSegmentCollector,
+};
+use super::model::{Collector, SegmentCollector};
+use super::range_facet::{RangeFacetCollector, RangeFacetSegmentCollector};
+use super::term_facet::{TermFacetCollector, TermFacetSegmentCollector};
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+use crate::model::search::{Facet, FacetBucket, FacetRequest};
+
+/// Collector of any requested facet, so facets of different kinds can be
+/// aggregated together in a single iteration, e.g. as a vector of collectors.
+pub enum FacetCollector {
+    Terms(TermFacetCollector),
+    Ranges(RangeFacetCollector),
+    Histogram(HistogramFacetCollector),
+}
+
+impl FacetCollector {
+    pub fn new(request: &FacetRequest) -> Self {
+        match request {
+            FacetRequest::Terms { field, limit } => {
+                Self::Terms(TermFacetCollector::new(field, *limit))
+            }
+            FacetRequest::Ranges { field, ranges } => {
+                Self::Ranges(RangeFacetCollector::new(field, ranges.clone()))
+            }
+            FacetRequest::Histogram { field, interval } => {
+                Self::Histogram(HistogramFacetCollector::new(field, *interval))
+            }
+        }
+    }
+}
+
+impl<'a> Collector<'a> for FacetCollector {
+    type SegmentCollector = FacetSegmentCollector<'a>;
+    type SegmentOutput = FacetSegmentOutput;
+    type Output = Facet;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        Ok(match self {
+            Self::Terms(collector) => FacetSegmentCollector::Terms(
+                collector.create_segment_collector(segment)?,
+            ),
+            Self::Ranges(collector) => FacetSegmentCollector::Ranges(
+                collector.create_segment_collector(segment)?,
+            ),
+            Self::Histogram(collector) => FacetSegmentCollector::Histogram(
+                collector.create_segment_collector(segment)?,
+            ),
+        })
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        match self {
+            Self::Terms(collector) => collector.merge_segment_outputs(
+                unwrap_outputs(outputs, |output| match output {
+                    FacetSegmentOutput::Terms(output) => Some(output),
+                    _ => None,
+                })?,
+            ),
+            Self::Ranges(collector) => collector.merge_segment_outputs(
+                unwrap_outputs(outputs, |output| match output {
+                    FacetSegmentOutput::Ranges(output) => Some(output),
+                    _ => None,
+                })?,
+            ),
+            Self::Histogram(collector) => collector.merge_segment_outputs(
+                unwrap_outputs(outputs, |output| match output {
+                    FacetSegmentOutput::Histogram(output) => Some(output),
+                    _ => None,
+                })?,
+            ),
+        }
+    }
+}
+
+/// Unwraps segment outputs of the same kind as the collector.
+fn unwrap_outputs<T>(
+    outputs: Vec<FacetSegmentOutput>,
+    unwrap: impl Fn(FacetSegmentOutput) -> Option<T>,
+) -> Result<Vec<T>> {
+    outputs
+        .into_iter()
+        .map(|output| match unwrap(output) {
+            Some(output) => Ok(output),
+            None => bail!("segment output should match facet collector"),
+        })
+        .collect()
+}
+
+pub enum FacetSegmentCollector<'a> {
+    Terms(TermFacetSegmentCollector<'a>),
+    Ranges(RangeFacetSegmentCollector<'a>),
+    Histogram(HistogramFacetSegmentCollector<'a>),
+}
+
+pub enum FacetSegmentOutput {
+    Terms(Vec<(String, u64)>),
+    Ranges(Vec<u64>),
+    Histogram(BTreeMap<i64, FacetBucket>),
+}
+
+impl SegmentCollector for FacetSegmentCollector<'_> {
+    type SegmentOutput = FacetSegmentOutput;
+
+    fn requires_score(&self) -> bool {
+        false
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        match self {
+            Self::Terms(collector) => collector.add_docid(docid),
+            Self::Ranges(collector) => collector.add_docid(docid),
+            Self::Histogram(collector) => collector.add_docid(docid),
+        }
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        Ok(match self {
+            Self::Terms(collector) => {
+                FacetSegmentOutput::Terms(collector.extract_output()?)
+            }
+            Self::Ranges(collector) => {
+                FacetSegmentOutput::Ranges(collector.extract_output()?)
+            }
+            Self::Histogram(collector) => {
+                FacetSegmentOutput::Histogram(collector.extract_output()?)
+            }
+        })
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+
+use anyhow::{Context, Result, bail, ensure};
+use chrono::{DateTime, Datelike, Utc};
+
+use super::model::{Collector, SegmentCollector};
+use crate::engines::nano::index::doc_values::{DocValueType, DocValuesColumn};
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+use crate::model::doc::DocValue;
+use crate::model::search::{Facet, FacetBucket, HistogramInterval};
+
+/// Collector that counts documents per interval of values of a number or
+/// date field, e.g. per year.
+///
+/// Intervals are numbered, so that intervals of all segments line up and go
+/// in order of values, and only intervals with documents get buckets.
+pub struct HistogramFacetCollector {
+    field: String,
+    interval: HistogramInterval,
+}
+
+impl HistogramFacetCollector {
+    pub fn new(field: impl Into<String>, interval: HistogramInterval) -> Self {
+        Self {
+            field: field.into(),
+            interval,
+        }
+    }
+}
+
+impl<'a> Collector<'a> for HistogramFacetCollector {
+    type SegmentCollector = HistogramFacetSegmentCollector<'a>;
+    type SegmentOutput = BTreeMap<i64, FacetBucket>;
+    type Output = Facet;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        if let HistogramInterval::Width(width) = self.interval {
+            ensure!(
+                width.is_finite() && width > 0.0,
+                "histogram interval should be positive, got {width}"
+            );
+        }
+
+        let column = segment.get_doc_values(&self.field)?;
+
+        if let Some(column) = &column {
+            let value_type = column.info.value_type;
+            match self.interval {
+                HistogramInterval::Width(_) => ensure!(
+                    value_type != DocValueType::Keyword,
+                    "histogram field '{}' should have number or date values",
+                    self.field
+                ),
+                HistogramInterval::Year | HistogramInterval::Month => ensure!(
+                    value_type == DocValueType::Date,
+                    "histogram field '{}' should have date values for \
+                     calendar interval",
+                    self.field
+                ),
+            }
+        }
+
+        Ok(HistogramFacetSegmentCollector {
+            column,
+            interval: self.interval,
+            buckets: BTreeMap::new(),
+        })
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        let mut buckets: BTreeMap<i64, FacetBucket> = BTreeMap::new();
+        for (idx, bucket) in outputs.into_iter().flatten() {
+            match buckets.entry(idx) {
+                Entry::Vacant(entry) => {
+                    entry.insert(bucket);
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().count += bucket.count;
+                }
+            }
+        }
+
+        Ok(Facet {
+            field: self.field.clone(),
+            buckets: buckets.into_values().collect(),
+        })
+    }
+}
+
+pub struct HistogramFacetSegmentCollector<'a> {
+    /// `None` if no document of the segment has value of the field
+    column: Option<DocValuesColumn<'a>>,
+    interval: HistogramInterval,
+
+    /// Buckets by number of interval
+    buckets: BTreeMap<i64, FacetBucket>,
+}
+
+impl HistogramFacetSegmentCollector<'_> {
+    /// Gets number of the interval which the value falls into, along with
+    /// key of its bucket, or `None` for values out of any interval, e.g.
+    /// `NaN`.
+    fn get_interval(&self, value: &DocValue) -> Result<Option<(i64, String)>> {
+        let HistogramInterval::Width(width) = self.interval else {
+            let DocValue::Date(timestamp) = value else {
+                bail!("calendar interval should only be used for dates");
+            };
+            let date = DateTime::<Utc>::from_timestamp(*timestamp, 0)
+                .context("date should be within supported range")?;
+
+            return Ok(Some(if self.interval == HistogramInterval::Year {
+                (i64::from(date.year()), date.format("%Y").to_string())
+            } else {
+                (
+                    i64::from(date.year()) * 12 + i64::from(date.month0()),
+                    date.format("%Y-%m").to_string(),
+                )
+            }));
+        };
+
+        let number = match value {
+            DocValue::U64(value) => *value as f64,
+            DocValue::F64(value) => *value,
+            DocValue::Date(value) => *value as f64,
+            DocValue::Keyword(_) => bail!("keyword should not be in histogram"),
+        };
+        if !number.is_finite() {
+            return Ok(None);
+        }
+
+        let idx = (number / width).floor();
+        let start = idx * width;
+        let key = match value {
+            DocValue::U64(_) => DocValue::U64(start as u64),
+            DocValue::Date(_) => DocValue::Date(start as i64),
+            _ => DocValue::F64(start),
+        };
+
+        Ok(Some((idx as i64, key.to_string())))
+    }
+}
+
+impl SegmentCollector for HistogramFacetSegmentCollector<'_> {
+    type SegmentOutput = BTreeMap<i64, FacetBucket>;
+
+    fn requires_score(&self) -> bool {
+        false
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        let Some(column) = &self.column else {
+            return Ok(());
+        };
+        let Some(value) = column.get_value(docid)? else {
+            return Ok(());
+        };
+
+        if let Some((idx, key)) = self.get_interval(&value)? {
+            self.buckets
+                .entry(idx)
+                .or_insert_with(|| FacetBucket::new(key, 0))
+                .count += 1;
+        }
+
+        Ok(())
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        Ok(self.buckets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::model::doc::DocValueField;
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    fn collect_facet(
+        collector: &HistogramFacetCollector,
+        segment_values: &[&[Option<DocValue>]],
+    ) -> Result<Vec<(String, u64)>> {
+        let mut outputs = Vec::new();
+        for values in segment_values {
+            let mut docs = TestDocsIterator::from_enumerated_fields(
+                values.iter().map(|_| [("text", "cat")]),
+            )
+            .with_values(values.iter().map(|value| {
+                value
+                    .iter()
+                    .map(|value| DocValueField::new("value", value.clone()))
+                    .collect()
+            }));
+            let index = build_index(&IndexMedium::Memory, &mut docs)?;
+            let segment = index.get_segments()[0];
+
+            let mut segment_collector =
+                collector.create_segment_collector(segment)?;
+            for docid in 0..values.len() as SegmentDocId {
+                segment_collector.add_docid(docid)?;
+            }
+            outputs.push(segment_collector.extract_output()?);
+        }
+
+        Ok(collector
+            .merge_segment_outputs(outputs)?
+            .buckets
+            .into_iter()
+            .map(|bucket| (bucket.key, bucket.count))
+            .collect())
+    }
+
+    fn buckets(buckets: &[(&str, u64)]) -> Vec<(String, u64)> {
+        buckets
+            .iter()
+            .map(|(key, count)| ((*key).to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram_of_numbers() -> Result<()> {
+        let len = |value| Some(DocValue::U64(value));
+        let score = |value| Some(DocValue::F64(value));
+
+        assert_eq!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "value",
+                    HistogramInterval::Width(100.0)
+                ),
+                &[&[len(150), None, len(5), len(420)], &[len(99), len(199)]]
+            )?,
+            buckets(&[("0", 2), ("100", 2), ("400", 1)])
+        );
+        assert_eq!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "value",
+                    HistogramInterval::Width(0.5)
+                ),
+                &[&[score(-0.2), score(0.7), score(f64::NAN), score(0.9)]]
+            )?,
+            buckets(&[("-0.5", 1), ("0.5", 2)])
+        );
+        assert!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "value",
+                    HistogramInterval::Width(0.0)
+                ),
+                &[&[len(1)]]
+            )
+            .is_err()
+        );
+        assert!(
+            collect_facet(
+                &HistogramFacetCollector::new("value", HistogramInterval::Year),
+                &[&[len(1)]]
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram_of_dates() -> Result<()> {
+        let date = |text| -> Result<Option<DocValue>> {
+            Ok(Some(DocValueType::Date.parse_value(text)?))
+        };
+        let segment_values: [&[Option<DocValue>]; 2] = [
+            &[date("2020-03-01")?, date("2021-12-31T23:59:59Z")?, None],
+            &[date("2020-03-15")?, date("1969-07-20")?],
+        ];
+
+        assert_eq!(
+            collect_facet(
+                &HistogramFacetCollector::new("value", HistogramInterval::Year),
+                &segment_values
+            )?,
+            buckets(&[("1969", 1), ("2020", 2), ("2021", 1)])
+        );
+        assert_eq!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "value",
+                    HistogramInterval::Month
+                ),
+                &segment_values
+            )?,
+            buckets(&[("1969-07", 1), ("2020-03", 2), ("2021-12", 1)])
+        );
+        assert_eq!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "value",
+                    HistogramInterval::Width(86_400.0 * 7.0)
+                ),
+                &segment_values[1..]
+            )?,
+            buckets(&[
+                ("1969-07-17T00:00:00Z", 1),
+                ("2020-03-12T00:00:00Z", 1)
+            ])
+        );
+        assert!(
+            collect_facet(
+                &HistogramFacetCollector::new(
+                    "unknown",
+                    HistogramInterval::Year
+                ),
+                &segment_values
+            )?
+            .is_empty()
+        );
+
+        Ok(())
+    }
+}
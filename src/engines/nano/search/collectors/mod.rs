@@ -1,3 +1,16 @@
 mod count;
+mod facet;
+mod histogram_facet;
 mod model;
+mod range_facet;
+mod sort;
+mod term_facet;
 mod top;
+mod tuple;
+mod vec;
+
+pub use count::CountCollector;
+pub use facet::FacetCollector;
+pub use model::{Collector, SegmentCollector};
+pub use sort::SortByFieldCollector;
+pub use top::TopCollector;
@@ -65,5 +65,12 @@ pub trait SegmentCollector {
         bail!("score should not be passed");
     }
 
+    /// Gets score, which documents should exceed to affect the output. Caller
+    /// may skip documents that cannot exceed it, without passing them to the
+    /// collector. `None` means that every document matters.
+    fn get_min_competitive_score(&self) -> Option<f64> {
+        None
+    }
+
     fn extract_output(self) -> Result<Self::SegmentOutput>;
 }
@@ -0,0 +1,241 @@
+use std::ops::{Bound, Range};
+
+use anyhow::{Context, Result, ensure};
+
+use super::model::{Collector, SegmentCollector};
+use crate::engines::nano::index::doc_values::{DocValueType, DocValuesColumn};
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+use crate::model::search::{Facet, FacetBucket, FacetRange};
+
+/// Collector that counts documents per requested range of values of a number
+/// or date field.
+///
+/// Bounds are converted into ranges of value codes once per segment, so
+/// values of documents are compared without decoding them, same as range
+/// queries do.
+pub struct RangeFacetCollector {
+    field: String,
+    ranges: Vec<FacetRange>,
+}
+
+impl RangeFacetCollector {
+    pub fn new(field: impl Into<String>, ranges: Vec<FacetRange>) -> Self {
+        Self {
+            field: field.into(),
+            ranges,
+        }
+    }
+
+    /// Gets range of codes of the column for each requested range.
+    fn get_code_ranges(
+        &self,
+        column: &DocValuesColumn,
+    ) -> Result<Vec<Range<u64>>> {
+        let value_type = column.info.value_type;
+        ensure!(
+            value_type != DocValueType::Keyword,
+            "range facet field '{}' should have number or date values",
+            self.field
+        );
+
+        self.ranges
+            .iter()
+            .map(|range| {
+                let from = range
+                    .from
+                    .as_deref()
+                    .map(|from| value_type.parse_value(from));
+                let to =
+                    range.to.as_deref().map(|to| value_type.parse_value(to));
+                let from = from.transpose()?;
+                let to = to.transpose()?;
+
+                column.get_code_range(
+                    from.as_ref().map_or(Bound::Unbounded, Bound::Included),
+                    to.as_ref().map_or(Bound::Unbounded, Bound::Excluded),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<'a> Collector<'a> for RangeFacetCollector {
+    type SegmentCollector = RangeFacetSegmentCollector<'a>;
+    type SegmentOutput = Vec<u64>;
+    type Output = Facet;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        let column = segment.get_doc_values(&self.field)?;
+        let code_ranges = match &column {
+            Some(column) => self.get_code_ranges(column)?,
+            None => Vec::new(),
+        };
+
+        Ok(RangeFacetSegmentCollector {
+            column,
+            code_ranges,
+            counts: vec![0; self.ranges.len()],
+        })
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        let mut counts = vec![0; self.ranges.len()];
+        for output in outputs {
+            for (count, segment_count) in counts.iter_mut().zip(output) {
+                *count += segment_count;
+            }
+        }
+
+        Ok(Facet {
+            field: self.field.clone(),
+            buckets: self
+                .ranges
+                .iter()
+                .zip(counts)
+                .map(|(range, count)| FacetBucket::new(range.get_key(), count))
+                .collect(),
+        })
+    }
+}
+
+pub struct RangeFacetSegmentCollector<'a> {
+    /// `None` if no document of the segment has value of the field
+    column: Option<DocValuesColumn<'a>>,
+
+    /// Ranges of value codes in the same order as requested ranges
+    code_ranges: Vec<Range<u64>>,
+
+    /// Number of documents per requested range
+    counts: Vec<u64>,
+}
+
+impl SegmentCollector for RangeFacetSegmentCollector<'_> {
+    type SegmentOutput = Vec<u64>;
+
+    fn requires_score(&self) -> bool {
+        false
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        if let Some(column) = &self.column
+            && let Some(code) = column.get_code(docid)?
+        {
+            for (idx, code_range) in self.code_ranges.iter().enumerate() {
+                if code_range.contains(&code) {
+                    *self
+                        .counts
+                        .get_mut(idx)
+                        .context("range should have count")? += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        Ok(self.counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::model::doc::{DocValue, DocValueField};
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    fn collect_facet(
+        collector: &RangeFacetCollector,
+        segment_values: &[&[Option<DocValue>]],
+    ) -> Result<Vec<(String, u64)>> {
+        let mut outputs = Vec::new();
+        for values in segment_values {
+            let mut docs = TestDocsIterator::from_enumerated_fields(
+                values.iter().map(|_| [("text", "cat")]),
+            )
+            .with_values(values.iter().map(|value| {
+                value
+                    .iter()
+                    .map(|value| DocValueField::new("len", value.clone()))
+                    .collect()
+            }));
+            let index = build_index(&IndexMedium::Memory, &mut docs)?;
+            let segment = index.get_segments()[0];
+
+            let mut segment_collector =
+                collector.create_segment_collector(segment)?;
+            for docid in 0..values.len() as SegmentDocId {
+                segment_collector.add_docid(docid)?;
+            }
+            outputs.push(segment_collector.extract_output()?);
+        }
+
+        Ok(collector
+            .merge_segment_outputs(outputs)?
+            .buckets
+            .into_iter()
+            .map(|bucket| (bucket.key, bucket.count))
+            .collect())
+    }
+
+    #[test]
+    fn test_range_facet() -> Result<()> {
+        let len = |value| Some(DocValue::U64(value));
+        let segment_values: [&[Option<DocValue>]; 2] =
+            [&[len(5), None, len(10), len(150)], &[len(99), len(100)]];
+        let ranges = ["*..10", "10..100", "100..*", "0..*", "20..30"]
+            .iter()
+            .map(|range| range.parse())
+            .collect::<Result<Vec<FacetRange>>>()?;
+
+        assert_eq!(
+            collect_facet(
+                &RangeFacetCollector::new("len", ranges.clone()),
+                &segment_values
+            )?,
+            vec![
+                ("*..10".to_string(), 1),
+                ("10..100".to_string(), 2),
+                ("100..*".to_string(), 2),
+                ("0..*".to_string(), 5),
+                ("20..30".to_string(), 0),
+            ]
+        );
+        assert_eq!(
+            collect_facet(
+                &RangeFacetCollector::new("unknown", ranges[..1].to_vec()),
+                &segment_values
+            )?,
+            vec![("*..10".to_string(), 0)]
+        );
+        assert!(
+            collect_facet(
+                &RangeFacetCollector::new(
+                    "len",
+                    vec![FacetRange::new(Some("ten"), None)]
+                ),
+                &segment_values
+            )
+            .is_err()
+        );
+        assert!(
+            collect_facet(
+                &RangeFacetCollector::new("len", ranges),
+                &[&[Some(DocValue::Keyword("long".to_string()))]]
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use anyhow::Result;
+
+use super::model::{Collector, SegmentCollector};
+use crate::engines::nano::index::doc_values::DocValuesColumn;
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+use crate::model::doc::{DocValue, ExternalDocId};
+use crate::model::search::{SortBy, SortOrder};
+
+/// Document ordered by its value of a doc values field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedDocCandidate {
+    pub id: ExternalDocId,
+    pub relevance: f64,
+    /// `None` if document has no value of the field
+    pub value: Option<DocValue>,
+}
+
+/// Collector that returns top-K documents ordered by their values of a doc
+/// values field, rather than by score.
+///
+/// Documents without value go last, and documents with equal values go in
+/// ascending order of docid. Scores are still calculated, so hits keep their
+/// relevance, but no documents can be skipped by score.
+pub struct SortByFieldCollector {
+    sort_by: SortBy,
+    doc_count: usize,
+    offset: usize,
+}
+
+impl SortByFieldCollector {
+    pub const fn new(sort_by: SortBy, doc_count: usize) -> Self {
+        Self {
+            sort_by,
+            doc_count,
+            offset: 0,
+        }
+    }
+
+    pub const fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    fn compare(
+        &self,
+        a: &SortedDocCandidate,
+        b: &SortedDocCandidate,
+    ) -> Ordering {
+        let values = match (&a.value, &b.value) {
+            (Some(a), Some(b)) => match self.sort_by.order {
+                SortOrder::Asc => a.total_cmp(b),
+                SortOrder::Desc => b.total_cmp(a),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        values.then_with(|| a.id.cmp(&b.id))
+    }
+}
+
+impl<'a> Collector<'a> for SortByFieldCollector {
+    type SegmentCollector = SortByFieldSegmentCollector<'a>;
+    type SegmentOutput = Vec<SortedDocCandidate>;
+    type Output = Vec<SortedDocCandidate>;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        Ok(SortByFieldSegmentCollector {
+            segment,
+            column: segment.get_doc_values(&self.sort_by.field)?,
+            order: self.sort_by.order,
+            doc_count: self.doc_count + self.offset,
+            max_heap: BinaryHeap::new(),
+        })
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        // keyword codes are segment-specific, so segments are merged by
+        // decoded values
+        let mut res: Vec<_> = outputs.into_iter().flatten().collect();
+        res.sort_by(|a, b| self.compare(a, b));
+        res.truncate(self.doc_count + self.offset);
+        res.drain(..self.offset.min(res.len()));
+
+        Ok(res)
+    }
+}
+
+pub struct SortByFieldSegmentCollector<'a> {
+    segment: &'a dyn IndexSegment,
+    /// `None` if no document of the segment has value of the field
+    column: Option<DocValuesColumn<'a>>,
+    order: SortOrder,
+    doc_count: usize,
+    /// Best candidates, with the worst one on top
+    max_heap: BinaryHeap<SegmentSortCandidate>,
+}
+
+/// Candidate of the segment ranked by code of its value, which goes in the
+/// same order as values within the segment.
+struct SegmentSortCandidate {
+    /// Document without value, rank of value code with respect to sort
+    /// order, and external docid to break ties. The less, the better
+    key: (bool, u64, ExternalDocId),
+    docid: SegmentDocId,
+    relevance: f64,
+}
+
+impl Ord for SegmentSortCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl PartialOrd for SegmentSortCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SegmentSortCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for SegmentSortCandidate {}
+
+impl SegmentCollector for SortByFieldSegmentCollector<'_> {
+    type SegmentOutput = Vec<SortedDocCandidate>;
+
+    fn requires_score(&self) -> bool {
+        true
+    }
+
+    fn add_docid_and_score(
+        &mut self,
+        docid: SegmentDocId,
+        score: f64,
+    ) -> Result<()> {
+        let code = match &self.column {
+            Some(column) => column.get_code(docid)?,
+            None => None,
+        };
+        let rank = match (code, self.order) {
+            (None, _) => 0,
+            (Some(code), SortOrder::Asc) => code,
+            (Some(code), SortOrder::Desc) => !code,
+        };
+        let candidate = SegmentSortCandidate {
+            key: (
+                code.is_none(),
+                rank,
+                self.segment.get_stored_doc(docid)?.docid,
+            ),
+            docid,
+            relevance: score,
+        };
+
+        if self.max_heap.len() < self.doc_count {
+            self.max_heap.push(candidate);
+        } else if matches!(self.max_heap.peek(), Some(max) if candidate < *max)
+        {
+            self.max_heap.pop();
+            self.max_heap.push(candidate);
+        }
+
+        Ok(())
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        self.max_heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|candidate| {
+                let value = match &self.column {
+                    Some(column) => column.get_value(candidate.docid)?,
+                    None => None,
+                };
+                Ok(SortedDocCandidate {
+                    id: candidate.key.2,
+                    relevance: candidate.relevance,
+                    value,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::model::doc::{Doc, DocField, DocValueField};
+
+    fn sorted_docids(
+        collector: &SortByFieldCollector,
+        segments: &[&dyn IndexSegment],
+    ) -> Result<Vec<(ExternalDocId, Option<DocValue>)>> {
+        let mut outputs = Vec::new();
+        for segment in segments {
+            let mut segment_collector =
+                collector.create_segment_collector(*segment)?;
+            let docs_count = segment.get_stats().indexed_docs_count;
+            for docid in 0..docs_count as SegmentDocId {
+                segment_collector.add_docid_and_score(docid, 1.0)?;
+            }
+            outputs.push(segment_collector.extract_output()?);
+        }
+
+        Ok(collector
+            .merge_segment_outputs(outputs)?
+            .into_iter()
+            .map(|candidate| (candidate.id, candidate.value))
+            .collect())
+    }
+
+    #[test]
+    fn test_sort_by_field() -> Result<()> {
+        // setup
+        // segments get their own keyword ordinals, so "b" has ordinal 0 in
+        // the second segment, but should still go after "a"
+        let segment_values = [
+            vec![(0, Some("c")), (1, None), (2, Some("a"))],
+            vec![(3, Some("b")), (4, Some("c")), (5, None)],
+        ];
+        let indices = segment_values
+            .iter()
+            .map(|values| {
+                let mut docs = values.iter().map(|(id, value)| {
+                    Ok(Doc {
+                        id: *id,
+                        fields: vec![DocField::new("text", "cat")],
+                        values: value
+                            .iter()
+                            .map(|value| {
+                                DocValueField::new(
+                                    "tag",
+                                    DocValue::Keyword((*value).to_string()),
+                                )
+                            })
+                            .collect(),
+                    })
+                });
+                build_index(&IndexMedium::Memory, &mut docs)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let segments = indices
+            .iter()
+            .flat_map(|index| index.get_segments())
+            .collect::<Vec<_>>();
+
+        let keyword = |value: &str| Some(DocValue::Keyword(value.to_string()));
+        let sort_by = |order| SortBy {
+            field: "tag".to_string(),
+            order,
+        };
+
+        // execute & assert
+        assert_eq!(
+            sorted_docids(
+                &SortByFieldCollector::new(sort_by(SortOrder::Asc), 10),
+                &segments
+            )?,
+            &[
+                (2, keyword("a")),
+                (3, keyword("b")),
+                (0, keyword("c")),
+                (4, keyword("c")),
+                (1, None),
+                (5, None),
+            ]
+        );
+        assert_eq!(
+            sorted_docids(
+                &SortByFieldCollector::new(sort_by(SortOrder::Desc), 3)
+                    .with_offset(1),
+                &segments
+            )?,
+            &[(4, keyword("c")), (3, keyword("b")), (2, keyword("a"))]
+        );
+        assert_eq!(
+            sorted_docids(
+                &SortByFieldCollector::new(
+                    SortBy {
+                        field: "unknown".to_string(),
+                        order: SortOrder::Desc,
+                    },
+                    2
+                ),
+                &segments
+            )?,
+            &[(0, None), (1, None)]
+        );
+
+        Ok(())
+    }
+}
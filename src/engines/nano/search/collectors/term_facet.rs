@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, ensure};
+
+use super::model::{Collector, SegmentCollector};
+use crate::engines::nano::index::doc_values::{DocValueType, DocValuesColumn};
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+use crate::model::search::{Facet, FacetBucket};
+
+/// Collector that counts documents per value of a keyword field, and returns
+/// `limit` values with the most documents.
+///
+/// Documents without value of the field are not counted.
+pub struct TermFacetCollector {
+    field: String,
+    limit: usize,
+}
+
+impl TermFacetCollector {
+    pub fn new(field: impl Into<String>, limit: usize) -> Self {
+        Self {
+            field: field.into(),
+            limit,
+        }
+    }
+}
+
+impl<'a> Collector<'a> for TermFacetCollector {
+    type SegmentCollector = TermFacetSegmentCollector<'a>;
+    type SegmentOutput = Vec<(String, u64)>;
+    type Output = Facet;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        let column = segment.get_doc_values(&self.field)?;
+
+        if let Some(column) = &column {
+            ensure!(
+                column.info.value_type == DocValueType::Keyword,
+                "term facet field '{}' should have keyword values",
+                self.field
+            );
+        }
+
+        let counts =
+            vec![0; column.map_or(0, |column| column.info.keywords.len())];
+
+        Ok(TermFacetSegmentCollector { column, counts })
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        // keyword ordinals are segment-specific, so segments are merged by
+        // keywords themselves
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for (keyword, count) in outputs.into_iter().flatten() {
+            *counts.entry(keyword).or_default() += count;
+        }
+
+        let mut buckets: Vec<FacetBucket> = counts
+            .into_iter()
+            .map(|(keyword, count)| FacetBucket::new(keyword, count))
+            .collect();
+        buckets.sort_by(|a, b| b.count.cmp(&a.count).then(a.key.cmp(&b.key)));
+        buckets.truncate(self.limit);
+
+        Ok(Facet {
+            field: self.field.clone(),
+            buckets,
+        })
+    }
+}
+
+pub struct TermFacetSegmentCollector<'a> {
+    /// `None` if no document of the segment has value of the field
+    column: Option<DocValuesColumn<'a>>,
+
+    /// Number of documents per keyword ordinal
+    counts: Vec<u64>,
+}
+
+impl SegmentCollector for TermFacetSegmentCollector<'_> {
+    type SegmentOutput = Vec<(String, u64)>;
+
+    fn requires_score(&self) -> bool {
+        false
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        if let Some(column) = &self.column
+            && let Some(code) = column.get_code(docid)?
+        {
+            *self
+                .counts
+                .get_mut(code as usize)
+                .context("keyword ordinal should be in column")? += 1;
+        }
+
+        Ok(())
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        let Some(column) = self.column else {
+            return Ok(Vec::new());
+        };
+
+        Ok(column
+            .info
+            .keywords
+            .iter()
+            .zip(self.counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(keyword, count)| (keyword.clone(), count))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::model::doc::{DocValue, DocValueField};
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    fn collect_facet(
+        collector: &TermFacetCollector,
+        segment_values: &[&[Option<DocValue>]],
+    ) -> Result<Facet> {
+        let mut outputs = Vec::new();
+        for values in segment_values {
+            let mut docs = TestDocsIterator::from_enumerated_fields(
+                values.iter().map(|_| [("text", "cat")]),
+            )
+            .with_values(values.iter().map(|value| {
+                value
+                    .iter()
+                    .map(|value| DocValueField::new("tag", value.clone()))
+                    .collect()
+            }));
+            let index = build_index(&IndexMedium::Memory, &mut docs)?;
+            let segment = index.get_segments()[0];
+
+            let mut segment_collector =
+                collector.create_segment_collector(segment)?;
+            for docid in 0..values.len() as SegmentDocId {
+                segment_collector.add_docid(docid)?;
+            }
+            outputs.push(segment_collector.extract_output()?);
+        }
+
+        collector.merge_segment_outputs(outputs)
+    }
+
+    #[test]
+    fn test_term_facet() -> Result<()> {
+        let keyword = |value: &str| Some(DocValue::Keyword(value.to_string()));
+        let segment_values: [&[Option<DocValue>]; 2] = [
+            &[keyword("dog"), None, keyword("cat"), keyword("dog")],
+            &[keyword("mouse"), keyword("cat"), keyword("bird")],
+        ];
+
+        assert_eq!(
+            collect_facet(&TermFacetCollector::new("tag", 3), &segment_values)?,
+            Facet {
+                field: "tag".to_string(),
+                buckets: vec![
+                    FacetBucket::new("cat", 2),
+                    FacetBucket::new("dog", 2),
+                    FacetBucket::new("bird", 1),
+                ],
+            }
+        );
+        assert!(
+            collect_facet(
+                &TermFacetCollector::new("unknown", 3),
+                &segment_values
+            )?
+            .buckets
+            .is_empty()
+        );
+        assert_eq!(
+            collect_facet(
+                &TermFacetCollector::new("tag", 3),
+                &[&[Some(DocValue::U64(1))]]
+            )
+            .err()
+            .map(|err| err.to_string())
+            .as_deref(),
+            Some("term facet field 'tag' should have keyword values")
+        );
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
 use anyhow::Result;
@@ -8,26 +9,53 @@ use crate::engines::nano::search::model::DocCandidate;
 use crate::model::doc::ExternalDocId;
 
 /// Collector that returns top-K documents with highest scores.
+///
+/// For paging it can skip `offset` best documents, and documents ranked before
+/// `search_after` candidate, i.e. last candidate of the previous page.
 pub struct TopCollector {
     doc_count: usize,
+    offset: usize,
+    search_after: Option<DocCandidate<ExternalDocId>>,
 }
 
 impl TopCollector {
     pub const fn new(doc_count: usize) -> Self {
-        Self { doc_count }
+        Self {
+            doc_count,
+            offset: 0,
+            search_after: None,
+        }
+    }
+
+    pub const fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub const fn with_search_after(
+        mut self,
+        search_after: Option<DocCandidate<ExternalDocId>>,
+    ) -> Self {
+        self.search_after = search_after;
+        self
     }
 }
 
 impl<'a> Collector<'a> for TopCollector {
     type SegmentCollector = TopSegmentCollector<'a>;
     type SegmentOutput = Vec<DocCandidate<ExternalDocId>>;
-    type Output = Vec<ExternalDocId>;
+    type Output = Vec<DocCandidate<ExternalDocId>>;
 
     fn create_segment_collector(
         &self,
         segment: &'a dyn IndexSegment,
     ) -> Result<Self::SegmentCollector> {
-        Ok(TopSegmentCollector::new(segment, self.doc_count))
+        // skipped documents should be collected too, to know which ones go
+        // after them
+        Ok(
+            TopSegmentCollector::new(segment, self.doc_count + self.offset)
+                .with_search_after(self.search_after.clone()),
+        )
     }
 
     fn merge_segment_outputs(
@@ -36,10 +64,11 @@ impl<'a> Collector<'a> for TopCollector {
     ) -> Result<Self::Output> {
         let mut min_heap: BinaryHeap<DocCandidate<ExternalDocId>> =
             BinaryHeap::new();
+        let heap_size = self.doc_count + self.offset;
 
         for output in outputs {
             for candidate in output {
-                if min_heap.len() < self.doc_count {
+                if min_heap.len() < heap_size {
                     min_heap.push(candidate);
                 } else if matches!(min_heap.peek(), Some(min) if candidate < *min)
                 {
@@ -49,13 +78,8 @@ impl<'a> Collector<'a> for TopCollector {
             }
         }
 
-        let mut res: Vec<ExternalDocId> = Vec::with_capacity(self.doc_count);
-
-        while let Some(candidate) = min_heap.pop() {
-            res.push(candidate.id);
-        }
-
-        res.reverse();
+        let mut res = min_heap.into_sorted_vec();
+        res.drain(..self.offset.min(res.len()));
 
         Ok(res)
     }
@@ -64,7 +88,13 @@ impl<'a> Collector<'a> for TopCollector {
 pub struct TopSegmentCollector<'a> {
     segment: &'a dyn IndexSegment,
     doc_count: usize,
-    min_heap: BinaryHeap<DocCandidate<SegmentDocId>>,
+
+    /// Candidates with external docids, so ties are broken same way as when
+    /// outputs of segments are merged
+    min_heap: BinaryHeap<DocCandidate<ExternalDocId>>,
+
+    /// Only candidates ranked after this one are collected
+    search_after: Option<DocCandidate<ExternalDocId>>,
 }
 
 impl<'a> TopSegmentCollector<'a> {
@@ -73,8 +103,33 @@ impl<'a> TopSegmentCollector<'a> {
             segment,
             doc_count,
             min_heap: BinaryHeap::new(),
+            search_after: None,
         }
     }
+
+    pub const fn with_search_after(
+        mut self,
+        search_after: Option<DocCandidate<ExternalDocId>>,
+    ) -> Self {
+        self.search_after = search_after;
+        self
+    }
+
+    fn is_after_cursor(&self, docid: SegmentDocId, score: f64) -> Result<bool> {
+        let Some(cursor) = &self.search_after else {
+            return Ok(true);
+        };
+
+        // external docid is only needed to break ties, so stored doc is not
+        // read for most candidates
+        Ok(match score.total_cmp(&cursor.relevance) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => {
+                self.segment.get_stored_doc(docid)?.docid > cursor.id
+            }
+        })
+    }
 }
 
 impl SegmentCollector for TopSegmentCollector<'_> {
@@ -89,12 +144,26 @@ impl SegmentCollector for TopSegmentCollector<'_> {
         docid: SegmentDocId,
         score: f64,
     ) -> Result<()> {
+        if !self.is_after_cursor(docid, score)? {
+            return Ok(());
+        }
+
+        let is_full = self.min_heap.len() >= self.doc_count;
+
+        // external docid is only needed for candidates, that can get into the
+        // heap, so stored doc is not read for most of them
+        if is_full
+            && self.min_heap.peek().is_none_or(|min| score < min.relevance)
+        {
+            return Ok(());
+        }
+
         let candidate = DocCandidate {
-            id: docid,
+            id: self.segment.get_stored_doc(docid)?.docid,
             relevance: score,
         };
 
-        if self.min_heap.len() < self.doc_count {
+        if !is_full {
             self.min_heap.push(candidate);
         } else if matches!(self.min_heap.peek(), Some(min) if candidate < *min)
         {
@@ -105,19 +174,19 @@ impl SegmentCollector for TopSegmentCollector<'_> {
         Ok(())
     }
 
-    fn extract_output(mut self) -> Result<Self::SegmentOutput> {
-        let mut res = Vec::with_capacity(self.doc_count);
-
-        while let Some(item) = self.min_heap.pop() {
-            res.push(DocCandidate {
-                id: self.segment.get_stored_doc(item.id)?.docid,
-                relevance: item.relevance,
-            });
+    fn get_min_competitive_score(&self) -> Option<f64> {
+        // once heap is full, candidate should beat the worst one to get in.
+        // ties are broken by external docid, which may go in any order, so
+        // candidate with equal score may still win the tie
+        if self.min_heap.len() < self.doc_count {
+            None
+        } else {
+            self.min_heap.peek().map(|min| min.relevance.next_down())
         }
+    }
 
-        res.reverse();
-
-        Ok(res)
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        Ok(self.min_heap.into_sorted_vec())
     }
 }
 
@@ -140,12 +209,16 @@ mod tests {
     }
 
     fn create_segment(doc_count: usize) -> Box<dyn IndexSegment> {
+        create_segment_with_docids((0..doc_count as ExternalDocId).collect())
+    }
+
+    fn create_segment_with_docids(
+        docids: Vec<ExternalDocId>,
+    ) -> Box<dyn IndexSegment> {
         let mut segment = MemoryIndex::default();
 
-        for i in 0..doc_count {
-            segment.docs.push(StoredDoc {
-                docid: i as ExternalDocId,
-            });
+        for docid in docids {
+            segment.docs.push(StoredDoc { docid });
         }
 
         Box::new(segment)
@@ -266,13 +339,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_segment_collector_same_scores_ranked_by_external_docid()
+    -> Result<()> {
+        // segment docids go in reverse order of external ones
+        let segment = create_segment_with_docids(vec![3, 2, 1, 0]);
+        let mut segment_collector =
+            TopSegmentCollector::new(segment.as_ref(), 2);
+
+        for docid in 0..4 {
+            segment_collector.add_docid_and_score(docid, 10.0)?;
+
+            // candidates with equal score are still competitive
+            if let Some(score) = segment_collector.get_min_competitive_score() {
+                assert!(score < 10.0);
+            }
+        }
+
+        // same as segment outputs are merged, ties are broken by external ID
+        assert_eq!(
+            segment_collector.extract_output()?,
+            &[DocCandidate::new(0, 10.0), DocCandidate::new(1, 10.0)]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_collector_merge_single_output() -> Result<()> {
         let output = vec![DocCandidate::new(2, 2.0), DocCandidate::new(1, 1.0)];
 
         assert_eq!(
             TopCollector::new(2).merge_segment_outputs(vec![output])?,
-            &[2, 1]
+            &[DocCandidate::new(2, 2.0), DocCandidate::new(1, 1.0)]
         );
 
         Ok(())
@@ -288,7 +387,11 @@ mod tests {
         assert_eq!(
             TopCollector::new(3)
                 .merge_segment_outputs(vec![output_a, output_b])?,
-            &[1, 3, 2]
+            &[
+                DocCandidate::new(1, 5.0),
+                DocCandidate::new(3, 3.0),
+                DocCandidate::new(2, 1.0)
+            ]
         );
 
         Ok(())
@@ -323,7 +426,7 @@ mod tests {
 
         assert_eq!(
             TopCollector::new(5).merge_segment_outputs(vec![output])?,
-            &[0]
+            &[DocCandidate::new(0, 0.0)]
         );
 
         Ok(())
@@ -340,8 +443,63 @@ mod tests {
         assert_eq!(
             TopCollector::new(3)
                 .merge_segment_outputs(vec![output_a, output_b])?,
-            &[0, 1, 2]
+            &[
+                DocCandidate::new(0, 10.0),
+                DocCandidate::new(1, 10.0),
+                DocCandidate::new(2, 10.0)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_collector_search_after() -> Result<()> {
+        let segment = create_segment(5);
+        let mut segment_collector =
+            TopSegmentCollector::new(segment.as_ref(), 3)
+                .with_search_after(Some(DocCandidate::new(1, 2.0)));
+
+        segment_collector.add_docid_and_score(0, 2.0)?;
+        segment_collector.add_docid_and_score(1, 2.0)?;
+        segment_collector.add_docid_and_score(2, 2.0)?;
+        segment_collector.add_docid_and_score(3, 3.0)?;
+        segment_collector.add_docid_and_score(4, 1.0)?;
+
+        // only candidates ranked after the cursor are collected, i.e. with
+        // lower score, or with the same score and larger ID
+        assert_eq!(
+            segment_collector.extract_output()?,
+            &[DocCandidate::new(2, 2.0), DocCandidate::new(4, 1.0)]
         );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collector_offset() -> Result<()> {
+        let segment = create_segment(4);
+        let collector = TopCollector::new(2).with_offset(1);
+
+        let mut segment_collector =
+            collector.create_segment_collector(segment.as_ref())?;
+        segment_collector.add_docid_and_score(0, 0.0)?;
+        segment_collector.add_docid_and_score(1, 1.0)?;
+        segment_collector.add_docid_and_score(2, 2.0)?;
+        segment_collector.add_docid_and_score(3, 3.0)?;
+
+        let output = segment_collector.extract_output()?;
+
+        assert_eq!(
+            collector.merge_segment_outputs(vec![output])?,
+            &[DocCandidate::new(2, 2.0), DocCandidate::new(1, 1.0)]
+        );
+        assert!(
+            TopCollector::new(2)
+                .with_offset(5)
+                .merge_segment_outputs(vec![vec![DocCandidate::new(0, 0.0)]])?
+                .is_empty()
+        );
+
         Ok(())
     }
 }
@@ -0,0 +1,124 @@
+use anyhow::Result;
+
+use super::model::{Collector, SegmentCollector};
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+
+/// Collector that passes the same documents to both collectors of the pair in
+/// a single iteration, e.g. to get top documents along with their total count.
+impl<'a, A: Collector<'a>, B: Collector<'a>> Collector<'a> for (A, B) {
+    type SegmentCollector = (A::SegmentCollector, B::SegmentCollector);
+    type SegmentOutput = (A::SegmentOutput, B::SegmentOutput);
+    type Output = (A::Output, B::Output);
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        Ok((
+            self.0.create_segment_collector(segment)?,
+            self.1.create_segment_collector(segment)?,
+        ))
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        let (outputs_a, outputs_b) = outputs.into_iter().unzip();
+
+        Ok((
+            self.0.merge_segment_outputs(outputs_a)?,
+            self.1.merge_segment_outputs(outputs_b)?,
+        ))
+    }
+}
+
+impl<A: SegmentCollector, B: SegmentCollector> SegmentCollector for (A, B) {
+    type SegmentOutput = (A::SegmentOutput, B::SegmentOutput);
+
+    fn requires_score(&self) -> bool {
+        self.0.requires_score() || self.1.requires_score()
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        self.0.add_docid(docid)?;
+        self.1.add_docid(docid)
+    }
+
+    fn add_docid_and_score(
+        &mut self,
+        docid: SegmentDocId,
+        score: f64,
+    ) -> Result<()> {
+        add_to_collector(&mut self.0, docid, score)?;
+        add_to_collector(&mut self.1, docid, score)
+    }
+
+    /// Document can be skipped only if it doesn't matter to both collectors.
+    fn get_min_competitive_score(&self) -> Option<f64> {
+        Some(
+            self.0
+                .get_min_competitive_score()?
+                .min(self.1.get_min_competitive_score()?),
+        )
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        Ok((self.0.extract_output()?, self.1.extract_output()?))
+    }
+}
+
+/// Passes document to the collector with score only if it requires one.
+pub(super) fn add_to_collector(
+    collector: &mut impl SegmentCollector,
+    docid: SegmentDocId,
+    score: f64,
+) -> Result<()> {
+    if collector.requires_score() {
+        collector.add_docid_and_score(docid, score)
+    } else {
+        collector.add_docid(docid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::index::MemoryIndex;
+    use crate::engines::nano::index::model::StoredDoc;
+    use crate::engines::nano::search::collectors::{
+        CountCollector, TopCollector,
+    };
+    use crate::engines::nano::search::model::DocCandidate;
+
+    #[test]
+    fn test_top_and_count() -> Result<()> {
+        let mut segment = MemoryIndex::default();
+        for docid in 0..3 {
+            segment.docs.push(StoredDoc { docid });
+        }
+
+        let collector = (TopCollector::new(1), CountCollector);
+        let mut segment_collector =
+            collector.create_segment_collector(&segment)?;
+
+        assert!(segment_collector.requires_score());
+
+        segment_collector.add_docid_and_score(0, 1.0)?;
+
+        // counting requires every document, so none can be skipped
+        assert_eq!(segment_collector.get_min_competitive_score(), None);
+
+        segment_collector.add_docid_and_score(1, 2.0)?;
+        segment_collector.add_docid_and_score(2, 0.5)?;
+
+        let output = segment_collector.extract_output()?;
+
+        assert_eq!(
+            collector.merge_segment_outputs(vec![output])?,
+            (vec![DocCandidate::new(1, 2.0)], 3)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+
+use super::model::{Collector, SegmentCollector};
+use super::tuple::add_to_collector;
+use crate::engines::nano::index::model::{IndexSegment, SegmentDocId};
+
+/// Collector that passes the same documents to each collector of the vector
+/// in a single iteration, e.g. to aggregate several facets at once.
+impl<'a, C: Collector<'a>> Collector<'a> for Vec<C> {
+    type SegmentCollector = Vec<C::SegmentCollector>;
+    type SegmentOutput = Vec<C::SegmentOutput>;
+    type Output = Vec<C::Output>;
+
+    fn create_segment_collector(
+        &self,
+        segment: &'a dyn IndexSegment,
+    ) -> Result<Self::SegmentCollector> {
+        self.iter()
+            .map(|collector| collector.create_segment_collector(segment))
+            .collect()
+    }
+
+    fn merge_segment_outputs(
+        &self,
+        outputs: Vec<Self::SegmentOutput>,
+    ) -> Result<Self::Output> {
+        // outputs go by segments, and should be merged by collectors
+        let mut outputs_by_collector: Vec<Vec<C::SegmentOutput>> =
+            self.iter().map(|_| Vec::new()).collect();
+        for segment_outputs in outputs {
+            for (idx, output) in segment_outputs.into_iter().enumerate() {
+                outputs_by_collector
+                    .get_mut(idx)
+                    .context("segment output should match collector")?
+                    .push(output);
+            }
+        }
+
+        self.iter()
+            .zip(outputs_by_collector)
+            .map(|(collector, outputs)| {
+                collector.merge_segment_outputs(outputs)
+            })
+            .collect()
+    }
+}
+
+impl<C: SegmentCollector> SegmentCollector for Vec<C> {
+    type SegmentOutput = Vec<C::SegmentOutput>;
+
+    fn requires_score(&self) -> bool {
+        self.iter().any(SegmentCollector::requires_score)
+    }
+
+    fn add_docid(&mut self, docid: SegmentDocId) -> Result<()> {
+        for collector in self {
+            collector.add_docid(docid)?;
+        }
+        Ok(())
+    }
+
+    fn add_docid_and_score(
+        &mut self,
+        docid: SegmentDocId,
+        score: f64,
+    ) -> Result<()> {
+        for collector in self {
+            add_to_collector(collector, docid, score)?;
+        }
+        Ok(())
+    }
+
+    /// Document can be skipped only if it doesn't matter to all collectors.
+    fn get_min_competitive_score(&self) -> Option<f64> {
+        self.iter()
+            .map(SegmentCollector::get_min_competitive_score)
+            .reduce(|a, b| Some(a?.min(b?)))?
+    }
+
+    fn extract_output(self) -> Result<Self::SegmentOutput> {
+        self.into_iter()
+            .map(SegmentCollector::extract_output)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::index::MemoryIndex;
+    use crate::engines::nano::search::collectors::CountCollector;
+
+    #[test]
+    fn test_vec_of_collectors() -> Result<()> {
+        let segment = MemoryIndex::default();
+        let collector = vec![CountCollector, CountCollector];
+
+        let mut outputs = Vec::new();
+        for docs_count in [2, 3] {
+            let mut segment_collector =
+                collector.create_segment_collector(&segment)?;
+            assert!(!segment_collector.requires_score());
+
+            for docid in 0..docs_count {
+                segment_collector.add_docid(docid)?;
+            }
+            outputs.push(segment_collector.extract_output()?);
+        }
+
+        assert_eq!(collector.merge_segment_outputs(outputs)?, vec![5, 5]);
+
+        Ok(())
+    }
+}
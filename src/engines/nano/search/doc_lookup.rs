@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+
+use crate::engines::nano::index::model::{Index, IndexSegment, SegmentDocId};
+use crate::model::doc::ExternalDocId;
+
+/// Segments and segment docids of documents of the index by their external
+/// docids.
+///
+/// Segments only map their docids to external ones, so without lookup each
+/// document would be found by scanning stored docs of all segments. Lookup is
+/// built with single scan, and should only be used with the same index.
+#[derive(Debug, Default)]
+pub struct DocLookup {
+    /// Index of segment and segment docid of each document
+    docids: HashMap<ExternalDocId, (usize, SegmentDocId)>,
+}
+
+impl DocLookup {
+    pub fn build(index: &dyn Index) -> Result<Self> {
+        let mut docids = HashMap::new();
+
+        for (segment_idx, segment) in index.get_segments().iter().enumerate() {
+            let docs_count = segment.get_stats().indexed_docs_count;
+
+            for docid in 0..docs_count {
+                let docid = SegmentDocId::try_from(docid)
+                    .context("docid should fit segment docid")?;
+                let external_docid = segment.get_stored_doc(docid)?.docid;
+                docids.insert(external_docid, (segment_idx, docid));
+            }
+        }
+
+        Ok(Self { docids })
+    }
+
+    /// Finds segment of the index and segment docid of the document, or
+    /// returns `None` if there is no such document.
+    pub fn find<'a>(
+        &self,
+        index: &'a dyn Index,
+        external_docid: ExternalDocId,
+    ) -> Option<(&'a dyn IndexSegment, SegmentDocId)> {
+        let (segment_idx, docid) = *self.docids.get(&external_docid)?;
+        let segment = *index.get_segments().get(segment_idx)?;
+        Some((segment, docid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::index::{DiskIndexOptions, build_index};
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    #[test]
+    fn test_find_docs_of_several_segments() -> Result<()> {
+        // setup
+        let dir = TempDir::new()?;
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            ["cat", "dog", "mouse"].map(|text| [("text", text)]),
+        );
+        let index = build_index(
+            &IndexMedium::Disk(
+                DiskIndexOptions::builder()
+                    .index_dir(dir.path())
+                    .index_threads(1)
+                    .max_segment_docs(2)
+                    .build(),
+            ),
+            &mut docs,
+        )?;
+
+        // execute
+        let lookup = DocLookup::build(index.as_ref())?;
+
+        // assert
+        assert_eq!(index.get_segments().len(), 2);
+        for external_docid in 0..3 {
+            let (segment, docid) = lookup
+                .find(index.as_ref(), external_docid)
+                .context("doc should be found")?;
+            assert_eq!(segment.get_stored_doc(docid)?.docid, external_docid);
+        }
+        assert!(lookup.find(index.as_ref(), 3).is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail, ensure};
+use itertools::Itertools;
+
+use super::options::{ExpansionScoring, SearchOptions};
+use super::query::QueryAst;
+use crate::engines::nano::analysis::{Analyzer, Tokenizer, UnicodeTokenizer};
+use crate::engines::nano::index::automaton::{
+    LevenshteinAutomaton, MAX_EDIT_DISTANCE,
+};
+use crate::engines::nano::index::model::{IndexSegment, Term};
+
+const ANY_CHARS: char = '*';
+const SINGLE_CHAR: char = '?';
+
+/// Letter wildcards are replaced with while pattern is tokenized, so they
+/// stay inside words
+const WILDCARD_PLACEHOLDER: char = 'x';
+
+/// Query node, that expands into multiple terms of each segment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TermExpansion {
+    /// Terms matching normalized wildcard pattern
+    Pattern(String),
+
+    /// Terms within edit distance from analyzed word
+    Fuzzy { term: Term, distance: u8 },
+}
+
+/// Term of a segment, that query node expanded into.
+#[derive(Debug, PartialEq)]
+pub struct ExpandedTerm {
+    pub term: Term,
+
+    /// Multiplier of term score, e.g. fuzzy terms further from query word get
+    /// lower boost
+    pub boost: f64,
+}
+
+impl TermExpansion {
+    /// Creates expansion for query node, or returns `None` if node does not
+    /// expand into terms.
+    ///
+    /// Fuzzy word is turned into term with `analyzer`, while literal parts of
+    /// patterns only pass through its filters, since tokenizer would split
+    /// patterns by wildcards.
+    pub fn from_query_ast(
+        query_ast: &QueryAst,
+        analyzer: &Analyzer,
+    ) -> Result<Option<Self>> {
+        Ok(match query_ast {
+            QueryAst::Prefix(prefix) => Some(Self::Pattern(normalize_pattern(
+                &format!("{prefix}*"),
+                analyzer,
+            )?)),
+            QueryAst::Wildcard(pattern) => {
+                Some(Self::Pattern(normalize_pattern(pattern, analyzer)?))
+            }
+            QueryAst::Fuzzy { word, distance } => {
+                ensure!(
+                    *distance <= u32::from(MAX_EDIT_DISTANCE),
+                    "fuzzy distance should be at most {MAX_EDIT_DISTANCE}"
+                );
+                Some(Self::Fuzzy {
+                    term: analyzer.analyze_word(word)?,
+                    distance: *distance as u8,
+                })
+            }
+            _ => None,
+        })
+    }
+
+    /// Checks if documents are scored by expanded terms, rather than given
+    /// constant score.
+    ///
+    /// Fuzzy terms are always scored, so closer terms could weigh more.
+    pub fn is_scored(&self, options: &SearchOptions) -> bool {
+        match self {
+            Self::Pattern(_) => {
+                options.expansion_scoring == ExpansionScoring::Summed
+            }
+            Self::Fuzzy { .. } => true,
+        }
+    }
+
+    /// Finds terms of the field in all segments, that node expands into.
+    ///
+    /// If node expands into more than `max_terms` terms, only terms with
+    /// highest boost are returned, and then terms found in most documents of
+    /// all segments, so each segment searches the same terms. Terms are
+    /// returned in lexicographic order.
+    pub fn expand(
+        &self,
+        segments: &[&dyn IndexSegment],
+        field: &str,
+        max_terms: usize,
+    ) -> Result<Vec<ExpandedTerm>> {
+        // same term gets the same boost in each segment
+        let mut boosts: BTreeMap<Term, f64> = BTreeMap::new();
+        for segment in segments {
+            for ExpandedTerm { term, boost } in
+                self.expand_in_segment(*segment, field)?
+            {
+                boosts.insert(term, boost);
+            }
+        }
+
+        let mut terms: Vec<ExpandedTerm> = boosts
+            .into_iter()
+            .map(|(term, boost)| ExpandedTerm { term, boost })
+            .collect();
+
+        if terms.len() > max_terms {
+            let mut counted_terms = Vec::new();
+            for term in terms {
+                let mut docs_count = 0;
+                for segment in segments {
+                    docs_count += segment
+                        .get_doc_postings_for_term(field, &term.term)?
+                        .map_or(0, |postings| postings.count);
+                }
+                counted_terms.push((docs_count, term));
+            }
+
+            // same boost and frequency terms go in lexicographic order to make
+            // expansion stable
+            counted_terms.sort_by(|(a_count, a), (b_count, b)| {
+                b.boost
+                    .total_cmp(&a.boost)
+                    .then_with(|| b_count.cmp(a_count))
+                    .then_with(|| a.term.cmp(&b.term))
+            });
+            counted_terms.truncate(max_terms);
+
+            terms = counted_terms.into_iter().map(|(_, term)| term).collect();
+            terms.sort_by(|a, b| a.term.cmp(&b.term));
+        }
+
+        Ok(terms)
+    }
+
+    /// Finds all terms of the field in the segment, that node expands into.
+    fn expand_in_segment(
+        &self,
+        segment: &dyn IndexSegment,
+        field: &str,
+    ) -> Result<Vec<ExpandedTerm>> {
+        match self {
+            Self::Pattern(pattern) => expand_pattern(pattern, segment, field),
+            Self::Fuzzy { term, distance } => {
+                expand_fuzzy(term, *distance, segment, field)
+            }
+        }
+    }
+}
+
+/// Normalizes term pattern, splitting it into words same way as default
+/// tokenizer does, and passes each literal part between wildcards through
+/// filters of `analyzer`, e.g. "Wom?n's" turns into "wom?ns", and "U.S*" into
+/// "u.s*" with default analyzer.
+///
+/// Wildcards are tokenized as letters. Patterns split into several words,
+/// e.g. "covid-19*", are rejected, since each word is a separate term of the
+/// index, and single pattern cannot match several terms.
+pub fn normalize_pattern(pattern: &str, analyzer: &Analyzer) -> Result<String> {
+    let placeholders: String = pattern
+        .chars()
+        .map(|ch| {
+            if is_wildcard(ch) {
+                WILDCARD_PLACEHOLDER
+            } else {
+                ch
+            }
+        })
+        .collect();
+
+    let word = match UnicodeTokenizer.tokenize(&placeholders).as_slice() {
+        [] => return Ok(String::new()),
+        [token] => {
+            restore_wildcards(&pattern[token.bytes.clone()], &token.text)
+        }
+        tokens => bail!(
+            "pattern '{pattern}' should be single word, but it's split into \
+             {} words",
+            tokens.len()
+        ),
+    };
+
+    Ok(word
+        .chars()
+        .chunk_by(|&ch| is_wildcard(ch))
+        .into_iter()
+        .map(|(is_wildcards, chars)| {
+            let part: String = chars.collect();
+            if is_wildcards {
+                part
+            } else {
+                analyzer.filter_word_part(&part)
+            }
+        })
+        .collect())
+}
+
+/// Puts wildcards of original pattern back in place of placeholders of
+/// tokenized pattern. Tokenizer only removes characters, e.g. apostrophes, so
+/// characters left go in the same order as in original pattern.
+fn restore_wildcards(original: &str, tokenized: &str) -> String {
+    let mut tokenized = tokenized.chars().peekable();
+
+    original
+        .chars()
+        .filter(|&ch| {
+            let kept = tokenized.peek().is_some_and(|&tokenized_ch| {
+                tokenized_ch == ch
+                    || (is_wildcard(ch) && tokenized_ch == WILDCARD_PLACEHOLDER)
+            });
+            if kept {
+                tokenized.next();
+            }
+            kept
+        })
+        .collect()
+}
+
+/// Gets regular characters of normalized pattern in front of its first
+/// wildcard. All terms matching pattern start with them.
+pub fn get_pattern_prefix(pattern: &str) -> &str {
+    pattern
+        .find(is_wildcard)
+        .map_or(pattern, |idx| &pattern[..idx])
+}
+
+/// Finds terms of the field matching normalized pattern.
+///
+/// Only terms starting with pattern prefix are walked in the term dictionary,
+/// so the longer the prefix, the cheaper the expansion.
+fn expand_pattern(
+    pattern: &str,
+    segment: &dyn IndexSegment,
+    field: &str,
+) -> Result<Vec<ExpandedTerm>> {
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    let mut term_chars = Vec::new();
+
+    let mut terms = Vec::new();
+
+    let prefix = get_pattern_prefix(pattern);
+
+    for term in segment.get_terms_with_prefix(field, prefix) {
+        let term = term?;
+
+        term_chars.clear();
+        term_chars.extend(term.chars());
+
+        if matches_pattern(&pattern_chars, &term_chars) {
+            terms.push(ExpandedTerm { term, boost: 1.0 });
+        }
+    }
+
+    Ok(terms)
+}
+
+/// Finds terms of the field within edit distance from normalized term.
+///
+/// Each term is boosted by `1 / (1 + distance)`, so exact term gets full score,
+/// and each edit makes score lower.
+fn expand_fuzzy(
+    term: &str,
+    max_distance: u8,
+    segment: &dyn IndexSegment,
+    field: &str,
+) -> Result<Vec<ExpandedTerm>> {
+    let automaton = LevenshteinAutomaton::new(term, max_distance)?;
+
+    let mut terms = Vec::new();
+
+    for term in segment.get_terms_matching(field, &automaton) {
+        let term = term?;
+        let distance = automaton.get_distance(&term).unwrap_or(max_distance);
+        terms.push(ExpandedTerm {
+            term,
+            boost: 1.0 / (1.0 + f64::from(distance)),
+        });
+    }
+
+    Ok(terms)
+}
+
+const fn is_wildcard(ch: char) -> bool {
+    ch == ANY_CHARS || ch == SINGLE_CHAR
+}
+
+/// Checks if term matches the whole pattern.
+///
+/// Greedy matching with backtracking to the last `*`, which takes linear time
+/// on typical patterns.
+pub fn matches_pattern(pattern: &[char], term: &[char]) -> bool {
+    let mut pattern_idx = 0;
+    let mut term_idx = 0;
+
+    // position of last `*` in pattern, and position in term it was matched at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while term_idx < term.len() {
+        match pattern.get(pattern_idx) {
+            Some(&ANY_CHARS) => {
+                backtrack = Some((pattern_idx, term_idx));
+                pattern_idx += 1;
+            }
+            Some(&ch) if ch == SINGLE_CHAR || ch == term[term_idx] => {
+                pattern_idx += 1;
+                term_idx += 1;
+            }
+            _ => {
+                // let last `*` take one more character and try again
+                let Some((star_idx, star_term_idx)) = backtrack else {
+                    return false;
+                };
+                backtrack = Some((star_idx, star_term_idx + 1));
+                pattern_idx = star_idx + 1;
+                term_idx = star_term_idx + 1;
+            }
+        }
+    }
+
+    pattern[pattern_idx..].iter().all(|&ch| ch == ANY_CHARS)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::engines::nano::analysis::{
+        AlphabeticFilter, WhitespaceTokenizer,
+    };
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::index::{DiskIndexOptions, build_index};
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    fn matches(pattern: &str, term: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let term: Vec<char> = term.chars().collect();
+        matches_pattern(&pattern, &term)
+    }
+
+    fn expand(
+        texts: &[&str],
+        expansion: &TermExpansion,
+        max_terms: usize,
+    ) -> Result<Vec<(Term, f64)>> {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            texts.iter().map(|text| [("text", *text)]),
+        );
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let terms =
+            expansion.expand(&index.get_segments(), "text", max_terms)?;
+        Ok(terms.into_iter().map(|t| (t.term, t.boost)).collect())
+    }
+
+    fn expand_terms(
+        texts: &[&str],
+        pattern: &str,
+        max_terms: usize,
+    ) -> Result<Vec<Term>> {
+        let expansion = TermExpansion::Pattern(pattern.to_string());
+        let terms = expand(texts, &expansion, max_terms)?;
+        Ok(terms.into_iter().map(|(term, _)| term).collect())
+    }
+
+    #[test]
+    fn test_normalize_pattern() -> Result<()> {
+        let analyzer = Analyzer::default();
+
+        assert_eq!(normalize_pattern("Wom?n's", &analyzer)?, "wom?ns");
+        assert_eq!(normalize_pattern("(Retriev*)", &analyzer)?, "retriev*");
+        assert_eq!(normalize_pattern("--*", &analyzer)?, "*");
+        assert_eq!(normalize_pattern("--", &analyzer)?, "");
+
+        // dots and commas inside words are kept, same as tokenizer keeps them
+        assert_eq!(normalize_pattern("U.S*", &analyzer)?, "u.s*");
+        assert_eq!(normalize_pattern("3.1?", &analyzer)?, "3.1?");
+        assert_eq!(normalize_pattern("1,00*", &analyzer)?, "1,00*");
+
+        // tokenizer splits these into several words, so they match no term
+        assert!(normalize_pattern("Covid-19*", &analyzer).is_err());
+        assert!(normalize_pattern("cat,dog*", &analyzer).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_pattern_with_analyzer_filters() -> Result<()> {
+        // without lowercase filter case is kept, same as in the index
+        let analyzer = Analyzer::new(WhitespaceTokenizer);
+        assert_eq!(normalize_pattern("Wom?N*", &analyzer)?, "Wom?N*");
+
+        // stop words are whole words, so parts of pattern are kept
+        assert_eq!(normalize_pattern("The*", &Analyzer::default())?, "the*");
+
+        let analyzer = Analyzer::default().with_filter(AlphabeticFilter);
+        assert_eq!(normalize_pattern("Cat9?", &analyzer)?, "cat?");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_pattern_prefix() {
+        assert_eq!(get_pattern_prefix("retriev*"), "retriev");
+        assert_eq!(get_pattern_prefix("wom?n"), "wom");
+        assert_eq!(get_pattern_prefix("*tion"), "");
+        assert_eq!(get_pattern_prefix("word"), "word");
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches("retriev*", "retrieval"));
+        assert!(matches("retriev*", "retriev"));
+        assert!(!matches("retriev*", "retrie"));
+
+        assert!(matches("wom?n", "woman"));
+        assert!(matches("wom?n", "women"));
+        assert!(!matches("wom?n", "womn"));
+        assert!(!matches("wom?n", "womaen"));
+
+        assert!(matches("inform*tion", "information"));
+        assert!(matches("inform*tion", "informtion"));
+        assert!(!matches("inform*tion", "informations"));
+
+        assert!(matches("a*b*c", "abbbc"));
+        assert!(matches("a*b*c", "axbxbxc"));
+        assert!(!matches("a*b*c", "axbx"));
+        assert!(matches("a**", "a"));
+    }
+
+    #[test]
+    fn test_expand_pattern() -> Result<()> {
+        let texts = ["retrieval retrieve retrieved", "retriever retina"];
+        assert_eq!(
+            expand_terms(&texts, "retriev*", 10)?,
+            &["retrieval", "retrieve", "retrieved", "retriever"]
+        );
+        assert_eq!(expand_terms(&texts, "retriev?", 10)?, &["retrieve"]);
+        assert_eq!(expand_terms(&texts, "ret*al", 10)?, &["retrieval"]);
+        assert!(expand_terms(&texts, "x*", 10)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_pattern_max_terms() -> Result<()> {
+        let texts = ["cat cattle", "cattle catalog", "catalog cattle"];
+        assert_eq!(expand_terms(&texts, "cat*", 2)?, &["catalog", "cattle"]);
+        assert_eq!(expand_terms(&texts, "cat*", 1)?, &["cattle"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_max_terms_across_segments() -> Result<()> {
+        let dir = TempDir::new()?;
+        let medium = IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .max_segment_docs(2)
+                .build(),
+        );
+        let texts = ["catalog", "catalog", "cattle", "cattle", "catalog", "x"];
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            texts.iter().map(|text| [("text", *text)]),
+        );
+        let index = build_index(&medium, &mut docs)?;
+        assert_eq!(index.get_segments().len(), 3);
+
+        // "cattle" is the most frequent term of its segment, but "catalog" is
+        // found in more documents of the whole index
+        let expansion = TermExpansion::Pattern("cat*".to_string());
+        let terms = expansion.expand(&index.get_segments(), "text", 1)?;
+        assert_eq!(
+            terms,
+            &[ExpandedTerm {
+                term: "catalog".to_string(),
+                boost: 1.0
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_fuzzy() -> Result<()> {
+        let texts = ["retrieval retrieve retreival", "retina retrieving"];
+        let expansion = TermExpansion::Fuzzy {
+            term: "retrieval".to_string(),
+            distance: 2,
+        };
+        assert_eq!(
+            expand(&texts, &expansion, 10)?,
+            &[
+                ("retreival".to_string(), 0.5),
+                ("retrieval".to_string(), 1.0),
+                ("retrieve".to_string(), 1.0 / 3.0),
+            ]
+        );
+        assert_eq!(
+            expand(&texts, &expansion, 2)?,
+            &[
+                ("retreival".to_string(), 0.5),
+                ("retrieval".to_string(), 1.0),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy_from_query_ast() -> Result<()> {
+        let query_ast = QueryAst::Fuzzy {
+            word: "Word",
+            distance: 3,
+        };
+        assert_eq!(
+            TermExpansion::from_query_ast(&query_ast, &Analyzer::default())
+                .err()
+                .map(|err| err.to_string())
+                .as_deref(),
+            Some("fuzzy distance should be at most 2")
+        );
+        assert!(
+            TermExpansion::from_query_ast(
+                &QueryAst::Word("word"),
+                &Analyzer::default()
+            )?
+            .is_none()
+        );
+        Ok(())
+    }
+}
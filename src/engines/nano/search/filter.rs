@@ -0,0 +1,361 @@
+use std::num::NonZero;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{Context, Result, anyhow, bail};
+use lru::LruCache;
+
+use super::iterators::{
+    BitSetDocIdIterator, ItDocId, PostingListIterator, ScoringDocIdIterator,
+};
+use super::planner::plan_range;
+use super::query::{Lexer, Parser, QueryAst};
+use super::stats::GlobalStats;
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::model::IndexSegment;
+use crate::utils::BitSet;
+
+/// Documents matching filter by segment id and filter text
+type BitSets = LruCache<(String, String), Arc<BitSet>>;
+
+/// Default number of bitsets kept in [`FilterCache`].
+const DEFAULT_FILTER_CACHE_CAPACITY: NonZero<usize> =
+    NonZero::new(1024).expect("filter cache capacity should not be zero");
+
+/// Documents matching filters, cached per segment.
+///
+/// Segments are immutable, so documents of a segment matching a filter never
+/// change, and the same filter repeated in many queries is only evaluated
+/// once per segment. Segments are identified by their unique ids, so bitsets
+/// of another index or an index built with another analyzer never match, but
+/// they still take memory until evicted or cleared.
+///
+/// Cache keeps up to `capacity` bitsets and evicts least recently used ones,
+/// same as Lucene's `LRUQueryCache`, which also caches only filters used
+/// often enough.
+#[derive(Debug)]
+pub struct FilterCache {
+    bitsets: Mutex<BitSets>,
+}
+
+impl Default for FilterCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILTER_CACHE_CAPACITY)
+    }
+}
+
+impl FilterCache {
+    #[must_use]
+    pub fn new(capacity: NonZero<usize>) -> Self {
+        Self {
+            bitsets: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Gets documents of the segment matching the filter from cache, or
+    /// builds and caches them if they are not there yet.
+    ///
+    /// Lock is not held while building, so concurrent searches may build the
+    /// same bitset twice, but don't wait for each other.
+    fn get_or_build(
+        &self,
+        segment: &dyn IndexSegment,
+        filter: &str,
+        build: impl FnOnce() -> Result<BitSet>,
+    ) -> Result<Arc<BitSet>> {
+        let key = (segment.get_id().to_string(), filter.to_string());
+
+        if let Some(bitset) = self.lock()?.get(&key) {
+            return Ok(Arc::clone(bitset));
+        }
+
+        let bitset = Arc::new(build()?);
+        self.lock()?.put(key, Arc::clone(&bitset));
+
+        Ok(bitset)
+    }
+
+    /// Gets number of cached bitsets of all segments.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.lock()?.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.lock()?.is_empty())
+    }
+
+    /// Removes all cached bitsets, e.g. once index is rebuilt.
+    pub fn clear(&self) -> Result<()> {
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, BitSets>> {
+        self.bitsets
+            .lock()
+            .map_err(|_| anyhow!("filter cache should not be poisoned"))
+    }
+}
+
+/// Filters of a search request, which restrict documents matching the query
+/// without affecting their scores.
+pub struct Filters<'a> {
+    filters: Vec<(&'a str, QueryAst<'a>)>,
+    cache: &'a FilterCache,
+}
+
+impl<'a> Filters<'a> {
+    pub fn parse(
+        filters: &'a [String],
+        cache: &'a FilterCache,
+    ) -> Result<Self> {
+        let filters = filters
+            .iter()
+            .map(|filter| {
+                let ast =
+                    Parser::new(Lexer::new(filter)).parse().with_context(
+                        || format!("filter '{filter}' should be parsed"),
+                    )?;
+                Ok((filter.as_str(), ast))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { filters, cache })
+    }
+
+    /// Plans iterator over documents of the segment matching each filter,
+    /// taking them from cache if possible.
+    pub fn plan_for_segment(
+        &self,
+        segment: &dyn IndexSegment,
+        stats: &GlobalStats,
+        analyzer: &Analyzer,
+    ) -> Result<Vec<Box<dyn ScoringDocIdIterator>>> {
+        let fields: Vec<&str> =
+            stats.fields.iter().map(String::as_str).collect();
+
+        self.filters
+            .iter()
+            .map(|(filter, ast)| {
+                let bitset =
+                    self.cache.get_or_build(segment, filter, || {
+                        build_bitset(ast, &fields, segment, stats, analyzer)
+                    })?;
+                let it: Box<dyn ScoringDocIdIterator> =
+                    Box::new(BitSetDocIdIterator::new(*filter, bitset));
+                Ok(it)
+            })
+            .collect()
+    }
+}
+
+/// Finds all documents of the segment matching the filter.
+///
+/// Unlike queries, filters are not normalized, since they are not scored: stop
+/// words match documents containing them, and `NOT` may go anywhere, e.g.
+/// "NOT draft" matches all documents without "draft". Word analyzed into
+/// several terms matches documents containing all of them, and word without
+/// terms matches nothing.
+fn build_bitset(
+    ast: &QueryAst,
+    fields: &[&str],
+    segment: &dyn IndexSegment,
+    stats: &GlobalStats,
+    analyzer: &Analyzer,
+) -> Result<BitSet> {
+    let docs_count = segment.get_stats().indexed_docs_count as usize;
+
+    Ok(match ast {
+        QueryAst::Word(word) => {
+            let tokens = analyzer.analyze(word);
+            let mut bitset = if tokens.is_empty() {
+                BitSet::new(docs_count)
+            } else {
+                BitSet::full(docs_count)
+            };
+            for token in tokens {
+                let mut term_bitset = BitSet::new(docs_count);
+                for field in fields {
+                    // docids are enough, so term frequencies are not even read
+                    let mut it = PostingListIterator::create_for_segment(
+                        segment,
+                        field,
+                        &token.text,
+                        stats,
+                    )?
+                    .without_scoring();
+                    insert_all(&mut term_bitset, &mut it)?;
+                }
+                bitset.intersect_with(&term_bitset);
+            }
+            bitset
+        }
+        QueryAst::Range { lower, upper } => {
+            let mut bitset = BitSet::new(docs_count);
+            let mut it = plan_range(*lower, *upper, fields, segment)?;
+            insert_all(&mut bitset, it.as_mut())?;
+            bitset
+        }
+        QueryAst::Field { field, query } => {
+            build_bitset(query, &[field], segment, stats, analyzer)?
+        }
+        QueryAst::And(operands) => {
+            let mut bitset = BitSet::full(docs_count);
+            for operand in operands {
+                bitset.intersect_with(&build_bitset(
+                    operand, fields, segment, stats, analyzer,
+                )?);
+            }
+            bitset
+        }
+        QueryAst::Or(operands) => {
+            let mut bitset = BitSet::new(docs_count);
+            for operand in operands {
+                bitset.union_with(&build_bitset(
+                    operand, fields, segment, stats, analyzer,
+                )?);
+            }
+            bitset
+        }
+        QueryAst::Not(operand) => {
+            let mut bitset =
+                build_bitset(operand, fields, segment, stats, analyzer)?;
+            bitset.invert();
+            bitset
+        }
+        QueryAst::Phrase(_)
+        | QueryAst::Proximity { .. }
+        | QueryAst::Prefix(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Fuzzy { .. } => {
+            bail!("filter should only contain words, ranges and operators")
+        }
+    })
+}
+
+fn insert_all(
+    bitset: &mut BitSet,
+    it: &mut dyn ScoringDocIdIterator,
+) -> Result<()> {
+    it.advance()?;
+    while let ItDocId::Active(docid) = it.current_docid()? {
+        bitset.insert(docid as usize);
+        it.advance()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::index::{MemoryIndex, build_index};
+    use crate::engines::nano::search::SearchOptions;
+    use crate::model::doc::{DocValue, DocValueField};
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    #[test]
+    fn test_filters() -> Result<()> {
+        // setup
+        let mut docs = TestDocsIterator::from_enumerated_fields([
+            [("title", "cat"), ("text", "the cat")],
+            [("title", "dog"), ("text", "cat and dog")],
+            [("title", "mouse"), ("text", "mouse")],
+            [("title", "cat"), ("text", "dog")],
+        ])
+        .with_values([1, 2, 3, 4].map(|count| {
+            vec![DocValueField::new("count", DocValue::U64(count))]
+        }));
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let analyzer = Analyzer::default();
+        let stats = GlobalStats::collect(
+            &QueryAst::Word("cat"),
+            index.as_ref(),
+            &analyzer,
+            &SearchOptions::default(),
+        )?;
+        let cache = FilterCache::default();
+
+        let filter_docids = |filter: &str| -> Result<Vec<usize>> {
+            let filters = [filter.to_string()];
+            let mut its = Filters::parse(&filters, &cache)?
+                .plan_for_segment(segment, &stats, &analyzer)?;
+            let mut docids = Vec::new();
+            let mut it = its.pop().ok_or_else(|| anyhow!("should exist"))?;
+            it.advance()?;
+            while let ItDocId::Active(docid) = it.current_docid()? {
+                docids.push(docid as usize);
+                it.advance()?;
+            }
+            Ok(docids)
+        };
+
+        // execute & assert
+        assert_eq!(filter_docids("cat")?, vec![0, 1, 3]);
+        assert_eq!(filter_docids("title:cat")?, vec![0, 3]);
+        // stop words are removed by analyzer, so they match no documents
+        assert!(filter_docids("the")?.is_empty());
+        assert_eq!(filter_docids("NOT cat")?, vec![2]);
+        assert_eq!(filter_docids("cat AND NOT title:cat")?, vec![1]);
+        assert_eq!(filter_docids("mouse OR count:[4 TO *]")?, vec![2, 3]);
+        assert_eq!(filter_docids("title:(dog OR mouse)")?, vec![1, 2]);
+        assert!(filter_docids("unknown")?.is_empty());
+
+        // repeated filter is taken from cache
+        assert_eq!(cache.len()?, 8);
+        assert_eq!(filter_docids("cat")?, vec![0, 1, 3]);
+        assert_eq!(cache.len()?, 8);
+
+        // words are analyzed same way as documents
+        assert_eq!(filter_docids("(Cat)")?, vec![0, 1, 3]);
+        assert_eq!(filter_docids("cat-dog")?, vec![1, 3]);
+        assert!(filter_docids("--")?.is_empty());
+
+        assert!(filter_docids("\"cat dog\"").is_err());
+        assert!(filter_docids("count:[a TO b]").is_err());
+        assert!(filter_docids("(cat").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_cache_evicts_least_recently_used() -> Result<()> {
+        // setup
+        let segment = |id: &str| MemoryIndex {
+            id: id.to_string(),
+            ..MemoryIndex::default()
+        };
+        let (first, second) = (segment("first"), segment("second"));
+        let cache = FilterCache::new(NonZero::new(2).context("not zero")?);
+
+        let builds = Cell::new(0);
+        let get = |segment: &MemoryIndex, filter: &str| -> Result<()> {
+            cache.get_or_build(segment, filter, || {
+                builds.set(builds.get() + 1);
+                Ok(BitSet::new(1))
+            })?;
+            Ok(())
+        };
+
+        // execute & assert
+        // segments are told apart by their ids
+        get(&first, "cat")?;
+        get(&second, "cat")?;
+        assert_eq!(builds.get(), 2);
+
+        // recently used bitset stays, while the other one gets evicted
+        get(&first, "cat")?;
+        get(&first, "dog")?;
+        assert_eq!(builds.get(), 3);
+        assert_eq!(cache.len()?, 2);
+
+        get(&first, "cat")?;
+        assert_eq!(builds.get(), 3);
+        get(&second, "cat")?;
+        assert_eq!(builds.get(), 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::Result;
+
+use super::expansion::{TermExpansion, matches_pattern};
+use super::normalizer::normalize_query;
+use super::query::QueryAst;
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::automaton::LevenshteinAutomaton;
+use crate::engines::nano::index::model::Term;
+use crate::model::doc::{Doc, DocField};
+use crate::model::highlight::{Fragment, HighlightOptions};
+
+/// Max part of fragment in front of its first matching word, so the word is
+/// shown with some context before it, but most of fragment goes after it.
+const CONTEXT_BEFORE_SHARE: f64 = 0.3;
+
+/// Query term, that words of document are highlighted for.
+struct TermMatcher<'a> {
+    /// Field the term is searched in, or `None` if it's searched in all fields
+    field: Option<&'a str>,
+    kind: TermMatcherKind,
+}
+
+enum TermMatcherKind {
+    Exact(Term),
+
+    /// Normalized wildcard pattern, split into chars
+    Pattern(Vec<char>),
+
+    Fuzzy(LevenshteinAutomaton),
+}
+
+impl TermMatcher<'_> {
+    fn matches(&self, field: &str, term: &str) -> bool {
+        if self
+            .field
+            .is_some_and(|matcher_field| matcher_field != field)
+        {
+            return false;
+        }
+
+        match &self.kind {
+            TermMatcherKind::Exact(exact) => exact == term,
+            TermMatcherKind::Pattern(pattern) => {
+                matches_pattern(pattern, &term.chars().collect::<Vec<_>>())
+            }
+            TermMatcherKind::Fuzzy(automaton) => {
+                automaton.get_distance(term).is_some()
+            }
+        }
+    }
+}
+
+/// Word of field text, analyzed same way as it was indexed.
+struct Token {
+    bytes: Range<usize>,
+    chars: Range<usize>,
+
+    /// Bytes of the word to highlight, without punctuation around it, and
+    /// index of the query term matching the word. `None` if word does not
+    /// match
+    highlight: Option<(Range<usize>, usize)>,
+}
+
+/// Picks fragments of document fields best matching the query, with words
+/// matching query terms highlighted.
+///
+/// Words are analyzed with `analyzer`, same as at indexing, so highlights
+/// match what was actually found, and stop words are only highlighted if
+/// `analyzer` keeps them. Words of phrase and proximity queries are
+/// highlighted wherever they go, without checking their positions. Words under
+/// `NOT` are never highlighted.
+///
+/// Each fragment is scored by words it contains, as sum of `1 + ln(count)`
+/// for each matching query term, so fragments with more distinct terms go
+/// first. Fragments do not overlap, and are returned from the best one.
+pub fn highlight_doc(
+    query_ast: &QueryAst,
+    doc: &Doc,
+    analyzer: &Analyzer,
+    options: &HighlightOptions,
+) -> Result<Vec<Fragment>> {
+    let Some(query_ast) = normalize_query(query_ast, analyzer) else {
+        return Ok(Vec::new());
+    };
+
+    let mut matchers = Vec::new();
+    collect_term_matchers(&query_ast, None, analyzer, &mut matchers)?;
+
+    let mut candidates = Vec::new();
+    for field in &doc.fields {
+        let tokens = tokenize_field(field, analyzer, &matchers);
+        collect_fragment_candidates(field, &tokens, options, &mut candidates);
+    }
+
+    candidates.sort_by(|(a, a_bytes), (b, b_bytes)| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a_bytes.start.cmp(&b_bytes.start))
+    });
+
+    let mut fragments: Vec<(Fragment, Range<usize>)> = Vec::new();
+    for (candidate, bytes) in candidates {
+        if fragments.len() >= options.max_fragments {
+            break;
+        }
+
+        let overlaps = fragments.iter().any(|(fragment, fragment_bytes)| {
+            fragment.field == candidate.field
+                && bytes.start.max(fragment_bytes.start)
+                    < bytes.end.min(fragment_bytes.end)
+        });
+        if !overlaps {
+            fragments.push((candidate, bytes));
+        }
+    }
+
+    Ok(fragments
+        .into_iter()
+        .map(|(fragment, _)| fragment)
+        .collect())
+}
+
+fn collect_term_matchers<'a>(
+    query_ast: &QueryAst<'a>,
+    field: Option<&'a str>,
+    analyzer: &Analyzer,
+    matchers: &mut Vec<TermMatcher<'a>>,
+) -> Result<()> {
+    match query_ast {
+        QueryAst::Word(word) => matchers.push(TermMatcher {
+            field,
+            kind: TermMatcherKind::Exact(analyzer.analyze_word(word)?),
+        }),
+        QueryAst::Phrase(words) | QueryAst::Proximity { words, .. } => {
+            for word in words {
+                matchers.push(TermMatcher {
+                    field,
+                    kind: TermMatcherKind::Exact(analyzer.analyze_word(word)?),
+                });
+            }
+        }
+        QueryAst::Prefix(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Fuzzy { .. } => {
+            let kind = match TermExpansion::from_query_ast(query_ast, analyzer)?
+            {
+                Some(TermExpansion::Pattern(pattern)) => {
+                    TermMatcherKind::Pattern(pattern.chars().collect())
+                }
+                Some(TermExpansion::Fuzzy { term, distance }) => {
+                    TermMatcherKind::Fuzzy(LevenshteinAutomaton::new(
+                        &term, distance,
+                    )?)
+                }
+                None => return Ok(()),
+            };
+            matchers.push(TermMatcher { field, kind });
+        }
+        QueryAst::Field { field, query } => {
+            collect_term_matchers(query, Some(field), analyzer, matchers)?;
+        }
+        QueryAst::And(queries) | QueryAst::Or(queries) => {
+            for query in queries {
+                collect_term_matchers(query, field, analyzer, matchers)?;
+            }
+        }
+        QueryAst::Range { .. } | QueryAst::Not(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Analyzes field text into words with their positions in text, and finds
+/// query terms matching them.
+fn tokenize_field(
+    field: &DocField,
+    analyzer: &Analyzer,
+    matchers: &[TermMatcher],
+) -> Vec<Token> {
+    let text = &field.text;
+
+    // chars are counted from the end of previous word, so text is walked
+    // about once. Words may step back over punctuation shared with previous
+    // word, e.g. "-" of "covid-19"
+    let mut counted_bytes = 0;
+    let mut counted_chars = 0;
+    let mut get_char_idx = |byte_idx: usize| {
+        if byte_idx >= counted_bytes {
+            counted_chars += text[counted_bytes..byte_idx].chars().count();
+        } else {
+            counted_chars -= text[byte_idx..counted_bytes].chars().count();
+        }
+        counted_bytes = byte_idx;
+        counted_chars
+    };
+
+    // words removed by analyzer, e.g. stop words, match nothing, but still
+    // make fragments
+    analyzer
+        .analyze_with_removed(text)
+        .into_iter()
+        .map(|(word_bytes, term)| {
+            let highlight = term
+                .and_then(|term| {
+                    matchers
+                        .iter()
+                        .position(|matcher| matcher.matches(&field.name, &term))
+                })
+                .map(|matcher_idx| {
+                    let word = &text[word_bytes.clone()];
+                    (trim_word(word, word_bytes.start), matcher_idx)
+                });
+
+            let bytes = extend_over_punctuation(text, &word_bytes);
+            Token {
+                chars: get_char_idx(bytes.start)..get_char_idx(bytes.end),
+                bytes,
+                highlight,
+            }
+        })
+        .collect()
+}
+
+/// Gets bytes of the word without punctuation around it, e.g. without quotes
+/// and commas kept by whitespace tokenizer.
+fn trim_word(word: &str, word_start_byte: usize) -> Range<usize> {
+    let start = word.find(char::is_alphanumeric).unwrap_or(0);
+    let end = word
+        .rfind(char::is_alphanumeric)
+        .and_then(|idx| {
+            word[idx..].chars().next().map(|ch| idx + ch.len_utf8())
+        })
+        .unwrap_or(word.len());
+
+    word_start_byte + start..word_start_byte + end
+}
+
+/// Extends bytes of the word over punctuation around it, up to whitespace or
+/// another word, so fragments keep e.g. quotes and the final dot.
+fn extend_over_punctuation(text: &str, bytes: &Range<usize>) -> Range<usize> {
+    let is_punctuation =
+        |ch: char| !ch.is_whitespace() && !ch.is_alphanumeric();
+
+    let start = text[..bytes.start].trim_end_matches(is_punctuation).len();
+    let end =
+        text.len() - text[bytes.end..].trim_start_matches(is_punctuation).len();
+
+    start..end
+}
+
+/// Makes fragment around each matching word of the field, along with bytes of
+/// the fragment in field text.
+fn collect_fragment_candidates(
+    field: &DocField,
+    tokens: &[Token],
+    options: &HighlightOptions,
+    candidates: &mut Vec<(Fragment, Range<usize>)>,
+) {
+    let max_context_before =
+        (options.fragment_chars as f64 * CONTEXT_BEFORE_SHARE) as usize;
+
+    for (match_idx, token) in tokens.iter().enumerate() {
+        if token.highlight.is_none() {
+            continue;
+        }
+
+        let mut start_idx = match_idx;
+        while start_idx > 0
+            && token.chars.start - tokens[start_idx - 1].chars.start
+                <= max_context_before
+        {
+            start_idx -= 1;
+        }
+
+        let start_char = tokens[start_idx].chars.start;
+        let mut end_idx = match_idx;
+        while end_idx + 1 < tokens.len()
+            && tokens[end_idx + 1].chars.end - start_char
+                <= options.fragment_chars
+        {
+            end_idx += 1;
+        }
+
+        let window = &tokens[start_idx..=end_idx];
+        let bytes = window[0].bytes.start..window[window.len() - 1].bytes.end;
+
+        let mut term_counts: HashMap<usize, usize> = HashMap::new();
+        let mut highlights = Vec::new();
+        for (highlight, matcher_idx) in
+            window.iter().filter_map(|token| token.highlight.as_ref())
+        {
+            *term_counts.entry(*matcher_idx).or_default() += 1;
+            highlights.push(
+                highlight.start - bytes.start..highlight.end - bytes.start,
+            );
+        }
+
+        let score = term_counts
+            .values()
+            .map(|count| 1.0 + (*count as f64).ln())
+            .sum();
+
+        candidates.push((
+            Fragment {
+                field: field.name.clone(),
+                text: field.text[bytes.clone()].to_string(),
+                highlights,
+                score,
+            },
+            bytes,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::search::query::{Lexer, Parser};
+
+    fn highlight(
+        query: &str,
+        fields: &[(&str, &str)],
+        options: &HighlightOptions,
+    ) -> Result<Vec<String>> {
+        let query_ast = Parser::new(Lexer::new(query)).parse()?;
+        let doc = Doc {
+            id: 0,
+            fields: fields
+                .iter()
+                .map(|(name, text)| DocField::new(*name, *text))
+                .collect(),
+            values: Vec::new(),
+        };
+
+        let analyzer = Analyzer::default();
+
+        Ok(highlight_doc(&query_ast, &doc, &analyzer, options)?
+            .iter()
+            .map(|fragment| {
+                format!(
+                    "{}: {}",
+                    fragment.field,
+                    fragment.mark(|word| format!("[{word}]"))
+                )
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_highlight_words() -> Result<()> {
+        let options = HighlightOptions::default();
+        let fields = [
+            ("title", "Cats and dogs"),
+            (
+                "text",
+                "The \"cat\" chased a mouse, then the dog chased the cat.",
+            ),
+        ];
+
+        assert_eq!(
+            highlight("cat dog", &fields, &options)?,
+            &[
+                "text: The \"[cat]\" chased a mouse, then the [dog] chased \
+                 the [cat]."
+            ]
+        );
+        assert_eq!(
+            highlight("cat* AND NOT mouse", &fields, &options)?,
+            &[
+                "text: The \"[cat]\" chased a mouse, then the dog chased the \
+                 [cat].",
+                "title: [Cats] and dogs",
+            ]
+        );
+        assert_eq!(
+            highlight("title:dogs mause~1", &fields, &options)?,
+            &[
+                "title: Cats and [dogs]",
+                "text: The \"cat\" chased a [mouse], then the dog chased the \
+                 cat.",
+            ]
+        );
+        assert_eq!(
+            highlight("\"chased the cat\"", &fields, &options)?,
+            &[
+                "text: The \"[cat]\" [chased] a mouse, then the dog [chased] \
+                 the [cat]."
+            ]
+        );
+        assert_eq!(
+            highlight("covid", &[("text", "Spread of covid-19.")], &options)?,
+            &["text: Spread of [covid]-19."]
+        );
+        assert!(highlight("bird", &fields, &options)?.is_empty());
+        assert!(highlight("the", &fields, &options)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlight_best_fragments() -> Result<()> {
+        let options = HighlightOptions::builder()
+            .fragment_chars(20)
+            .max_fragments(2)
+            .build();
+        let fields = [(
+            "text",
+            "cat one two three four five six seven cat dog eight nine ten \
+             eleven twelve thirteen dog",
+        )];
+
+        assert_eq!(
+            highlight("cat dog", &fields, &options)?,
+            &["text: seven [cat] [dog] eight", "text: [cat] one two three"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use crate::engines::nano::index::model::SegmentDocId;
+use crate::utils::{BitSet, TreeNode};
+
+/// Iterator over documents of a bitset, e.g. documents of the segment
+/// matching a cached filter.
+///
+/// All documents get zero score, so it only restricts documents of other
+/// operands it's intersected with, without changing their ranking.
+pub struct BitSetDocIdIterator {
+    name: String,
+    docids: Arc<BitSet>,
+    current_docid: ItDocId,
+}
+
+impl BitSetDocIdIterator {
+    pub fn new(name: impl Into<String>, docids: Arc<BitSet>) -> Self {
+        Self {
+            name: name.into(),
+            docids,
+            current_docid: ItDocId::NotStarted,
+        }
+    }
+
+    fn advance_from(&mut self, docid: SegmentDocId) {
+        self.current_docid = self
+            .docids
+            .next_from(docid as usize)
+            .map_or(ItDocId::Exhausted, |docid| {
+                ItDocId::Active(docid as SegmentDocId)
+            });
+    }
+}
+
+impl DocIdIterator for BitSetDocIdIterator {
+    fn advance(&mut self) -> Result<()> {
+        match self.current_docid {
+            ItDocId::NotStarted => self.advance_from(0),
+            ItDocId::Active(docid) => self.advance_from(docid + 1),
+            ItDocId::Exhausted => {}
+        }
+        Ok(())
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        match self.current_docid {
+            ItDocId::Active(docid) if docid >= target => {}
+            ItDocId::Exhausted => {}
+            _ => self.advance_from(target),
+        }
+        Ok(())
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        Ok(self.current_docid)
+    }
+
+    fn explain(&self) -> TreeNode {
+        TreeNode::new("BitSet")
+            .with_attr("name", self.name.clone())
+            .with_attr("docs_count", self.docids.count().to_string())
+    }
+}
+
+impl ScoringDocIdIterator for BitSetDocIdIterator {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.current_docid {
+            ItDocId::NotStarted => ItScore::NotStarted,
+            ItDocId::Active(_) => ItScore::Active(0.0),
+            ItDocId::Exhausted => ItScore::Exhausted,
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        Ok(self.explain().with_attr(
+            "score",
+            self.current_score()?.expect_val()?.to_string(),
+        ))
+    }
+
+    fn get_block_max_score(
+        &self,
+        _target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        Ok(if self.current_docid.is_exhausted() {
+            BlockMaxScore::EXHAUSTED
+        } else {
+            BlockMaxScore {
+                last_docid: SegmentDocId::MAX,
+                max_score: 0.0,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset() -> Result<()> {
+        let mut docids = BitSet::new(200);
+        for docid in [3, 70, 150] {
+            docids.insert(docid);
+        }
+        let mut it = BitSetDocIdIterator::new("filter", Arc::new(docids));
+        assert_eq!(it.current_docid()?, ItDocId::NotStarted);
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+        assert_eq!(it.current_score()?, ItScore::Active(0.0));
+
+        it.advance_to(3)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+
+        it.advance_to(71)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(150));
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Exhausted);
+        assert_eq!(it.get_block_max_score(151)?, BlockMaxScore::EXHAUSTED);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use crate::engines::nano::index::model::SegmentDocId;
+use crate::utils::TreeNode;
+
+/// Iterator over document IDs, that returns the same IDs as `input`, but
+/// multiplies their scores by `boost`.
+///
+/// It's called `BoostScorer` in Tantivy.
+pub struct BoostingDocIdIterator<'a> {
+    input: Box<dyn ScoringDocIdIterator + 'a>,
+    boost: f64,
+}
+
+impl<'a> BoostingDocIdIterator<'a> {
+    pub fn new(input: Box<dyn ScoringDocIdIterator + 'a>, boost: f64) -> Self {
+        Self { input, boost }
+    }
+}
+
+impl DocIdIterator for BoostingDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        self.input.advance()
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        self.input.advance_to(target)
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        self.input.current_docid()
+    }
+
+    fn explain(&self) -> TreeNode {
+        TreeNode::new("Boost")
+            .with_attr("boost", self.boost.to_string())
+            .with_child(self.input.explain())
+    }
+}
+
+impl ScoringDocIdIterator for BoostingDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.input.current_score()? {
+            ItScore::Active(score) => ItScore::Active(score * self.boost),
+            score => score,
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        Ok(TreeNode::new("Boost")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_attr("boost", self.boost.to_string())
+            .with_child(self.input.explain_score()?))
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        let block_max = self.input.get_block_max_score(target)?;
+        Ok(BlockMaxScore {
+            last_docid: block_max.last_docid,
+            max_score: block_max.max_score * self.boost,
+        })
+    }
+
+    fn set_min_competitive_score(&mut self, score: f64) {
+        self.input.set_min_competitive_score(score / self.boost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::search::iterators::test_utils::TestScoringDocIdIterator;
+
+    #[test]
+    fn test_boost() -> Result<()> {
+        let input = TestScoringDocIdIterator::new(vec![1, 3], vec![0.5, 7.0])?;
+        let mut it = BoostingDocIdIterator::new(Box::new(input), 0.5);
+
+        assert_eq!(it.current_score()?, ItScore::NotStarted);
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(1));
+        assert_eq!(it.current_score()?, ItScore::Active(0.25));
+
+        it.advance_to(2)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+        assert_eq!(it.current_score()?, ItScore::Active(3.5));
+
+        it.advance()?;
+        assert_eq!(it.current_score()?, ItScore::Exhausted);
+
+        Ok(())
+    }
+}
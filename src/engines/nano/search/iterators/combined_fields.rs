@@ -0,0 +1,272 @@
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use super::posting_list::PostingListIterator;
+use crate::engines::nano::index::model::SegmentDocId;
+use crate::engines::nano::search::scoring::{
+    FieldTermFreq, TermStats, combine_term_stats,
+};
+use crate::engines::nano::search::stats::GlobalStats;
+use crate::utils::TreeNode;
+
+/// Iterator over document IDs, that returns IDs of documents containing the
+/// term in any of `inputs` fields, and scores them as if all fields were one.
+///
+/// Each input reads posting list of the same term in different field, and has
+/// its own weight. Unlike union of fields, which sums scores of each field,
+/// occurrences in all fields are combined into single term frequency first,
+/// see `Scorer::score_fields` (e.g. BM25F for BM25).
+///
+/// It's called `CombinedFieldQuery` in Lucene.
+pub struct CombinedFieldsDocIdIterator<'a> {
+    inputs: Vec<PostingListIterator<'a>>,
+    weights: Vec<f64>,
+    current_docid: ItDocId,
+    stats: &'a GlobalStats,
+    /// Statistics of the term in all fields
+    term_stats: TermStats,
+}
+
+impl<'a> CombinedFieldsDocIdIterator<'a> {
+    pub fn new(
+        inputs: Vec<(PostingListIterator<'a>, f64)>,
+        stats: &'a GlobalStats,
+    ) -> Self {
+        let (inputs, weights): (Vec<_>, Vec<_>) = inputs.into_iter().unzip();
+
+        let term_stats = combine_term_stats(
+            &inputs
+                .iter()
+                .map(PostingListIterator::get_term_stats)
+                .zip(weights.iter().copied())
+                .collect::<Vec<_>>(),
+        );
+
+        Self {
+            inputs,
+            weights,
+            current_docid: ItDocId::NotStarted,
+            stats,
+            term_stats,
+        }
+    }
+
+    fn advance_internal(&mut self, target: Option<SegmentDocId>) -> Result<()> {
+        if self.current_docid.is_exhausted() {
+            return Ok(());
+        }
+
+        for it in &mut self.inputs {
+            if let Some(target) = target {
+                it.advance_to(target)?;
+            } else if self.current_docid.is_not_started()
+                // advance previously matched iterators
+                || it.current_docid()? == self.current_docid
+            {
+                it.advance()?;
+            }
+        }
+
+        let mut candidate: Option<SegmentDocId> = None;
+
+        for it in &self.inputs {
+            if let ItDocId::Active(docid) = it.current_docid()? {
+                candidate = Some(candidate.map_or(docid, |c| c.min(docid)));
+            }
+        }
+
+        self.current_docid =
+            candidate.map_or(ItDocId::Exhausted, ItDocId::Active);
+
+        Ok(())
+    }
+
+    /// Gets occurrences of the term in fields of current document.
+    fn get_field_term_freqs(
+        &self,
+        current_docid: SegmentDocId,
+    ) -> Result<Vec<FieldTermFreq>> {
+        let mut field_term_freqs = Vec::with_capacity(self.inputs.len());
+
+        for (it, weight) in self.inputs.iter().zip(&self.weights) {
+            if it.current_docid()? == ItDocId::Active(current_docid)
+                && let Some(freq) = it.get_field_term_freq(*weight)?
+            {
+                field_term_freqs.push(freq);
+            }
+        }
+
+        Ok(field_term_freqs)
+    }
+
+    fn calc_score(&self, field_term_freqs: &[FieldTermFreq]) -> f64 {
+        self.stats
+            .scorer
+            .score_fields(field_term_freqs, &self.term_stats)
+    }
+}
+
+impl DocIdIterator for CombinedFieldsDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        self.advance_internal(None)
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        self.advance_internal(Some(target))
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        Ok(self.current_docid)
+    }
+
+    fn explain(&self) -> TreeNode {
+        let mut tree = TreeNode::new("CombinedFields");
+        for (input, weight) in self.inputs.iter().zip(&self.weights) {
+            tree.add_child(
+                input.explain().with_attr("weight", weight.to_string()),
+            );
+        }
+        tree
+    }
+}
+
+impl ScoringDocIdIterator for CombinedFieldsDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        let current_docid = match self.current_docid {
+            ItDocId::NotStarted => return Ok(ItScore::NotStarted),
+            ItDocId::Exhausted => return Ok(ItScore::Exhausted),
+            ItDocId::Active(docid) => docid,
+        };
+
+        let field_term_freqs = self.get_field_term_freqs(current_docid)?;
+
+        Ok(ItScore::Active(self.calc_score(&field_term_freqs)))
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let current_docid = self.current_docid.expect_val()?;
+        let field_term_freqs = self.get_field_term_freqs(current_docid)?;
+
+        // fields go in the same order as their term frequencies
+        let mut fields = Vec::new();
+        for it in &self.inputs {
+            if it.current_docid()? == ItDocId::Active(current_docid) {
+                fields.push(it.get_field());
+            }
+        }
+
+        let term = self.inputs.first().map_or("", |it| it.get_term());
+
+        Ok(TreeNode::new("CombinedFields")
+            .with_attr("term", term)
+            .with_attr("score", self.calc_score(&field_term_freqs).to_string())
+            .with_attr("fields", fields.join(", "))
+            .with_attr("combine", "fields scored as one field")
+            .with_child(
+                self.stats
+                    .scorer
+                    .explain_fields(&field_term_freqs, &self.term_stats),
+            ))
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        let mut last_docid = SegmentDocId::MAX;
+        let mut field_term_freqs = Vec::new();
+
+        for (it, weight) in self.inputs.iter().zip(&self.weights) {
+            if let Some((block_last_docid, freq)) =
+                it.get_block_max_field_term_freq(target, *weight)?
+            {
+                last_docid = last_docid.min(block_last_docid);
+                field_term_freqs.push(freq);
+            }
+        }
+
+        if field_term_freqs.is_empty() {
+            return Ok(BlockMaxScore::EXHAUSTED);
+        }
+
+        // highest frequencies in shortest fields of the blocks can't be
+        // exceeded by any real document in them
+        Ok(BlockMaxScore {
+            last_docid,
+            max_score: self.calc_score(&field_term_freqs),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::analysis::Analyzer;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::search::options::SearchOptions;
+    use crate::engines::nano::search::query::QueryAst;
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    #[test]
+    fn test_combined_fields() -> Result<()> {
+        let mut docs = TestDocsIterator::from_enumerated_fields([
+            [("title", "cat"), ("text", "dog")],
+            [("title", "dog"), ("text", "cat cat")],
+            [("title", "cat"), ("text", "cat")],
+        ]);
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let stats = GlobalStats::collect(
+            &QueryAst::Word("cat"),
+            index.as_ref(),
+            &Analyzer::default(),
+            &SearchOptions::default(),
+        )?;
+
+        let mut it = CombinedFieldsDocIdIterator::new(
+            vec![
+                (
+                    PostingListIterator::create_for_segment(
+                        segment, "title", "cat", &stats,
+                    )?,
+                    2.0,
+                ),
+                (
+                    PostingListIterator::create_for_segment(
+                        segment, "text", "cat", &stats,
+                    )?,
+                    1.0,
+                ),
+            ],
+            &stats,
+        );
+
+        assert_eq!(it.current_docid()?, ItDocId::NotStarted);
+        assert_eq!(it.current_score()?, ItScore::NotStarted);
+
+        let mut scores = Vec::new();
+        it.advance()?;
+        while let ItDocId::Active(docid) = it.current_docid()? {
+            let score = it.current_score()?.expect_val()?;
+            assert!(score <= it.get_block_max_score(docid)?.max_score);
+            scores.push((docid, score));
+            it.advance()?;
+        }
+
+        assert_eq!(it.current_score()?, ItScore::Exhausted);
+
+        // cat found in all docs, in title of 2 docs and in text of 2 docs
+        assert_eq!(scores.len(), 3);
+        assert_eq!(it.term_stats.docs_with_term_count, 2);
+
+        // match in both fields beats match in single field, and match in
+        // heavier title beats two matches in text
+        assert!(scores[2].1 > scores[0].1);
+        assert!(scores[0].1 > scores[1].1);
+
+        Ok(())
+    }
+}
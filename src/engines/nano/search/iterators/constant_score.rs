@@ -0,0 +1,102 @@
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use crate::engines::nano::index::model::SegmentDocId;
+use crate::utils::TreeNode;
+
+/// Iterator over document IDs, that returns the same IDs as `input`, but gives
+/// all of them the same `score`.
+///
+/// It's called `ConstantScoreScorer` in Lucene and `ConstScorer` in Tantivy.
+pub struct ConstantScoreDocIdIterator<'a> {
+    input: Box<dyn ScoringDocIdIterator + 'a>,
+    score: f64,
+}
+
+impl<'a> ConstantScoreDocIdIterator<'a> {
+    pub fn new(input: Box<dyn ScoringDocIdIterator + 'a>, score: f64) -> Self {
+        Self { input, score }
+    }
+}
+
+impl DocIdIterator for ConstantScoreDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        self.input.advance()
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        self.input.advance_to(target)
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        self.input.current_docid()
+    }
+
+    fn explain(&self) -> TreeNode {
+        TreeNode::new("ConstantScore")
+            .with_attr("score", self.score.to_string())
+            .with_child(self.input.explain())
+    }
+}
+
+impl ScoringDocIdIterator for ConstantScoreDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.input.current_docid()? {
+            ItDocId::NotStarted => ItScore::NotStarted,
+            ItDocId::Exhausted => ItScore::Exhausted,
+            ItDocId::Active(_) => ItScore::Active(self.score),
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        // score of input is ignored, so only its plan is explained
+        Ok(TreeNode::new("ConstantScore")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_child(self.input.explain()))
+    }
+
+    fn get_block_max_score(
+        &self,
+        _target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        Ok(if self.input.current_docid()?.is_exhausted() {
+            BlockMaxScore::EXHAUSTED
+        } else {
+            BlockMaxScore {
+                last_docid: SegmentDocId::MAX,
+                max_score: self.score,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::search::iterators::test_utils::TestScoringDocIdIterator;
+
+    #[test]
+    fn test_constant_score() -> Result<()> {
+        let input = TestScoringDocIdIterator::new(vec![1, 3], vec![0.5, 7.0])?;
+        let mut it = ConstantScoreDocIdIterator::new(Box::new(input), 2.0);
+
+        assert_eq!(it.current_score()?, ItScore::NotStarted);
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(1));
+        assert_eq!(it.current_score()?, ItScore::Active(2.0));
+
+        it.advance_to(2)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+        assert_eq!(it.current_score()?, ItScore::Active(2.0));
+        assert_eq!(it.get_block_max_score(3)?.max_score, 2.0);
+
+        it.advance()?;
+        assert_eq!(it.current_score()?, ItScore::Exhausted);
+        assert_eq!(it.get_block_max_score(4)?, BlockMaxScore::EXHAUSTED);
+
+        Ok(())
+    }
+}
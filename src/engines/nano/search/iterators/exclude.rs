@@ -1,6 +1,8 @@
 use anyhow::Result;
 
-use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
 use crate::engines::nano::index::model::SegmentDocId;
 use crate::utils::TreeNode;
 
@@ -101,6 +103,27 @@ impl ScoringDocIdIterator for ExcludingDocIdIterator<'_> {
     fn current_score(&self) -> Result<ItScore> {
         self.include.current_score()
     }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        // excluded iterator doesn't match the document, so it doesn't affect
+        // the score
+        Ok(TreeNode::new("Exclusion")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_keyed_child("include", self.include.explain_score()?))
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        self.include.get_block_max_score(target)
+    }
+
+    fn set_min_competitive_score(&mut self, score: f64) {
+        // excluding doesn't change scores, so the same documents are not
+        // competitive in included iterator
+        self.include.set_min_competitive_score(score);
+    }
 }
 
 #[cfg(test)]
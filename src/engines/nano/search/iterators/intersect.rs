@@ -1,6 +1,8 @@
 use anyhow::{Ok, Result, bail};
 
-use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
 use crate::engines::nano::index::model::SegmentDocId;
 use crate::utils::TreeNode;
 
@@ -66,7 +68,7 @@ impl<'a> IntersectingDocIdIterator<'a> {
         // new()), and use shortest one as a leader, advancing all other
         // iterators to current leader docid. this works better when
         // iterator sizes are much different and they have ability to
-        // quickly skip gaps - term iterator should use skip lists
+        // quickly skip gaps (term iterators jump over blocks with skip data)
 
         loop {
             let candidate = self.max_docid()?;
@@ -140,6 +142,35 @@ impl ScoringDocIdIterator for IntersectingDocIdIterator<'_> {
             }
         })
     }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let mut tree = TreeNode::new("Intersection")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_attr("combine", "sum of all inputs");
+
+        for it in &self.inputs {
+            tree.add_child(it.explain_score()?);
+        }
+
+        Ok(tree)
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        // matching document gets scores of all inputs, which are summed in the
+        // same order as bounds, so sum of bounds is not exceeded
+        let mut res = BlockMaxScore::EXHAUSTED;
+
+        for it in &self.inputs {
+            let block_max = it.get_block_max_score(target)?;
+            res.last_docid = res.last_docid.min(block_max.last_docid);
+            res.max_score += block_max.max_score;
+        }
+
+        Ok(res)
+    }
 }
 
 #[cfg(test)]
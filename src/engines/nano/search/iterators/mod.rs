@@ -1,3 +1,15 @@
+mod bitset;
+pub use bitset::BitSetDocIdIterator;
+
+mod boost;
+pub use boost::BoostingDocIdIterator;
+
+mod combined_fields;
+pub use combined_fields::CombinedFieldsDocIdIterator;
+
+mod constant_score;
+pub use constant_score::ConstantScoreDocIdIterator;
+
 mod exclude;
 pub use exclude::ExcludingDocIdIterator;
 
@@ -5,11 +17,20 @@ mod intersect;
 pub use intersect::IntersectingDocIdIterator;
 
 mod model;
-pub use model::ScoringDocIdIterator;
+pub use model::{ItDocId, ScoringDocIdIterator};
+
+mod phrase;
+pub use phrase::PhraseDocIdIterator;
 
 mod posting_list;
 pub use posting_list::PostingListIterator;
 
+mod proximity;
+pub use proximity::ProximityDocIdIterator;
+
+mod range;
+pub use range::RangeDocIdIterator;
+
 mod union;
 pub use union::UnionDocIdIterator;
 
@@ -26,6 +26,58 @@ pub trait DocIdIterator {
 /// In lucene/tantivy it's called `Scorer`.
 pub trait ScoringDocIdIterator: DocIdIterator {
     fn current_score(&self) -> Result<ItScore>;
+
+    /// Explains how score of current document is calculated, i.e. which inputs
+    /// matched it and how their scores were combined.
+    ///
+    /// Iterator should point to a document.
+    ///
+    /// In lucene it's called `Weight.explain`.
+    fn explain_score(&self) -> Result<TreeNode>;
+
+    /// Gets upper bound of scores for documents starting from `target` docid,
+    /// without moving the iterator.
+    ///
+    /// The bound is only valid until returned last docid, after which it should
+    /// be requested again. By default there is no known bound.
+    ///
+    /// In lucene it's called `advanceShallow` + `getMaxScore`.
+    fn get_block_max_score(
+        &self,
+        _target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        Ok(BlockMaxScore::UNKNOWN)
+    }
+
+    /// Notifies iterator that documents with scores not greater than `score`
+    /// will be discarded by collector anyway, so iterator may skip them.
+    ///
+    /// Score can only grow over the course of iteration. Ignored by default.
+    ///
+    /// In lucene it's called `setMinCompetitiveScore`.
+    fn set_min_competitive_score(&mut self, _score: f64) {}
+}
+
+/// Upper bound of scores for a range of documents, from requested docid up to
+/// `last_docid` inclusively.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BlockMaxScore {
+    pub last_docid: SegmentDocId,
+    pub max_score: f64,
+}
+
+impl BlockMaxScore {
+    /// Bound of iterator, which cannot estimate its scores.
+    pub const UNKNOWN: Self = Self {
+        last_docid: SegmentDocId::MAX,
+        max_score: f64::INFINITY,
+    };
+
+    /// Bound of iterator, which has no documents left.
+    pub const EXHAUSTED: Self = Self {
+        last_docid: SegmentDocId::MAX,
+        max_score: 0.0,
+    };
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -0,0 +1,351 @@
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use super::posting_list::{PostingListIterator, intersect_posting_lists};
+use crate::engines::nano::index::model::{SegmentDocId, TermPosition};
+use crate::utils::TreeNode;
+
+/// Iterator over document IDs, that returns only IDs of documents containing
+/// all `inputs` terms going in a row, in the same order as inputs go.
+///
+/// First it intersects inputs, and then checks term positions of each
+/// candidate document, so positions are only read for documents containing all
+/// terms.
+///
+/// Document is scored as if phrase was a single term, with number of phrase
+/// occurrences as term frequency and sum of IDFs of phrase terms as IDF. Same
+/// as Lucene and Tantivy score phrases with BM25.
+pub struct PhraseDocIdIterator<'a> {
+    inputs: Vec<PostingListIterator<'a>>,
+    current_docid: ItDocId,
+
+    /// Number of phrase occurrences in current document
+    phrase_freq: u32,
+
+    /// Buffers for positions of each input term in candidate document
+    positions: Vec<Vec<TermPosition>>,
+}
+
+impl<'a> PhraseDocIdIterator<'a> {
+    pub fn new(inputs: Vec<PostingListIterator<'a>>) -> Self {
+        let positions = vec![Vec::new(); inputs.len()];
+
+        Self {
+            inputs,
+            current_docid: ItDocId::NotStarted,
+            phrase_freq: 0,
+            positions,
+        }
+    }
+
+    fn advance_internal(&mut self, target: Option<SegmentDocId>) -> Result<()> {
+        if self.current_docid.is_exhausted() {
+            return Ok(());
+        }
+
+        if self.inputs.is_empty() {
+            self.current_docid = ItDocId::Exhausted;
+            return Ok(());
+        }
+
+        // all inputs point to the same document (unless not started yet), so
+        // all of them should move
+        for it in &mut self.inputs {
+            if let Some(target) = target {
+                it.advance_to(target)?;
+            } else {
+                it.advance()?;
+            }
+        }
+
+        loop {
+            let Some(candidate) = intersect_posting_lists(&mut self.inputs)?
+            else {
+                self.current_docid = ItDocId::Exhausted;
+                return Ok(());
+            };
+
+            for (it, positions) in
+                self.inputs.iter_mut().zip(&mut self.positions)
+            {
+                it.read_positions(positions)?;
+            }
+
+            self.phrase_freq = count_phrase_occurrences(&self.positions);
+
+            if self.phrase_freq > 0 {
+                self.current_docid = ItDocId::Active(candidate);
+                return Ok(());
+            }
+
+            for it in &mut self.inputs {
+                it.advance()?;
+            }
+        }
+    }
+}
+
+/// Counts occurrences of phrase in a document, given sorted positions of each
+/// phrase term in it.
+///
+/// Phrase occurs at position `P` if its first term is at `P`, second term is
+/// at `P + 1`, and so on.
+fn count_phrase_occurrences(positions: &[Vec<TermPosition>]) -> u32 {
+    let Some((first_term_positions, other_terms_positions)) =
+        positions.split_first()
+    else {
+        return 0;
+    };
+
+    // positions of each term are sorted, so cursors only move forward
+    let mut cursors = vec![0; other_terms_positions.len()];
+    let mut count = 0;
+
+    'starts: for &start in first_term_positions {
+        for (idx, term_positions) in other_terms_positions.iter().enumerate() {
+            let expected = start + idx as TermPosition + 1;
+            let cursor = &mut cursors[idx];
+
+            while *cursor < term_positions.len()
+                && term_positions[*cursor] < expected
+            {
+                *cursor += 1;
+            }
+
+            match term_positions.get(*cursor) {
+                // term has no positions left, so phrase cannot occur further
+                None => break 'starts,
+                Some(&position) if position != expected => continue 'starts,
+                Some(_) => {}
+            }
+        }
+
+        count += 1;
+    }
+
+    count
+}
+
+impl DocIdIterator for PhraseDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        self.advance_internal(None)
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        if matches!(self.current_docid, ItDocId::Active(docid) if docid >= target)
+        {
+            return Ok(());
+        }
+
+        self.advance_internal(Some(target))
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        Ok(self.current_docid)
+    }
+
+    fn explain(&self) -> TreeNode {
+        let mut tree = TreeNode::new("Phrase");
+        for input in &self.inputs {
+            tree.add_child(input.explain());
+        }
+        tree
+    }
+}
+
+impl ScoringDocIdIterator for PhraseDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.current_docid {
+            ItDocId::NotStarted => ItScore::NotStarted,
+            ItDocId::Exhausted => ItScore::Exhausted,
+            ItDocId::Active(_) => {
+                // BM25 is linear in IDF, so summing scores of phrase terms with
+                // phrase frequency is the same as scoring phrase with sum of
+                // term IDFs
+                let mut total_score = 0.0;
+
+                for it in &self.inputs {
+                    total_score += it
+                        .score_with_term_freq(self.phrase_freq.into())?
+                        .expect_val()?;
+                }
+
+                ItScore::Active(total_score)
+            }
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let mut tree = TreeNode::new("Phrase")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_attr("phrase_freq", self.phrase_freq.to_string())
+            .with_attr("combine", "sum of terms scored with phrase_freq");
+
+        for it in &self.inputs {
+            tree.add_child(
+                it.explain_score_with_term_freq(self.phrase_freq.into())?,
+            );
+        }
+
+        Ok(tree)
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        // phrase cannot occur more times than any of its terms, and BM25 grows
+        // with term frequency, so sum of term bounds is not exceeded
+        let mut res = BlockMaxScore::EXHAUSTED;
+
+        for it in &self.inputs {
+            let block_max = it.get_block_max_score(target)?;
+            res.last_docid = res.last_docid.min(block_max.last_docid);
+            res.max_score += block_max.max_score;
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::analysis::Analyzer;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::search::options::SearchOptions;
+    use crate::engines::nano::search::query::QueryAst;
+    use crate::engines::nano::search::stats::GlobalStats;
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    /// Builds index of `texts` and collects IDs of docs containing phrase
+    /// along with number of phrase occurrences.
+    fn collect_phrase_matches(
+        texts: &[&str],
+        phrase: &[&str],
+    ) -> Result<Vec<(SegmentDocId, u32)>> {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            texts.iter().map(|text| [("text", *text)]),
+        );
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let stats = GlobalStats::collect(
+            &QueryAst::Phrase(phrase.to_vec()),
+            &*index,
+            &Analyzer::default(),
+            &SearchOptions::default(),
+        )?;
+
+        let inputs = phrase
+            .iter()
+            .map(|term| {
+                PostingListIterator::create_for_segment(
+                    segment, "text", term, &stats,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut it = PhraseDocIdIterator::new(inputs);
+        let mut res = Vec::new();
+
+        it.advance()?;
+        while let ItDocId::Active(docid) = it.current_docid()? {
+            res.push((docid, it.phrase_freq));
+            it.advance()?;
+        }
+
+        Ok(res)
+    }
+
+    #[test]
+    fn test_count_phrase_occurrences() {
+        assert_eq!(count_phrase_occurrences(&[]), 0);
+        assert_eq!(count_phrase_occurrences(&[vec![1, 5]]), 2);
+        assert_eq!(count_phrase_occurrences(&[vec![1, 5], vec![2, 7]]), 1);
+        assert_eq!(count_phrase_occurrences(&[vec![1, 5], vec![2, 6]]), 2);
+        assert_eq!(count_phrase_occurrences(&[vec![1, 5], vec![0, 4]]), 0);
+        assert_eq!(
+            count_phrase_occurrences(&[vec![0, 3, 6], vec![1, 4], vec![2, 7]]),
+            1
+        );
+    }
+
+    #[test]
+    fn test_count_phrase_occurrences_repeated_term() {
+        // "a a" in "a a a"
+        assert_eq!(
+            count_phrase_occurrences(&[vec![0, 1, 2], vec![0, 1, 2]]),
+            2
+        );
+    }
+
+    #[test]
+    fn test_phrase_matches() -> Result<()> {
+        assert_eq!(
+            collect_phrase_matches(
+                &[
+                    "information retrieval",
+                    "retrieval of information",
+                    "information plus retrieval",
+                    "information retrieval is information retrieval",
+                    "retrieval",
+                ],
+                &["information", "retrieval"]
+            )?,
+            &[(0, 1), (3, 2)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_with_unknown_term() -> Result<()> {
+        assert_eq!(
+            collect_phrase_matches(
+                &["information retrieval"],
+                &["information", "unknown"]
+            )?,
+            &[]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_advance_to() -> Result<()> {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            ["cat dog", "cat dog", "dog cat", "cat dog"]
+                .map(|text| [("text", text)]),
+        );
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let stats = GlobalStats::collect(
+            &QueryAst::Phrase(vec![]),
+            &*index,
+            &Analyzer::default(),
+            &SearchOptions::default(),
+        )?;
+
+        let mut it = PhraseDocIdIterator::new(vec![
+            PostingListIterator::create_for_segment(
+                segment, "text", "cat", &stats,
+            )?,
+            PostingListIterator::create_for_segment(
+                segment, "text", "dog", &stats,
+            )?,
+        ]);
+
+        it.advance_to(1)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(1));
+
+        // docid 2 contains terms in wrong order
+        it.advance_to(2)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Exhausted);
+
+        Ok(())
+    }
+}
@@ -1,41 +1,213 @@
 use std::borrow::Cow;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail, ensure};
 
-use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
 use crate::engines::nano::index::model::{
-    DocPosting, DocPostingsForTerm, IndexSegment, SegmentDocId,
+    DocPosting, DocPostingsForTerm, IndexSegment, SegmentDocId, TermPosition,
 };
-use crate::engines::nano::search::scoring;
+use crate::engines::nano::search::scoring::{FieldTermFreq, TermStats};
+use crate::engines::nano::search::stats::GlobalStats;
 use crate::utils::TreeNode;
 
-// Iterator over document IDs, that reads posting list of concrete term from
-// index segment.
+// Iterator over document IDs, that reads posting list of concrete term in
+// concrete field from index segment.
 pub struct PostingListIterator<'a> {
     segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    field: String,
     term: String,
+    /// Statistics of the term in the field in all segments
+    term_stats: TermStats,
     postings: Option<DocPostingsForTerm<'a>>,
     current_posting: Option<Cow<'a, DocPosting>>,
     is_exhausted: bool,
+    /// Whether term frequencies are read to score documents
+    is_scoring: bool,
 }
 
 impl<'a> PostingListIterator<'a> {
     pub fn create_for_segment(
         segment: &'a dyn IndexSegment,
+        field: &str,
         term: &str,
+        stats: &'a GlobalStats,
     ) -> Result<Self> {
-        // TODO: ignore stop words
-        let postings = segment.get_doc_postings_for_term(term)?;
+        let postings = segment.get_doc_postings_for_term(field, term)?;
 
         Ok(Self {
             segment,
+            stats,
+            field: field.to_string(),
             term: term.to_string(),
+            term_stats: stats.get_term_stats(field, term),
             postings,
             current_posting: None,
             is_exhausted: false,
+            is_scoring: true,
         })
     }
 
+    /// Switches iterator to non-scoring mode, for filters which only need
+    /// docids. Term frequencies of postings are not read, and all documents
+    /// get zero score.
+    pub fn without_scoring(mut self) -> Self {
+        if let Some(postings) = &mut self.postings {
+            postings.iterator.skip_term_freqs();
+        }
+        self.is_scoring = false;
+        self
+    }
+
+    /// Reads positions of the term in current document into `output`.
+    pub fn read_positions(
+        &mut self,
+        output: &mut Vec<TermPosition>,
+    ) -> Result<()> {
+        ensure!(
+            self.current_posting.is_some() && !self.is_exhausted,
+            "iterator should point to a document to read positions"
+        );
+
+        self.postings
+            .as_mut()
+            .context("should exist")?
+            .iterator
+            .read_positions(output)
+    }
+
+    /// Scores current document as if it contained the term `term_freq` times.
+    ///
+    /// Useful for iterators, which count occurrences on their own, e.g.
+    /// occurrences of a phrase.
+    pub fn score_with_term_freq(&self, term_freq: f64) -> Result<ItScore> {
+        if self.is_exhausted {
+            return Ok(ItScore::Exhausted);
+        }
+
+        let Some(posting) = &self.current_posting else {
+            return Ok(ItScore::NotStarted);
+        };
+
+        let score = self.calc_score(
+            term_freq,
+            *self
+                .segment
+                .get_doc_terms_count(&self.field, posting.docid)?,
+        );
+
+        Ok(ItScore::Active(score))
+    }
+
+    /// Explains score of current document as if it contained the term
+    /// `term_freq` times, see [`Self::score_with_term_freq`].
+    pub fn explain_score_with_term_freq(
+        &self,
+        term_freq: f64,
+    ) -> Result<TreeNode> {
+        let posting = self
+            .current_posting
+            .as_ref()
+            .filter(|_| !self.is_exhausted)
+            .context("iterator should point to a document to explain score")?;
+
+        let doc_terms_count = *self
+            .segment
+            .get_doc_terms_count(&self.field, posting.docid)?;
+
+        Ok(TreeNode::new("Term")
+            .with_attr("field", self.field.clone())
+            .with_attr("term", self.term.clone())
+            .with_attr(
+                "score",
+                self.calc_score(term_freq, doc_terms_count).to_string(),
+            )
+            .with_child(self.stats.scorer.explain(
+                term_freq,
+                doc_terms_count.into(),
+                &self.term_stats,
+            )))
+    }
+
+    pub fn get_field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn get_term(&self) -> &str {
+        &self.term
+    }
+
+    /// Statistics of the term in the field in all segments.
+    pub const fn get_term_stats(&self) -> TermStats {
+        self.term_stats
+    }
+
+    /// Gets occurrences of the term in the field of current document, for
+    /// scoring it together with other fields.
+    ///
+    /// Returns `None` if iterator does not point to a document.
+    pub fn get_field_term_freq(
+        &self,
+        weight: f64,
+    ) -> Result<Option<FieldTermFreq>> {
+        let Some(posting) = &self.current_posting else {
+            return Ok(None);
+        };
+
+        if self.is_exhausted {
+            return Ok(None);
+        }
+
+        Ok(Some(FieldTermFreq {
+            term_freq: posting.term_freq.into(),
+            doc_terms_count: *self
+                .segment
+                .get_doc_terms_count(&self.field, posting.docid)?,
+            stats: self.term_stats,
+            weight,
+        }))
+    }
+
+    /// Gets occurrences of the term, that can't be exceeded by any document
+    /// starting from `target` docid up to returned last docid.
+    ///
+    /// Returns `None` if there are no documents left.
+    pub fn get_block_max_field_term_freq(
+        &self,
+        target: SegmentDocId,
+        weight: f64,
+    ) -> Result<Option<(SegmentDocId, FieldTermFreq)>> {
+        let Some(postings) = &self.postings else {
+            return Ok(None);
+        };
+
+        let Some(meta) = postings.iterator.peek_block_meta(target)? else {
+            return Ok(None);
+        };
+
+        Ok(Some((
+            meta.last_docid,
+            FieldTermFreq {
+                term_freq: meta.max_term_freq.into(),
+                doc_terms_count: meta.min_doc_terms_count,
+                stats: self.term_stats,
+                weight,
+            },
+        )))
+    }
+
+    fn calc_score(&self, term_freq: f64, doc_terms_count: u16) -> f64 {
+        // use index-wide statistics instead of segment ones, so the document
+        // gets the same score regardless of segment it belongs to
+        self.stats.scorer.score(
+            term_freq,
+            doc_terms_count.into(),
+            &self.term_stats,
+        )
+    }
+
     fn advance_internal(&mut self, target: Option<SegmentDocId>) -> Result<()> {
         if self.is_exhausted {
             return Ok(());
@@ -46,41 +218,40 @@ impl<'a> PostingListIterator<'a> {
             return Ok(());
         }
 
-        // TODO: optimize non-scoring iteration.
+        // TODO: use non-scoring iteration for more cases.
         //
-        // reading scoring data (e.g. term frequencies) may require additional
-        // work while advancing docids. when scores are not needed (e.g. NOT
-        // branches or a counting collector), it should be possible to skip
-        // reading them.
+        // reading scoring data (e.g. term frequencies) requires additional
+        // work while advancing docids. filters already skip it with
+        // `without_scoring()`, but other branches, which don't need scores
+        // (e.g. NOT branches or a counting collector), still read it.
         //
-        // approaches:
-        //
-        // - runtime ScoringMode::On/Off flag propagated through the iterator
-        //   tree. non-scoring branches (such as the excluded side of Exclusion)
-        //   would always use Off. simple, but current_score() remains callable
-        //   and would have to fail at runtime
-        //
-        // - encode scoring capability in the type system, making
-        //   current_score() unavailable on non-scoring iterators. more
-        //   type-safe, but requires additional iterator/planner abstractions
-        //   and lots of boilerplate
-        //
-        // Lucene and Tantivy use a runtime flag approach, but calling score()
-        // when scoring is disabled does not fail; it returns a constant (dummy)
-        // score instead
+        // this uses runtime flag, same as Lucene and Tantivy do: calling
+        // current_score() on non-scoring iterator does not fail, but returns
+        // a constant score instead. encoding scoring capability in the type
+        // system would make it more type-safe, but would require additional
+        // iterator/planner abstractions and lots of boilerplate
         let postings = self.postings.as_mut().context("should exist")?;
 
         if let Some(target) = target {
-            if self.current_posting.is_none() {
-                self.current_posting = postings.iterator.next().transpose()?;
-            }
-
-            // TODO: use skip list for fast advancing to target
-            while matches!(
+            if !matches!(
                 &self.current_posting,
-                Some(posting) if posting.docid < target
+                Some(posting) if posting.docid >= target
             ) {
-                self.current_posting = postings.iterator.next().transpose()?;
+                // skip whole blocks before target without decoding them, and
+                // then read postings one by one within the target block
+                postings.iterator.skip_to(target)?;
+
+                loop {
+                    self.current_posting =
+                        postings.iterator.next().transpose()?;
+
+                    if !matches!(
+                        &self.current_posting,
+                        Some(posting) if posting.docid < target
+                    ) {
+                        break;
+                    }
+                }
             }
         } else {
             self.current_posting = postings.iterator.next().transpose()?;
@@ -94,6 +265,44 @@ impl<'a> PostingListIterator<'a> {
     }
 }
 
+/// Advances started posting list iterators until all of them point to the same
+/// document, and returns its ID.
+///
+/// Returns `None` if any iterator is exhausted before that.
+pub fn intersect_posting_lists(
+    inputs: &mut [PostingListIterator<'_>],
+) -> Result<Option<SegmentDocId>> {
+    loop {
+        let mut candidate = SegmentDocId::MIN;
+
+        for it in inputs.iter() {
+            match it.current_docid()? {
+                ItDocId::Active(docid) => candidate = candidate.max(docid),
+                ItDocId::Exhausted => return Ok(None),
+                ItDocId::NotStarted => bail!("input should be started"),
+            }
+        }
+
+        let mut matched = true;
+
+        for it in inputs.iter_mut() {
+            if it.current_docid()?.expect_val()? < candidate {
+                it.advance_to(candidate)?;
+            }
+
+            match it.current_docid()? {
+                ItDocId::Active(docid) if docid == candidate => {}
+                ItDocId::Exhausted => return Ok(None),
+                _ => matched = false,
+            }
+        }
+
+        if matched {
+            return Ok(Some(candidate));
+        }
+    }
+}
+
 impl DocIdIterator for PostingListIterator<'_> {
     fn advance(&mut self) -> Result<()> {
         self.advance_internal(None)
@@ -114,8 +323,9 @@ impl DocIdIterator for PostingListIterator<'_> {
     }
 
     fn explain(&self) -> TreeNode {
-        let mut node =
-            TreeNode::new("Term").with_attr("term", self.term.clone());
+        let mut node = TreeNode::new("Term")
+            .with_attr("field", self.field.clone())
+            .with_attr("term", self.term.clone());
 
         if self.postings.is_none() {
             node.add_attr("unknown_term", "true");
@@ -127,29 +337,56 @@ impl DocIdIterator for PostingListIterator<'_> {
 
 impl ScoringDocIdIterator for PostingListIterator<'_> {
     fn current_score(&self) -> Result<ItScore> {
-        if self.is_exhausted {
-            return Ok(ItScore::Exhausted);
+        if !self.is_scoring {
+            return Ok(match self.current_docid()? {
+                ItDocId::NotStarted => ItScore::NotStarted,
+                ItDocId::Active(_) => ItScore::Active(0.0),
+                ItDocId::Exhausted => ItScore::Exhausted,
+            });
         }
 
-        let Some(posting) = &self.current_posting else {
-            return Ok(ItScore::NotStarted);
+        let term_freq = self
+            .current_posting
+            .as_ref()
+            .map_or(0, |posting| posting.term_freq);
+
+        self.score_with_term_freq(term_freq.into())
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let term_freq = self
+            .current_posting
+            .as_ref()
+            .map_or(0, |posting| posting.term_freq);
+
+        self.explain_score_with_term_freq(term_freq.into())
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        let Some(postings) = &self.postings else {
+            return Ok(BlockMaxScore::EXHAUSTED);
         };
 
-        let postings = self.postings.as_ref().context("should exist")?;
+        let Some(meta) = postings.iterator.peek_block_meta(target)? else {
+            return Ok(BlockMaxScore::EXHAUSTED);
+        };
 
-        let score = scoring::calc_bm25(
-            scoring::ScoringParams {
-                doc_term_freq: posting.term_freq,
-                doc_total_terms_count: *self
-                    .segment
-                    .get_doc_terms_count(posting.docid)?,
-                docs_with_term_count: postings.count as u64,
-                docs_total_count: self.segment.get_stats().indexed_docs_count,
+        // score of imaginary posting with highest term frequency in shortest
+        // document of the block can't be exceeded by any real posting in it
+        Ok(BlockMaxScore {
+            last_docid: meta.last_docid,
+            max_score: if self.is_scoring {
+                self.calc_score(
+                    meta.max_term_freq.into(),
+                    meta.min_doc_terms_count,
+                )
+            } else {
+                0.0
             },
-            self.segment.get_stats().terms_count_per_doc_avg,
-        );
-
-        Ok(ItScore::Active(score))
+        })
     }
 }
 
@@ -161,8 +398,10 @@ mod tests {
     #[test]
     fn test_unknown_term() -> Result<()> {
         let segment = MemoryIndex::default();
-        let mut it =
-            PostingListIterator::create_for_segment(&segment, "unknown")?;
+        let stats = GlobalStats::default();
+        let mut it = PostingListIterator::create_for_segment(
+            &segment, "text", "unknown", &stats,
+        )?;
 
         assert!(matches!(it.current_docid()?, ItDocId::NotStarted));
         assert!(matches!(it.current_score()?, ItScore::NotStarted));
@@ -0,0 +1,419 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+
+use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::posting_list::{PostingListIterator, intersect_posting_lists};
+use crate::engines::nano::index::model::{SegmentDocId, TermPosition};
+use crate::utils::TreeNode;
+
+/// Iterator over document IDs, that returns only IDs of documents containing
+/// all `inputs` terms within `distance` positions from each other, in any
+/// order.
+///
+/// Each input goes with number of times its term is repeated in query, so
+/// "a a b" requires two distinct positions of "a" near "b".
+///
+/// Distance of a match is the number of extra positions its window has beyond
+/// the words themselves. E.g. for "a b" both "a b" and "b a" have distance 0,
+/// while "a x b" has distance 1. Unlike Lucene slop, swapping words costs
+/// nothing.
+///
+/// Document is scored as if proximity was a single term, with sloppy frequency
+/// as term frequency. Each match adds `1 / (1 + distance)` to sloppy frequency,
+/// so closer matches weigh more.
+pub struct ProximityDocIdIterator<'a> {
+    inputs: Vec<PostingListIterator<'a>>,
+
+    /// Number of times each input term is repeated in query
+    counts: Vec<usize>,
+
+    distance: u32,
+    current_docid: ItDocId,
+
+    /// Sloppy frequency of proximity in current document
+    proximity_freq: f64,
+
+    /// Buffers for positions of each input term in candidate document
+    positions: Vec<Vec<TermPosition>>,
+}
+
+impl<'a> ProximityDocIdIterator<'a> {
+    pub fn new(
+        inputs: Vec<(PostingListIterator<'a>, usize)>,
+        distance: u32,
+    ) -> Self {
+        let positions = vec![Vec::new(); inputs.len()];
+        let (inputs, counts) = inputs.into_iter().unzip();
+
+        Self {
+            inputs,
+            counts,
+            distance,
+            current_docid: ItDocId::NotStarted,
+            proximity_freq: 0.0,
+            positions,
+        }
+    }
+
+    fn advance_internal(&mut self, target: Option<SegmentDocId>) -> Result<()> {
+        if self.current_docid.is_exhausted() {
+            return Ok(());
+        }
+
+        if self.inputs.is_empty() {
+            self.current_docid = ItDocId::Exhausted;
+            return Ok(());
+        }
+
+        // all inputs point to the same document (unless not started yet), so
+        // all of them should move
+        for it in &mut self.inputs {
+            if let Some(target) = target {
+                it.advance_to(target)?;
+            } else {
+                it.advance()?;
+            }
+        }
+
+        loop {
+            let Some(candidate) = intersect_posting_lists(&mut self.inputs)?
+            else {
+                self.current_docid = ItDocId::Exhausted;
+                return Ok(());
+            };
+
+            for (it, positions) in
+                self.inputs.iter_mut().zip(&mut self.positions)
+            {
+                it.read_positions(positions)?;
+            }
+
+            self.proximity_freq =
+                calc_sloppy_freq(&self.positions, &self.counts, self.distance);
+
+            if self.proximity_freq > 0.0 {
+                self.current_docid = ItDocId::Active(candidate);
+                return Ok(());
+            }
+
+            for it in &mut self.inputs {
+                it.advance()?;
+            }
+        }
+    }
+}
+
+/// Calculates sloppy frequency of proximity in a document, given sorted
+/// positions of each term and number of times each term is repeated in query.
+///
+/// Walks positions of all terms in order, and for each position takes the
+/// tightest window of all query words ending there. Window matches if it has
+/// no more than `max_distance` extra positions, and adds `1 / (1 + distance)`
+/// to frequency.
+fn calc_sloppy_freq(
+    positions: &[Vec<TermPosition>],
+    counts: &[usize],
+    max_distance: u32,
+) -> f64 {
+    let mut events: Vec<(TermPosition, usize)> = positions
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, term_positions)| {
+            term_positions.iter().map(move |&position| (position, idx))
+        })
+        .collect();
+    events.sort_unstable();
+
+    let words_count: usize = counts.iter().sum();
+
+    // last positions of each term, as many as term is repeated in query
+    let mut last_positions: Vec<VecDeque<TermPosition>> = counts
+        .iter()
+        .map(|&count| VecDeque::with_capacity(count))
+        .collect();
+
+    let mut freq = 0.0;
+
+    for (position, idx) in events {
+        let term_last_positions = &mut last_positions[idx];
+        if term_last_positions.len() == counts[idx] {
+            term_last_positions.pop_front();
+        }
+        term_last_positions.push_back(position);
+
+        let mut start = position;
+        let mut complete = true;
+
+        for (term_last_positions, &count) in last_positions.iter().zip(counts) {
+            match term_last_positions.front() {
+                Some(&first) if term_last_positions.len() == count => {
+                    start = start.min(first);
+                }
+                _ => {
+                    complete = false;
+                    break;
+                }
+            }
+        }
+
+        if !complete {
+            continue;
+        }
+
+        // each position holds single term, so window is never shorter than
+        // number of words
+        let window_len = (position - start) as usize + 1;
+        let distance = window_len - words_count;
+
+        if distance <= max_distance as usize {
+            freq += 1.0 / (1.0 + distance as f64);
+        }
+    }
+
+    freq
+}
+
+impl DocIdIterator for ProximityDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        self.advance_internal(None)
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        if matches!(self.current_docid, ItDocId::Active(docid) if docid >= target)
+        {
+            return Ok(());
+        }
+
+        self.advance_internal(Some(target))
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        Ok(self.current_docid)
+    }
+
+    fn explain(&self) -> TreeNode {
+        let mut tree = TreeNode::new("Proximity")
+            .with_attr("distance", self.distance.to_string());
+        for (input, count) in self.inputs.iter().zip(&self.counts) {
+            let mut child = input.explain();
+            if *count > 1 {
+                child.add_attr("count", count.to_string());
+            }
+            tree.add_child(child);
+        }
+        tree
+    }
+}
+
+impl ScoringDocIdIterator for ProximityDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.current_docid {
+            ItDocId::NotStarted => ItScore::NotStarted,
+            ItDocId::Exhausted => ItScore::Exhausted,
+            ItDocId::Active(_) => {
+                // same as phrase, score is the sum of term scores with sloppy
+                // frequency, where repeated terms count several times
+                let mut total_score = 0.0;
+
+                for (it, count) in self.inputs.iter().zip(&self.counts) {
+                    total_score += it
+                        .score_with_term_freq(self.proximity_freq)?
+                        .expect_val()?
+                        * *count as f64;
+                }
+
+                ItScore::Active(total_score)
+            }
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let mut tree = TreeNode::new("Proximity")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_attr("proximity_freq", self.proximity_freq.to_string())
+            .with_attr(
+                "combine",
+                "sum of terms scored with proximity_freq, times count",
+            );
+
+        for (it, count) in self.inputs.iter().zip(&self.counts) {
+            let mut child =
+                it.explain_score_with_term_freq(self.proximity_freq)?;
+            if *count > 1 {
+                child.add_attr("count", count.to_string());
+            }
+            tree.add_child(child);
+        }
+
+        Ok(tree)
+    }
+
+    // block max score is unknown, because sloppy frequency is not bounded by
+    // max frequency of any single term (e.g. "a b a" has sloppy frequency 2
+    // for "a b"~0 while "b" occurs once)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::nano::analysis::Analyzer;
+    use crate::engines::nano::index::build_index;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::search::options::SearchOptions;
+    use crate::engines::nano::search::query::QueryAst;
+    use crate::engines::nano::search::stats::GlobalStats;
+    use crate::utils::test_docs_iterator::TestDocsIterator;
+
+    /// Builds index of `texts` and collects IDs of docs matching proximity of
+    /// `terms` (with repeat counts) along with sloppy frequencies.
+    fn collect_proximity_matches(
+        texts: &[&str],
+        terms: &[(&str, usize)],
+        distance: u32,
+    ) -> Result<Vec<(SegmentDocId, f64)>> {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            texts.iter().map(|text| [("text", *text)]),
+        );
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let words = terms.iter().map(|(term, _)| *term).collect();
+        let stats = GlobalStats::collect(
+            &QueryAst::Proximity { words, distance },
+            &*index,
+            &Analyzer::default(),
+            &SearchOptions::default(),
+        )?;
+
+        let inputs = terms
+            .iter()
+            .map(|(term, count)| {
+                Ok((
+                    PostingListIterator::create_for_segment(
+                        segment, "text", term, &stats,
+                    )?,
+                    *count,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut it = ProximityDocIdIterator::new(inputs, distance);
+        let mut res = Vec::new();
+
+        it.advance()?;
+        while let ItDocId::Active(docid) = it.current_docid()? {
+            res.push((docid, it.proximity_freq));
+            it.advance()?;
+        }
+
+        Ok(res)
+    }
+
+    #[test]
+    fn test_calc_sloppy_freq() {
+        // "a b"
+        assert_eq!(calc_sloppy_freq(&[vec![0], vec![1]], &[1, 1], 0), 1.0);
+        // "b a"
+        assert_eq!(calc_sloppy_freq(&[vec![1], vec![0]], &[1, 1], 0), 1.0);
+        // "a x b"
+        assert_eq!(calc_sloppy_freq(&[vec![0], vec![2]], &[1, 1], 0), 0.0);
+        assert_eq!(calc_sloppy_freq(&[vec![0], vec![2]], &[1, 1], 1), 0.5);
+        // "a b a"
+        assert_eq!(calc_sloppy_freq(&[vec![0, 2], vec![1]], &[1, 1], 0), 2.0);
+        // "a x x b x a"
+        assert_eq!(
+            calc_sloppy_freq(&[vec![0, 5], vec![3]], &[1, 1], 2),
+            1.0 / 3.0 + 1.0 / 2.0
+        );
+    }
+
+    #[test]
+    fn test_calc_sloppy_freq_repeated_term() {
+        // "a a b" in "a b"
+        assert_eq!(calc_sloppy_freq(&[vec![0], vec![1]], &[2, 1], 5), 0.0);
+        // "a a b" in "a b a"
+        assert_eq!(calc_sloppy_freq(&[vec![0, 2], vec![1]], &[2, 1], 0), 1.0);
+        // "a a b" in "a x a x b"
+        assert_eq!(
+            calc_sloppy_freq(&[vec![0, 2], vec![4]], &[2, 1], 2),
+            1.0 / 3.0
+        );
+    }
+
+    #[test]
+    fn test_proximity_matches() -> Result<()> {
+        assert_eq!(
+            collect_proximity_matches(
+                &[
+                    "information retrieval",
+                    "retrieval via information",
+                    "information plus more retrieval",
+                    "retrieval",
+                ],
+                &[("information", 1), ("retrieval", 1)],
+                1
+            )?,
+            &[(0, 1.0), (1, 0.5)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity_with_unknown_term() -> Result<()> {
+        assert_eq!(
+            collect_proximity_matches(
+                &["information retrieval"],
+                &[("information", 1), ("unknown", 1)],
+                10
+            )?,
+            &[]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_advance_to() -> Result<()> {
+        let mut docs = TestDocsIterator::from_enumerated_fields(
+            ["cat dog", "dog cat", "cat x x dog", "dog x cat"]
+                .map(|text| [("text", text)]),
+        );
+        let index = build_index(&IndexMedium::Memory, &mut docs)?;
+        let segment = index.get_segments()[0];
+        let stats = GlobalStats::collect(
+            &QueryAst::Phrase(vec![]),
+            &*index,
+            &Analyzer::default(),
+            &SearchOptions::default(),
+        )?;
+
+        let mut it = ProximityDocIdIterator::new(
+            vec![
+                (
+                    PostingListIterator::create_for_segment(
+                        segment, "text", "cat", &stats,
+                    )?,
+                    1,
+                ),
+                (
+                    PostingListIterator::create_for_segment(
+                        segment, "text", "dog", &stats,
+                    )?,
+                    1,
+                ),
+            ],
+            1,
+        );
+
+        it.advance_to(1)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(1));
+
+        // docid 2 contains terms too far from each other
+        it.advance_to(2)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(3));
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Exhausted);
+
+        Ok(())
+    }
+}
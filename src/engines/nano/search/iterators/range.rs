@@ -0,0 +1,179 @@
+use std::ops::Range;
+
+use anyhow::Result;
+
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
+use crate::engines::nano::index::doc_values::DocValuesColumn;
+use crate::engines::nano::index::model::SegmentDocId;
+use crate::utils::TreeNode;
+
+/// Iterator over documents with value of the field within range, e.g. pages
+/// edited within some dates.
+///
+/// Range is given as range of value codes, so the column is scanned document
+/// by document, comparing codes without decoding values. All documents get
+/// zero score, so range only filters documents and does not change ranking of
+/// other operands it's combined with.
+///
+/// Note: Lucene/Tantivy can also find ranges with a dedicated index of values
+/// (BKD tree), which skips documents without reading their values. Here every
+/// value is checked, which is fine for columns of memory-mapped codes.
+pub struct RangeDocIdIterator<'a> {
+    field: String,
+
+    /// `None` if no document of the segment has values of the field
+    column: Option<DocValuesColumn<'a>>,
+    codes: Range<u64>,
+    current_docid: ItDocId,
+}
+
+impl<'a> RangeDocIdIterator<'a> {
+    pub fn new(
+        field: impl Into<String>,
+        column: Option<DocValuesColumn<'a>>,
+        codes: Range<u64>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            column,
+            codes,
+            current_docid: ItDocId::NotStarted,
+        }
+    }
+
+    /// Moves to the first document with matching value, starting from
+    /// `docid`.
+    fn advance_from(&mut self, docid: SegmentDocId) -> Result<()> {
+        let Some(column) = &self.column else {
+            self.current_docid = ItDocId::Exhausted;
+            return Ok(());
+        };
+
+        for docid in docid..column.get_docs_count() as SegmentDocId {
+            if column
+                .get_code(docid)?
+                .is_some_and(|code| self.codes.contains(&code))
+            {
+                self.current_docid = ItDocId::Active(docid);
+                return Ok(());
+            }
+        }
+
+        self.current_docid = ItDocId::Exhausted;
+        Ok(())
+    }
+}
+
+impl DocIdIterator for RangeDocIdIterator<'_> {
+    fn advance(&mut self) -> Result<()> {
+        match self.current_docid {
+            ItDocId::NotStarted => self.advance_from(0),
+            ItDocId::Active(docid) => self.advance_from(docid + 1),
+            ItDocId::Exhausted => Ok(()),
+        }
+    }
+
+    fn advance_to(&mut self, target: SegmentDocId) -> Result<()> {
+        match self.current_docid {
+            ItDocId::Active(docid) if docid >= target => Ok(()),
+            ItDocId::Exhausted => Ok(()),
+            _ => self.advance_from(target),
+        }
+    }
+
+    fn current_docid(&self) -> Result<ItDocId> {
+        Ok(self.current_docid)
+    }
+
+    fn explain(&self) -> TreeNode {
+        let mut node = TreeNode::new("Range")
+            .with_attr("field", self.field.clone())
+            .with_attr("codes", format!("{:?}", self.codes));
+
+        if self.column.is_none() {
+            node.add_attr("unknown_field", "true");
+        }
+
+        node
+    }
+}
+
+impl ScoringDocIdIterator for RangeDocIdIterator<'_> {
+    fn current_score(&self) -> Result<ItScore> {
+        Ok(match self.current_docid {
+            ItDocId::NotStarted => ItScore::NotStarted,
+            ItDocId::Active(_) => ItScore::Active(0.0),
+            ItDocId::Exhausted => ItScore::Exhausted,
+        })
+    }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        Ok(self.explain().with_attr(
+            "score",
+            self.current_score()?.expect_val()?.to_string(),
+        ))
+    }
+
+    fn get_block_max_score(
+        &self,
+        _target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        Ok(if self.current_docid.is_exhausted() {
+            BlockMaxScore::EXHAUSTED
+        } else {
+            BlockMaxScore {
+                last_docid: SegmentDocId::MAX,
+                max_score: 0.0,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zerocopy::little_endian::U64;
+
+    use super::*;
+    use crate::engines::nano::index::doc_values::{
+        DocValueType, DocValuesColumnInfo, MISSING_CODE,
+    };
+
+    #[test]
+    fn test_range() -> Result<()> {
+        let info = DocValuesColumnInfo {
+            name: "count".to_string(),
+            value_type: DocValueType::U64,
+            keywords: Vec::new(),
+        };
+        let codes = [5, MISSING_CODE, 10, 3, 7, 20].map(U64::new);
+        let column = DocValuesColumn::new(&info, &codes);
+
+        let mut it = RangeDocIdIterator::new("count", Some(column), 5..11);
+        assert_eq!(it.current_docid()?, ItDocId::NotStarted);
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(0));
+        assert_eq!(it.current_score()?, ItScore::Active(0.0));
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(2));
+
+        it.advance_to(1)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(2));
+
+        it.advance_to(3)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(4));
+
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Exhausted);
+        assert_eq!(it.get_block_max_score(5)?, BlockMaxScore::EXHAUSTED);
+
+        let mut it = RangeDocIdIterator::new("unknown", None, 0..MISSING_CODE);
+        it.advance()?;
+        assert_eq!(it.current_docid()?, ItDocId::Exhausted);
+
+        Ok(())
+    }
+}
@@ -3,7 +3,9 @@ use anyhow::{Result, ensure};
 
 use super::exclude::ExcludingDocIdIterator;
 use super::intersect::IntersectingDocIdIterator;
-use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
 use super::union::UnionDocIdIterator;
 use crate::engines::nano::index::model::SegmentDocId;
 use crate::utils::TreeNode;
@@ -12,6 +14,8 @@ pub struct TestScoringDocIdIterator {
     docids: Vec<SegmentDocId>,
     scores: Vec<f64>,
     current_idx: Option<usize>,
+    /// Number of docids in a block for block-max scores, if they are known
+    block_size: Option<usize>,
 }
 
 impl TestScoringDocIdIterator {
@@ -22,8 +26,14 @@ impl TestScoringDocIdIterator {
             docids,
             scores,
             current_idx: None,
+            block_size: None,
         })
     }
+
+    pub const fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = Some(block_size);
+        self
+    }
 }
 
 impl DocIdIterator for TestScoringDocIdIterator {
@@ -62,6 +72,13 @@ impl DocIdIterator for TestScoringDocIdIterator {
 }
 
 impl ScoringDocIdIterator for TestScoringDocIdIterator {
+    fn explain_score(&self) -> Result<TreeNode> {
+        Ok(TreeNode::new("Test").with_attr(
+            "score",
+            self.current_score()?.expect_val()?.to_string(),
+        ))
+    }
+
     fn current_score(&self) -> Result<ItScore> {
         Ok(self.current_idx.map_or_else(
             || ItScore::NotStarted,
@@ -74,6 +91,31 @@ impl ScoringDocIdIterator for TestScoringDocIdIterator {
             },
         ))
     }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        let Some(block_size) = self.block_size else {
+            return Ok(BlockMaxScore::UNKNOWN);
+        };
+
+        let idx = self.docids.partition_point(|&docid| docid < target);
+        if idx == self.docids.len() {
+            return Ok(BlockMaxScore::EXHAUSTED);
+        }
+
+        let block_start = idx / block_size * block_size;
+        let block_end = (block_start + block_size).min(self.docids.len());
+
+        Ok(BlockMaxScore {
+            last_docid: self.docids[block_end - 1],
+            max_score: self.scores[block_start..block_end]
+                .iter()
+                .copied()
+                .fold(0.0, f64::max),
+        })
+    }
 }
 
 pub struct TestDocIdIterator {
@@ -190,6 +232,19 @@ pub fn create_union_it(
     Ok(UnionDocIdIterator::new(input))
 }
 
+pub fn create_union_it_with_blocks(
+    docids_and_scores: Vec<(Vec<SegmentDocId>, Vec<f64>)>,
+    block_size: usize,
+) -> Result<UnionDocIdIterator<'static>> {
+    let mut input: Vec<Box<dyn ScoringDocIdIterator>> = Vec::new();
+    for (docids, scores) in docids_and_scores {
+        let it = TestScoringDocIdIterator::new(docids, scores)?
+            .with_block_size(block_size);
+        input.push(Box::new(it));
+    }
+    Ok(UnionDocIdIterator::new(input))
+}
+
 pub fn create_union_it_and_collect(
     docids_and_scores: Vec<(Vec<SegmentDocId>, Vec<f64>)>,
 ) -> Result<Vec<(SegmentDocId, f64)>> {
@@ -1,13 +1,65 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use super::model::{DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator};
+use super::model::{
+    BlockMaxScore, DocIdIterator, ItDocId, ItScore, ScoringDocIdIterator,
+};
 use crate::engines::nano::index::model::SegmentDocId;
 use crate::utils::TreeNode;
 
 /// Iterator over document IDs, that returns all IDs present in `inputs`
+///
+/// Once collector sets min competitive score, it starts skipping documents
+/// which cannot exceed that score, using block-max scores of the inputs
+/// (i.e. block-max variant of `MaxScore` algorithm).
+///
+/// Docids are split into windows, within which block-max scores of all
+/// inputs stay the same. In each window inputs are split into two groups:
+/// - non-essential - inputs with lowest block-max scores, which even all
+///   together cannot exceed min competitive score
+/// - essential - the rest of the inputs
+///
+/// Any competitive document has to match at least one essential input, so
+/// only essential inputs are used to find candidates. Non-essential inputs are
+/// only advanced to candidates for scoring, and only if candidate score can
+/// exceed min competitive score with their help. And if all inputs together
+/// cannot exceed min competitive score, whole window is skipped.
+///
+/// Skipped documents could not get into results anyway, so results are the
+/// same as with exhaustive evaluation. Scores of returned documents are the
+/// same too, since all matching inputs are positioned on the candidate, and
+/// their scores are summed in the same order.
+///
+/// It's called `MaxScoreBulkScorer` in Lucene and `block_wand` in Tantivy
+/// (which uses Block-Max WAND algorithm instead).
 pub struct UnionDocIdIterator<'a> {
     inputs: Vec<Box<dyn ScoringDocIdIterator + 'a>>,
     current_docid: ItDocId,
+
+    /// Score, which document should exceed to get into search results
+    min_competitive_score: Option<f64>,
+
+    /// Current window of docids for pruning
+    window: Option<PruningWindow>,
+
+    /// Scores of inputs matching current docid, calculated while pruning
+    input_scores: Vec<Option<f64>>,
+
+    /// Score of current docid, if it was calculated while pruning
+    current_score: Option<f64>,
+}
+
+struct PruningWindow {
+    /// Last docid of the window, inclusively
+    last_docid: SegmentDocId,
+
+    /// Upper bound of document scores in the window
+    max_score: f64,
+
+    /// Upper bound of sum of scores of non-essential inputs in the window
+    non_essential_max_score: f64,
+
+    /// Flags of inputs, that can make document competitive in the window
+    is_essential: Vec<bool>,
 }
 
 impl<'a> UnionDocIdIterator<'a> {
@@ -15,6 +67,10 @@ impl<'a> UnionDocIdIterator<'a> {
         Self {
             inputs,
             current_docid: ItDocId::NotStarted,
+            min_competitive_score: None,
+            window: None,
+            input_scores: Vec::new(),
+            current_score: None,
         }
     }
 
@@ -23,6 +79,16 @@ impl<'a> UnionDocIdIterator<'a> {
             return Ok(());
         }
 
+        if let Some(min_competitive_score) = self.min_competitive_score {
+            let target = target.unwrap_or(match self.current_docid {
+                ItDocId::Active(docid) => docid + 1,
+                _ => 0,
+            });
+            return self.advance_with_pruning(target, min_competitive_score);
+        }
+
+        self.current_score = None;
+
         for it in &mut self.inputs {
             if let Some(target) = target {
                 it.advance_to(target)?;
@@ -54,6 +120,176 @@ impl<'a> UnionDocIdIterator<'a> {
 
         Ok(())
     }
+
+    fn advance_with_pruning(
+        &mut self,
+        mut target: SegmentDocId,
+        min_competitive_score: f64,
+    ) -> Result<()> {
+        loop {
+            if !matches!(&self.window, Some(w) if target <= w.last_docid) {
+                self.window =
+                    Some(self.create_window(target, min_competitive_score)?);
+            }
+
+            let window = self.window.as_ref().context("should exist")?;
+
+            let mut candidate: Option<SegmentDocId> = None;
+
+            if window.max_score > min_competitive_score {
+                for (it, &is_essential) in
+                    self.inputs.iter_mut().zip(&window.is_essential)
+                {
+                    if is_essential {
+                        advance_input_to(it.as_mut(), target)?;
+
+                        if let ItDocId::Active(docid) = it.current_docid()? {
+                            candidate =
+                                Some(candidate.map_or(docid, |c| c.min(docid)));
+                        }
+                    }
+                }
+            }
+
+            match candidate {
+                Some(candidate) if candidate <= window.last_docid => {
+                    self.input_scores.clear();
+                    self.input_scores.resize(self.inputs.len(), None);
+
+                    // score essential inputs first, and check if candidate
+                    // can become competitive with non-essential ones at all
+                    let mut essential_score = 0.0;
+
+                    for (idx, it) in self.inputs.iter().enumerate() {
+                        if window.is_essential[idx]
+                            && it.current_docid()? == ItDocId::Active(candidate)
+                        {
+                            let score = it.current_score()?.expect_val()?;
+                            self.input_scores[idx] = Some(score);
+                            essential_score += score;
+                        }
+                    }
+
+                    let max_score = sum_upper_bound(
+                        essential_score + window.non_essential_max_score,
+                        self.inputs.len(),
+                    );
+
+                    if max_score <= min_competitive_score {
+                        if candidate == SegmentDocId::MAX {
+                            self.current_docid = ItDocId::Exhausted;
+                            return Ok(());
+                        }
+                        target = candidate + 1;
+                        continue;
+                    }
+
+                    // position non-essential inputs for scoring the candidate
+                    for (idx, it) in self.inputs.iter_mut().enumerate() {
+                        if !window.is_essential[idx] {
+                            advance_input_to(it.as_mut(), candidate)?;
+
+                            if it.current_docid()? == ItDocId::Active(candidate)
+                            {
+                                let score = it.current_score()?.expect_val()?;
+                                self.input_scores[idx] = Some(score);
+                            }
+                        }
+                    }
+
+                    // sum in the same order as exhaustive evaluation does
+                    let mut total_score = 0.0;
+                    for score in self.input_scores.iter().flatten() {
+                        total_score += score;
+                    }
+
+                    self.current_docid = ItDocId::Active(candidate);
+                    self.current_score = Some(total_score);
+                    return Ok(());
+                }
+                Some(candidate) => {
+                    // candidate is in one of the next windows
+                    target = candidate;
+                }
+                None => {
+                    // nothing competitive is left in the window
+                    if window.last_docid == SegmentDocId::MAX {
+                        self.current_docid = ItDocId::Exhausted;
+                        return Ok(());
+                    }
+                    target = window.last_docid + 1;
+                }
+            }
+        }
+    }
+
+    fn create_window(
+        &self,
+        target: SegmentDocId,
+        min_competitive_score: f64,
+    ) -> Result<PruningWindow> {
+        let mut last_docid = SegmentDocId::MAX;
+        let mut max_scores = Vec::with_capacity(self.inputs.len());
+
+        for it in &self.inputs {
+            let block_max = it.get_block_max_score(target)?;
+            last_docid = last_docid.min(block_max.last_docid);
+            max_scores.push(block_max.max_score);
+        }
+
+        // inputs with lowest max scores are non-essential, as long as their
+        // sum doesn't exceed min competitive score
+        let mut order: Vec<usize> = (0..self.inputs.len()).collect();
+        order.sort_by(|a, b| max_scores[*a].total_cmp(&max_scores[*b]));
+
+        let mut is_essential = vec![true; self.inputs.len()];
+        let mut non_essential_max_score = 0.0;
+
+        for (count, idx) in order.into_iter().enumerate() {
+            let max_score = sum_upper_bound(
+                non_essential_max_score + max_scores[idx],
+                count + 1,
+            );
+
+            if max_score > min_competitive_score {
+                break;
+            }
+
+            non_essential_max_score += max_scores[idx];
+            is_essential[idx] = false;
+        }
+
+        Ok(PruningWindow {
+            last_docid,
+            max_score: sum_upper_bound(
+                max_scores.iter().sum(),
+                max_scores.len(),
+            ),
+            non_essential_max_score,
+            is_essential,
+        })
+    }
+}
+
+/// Advances input to `target`, unless it is already there or beyond.
+fn advance_input_to(
+    it: &mut dyn ScoringDocIdIterator,
+    target: SegmentDocId,
+) -> Result<()> {
+    match it.current_docid()? {
+        ItDocId::NotStarted => it.advance_to(target),
+        ItDocId::Active(docid) if docid < target => it.advance_to(target),
+        _ => Ok(()),
+    }
+}
+
+/// Adjusts sum of upper bounds for floating point rounding errors.
+///
+/// Document score is summed in different order than bounds, so it may slightly
+/// exceed the sum of bounds, which would make pruning discard competitive
+/// document. Same as `MathUtil.sumUpperBound` in Lucene.
+fn sum_upper_bound(sum: f64, count: usize) -> f64 {
+    sum * 2.0f64.mul_add(count as f64 * f64::EPSILON, 1.0)
 }
 
 impl DocIdIterator for UnionDocIdIterator<'_> {
@@ -83,6 +319,9 @@ impl ScoringDocIdIterator for UnionDocIdIterator<'_> {
         Ok(match self.current_docid {
             ItDocId::NotStarted => ItScore::NotStarted,
             ItDocId::Exhausted => ItScore::Exhausted,
+            ItDocId::Active(_) if self.current_score.is_some() => {
+                ItScore::Active(self.current_score.context("should exist")?)
+            }
             ItDocId::Active(current_docid) => {
                 let mut total_score = 0.0;
 
@@ -99,6 +338,46 @@ impl ScoringDocIdIterator for UnionDocIdIterator<'_> {
             }
         })
     }
+
+    fn explain_score(&self) -> Result<TreeNode> {
+        let mut tree = TreeNode::new("Union")
+            .with_attr("score", self.current_score()?.expect_val()?.to_string())
+            .with_attr("combine", "sum of matching inputs");
+
+        for it in &self.inputs {
+            if it.current_docid()? == self.current_docid {
+                tree.add_child(it.explain_score()?);
+            }
+        }
+
+        Ok(tree)
+    }
+
+    fn get_block_max_score(
+        &self,
+        target: SegmentDocId,
+    ) -> Result<BlockMaxScore> {
+        let mut res = BlockMaxScore::EXHAUSTED;
+
+        for it in &self.inputs {
+            let block_max = it.get_block_max_score(target)?;
+            res.last_docid = res.last_docid.min(block_max.last_docid);
+            res.max_score += block_max.max_score;
+        }
+
+        res.max_score = sum_upper_bound(res.max_score, self.inputs.len());
+
+        Ok(res)
+    }
+
+    fn set_min_competitive_score(&mut self, score: f64) {
+        if self.min_competitive_score != Some(score) {
+            self.min_competitive_score = Some(score);
+
+            // split inputs for new score
+            self.window = None;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -194,4 +473,76 @@ mod tests {
         );
         Ok(())
     }
+
+    fn create_pruning_it() -> Result<UnionDocIdIterator<'static>> {
+        create_union_it_with_blocks(
+            vec![
+                (vec![1, 2, 3, 4], vec![1.0, 1.0, 1.0, 1.0]),
+                (vec![2, 5, 6, 7], vec![5.0, 1.0, 1.0, 9.0]),
+            ],
+            2,
+        )
+    }
+
+    #[test]
+    fn test_pruning_skips_non_essential_input() -> Result<()> {
+        let mut it = create_pruning_it()?;
+
+        // 1st input alone can't exceed the score, so docs 1, 3, 4 are skipped
+        // without scoring, and docs 5, 6 are skipped after scoring 2nd input
+        it.set_min_competitive_score(2.0);
+
+        assert_eq!(collect_from_it(it)?, &[(2, 6.0), (7, 9.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pruning_skips_windows() -> Result<()> {
+        let mut it = create_pruning_it()?;
+
+        // no doc before 6 can exceed the score
+        it.set_min_competitive_score(7.0);
+
+        assert_eq!(collect_from_it(it)?, &[(7, 9.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pruning_score_set_in_the_middle() -> Result<()> {
+        let mut it = create_pruning_it()?;
+
+        it.advance_to(2)?;
+        assert_eq!(it.current_docid()?, ItDocId::Active(2));
+        assert_eq!(it.current_score()?, ItScore::Active(6.0));
+
+        it.set_min_competitive_score(7.0);
+
+        assert_eq!(collect_from_it(it)?, &[(2, 6.0), (7, 9.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pruning_all_skipped() -> Result<()> {
+        let mut it = create_pruning_it()?;
+
+        it.set_min_competitive_score(10.0);
+
+        assert_eq!(collect_from_it(it)?, &[]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pruning_without_block_max_scores() -> Result<()> {
+        let mut it = create_union_it(vec![
+            (vec![1, 2], vec![1.0, 1.0]),
+            (vec![2, 3], vec![5.0, 1.0]),
+        ])?;
+
+        // unknown bounds do not let skip inputs, but fully scored docs, that
+        // do not exceed the score, are still skipped
+        it.set_min_competitive_score(5.0);
+
+        assert_eq!(collect_from_it(it)?, &[(2, 6.0)]);
+        Ok(())
+    }
 }
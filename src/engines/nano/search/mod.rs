@@ -1,20 +1,30 @@
 mod model;
 
-mod scoring;
-mod stop_words;
-
-#[allow(dead_code)] // TODO: remove unused code silencer
 mod collectors;
-
-#[allow(dead_code)] // TODO: remove unused code silencer
+mod doc_lookup;
+mod expansion;
+mod filter;
+mod highlight;
 mod iterators;
-
-#[allow(dead_code)] // TODO: remove unused code silencer
-mod query;
-
-#[allow(dead_code)] // TODO: remove unused code silencer
+mod normalizer;
+mod options;
 mod planner;
+mod pool;
+mod query;
+mod scoring;
+mod stats;
 
 #[expect(clippy::module_inception)]
 mod search;
-pub use search::search;
+pub use doc_lookup::DocLookup;
+pub use filter::FilterCache;
+pub use options::{ExpansionScoring, FieldScoring, SearchOptions};
+pub use pool::SearchPool;
+pub use scoring::{
+    Bm25, Bm25Plus, Dfr, Dirichlet, FieldTermFreq, Scorer, TermStats, TfIdf,
+    parse_scorer,
+};
+pub use search::{
+    explain, get_document, get_segment_document, highlight, search,
+    search_with_options, search_with_request,
+};
@@ -0,0 +1,428 @@
+use super::expansion::{get_pattern_prefix, normalize_pattern};
+use super::query::QueryAst;
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::model::Term;
+
+/// Rewrites query AST into equivalent, but simpler form before planning.
+///
+/// - Removes words that can never match any document, since `analyzer` turns
+///   them into no terms (e.g. stop word "the" or "--"). `NOT` and `AND`
+///   operands made of such words are removed too, so "cat AND the" turns into
+///   "cat", same as Lucene/Tantivy analyzers drop stop words from query
+/// - Turns words analyzed into several terms into phrases of parts of the word,
+///   which have single term each, e.g. "covid-19" turns into "\"covid 19\"",
+///   since default tokenizer splits words by hyphens
+/// - Removes words without terms from phrases and proximity queries, since they
+///   do not take positions in documents either, so "\"retrieval of
+///   information\"" matches "retrieval" right before "information" if
+///   `analyzer` removes stop words. Phrases and proximity queries with single
+///   word left turn into that word
+/// - Removes prefix and wildcard patterns without indexable characters in front
+///   of the first wildcard (e.g. "-*"), since they would match too many terms
+/// - Removes fuzzy words without terms, and turns fuzzy words with zero
+///   distance into plain words (e.g. "cat~0" turns into "cat"). Fuzzy words
+///   with several terms turn into plain words too, since edit distance applies
+///   to single term
+/// - Moves field restrictions under `NOT`, e.g. "title:(NOT a)" turns into "NOT
+///   title:a", so planner finds excluding operands of `AND` right away
+/// - Flattens nested operators of the same kind, e.g. "a OR (b OR c)" turns
+///   into "a OR b OR c". This keeps scores of implicit-OR queries summed in the
+///   same order as query words go, regardless of parentheses
+/// - Unwraps operators with single operand left after removals
+///
+/// Returns `None` if nothing is left from the query, meaning it cannot match
+/// any document.
+pub fn normalize_query<'a>(
+    query_ast: &QueryAst<'a>,
+    analyzer: &Analyzer,
+) -> Option<QueryAst<'a>> {
+    match query_ast {
+        QueryAst::Word(word) => match split_word(word, analyzer).as_slice() {
+            [] => None,
+            [(part, _)] => Some(QueryAst::Word(part)),
+            parts => normalize_query(
+                &QueryAst::Phrase(
+                    parts.iter().map(|(part, _)| *part).collect(),
+                ),
+                analyzer,
+            ),
+        },
+        QueryAst::Phrase(words) => {
+            let words = words
+                .iter()
+                .flat_map(|word| split_word(word, analyzer))
+                .map(|(part, _)| part)
+                .collect::<Vec<_>>();
+
+            match words.as_slice() {
+                [] => None,
+                [word] => normalize_query(&QueryAst::Word(word), analyzer),
+                _ => Some(QueryAst::Phrase(words)),
+            }
+        }
+        QueryAst::Proximity { words, distance } => {
+            let words = words
+                .iter()
+                .flat_map(|word| split_word(word, analyzer))
+                .map(|(part, _)| part)
+                .collect::<Vec<_>>();
+
+            match words.as_slice() {
+                [] => None,
+                [word] => Some(QueryAst::Word(word)),
+                _ => Some(QueryAst::Proximity {
+                    words,
+                    distance: *distance,
+                }),
+            }
+        }
+        // invalid patterns are kept, so search reports why they are invalid
+        QueryAst::Prefix(prefix) => match normalize_pattern(prefix, analyzer) {
+            Ok(normalized) if normalized.is_empty() => None,
+            _ => Some(QueryAst::Prefix(prefix)),
+        },
+        QueryAst::Wildcard(pattern) => {
+            match normalize_pattern(pattern, analyzer) {
+                Ok(normalized)
+                    if get_pattern_prefix(&normalized).is_empty() =>
+                {
+                    None
+                }
+                _ => Some(QueryAst::Wildcard(pattern)),
+            }
+        }
+        QueryAst::Fuzzy { word, distance } => {
+            match split_word(word, analyzer).as_slice() {
+                [] => None,
+                [(part, _)] if *distance > 0 => Some(QueryAst::Fuzzy {
+                    word: part,
+                    distance: *distance,
+                }),
+                _ => normalize_query(&QueryAst::Word(word), analyzer),
+            }
+        }
+        QueryAst::Range { lower, upper } => Some(QueryAst::Range {
+            lower: *lower,
+            upper: *upper,
+        }),
+        QueryAst::Field { field, query } => {
+            normalize_field(field, query, analyzer)
+        }
+        QueryAst::Not(inner) => normalize_query(inner, analyzer)
+            .map(|inner| QueryAst::Not(Box::new(inner))),
+        QueryAst::Or(operands) => {
+            let mut normalized = Vec::new();
+
+            for operand in operands {
+                match normalize_query(operand, analyzer) {
+                    Some(QueryAst::Or(nested)) => normalized.extend(nested),
+                    Some(operand) => normalized.push(operand),
+                    None => {}
+                }
+            }
+
+            unwrap_single(normalized, QueryAst::Or)
+        }
+        QueryAst::And(operands) => normalize_and(operands, analyzer),
+    }
+}
+
+/// Splits query word into parts, which are analyzed into single term each,
+/// along with their terms. Parts are slices of the word, so they live as long
+/// as the query.
+fn split_word<'a>(word: &'a str, analyzer: &Analyzer) -> Vec<(&'a str, Term)> {
+    analyzer
+        .analyze(word)
+        .into_iter()
+        .map(|token| (&word[token.bytes], token.text))
+        .collect()
+}
+
+fn normalize_and<'a>(
+    operands: &[QueryAst<'a>],
+    analyzer: &Analyzer,
+) -> Option<QueryAst<'a>> {
+    let included_before =
+        operands.iter().any(|op| !matches!(op, QueryAst::Not(_)));
+
+    let mut normalized = Vec::new();
+
+    for operand in operands {
+        match normalize_query(operand, analyzer) {
+            Some(QueryAst::And(nested)) => normalized.extend(nested),
+            Some(operand) => normalized.push(operand),
+            None => {}
+        }
+    }
+
+    let includes_left =
+        normalized.iter().any(|op| !matches!(op, QueryAst::Not(_)));
+
+    if included_before && !includes_left {
+        // all including operands were removed, so intersection is
+        // empty regardless of excluding operands
+        return None;
+    }
+
+    unwrap_single(normalized, QueryAst::And)
+}
+
+fn normalize_field<'a>(
+    field: &'a str,
+    query: &QueryAst<'a>,
+    analyzer: &Analyzer,
+) -> Option<QueryAst<'a>> {
+    normalize_query(query, analyzer).map(|query| match query {
+        QueryAst::Not(inner) => QueryAst::Not(Box::new(QueryAst::Field {
+            field,
+            query: inner,
+        })),
+        query => QueryAst::Field {
+            field,
+            query: Box::new(query),
+        },
+    })
+}
+
+fn unwrap_single<'a>(
+    mut operands: Vec<QueryAst<'a>>,
+    wrap: fn(Vec<QueryAst<'a>>) -> QueryAst<'a>,
+) -> Option<QueryAst<'a>> {
+    match operands.len() {
+        0 => None,
+        1 => operands.pop(),
+        _ => Some(wrap(operands)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::engines::nano::search::query::{Lexer, Parser};
+
+    fn normalize(query: &str) -> Result<Option<String>> {
+        let query_ast = Parser::new(Lexer::new(query)).parse()?;
+        Ok(normalize_query(&query_ast, &Analyzer::default())
+            .map(|ast| format!("{ast:?}")))
+    }
+
+    #[test]
+    fn test_word() -> Result<()> {
+        assert_eq!(normalize("cat")?.as_deref(), Some(r#"Word("cat")"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_word() -> Result<()> {
+        assert_eq!(normalize("the")?, None);
+        assert_eq!(normalize("The")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_without_terms() -> Result<()> {
+        assert_eq!(normalize("--")?, None);
+        assert_eq!(normalize("cat &")?.as_deref(), Some(r#"Word("cat")"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_with_digits() -> Result<()> {
+        assert_eq!(
+            normalize("cat 1980")?.as_deref(),
+            Some(r#"Or([Word("cat"), Word("1980")])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_with_several_terms() -> Result<()> {
+        assert_eq!(
+            normalize("state-of-the-art")?.as_deref(),
+            Some(r#"Phrase(["state", "art"])"#)
+        );
+        assert_eq!(
+            normalize("(covid-19)")?.as_deref(),
+            Some(r#"Phrase(["covid", "19"])"#)
+        );
+        assert_eq!(normalize("don't")?.as_deref(), Some(r#"Word("don't")"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase() -> Result<()> {
+        assert_eq!(
+            normalize(r#""retrieval of information""#)?.as_deref(),
+            Some(r#"Phrase(["retrieval", "information"])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_removes_words_without_terms() -> Result<()> {
+        assert_eq!(
+            normalize(r#""cat & mouse""#)?.as_deref(),
+            Some(r#"Phrase(["cat", "mouse"])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_single_word() -> Result<()> {
+        assert_eq!(normalize(r#""cat &""#)?.as_deref(), Some(r#"Word("cat")"#));
+        assert_eq!(normalize(r#""the""#)?, None);
+        assert_eq!(normalize(r#""""#)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity() -> Result<()> {
+        assert_eq!(
+            normalize(r#""retrieval of information"~2"#)?.as_deref(),
+            Some(
+                r#"Proximity { words: ["retrieval", "information"], distance: 2 }"#
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity_single_word() -> Result<()> {
+        assert_eq!(
+            normalize(r#""the cat &"~3"#)?.as_deref(),
+            Some(r#"Word("cat")"#)
+        );
+        assert_eq!(normalize(r#""the of"~3"#)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_and_wildcard() -> Result<()> {
+        assert_eq!(
+            normalize("the* wom?n")?.as_deref(),
+            Some(r#"Or([Prefix("the"), Wildcard("wom?n")])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_and_wildcard_without_terms() -> Result<()> {
+        assert_eq!(normalize("-* -?-")?, None);
+        assert_eq!(normalize("cat -*x")?.as_deref(), Some(r#"Word("cat")"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_of_several_words() -> Result<()> {
+        // kept, so search reports that pattern cannot match single term
+        assert_eq!(
+            normalize("covid-19*")?.as_deref(),
+            Some(r#"Prefix("covid-19")"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy() -> Result<()> {
+        assert_eq!(
+            normalize("retreival~1 &~1")?.as_deref(),
+            Some(r#"Fuzzy { word: "retreival", distance: 1 }"#)
+        );
+        assert_eq!(normalize("cat~0")?.as_deref(), Some(r#"Word("cat")"#));
+        assert_eq!(normalize("the~0")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_field() -> Result<()> {
+        assert_eq!(
+            normalize("title:(the cat)")?.as_deref(),
+            Some(r#"Field { field: "title", query: Word("cat") }"#)
+        );
+        assert_eq!(normalize("title:the")?, None);
+        assert_eq!(
+            normalize("cat AND title:(NOT dog)")?.as_deref(),
+            Some(
+                r#"And([Word("cat"), Not(Field { field: "title", query: Word("dog") })])"#
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_or_removes_stop_words() -> Result<()> {
+        assert_eq!(
+            normalize("the cat and the mouse")?.as_deref(),
+            Some(r#"Or([Word("cat"), Word("mouse")])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_or_flattens_nested_or() -> Result<()> {
+        assert_eq!(
+            normalize("cat (dog (mouse))")?.as_deref(),
+            Some(r#"Or([Word("cat"), Word("dog"), Word("mouse")])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_or_keeps_nested_and() -> Result<()> {
+        assert_eq!(
+            normalize("cat (dog AND mouse)")?.as_deref(),
+            Some(r#"Or([Word("cat"), And([Word("dog"), Word("mouse")])])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_and_removes_stop_words() -> Result<()> {
+        assert_eq!(
+            normalize("cat AND the AND mouse")?.as_deref(),
+            Some(r#"And([Word("cat"), Word("mouse")])"#)
+        );
+        assert_eq!(
+            normalize("cat AND the")?.as_deref(),
+            Some(r#"Word("cat")"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_and_removes_excluded_stop_words() -> Result<()> {
+        assert_eq!(
+            normalize("cat AND NOT the")?.as_deref(),
+            Some(r#"Word("cat")"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_and_all_includes_removed() -> Result<()> {
+        assert_eq!(normalize("the AND NOT cat")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_and_flattens_nested_and() -> Result<()> {
+        assert_eq!(
+            normalize("cat AND (NOT dog AND NOT mouse)")?.as_deref(),
+            Some(r#"And([Word("cat"), Not(Word("dog")), Not(Word("mouse"))])"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_and_all_excluding_kept() -> Result<()> {
+        // invalid expression is kept as is, so planner can report it
+        assert_eq!(
+            normalize("NOT cat AND NOT dog")?.as_deref(),
+            Some(r#"And([Not(Word("cat")), Not(Word("dog"))])"#)
+        );
+        Ok(())
+    }
+}
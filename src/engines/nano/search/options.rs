@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::num::NonZero;
+use std::sync::Arc;
+
+use super::scoring::{Bm25, Scorer};
+
+/// Options of search routine, which affect how query is executed.
+#[derive(bon::Builder, Clone, Debug)]
+pub struct SearchOptions {
+    /// Scoring model, that scores documents matching query terms.
+    #[builder(default = Arc::new(Bm25::default()))]
+    pub scorer: Arc<dyn Scorer>,
+
+    /// Maximum number of terms, that prefix, wildcard pattern or fuzzy word
+    /// can expand to in the whole index. When it matches more terms, only
+    /// closest fuzzy terms and terms found in most documents of all segments
+    /// are taken.
+    ///
+    /// Each expanded term adds posting list to iterate, so without a cap short
+    /// patterns like "a*" would walk most of the index.
+    #[builder(default = 64)]
+    pub max_expansions: usize,
+
+    /// How documents matching terms expanded from patterns are scored.
+    ///
+    /// Fuzzy terms are always summed, so terms closer to query word weigh
+    /// more.
+    #[builder(default)]
+    pub expansion_scoring: ExpansionScoring,
+
+    /// How scores of query word found in several fields are combined, when
+    /// word is searched in all fields.
+    #[builder(default)]
+    pub field_scoring: FieldScoring,
+
+    /// Weights of fields, that make occurrences of query words in some fields
+    /// count more than in others, e.g. `title` over `text`. Fields without
+    /// weight get weight of 1.
+    #[builder(default)]
+    pub field_weights: HashMap<String, f64>,
+
+    /// Number of threads to search segments of the index in parallel. By
+    /// default equals to number of available CPU cores.
+    ///
+    /// Engine starts the threads once and reuses them for all searches, see
+    /// [`SearchPool`](super::SearchPool). Each segment is searched by single
+    /// thread, so there's no use in more threads than segments.
+    pub search_threads: Option<usize>,
+
+    /// Whether to skip documents, which cannot get into top hits, when only
+    /// top hits are collected.
+    ///
+    /// Turning it off doesn't change hits, but makes search score every
+    /// matching document, e.g. to measure how much skipping speeds it up.
+    #[builder(default = true)]
+    pub pruning: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl SearchOptions {
+    #[must_use]
+    pub fn get_field_weight(&self, field: &str) -> f64 {
+        self.field_weights.get(field).copied().unwrap_or(1.0)
+    }
+
+    #[must_use]
+    pub fn get_search_threads(&self) -> usize {
+        self.search_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, NonZero::get)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ExpansionScoring {
+    /// All documents matching pattern get the same score of 1, regardless of
+    /// which and how many expanded terms they contain.
+    ///
+    /// Lucene and Tantivy score multi-term queries this way by default.
+    #[default]
+    Constant,
+
+    /// Scores of expanded terms are summed, as if they were query words joined
+    /// with `OR`, so rare terms weigh more.
+    Summed,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum FieldScoring {
+    /// Occurrences of the word in all fields are weighted and combined into
+    /// single term frequency, which is scored with BM25F.
+    ///
+    /// Lucene scores multi-field words this way with `CombinedFieldQuery`.
+    #[default]
+    Combined,
+
+    /// BM25 scores of each field are weighted and summed, as if the word was
+    /// searched in each field separately and joined with `OR`.
+    ///
+    /// Tantivy's `QueryParser` scores words of default fields this way.
+    Summed,
+}
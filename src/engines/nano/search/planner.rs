@@ -1,12 +1,21 @@
+use std::ops::Bound;
+
 use anyhow::{Context, Result, bail, ensure};
 
+use super::expansion::{ExpandedTerm, TermExpansion};
 use super::iterators::{
-    ExcludingDocIdIterator, IntersectingDocIdIterator, PostingListIterator,
-    ScoringDocIdIterator, UnionDocIdIterator,
+    BoostingDocIdIterator, CombinedFieldsDocIdIterator,
+    ConstantScoreDocIdIterator, ExcludingDocIdIterator,
+    IntersectingDocIdIterator, PhraseDocIdIterator, PostingListIterator,
+    ProximityDocIdIterator, RangeDocIdIterator, ScoringDocIdIterator,
+    UnionDocIdIterator,
 };
+use super::options::{FieldScoring, SearchOptions};
 use super::query::QueryAst;
+use super::stats::GlobalStats;
+use crate::engines::nano::analysis::Analyzer;
 use crate::engines::nano::index::model::IndexSegment;
-use crate::utils::normalize_word;
+use crate::model::doc::DocValue;
 
 /// Builds document ID iterators tree out of query AST for one index segment.
 ///
@@ -18,6 +27,9 @@ use crate::utils::normalize_word;
 /// - `AND` expression should contain at least one including operand and at
 ///   least two operands total (e.g. "x AND y" or "x AND NOT y")
 /// - `OR` should contain at least two operands (e.g. "x OR y")
+/// - `Phrase` should contain at least two words (e.g. "\"x y\"")
+/// - `Proximity` should contain at least two words (e.g. "\"x y\"~2")
+/// - Each word should be analyzed into single term with `analyzer`
 ///
 /// Implementation note:
 /// Lucene and Tantivy distribute this planning logic across their `Query`
@@ -27,38 +39,285 @@ use crate::utils::normalize_word;
 pub fn plan_query_for_segment<'a>(
     query_ast: &QueryAst,
     segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    // words without field are searched in all fields of the index
+    let fields: Vec<&str> = stats.fields.iter().map(String::as_str).collect();
+    plan_query(query_ast, &fields, segment, stats, analyzer, options)
+}
+
+fn plan_query<'a>(
+    query_ast: &QueryAst,
+    fields: &[&str],
+    segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
 ) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
     Ok(match query_ast {
-        QueryAst::Word(word) => plan_word(word, segment)?,
-        QueryAst::And(operands) => plan_and(operands, segment)?,
-        QueryAst::Or(operands) => plan_or(operands, segment)?,
+        QueryAst::Word(word) => {
+            plan_word(word, fields, segment, stats, analyzer, options)?
+        }
+        QueryAst::Phrase(words) => plan_for_fields(fields, |field| {
+            plan_phrase(words, field, segment, stats, analyzer)
+        })?,
+        QueryAst::Proximity { words, distance } => {
+            plan_for_fields(fields, |field| {
+                plan_proximity(
+                    words, *distance, field, segment, stats, analyzer,
+                )
+            })?
+        }
+        QueryAst::Prefix(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Fuzzy { .. } => {
+            let expansion = TermExpansion::from_query_ast(query_ast, analyzer)?
+                .context("query node should expand into terms")?;
+            plan_expansion(&expansion, fields, segment, stats, options)?
+        }
+        QueryAst::Range { lower, upper } => {
+            plan_range(*lower, *upper, fields, segment)?
+        }
+        QueryAst::Field { field, query } => {
+            plan_query(query, &[field], segment, stats, analyzer, options)?
+        }
+        QueryAst::And(operands) => {
+            plan_and(operands, fields, segment, stats, analyzer, options)?
+        }
+        QueryAst::Or(operands) => {
+            plan_or(operands, fields, segment, stats, analyzer, options)?
+        }
         QueryAst::Not(_) => {
             bail!("NOT should only appear as direct operand of AND")
         }
     })
 }
 
+/// Plans query node separately for each field, and matches documents where
+/// it matches in any field. Scores of matching fields are summed, same as
+/// Tantivy's `QueryParser` does for default fields.
+fn plan_for_fields<'a>(
+    fields: &[&str],
+    mut plan_field: impl FnMut(&str) -> Result<Box<dyn ScoringDocIdIterator + 'a>>,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    let mut inputs = Vec::new();
+    for field in fields {
+        inputs.push(plan_field(field)?);
+    }
+
+    Ok(if inputs.len() == 1 {
+        inputs.pop().context("should exist")?
+    } else {
+        Box::new(UnionDocIdIterator::new(inputs))
+    })
+}
+
+/// Word searched in several fields is scored either with BM25F, or as union
+/// of weighted fields. Weights do not apply to word searched in single field.
 fn plan_word<'a>(
     word: &str,
+    fields: &[&str],
+    segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    let term = analyzer.analyze_word(word)?;
+
+    if let [field] = fields {
+        let it = PostingListIterator::create_for_segment(
+            segment, field, &term, stats,
+        )?;
+        return Ok(Box::new(it));
+    }
+
+    Ok(match options.field_scoring {
+        FieldScoring::Combined => {
+            let mut inputs = Vec::new();
+            for field in fields {
+                inputs.push((
+                    PostingListIterator::create_for_segment(
+                        segment, field, &term, stats,
+                    )?,
+                    options.get_field_weight(field),
+                ));
+            }
+            Box::new(CombinedFieldsDocIdIterator::new(inputs, stats))
+        }
+        FieldScoring::Summed => plan_for_fields(fields, |field| {
+            let it = Box::new(PostingListIterator::create_for_segment(
+                segment, field, &term, stats,
+            )?);
+            let weight = options.get_field_weight(field);
+            Ok(if weight == 1.0 {
+                it
+            } else {
+                Box::new(BoostingDocIdIterator::new(it, weight))
+            })
+        })?,
+    })
+}
+
+fn plan_phrase<'a>(
+    words: &[&str],
+    field: &str,
     segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
 ) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
-    let term = normalize_word(word);
-    let it = PostingListIterator::create_for_segment(segment, &term)?;
-    Ok(Box::new(it))
+    ensure!(words.len() >= 2, "phrase should contain at least two words");
+
+    ensure!(
+        segment.has_positions(),
+        "index should contain term positions to search phrases"
+    );
+
+    let mut inputs = Vec::new();
+    for word in words {
+        let term = analyzer.analyze_word(word)?;
+        inputs.push(PostingListIterator::create_for_segment(
+            segment, field, &term, stats,
+        )?);
+    }
+
+    Ok(Box::new(PhraseDocIdIterator::new(inputs)))
+}
+
+fn plan_proximity<'a>(
+    words: &[&str],
+    distance: u32,
+    field: &str,
+    segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    ensure!(
+        words.len() >= 2,
+        "proximity should contain at least two words"
+    );
+
+    ensure!(
+        segment.has_positions(),
+        "index should contain term positions to search proximity"
+    );
+
+    // repeated words share single posting list, since words go in any order
+    let mut terms: Vec<(String, usize)> = Vec::new();
+    for word in words {
+        let term = analyzer.analyze_word(word)?;
+        match terms.iter_mut().find(|(existing, _)| *existing == term) {
+            Some((_, count)) => *count += 1,
+            None => terms.push((term, 1)),
+        }
+    }
+
+    let mut inputs = Vec::new();
+    for (term, count) in terms {
+        inputs.push((
+            PostingListIterator::create_for_segment(
+                segment, field, &term, stats,
+            )?,
+            count,
+        ));
+    }
+
+    Ok(Box::new(ProximityDocIdIterator::new(inputs, distance)))
+}
+
+/// Expanded terms of all fields go into single union, so constant score is
+/// given once per document, regardless of how many fields it matches in.
+fn plan_expansion<'a>(
+    expansion: &TermExpansion,
+    fields: &[&str],
+    segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    options: &SearchOptions,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    let mut inputs: Vec<Box<dyn ScoringDocIdIterator + 'a>> = Vec::new();
+
+    for field in fields {
+        let terms = stats.get_expanded_terms(field, expansion)?;
+
+        for ExpandedTerm { term, boost } in terms {
+            let it = Box::new(PostingListIterator::create_for_segment(
+                segment, field, term, stats,
+            )?);
+            if *boost < 1.0 {
+                inputs.push(Box::new(BoostingDocIdIterator::new(it, *boost)));
+            } else {
+                inputs.push(it);
+            }
+        }
+    }
+
+    let union = Box::new(UnionDocIdIterator::new(inputs));
+
+    Ok(if expansion.is_scored(options) {
+        union
+    } else {
+        Box::new(ConstantScoreDocIdIterator::new(union, 1.0))
+    })
+}
+
+/// Range is matched against doc values of single field, with bounds parsed by
+/// type of its values. Segment without values of the field matches nothing.
+pub(super) fn plan_range<'a>(
+    lower: Bound<&str>,
+    upper: Bound<&str>,
+    fields: &[&str],
+    segment: &'a dyn IndexSegment,
+) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
+    let [field] = fields else {
+        bail!("range should be restricted to field, e.g. \"date:[a TO b]\"");
+    };
+
+    let Some(column) = segment.get_doc_values(field)? else {
+        return Ok(Box::new(RangeDocIdIterator::new(*field, None, 0..0)));
+    };
+
+    let parse = |bound: Bound<&str>| -> Result<Bound<DocValue>> {
+        Ok(match bound {
+            Bound::Included(text) => {
+                Bound::Included(column.info.value_type.parse_value(text)?)
+            }
+            Bound::Excluded(text) => {
+                Bound::Excluded(column.info.value_type.parse_value(text)?)
+            }
+            Bound::Unbounded => Bound::Unbounded,
+        })
+    };
+    let codes = column
+        .get_code_range(parse(lower)?.as_ref(), parse(upper)?.as_ref())?;
+
+    Ok(Box::new(RangeDocIdIterator::new(
+        *field,
+        Some(column),
+        codes,
+    )))
 }
 
 fn plan_and<'a>(
     operands: &[QueryAst],
+    fields: &[&str],
     segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
 ) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
     let mut includes = Vec::new();
     let mut excludes = Vec::new();
 
     for operand in operands {
         if let QueryAst::Not(inner) = operand {
-            excludes.push(plan_query_for_segment(inner, segment)?);
+            excludes.push(plan_query(
+                inner, fields, segment, stats, analyzer, options,
+            )?);
         } else {
-            includes.push(plan_query_for_segment(operand, segment)?);
+            includes.push(plan_query(
+                operand, fields, segment, stats, analyzer, options,
+            )?);
         }
     }
 
@@ -94,7 +353,11 @@ fn plan_and<'a>(
 
 fn plan_or<'a>(
     operands: &[QueryAst],
+    fields: &[&str],
     segment: &'a dyn IndexSegment,
+    stats: &'a GlobalStats,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
 ) -> Result<Box<dyn ScoringDocIdIterator + 'a>> {
     ensure!(
         operands.len() >= 2,
@@ -107,7 +370,8 @@ fn plan_or<'a>(
             !matches!(operand, QueryAst::Not(_)),
             "NOT should not be a part of OR expression"
         );
-        let it = plan_query_for_segment(operand, segment)?;
+        let it =
+            plan_query(operand, fields, segment, stats, analyzer, options)?;
         inputs.push(it);
     }
 
@@ -116,34 +380,109 @@ fn plan_or<'a>(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, HashMap};
 
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::engines::nano::analysis::{LowercaseFilter, UnicodeTokenizer};
     use crate::engines::nano::index::MemoryIndex;
+    use crate::engines::nano::index::doc_values::{
+        DocValueType, DocValuesColumnBuilder,
+    };
+    use crate::engines::nano::index::model::make_field_term_key;
+    use crate::engines::nano::search::options::ExpansionScoring;
     use crate::engines::nano::search::query::{Lexer, Parser};
 
-    fn create_segment() -> Box<dyn IndexSegment> {
-        let mut segment = MemoryIndex::default();
-        for term in &["a", "b", "c", "d", "e"] {
-            segment.terms.insert(term.to_string(), BTreeMap::new());
+    /// Default analyzer, but without stop words, since planner gets normalized
+    /// query without them anyway, and terms like "a" are handy in tests.
+    fn create_analyzer() -> Analyzer {
+        Analyzer::new(UnicodeTokenizer).with_filter(LowercaseFilter)
+    }
+
+    fn create_segment() -> Result<Box<dyn IndexSegment>> {
+        let mut segment = MemoryIndex {
+            has_positions: true,
+            ..MemoryIndex::default()
+        };
+        for term in &["a", "ab", "b", "c", "d", "e"] {
+            segment
+                .terms
+                .insert(make_field_term_key("text", term), BTreeMap::new());
         }
-        Box::new(segment)
+        segment
+            .terms
+            .insert(make_field_term_key("title", "a"), BTreeMap::new());
+
+        let mut builder =
+            DocValuesColumnBuilder::new("count", DocValueType::U64);
+        builder.add(0, DocValue::U64(5))?;
+        segment.doc_values.push(builder.build(1)?);
+
+        Ok(Box::new(segment))
+    }
+
+    fn create_stats(fields: &[&str]) -> GlobalStats {
+        let mut stats = GlobalStats::default();
+        stats.fields = fields.iter().map(ToString::to_string).collect();
+        stats
+    }
+
+    fn create_expanded_stats(
+        query_ast: &QueryAst,
+        fields: &[&str],
+        segment: &dyn IndexSegment,
+        options: &SearchOptions,
+    ) -> Result<GlobalStats> {
+        let mut stats = create_stats(fields);
+        stats.expand_terms(
+            query_ast,
+            &[segment],
+            &create_analyzer(),
+            options,
+        )?;
+        Ok(stats)
     }
 
     fn plan_query_and_explain(query: &str) -> Result<String> {
+        plan_query_with_fields_and_explain(query, &["text"])
+    }
+
+    fn plan_query_with_fields_and_explain(
+        query: &str,
+        fields: &[&str],
+    ) -> Result<String> {
         let tokens = Lexer::new(query);
         let query_ast = Parser::new(tokens).parse()?;
-        let segment = create_segment();
-        let it = plan_query_for_segment(&query_ast, segment.as_ref())?;
+        let segment = create_segment()?;
+        let options = SearchOptions::default();
+        let stats = create_expanded_stats(
+            &query_ast,
+            fields,
+            segment.as_ref(),
+            &options,
+        )?;
+        let it = plan_query_for_segment(
+            &query_ast,
+            segment.as_ref(),
+            &stats,
+            &create_analyzer(),
+            &options,
+        )?;
         Ok(it.explain().to_string())
     }
 
     fn plan_query_ast_and_explain(query_ast: &QueryAst) -> Result<String> {
-        let segment = create_segment();
-        let it = plan_query_for_segment(query_ast, segment.as_ref())?;
+        let segment = create_segment()?;
+        let stats = create_stats(&["text"]);
+        let it = plan_query_for_segment(
+            query_ast,
+            segment.as_ref(),
+            &stats,
+            &create_analyzer(),
+            &SearchOptions::default(),
+        )?;
         Ok(it.explain().to_string())
     }
 
@@ -156,7 +495,10 @@ mod tests {
 
     #[test]
     fn test_word() -> Result<()> {
-        assert_eq!(plan_query_and_explain("a")?, "Term (term = a)\n");
+        assert_eq!(
+            plan_query_and_explain("a")?,
+            "Term (field = text, term = a)\n"
+        );
         Ok(())
     }
 
@@ -164,7 +506,313 @@ mod tests {
     fn test_word_unknown() -> Result<()> {
         assert_eq!(
             plan_query_and_explain("x")?,
-            "Term (term = x, unknown_term = true)\n"
+            "Term (field = text, term = x, unknown_term = true)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_without_single_term() -> Result<()> {
+        assert_eq!(
+            err(plan_query_ast_and_explain(&QueryAst::Word("&")))?,
+            "word '&' should be analyzed into single term"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase() -> Result<()> {
+        assert_eq!(
+            plan_query_and_explain(r#""a b x""#)?,
+            indoc! {"
+                Phrase
+                ├── Term (field = text, term = a)
+                ├── Term (field = text, term = b)
+                └── Term (field = text, term = x, unknown_term = true)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_single_word() -> Result<()> {
+        assert_eq!(
+            err(plan_query_ast_and_explain(&QueryAst::Phrase(vec!["a"])))?,
+            "phrase should contain at least two words"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_without_positions() -> Result<()> {
+        let segment = MemoryIndex::default();
+        let stats = create_stats(&["text"]);
+        let query_ast = QueryAst::Phrase(vec!["a", "b"]);
+        assert_eq!(
+            err(plan_query_for_segment(
+                &query_ast,
+                &segment,
+                &stats,
+                &create_analyzer(),
+                &SearchOptions::default()
+            ))?,
+            "index should contain term positions to search phrases"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity() -> Result<()> {
+        assert_eq!(
+            plan_query_and_explain(r#""a b a"~3"#)?,
+            indoc! {"
+                Proximity (distance = 3)
+                ├── Term (field = text, term = a, count = 2)
+                └── Term (field = text, term = b)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity_single_word() -> Result<()> {
+        let query_ast = QueryAst::Proximity {
+            words: vec!["a"],
+            distance: 1,
+        };
+        assert_eq!(
+            err(plan_query_ast_and_explain(&query_ast))?,
+            "proximity should contain at least two words"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity_without_positions() -> Result<()> {
+        let segment = MemoryIndex::default();
+        let stats = create_stats(&["text"]);
+        let query_ast = QueryAst::Proximity {
+            words: vec!["a", "b"],
+            distance: 1,
+        };
+        assert_eq!(
+            err(plan_query_for_segment(
+                &query_ast,
+                &segment,
+                &stats,
+                &create_analyzer(),
+                &SearchOptions::default()
+            ))?,
+            "index should contain term positions to search proximity"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix() -> Result<()> {
+        assert_eq!(
+            plan_query_and_explain("a*")?,
+            indoc! {"
+                ConstantScore (score = 1)
+                └── Union
+                    ├── Term (field = text, term = a)
+                    └── Term (field = text, term = ab)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard() -> Result<()> {
+        // leading wildcard is not supported
+        assert_eq!(
+            plan_query_and_explain("?b")?,
+            "Term (field = text, term = b)\n"
+        );
+        assert_eq!(
+            plan_query_and_explain("a*b")?,
+            indoc! {"
+                ConstantScore (score = 1)
+                └── Union
+                    └── Term (field = text, term = ab)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy() -> Result<()> {
+        assert_eq!(
+            plan_query_and_explain("ab~1")?,
+            indoc! {"
+                Union
+                ├── Boost (boost = 0.5)
+                │   └── Term (field = text, term = a)
+                ├── Term (field = text, term = ab)
+                └── Boost (boost = 0.5)
+                    └── Term (field = text, term = b)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy_max_distance() -> Result<()> {
+        assert_eq!(
+            err(plan_query_and_explain("ab~3"))?,
+            "fuzzy distance should be at most 2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_summed_scoring() -> Result<()> {
+        let query_ast = QueryAst::Prefix("a");
+        let segment = create_segment()?;
+        let options = SearchOptions::builder()
+            .expansion_scoring(ExpansionScoring::Summed)
+            .max_expansions(1)
+            .build();
+        let stats = create_expanded_stats(
+            &query_ast,
+            &["text"],
+            segment.as_ref(),
+            &options,
+        )?;
+        let it = plan_query_for_segment(
+            &query_ast,
+            segment.as_ref(),
+            &stats,
+            &create_analyzer(),
+            &options,
+        )?;
+        assert_eq!(
+            it.explain().to_string(),
+            indoc! {"
+                Union
+                └── Term (field = text, term = a)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_fields() -> Result<()> {
+        assert_eq!(
+            plan_query_with_fields_and_explain(
+                r#"a "a b""#,
+                &["title", "text"]
+            )?,
+            indoc! {"
+                Union
+                ├── CombinedFields
+                │   ├── Term (field = title, term = a, weight = 1)
+                │   └── Term (field = text, term = a, weight = 1)
+                └── Union
+                    ├── Phrase
+                    │   ├── Term (field = title, term = a)
+                    │   └── Term (field = title, term = b, unknown_term = true)
+                    └── Phrase
+                        ├── Term (field = text, term = a)
+                        └── Term (field = text, term = b)
+            "}
+        );
+        assert_eq!(
+            plan_query_with_fields_and_explain("a*", &["title", "text"])?,
+            indoc! {"
+                ConstantScore (score = 1)
+                └── Union
+                    ├── Term (field = title, term = a)
+                    ├── Term (field = text, term = a)
+                    └── Term (field = text, term = ab)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_fields_weights() -> Result<()> {
+        let query_ast = QueryAst::Word("a");
+        let segment = create_segment()?;
+        let stats = create_stats(&["title", "text"]);
+        let explain = |field_scoring| -> Result<String> {
+            let options = SearchOptions::builder()
+                .field_scoring(field_scoring)
+                .field_weights(HashMap::from([("title".to_string(), 2.0)]))
+                .build();
+            let it = plan_query_for_segment(
+                &query_ast,
+                segment.as_ref(),
+                &stats,
+                &create_analyzer(),
+                &options,
+            )?;
+            Ok(it.explain().to_string())
+        };
+
+        assert_eq!(
+            explain(FieldScoring::Combined)?,
+            indoc! {"
+                CombinedFields
+                ├── Term (field = title, term = a, weight = 2)
+                └── Term (field = text, term = a, weight = 1)
+            "}
+        );
+        assert_eq!(
+            explain(FieldScoring::Summed)?,
+            indoc! {"
+                Union
+                ├── Boost (boost = 2)
+                │   └── Term (field = title, term = a)
+                └── Term (field = text, term = a)
+            "}
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_field() -> Result<()> {
+        assert_eq!(
+            plan_query_with_fields_and_explain(
+                "title:a AND NOT text:(b OR c)",
+                &["title", "text"]
+            )?,
+            indoc! {"
+                Exclusion
+                ├── include = Term (field = title, term = a)
+                └── exclude = Union
+                    ├── Term (field = text, term = b)
+                    └── Term (field = text, term = c)
+            "}
+        );
+        assert_eq!(
+            plan_query_and_explain("author:a")?,
+            "Term (field = author, term = a, unknown_term = true)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_range() -> Result<()> {
+        assert_eq!(
+            plan_query_and_explain("a AND count:[5 TO 10}")?,
+            indoc! {"
+                Intersection
+                ├── Term (field = text, term = a)
+                └── Range (field = count, codes = 5..10)
+            "}
+        );
+        assert_eq!(
+            plan_query_and_explain("count:{5 TO 10]")?,
+            "Range (field = count, codes = 6..11)\n"
+        );
+        assert_eq!(
+            plan_query_and_explain("author:[1 TO *]")?,
+            "Range (field = author, codes = 0..0, unknown_field = true)\n"
+        );
+        assert_eq!(
+            err(plan_query_and_explain("count:[a TO 10]"))?,
+            "'a' should be a number"
         );
         Ok(())
     }
@@ -175,8 +823,8 @@ mod tests {
             plan_query_and_explain("a AND b")?,
             indoc! {"
                 Intersection
-                ├── Term (term = a)
-                └── Term (term = b)
+                ├── Term (field = text, term = a)
+                └── Term (field = text, term = b)
             "}
         );
         Ok(())
@@ -188,8 +836,8 @@ mod tests {
             plan_query_and_explain("a AND NOT b")?,
             indoc! {"
                 Exclusion
-                ├── include = Term (term = a)
-                └── exclude = Term (term = b)
+                ├── include = Term (field = text, term = a)
+                └── exclude = Term (field = text, term = b)
             "}
         );
         Ok(())
@@ -202,9 +850,9 @@ mod tests {
             indoc! {"
                 Exclusion
                 ├── include = Union
-                │   ├── Term (term = a)
-                │   └── Term (term = b)
-                └── exclude = Term (term = c)
+                │   ├── Term (field = text, term = a)
+                │   └── Term (field = text, term = b)
+                └── exclude = Term (field = text, term = c)
             "}
         );
         Ok(())
@@ -217,9 +865,9 @@ mod tests {
             indoc! {"
                 Exclusion
                 ├── include = Intersection
-                │   ├── Term (term = a)
-                │   └── Term (term = b)
-                └── exclude = Term (term = c)
+                │   ├── Term (field = text, term = a)
+                │   └── Term (field = text, term = b)
+                └── exclude = Term (field = text, term = c)
             "}
         );
         Ok(())
@@ -231,10 +879,10 @@ mod tests {
             plan_query_and_explain("a AND NOT b AND NOT c")?,
             indoc! {"
                 Exclusion
-                ├── include = Term (term = a)
+                ├── include = Term (field = text, term = a)
                 └── exclude = Union
-                    ├── Term (term = b)
-                    └── Term (term = c)
+                    ├── Term (field = text, term = b)
+                    └── Term (field = text, term = c)
             "}
         );
         Ok(())
@@ -286,8 +934,8 @@ mod tests {
             plan_query_and_explain("a OR b")?,
             indoc! {"
                 Union
-                ├── Term (term = a)
-                └── Term (term = b)
+                ├── Term (field = text, term = a)
+                └── Term (field = text, term = b)
             "}
         );
         Ok(())
@@ -328,10 +976,10 @@ mod tests {
             plan_query_and_explain("a AND NOT (b OR c)")?,
             indoc! {"
                 Exclusion
-                ├── include = Term (term = a)
+                ├── include = Term (field = text, term = a)
                 └── exclude = Union
-                    ├── Term (term = b)
-                    └── Term (term = c)
+                    ├── Term (field = text, term = b)
+                    └── Term (field = text, term = c)
             "}
         );
         Ok(())
@@ -343,10 +991,10 @@ mod tests {
             plan_query_and_explain("a AND NOT (b AND c)")?,
             indoc! {"
                 Exclusion
-                ├── include = Term (term = a)
+                ├── include = Term (field = text, term = a)
                 └── exclude = Intersection
-                    ├── Term (term = b)
-                    └── Term (term = c)
+                    ├── Term (field = text, term = b)
+                    └── Term (field = text, term = c)
             "}
         );
         Ok(())
@@ -377,13 +1025,13 @@ mod tests {
             indoc! {"
                 Exclusion
                 ├── include = Intersection
-                │   ├── Term (term = a)
+                │   ├── Term (field = text, term = a)
                 │   └── Union
                 │       ├── Exclusion
-                │       │   ├── include = Term (term = b)
-                │       │   └── exclude = Term (term = c)
-                │       └── Term (term = d)
-                └── exclude = Term (term = e)
+                │       │   ├── include = Term (field = text, term = b)
+                │       │   └── exclude = Term (field = text, term = c)
+                │       └── Term (field = text, term = d)
+                └── exclude = Term (field = text, term = e)
             "}
         );
         Ok(())
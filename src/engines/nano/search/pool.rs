@@ -0,0 +1,128 @@
+use anyhow::Result;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Threads searching segments of the index in parallel.
+///
+/// Threads are started once and reused by all searches, so a query doesn't
+/// pay for spawning threads, which may take longer than searching small
+/// segments. Pool of single thread starts no threads, and searches on the
+/// calling one.
+#[derive(Debug)]
+pub struct SearchPool {
+    threads_count: usize,
+    pool: Option<ThreadPool>,
+}
+
+impl Default for SearchPool {
+    /// Searches on the calling thread.
+    fn default() -> Self {
+        Self {
+            threads_count: 1,
+            pool: None,
+        }
+    }
+}
+
+impl SearchPool {
+    pub fn new(threads_count: usize) -> Result<Self> {
+        if threads_count <= 1 {
+            return Ok(Self::default());
+        }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads_count)
+            .thread_name(|thread_idx| format!("searcher-{thread_idx}"))
+            .build()?;
+
+        Ok(Self {
+            threads_count,
+            pool: Some(pool),
+        })
+    }
+
+    #[must_use]
+    pub const fn get_threads_count(&self) -> usize {
+        self.threads_count
+    }
+
+    /// Maps each item with `f` on threads of the pool, and returns outputs in
+    /// the same order as items go.
+    ///
+    /// Each item is a separate task, and idle threads steal tasks from busy
+    /// ones, so threads stay busy even if items differ in size.
+    pub(super) fn map<T, O, F>(&self, items: &[T], f: F) -> Result<Vec<O>>
+    where
+        T: Sync,
+        O: Send,
+        F: Fn(&T) -> Result<O> + Sync,
+    {
+        match &self.pool {
+            Some(pool) if items.len() > 1 => pool
+                .install(|| items.par_iter().with_max_len(1).map(&f).collect()),
+            _ => items.iter().map(f).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_map_keeps_order_of_items() -> Result<()> {
+        for threads_count in [1, 4] {
+            // setup
+            let pool = SearchPool::new(threads_count)?;
+            let items: Vec<u64> = (0..100).collect();
+
+            // execute
+            let outputs = pool.map(&items, |item| Ok(item * 2))?;
+
+            // assert
+            assert_eq!(
+                outputs,
+                (0..100).map(|item| item * 2).collect::<Vec<_>>()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_runs_on_pool_threads() -> Result<()> {
+        // setup
+        let pool = SearchPool::new(2)?;
+
+        // execute
+        let names = pool.map(&[0, 1, 2], |_| {
+            Ok(std::thread::current().name().map(str::to_string))
+        })?;
+
+        // assert
+        for name in names {
+            assert!(name.is_some_and(|name| name.starts_with("searcher-")));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_with_error_is_error() -> Result<()> {
+        // setup
+        let pool = SearchPool::new(2)?;
+
+        // execute
+        let result = pool.map(&[0, 1, 2], |item| {
+            if *item == 1 {
+                bail!("segment {item} should be searched");
+            }
+            Ok(*item)
+        });
+
+        // assert
+        assert!(result.is_err());
+        Ok(())
+    }
+}
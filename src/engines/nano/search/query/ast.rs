@@ -1,3 +1,5 @@
+use std::ops::Bound;
+
 /// Abstract syntax tree (AST) node of a search query.
 ///
 /// This is intented to be pure object data structure directly reflecting
@@ -6,6 +8,43 @@
 pub enum QueryAst<'a> {
     Word(&'a str),
 
+    /// Words that should go in a row in the same order
+    Phrase(Vec<&'a str>),
+
+    /// Words that should go within `distance` positions from each other, in
+    /// any order
+    Proximity {
+        words: Vec<&'a str>,
+        distance: u32,
+    },
+
+    /// Word, that matches all terms starting with it
+    Prefix(&'a str),
+
+    /// Term pattern, where `*` matches any number of characters, and `?`
+    /// matches single character
+    Wildcard(&'a str),
+
+    /// Word, that matches all terms within `distance` edits from it
+    Fuzzy {
+        word: &'a str,
+        distance: u32,
+    },
+
+    /// Document value within bounds, e.g. number or date. Bounds are parsed
+    /// according to type of the field, which is only known from the index
+    Range {
+        lower: Bound<&'a str>,
+        upper: Bound<&'a str>,
+    },
+
+    /// Query, that should match in single field of documents, rather than in
+    /// any field
+    Field {
+        field: &'a str,
+        query: Box<Self>,
+    },
+
     // operators
     And(Vec<Self>),
     Or(Vec<Self>),
@@ -1,9 +1,13 @@
 use std::iter::Peekable;
+use std::ops::Bound;
 use std::str::CharIndices;
 
 pub struct Lexer<'a> {
     input: &'a str,
     chars: Peekable<CharIndices<'a>>,
+
+    /// Whether the previous token is a field name, so a range may go next
+    after_field: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -11,6 +15,7 @@ impl<'a> Lexer<'a> {
         Lexer {
             input,
             chars: input.char_indices().peekable(),
+            after_field: false,
         }
     }
 }
@@ -19,6 +24,42 @@ impl<'a> Lexer<'a> {
 pub enum Token<'a> {
     Word(&'a str),
 
+    /// Raw text between double quotes. Operators and parentheses inside quotes
+    /// are treated as regular text, e.g. "(cat AND dog)" is phrase of 3 words
+    Phrase(&'a str),
+
+    /// Phrase followed by `~N` suffix, e.g. "cat dog"~2
+    Proximity {
+        text: &'a str,
+        distance: u32,
+    },
+
+    /// Word with single trailing `*`, without the asterisk, e.g. "retriev" for
+    /// "retriev*"
+    Prefix(&'a str),
+
+    /// Word with `*` (any number of characters) or `?` (single character)
+    /// wildcards, e.g. "wom?n" or "inform*tion"
+    Wildcard(&'a str),
+
+    /// Word followed by `~N` suffix, e.g. retreival~1
+    Fuzzy {
+        word: &'a str,
+        distance: u32,
+    },
+
+    /// Field name followed by `:`, without the colon, e.g. "title" for
+    /// "title:retrieval". Applies to the next term or group
+    Field(&'a str),
+
+    /// Range of values right after field name, e.g. "[2020-01-01 TO *}".
+    /// Square brackets include bounds, curly braces exclude them, and `*`
+    /// means there is no bound
+    Range {
+        lower: Bound<&'a str>,
+        upper: Bound<&'a str>,
+    },
+
     // operators
     And,
     Or,
@@ -29,6 +70,106 @@ pub enum Token<'a> {
     RParen,
 }
 
+impl<'a> Lexer<'a> {
+    /// Scans text up to closing quote, starting right after opening one.
+    ///
+    /// Phrase without closing quote lasts until the end of input. Natural
+    /// language queries often have unbalanced quotes, so it's more useful to
+    /// search them anyway than to fail the whole query.
+    fn scan_phrase(&mut self, start_idx: usize) -> Token<'a> {
+        for (idx, ch) in self.chars.by_ref() {
+            if ch == '"' {
+                let text = &self.input[start_idx..idx];
+
+                return self
+                    .scan_distance()
+                    .map_or(Token::Phrase(text), |distance| {
+                        Token::Proximity { text, distance }
+                    });
+            }
+        }
+
+        Token::Phrase(&self.input[start_idx..])
+    }
+
+    /// Scans `~N` suffix right after closing quote of a phrase.
+    ///
+    /// Nothing is consumed if there is no valid suffix, so e.g. `~x` is
+    /// scanned as regular text afterwards.
+    fn scan_distance(&mut self) -> Option<u32> {
+        let mut chars = self.chars.clone();
+
+        let (tilde_idx, '~') = chars.next()? else {
+            return None;
+        };
+
+        let digits_start_idx = tilde_idx + 1;
+        let mut digits_end_idx = digits_start_idx;
+
+        while let Some(&(idx, ch)) = chars.peek() {
+            if !ch.is_ascii_digit() {
+                break;
+            }
+            chars.next();
+            digits_end_idx = idx + 1;
+        }
+
+        let distance =
+            self.input[digits_start_idx..digits_end_idx].parse().ok()?;
+
+        self.chars = chars;
+
+        Some(distance)
+    }
+
+    /// Scans `[lower TO upper]` range, starting right after opening bracket.
+    ///
+    /// Nothing is consumed if there is no valid range, so e.g. "[note]" is
+    /// scanned as regular text afterwards.
+    fn scan_range(
+        &mut self,
+        start_idx: usize,
+        open: char,
+    ) -> Option<Token<'a>> {
+        let text = &self.input[start_idx..];
+        let close_idx = text.find([']', '}'])?;
+
+        let [lower, "TO", upper] =
+            text[..close_idx].split_whitespace().collect::<Vec<_>>()[..]
+        else {
+            return None;
+        };
+
+        let bound = |value, is_inclusive| match value {
+            "*" => Bound::Unbounded,
+            _ if is_inclusive => Bound::Included(value),
+            _ => Bound::Excluded(value),
+        };
+        let close = &text[close_idx..=close_idx];
+
+        while self
+            .chars
+            .next_if(|&(idx, _)| idx <= start_idx + close_idx)
+            .is_some()
+        {}
+
+        Some(Token::Range {
+            lower: bound(lower, open == '['),
+            upper: bound(upper, close == "]"),
+        })
+    }
+
+    /// Checks if text right after `:` can be value of a field.
+    ///
+    /// Colon at the end of a word, e.g. "note: retrieval", is regular
+    /// punctuation of natural language queries rather than a field.
+    fn is_field_value_next(&mut self) -> bool {
+        self.chars
+            .peek()
+            .is_some_and(|&(_, ch)| !ch.is_whitespace() && ch != ')')
+    }
+}
+
 impl<'a> Iterator for Lexer<'a> {
     type Item = Token<'a>;
 
@@ -44,10 +185,17 @@ impl<'a> Iterator for Lexer<'a> {
 
         // get the structural starting point
         let (start_idx, first_ch) = self.chars.next()?;
+        let after_field = std::mem::take(&mut self.after_field);
 
         match first_ch {
             '(' => return Some(Token::LParen),
             ')' => return Some(Token::RParen),
+            '"' => return Some(self.scan_phrase(start_idx + 1)),
+            '[' | '{' if after_field => {
+                if let Some(range) = self.scan_range(start_idx + 1, first_ch) {
+                    return Some(range);
+                }
+            }
             _ => {}
         }
 
@@ -55,10 +203,17 @@ impl<'a> Iterator for Lexer<'a> {
         let mut end_idx = start_idx + first_ch.len_utf8();
 
         while let Some(&(idx, ch)) = self.chars.peek() {
-            if ch.is_whitespace() || matches!(ch, '(' | ')') {
+            if ch.is_whitespace() || matches!(ch, '(' | ')' | '"') {
                 break;
             }
             self.chars.next();
+
+            let text = &self.input[start_idx..end_idx];
+            if ch == ':' && is_field_name(text) && self.is_field_value_next() {
+                self.after_field = true;
+                return Some(Token::Field(text));
+            }
+
             end_idx = idx + ch.len_utf8();
         }
 
@@ -69,11 +224,47 @@ impl<'a> Iterator for Lexer<'a> {
             "AND" => Some(Token::And),
             "OR" => Some(Token::Or),
             "NOT" => Some(Token::Not),
-            _ => Some(Token::Word(slice)),
+            _ => Some(scan_word(slice)),
         }
     }
 }
 
+fn is_field_name(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// Tells if continuous text block is a regular word, fuzzy word or a term
+/// pattern.
+///
+/// Pattern should start with regular character, since leading wildcard would
+/// require to walk all terms of the index. Trailing question marks are treated
+/// as punctuation, since natural language queries are often questions, e.g.
+/// "what is retrieval?"
+fn scan_word(text: &str) -> Token<'_> {
+    if let Some((word, distance)) = text.rsplit_once('~')
+        && !word.is_empty()
+        && distance.chars().all(|ch| ch.is_ascii_digit())
+        && let Ok(distance) = distance.parse()
+    {
+        return Token::Fuzzy { word, distance };
+    }
+
+    let is_wildcard = |ch: char| matches!(ch, '*' | '?');
+    let pattern = text.trim_end_matches('?');
+
+    if pattern.starts_with(is_wildcard) || !pattern.contains(is_wildcard) {
+        return Token::Word(text);
+    }
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) if !prefix.contains(is_wildcard) => Token::Prefix(prefix),
+        _ => Token::Wildcard(pattern),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,6 +397,238 @@ mod tests {
         assert_eq!(lex.next(), None);
     }
 
+    #[test]
+    fn test_phrase() {
+        let mut lex = Lexer::new(r#"word1 "word2 word3" word4"#);
+
+        assert_eq!(lex.next(), Some(Token::Word("word1")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word2 word3")));
+        assert_eq!(lex.next(), Some(Token::Word("word4")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_phrase_without_spaces() {
+        let mut lex = Lexer::new(r#"word1"word2 word3"(word4)"#);
+
+        assert_eq!(lex.next(), Some(Token::Word("word1")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word2 word3")));
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(lex.next(), Some(Token::Word("word4")));
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_phrase_with_operators_and_parentheses() {
+        let mut lex = Lexer::new(r#"("word1 AND (NOT word2)")"#);
+
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(lex.next(), Some(Token::Phrase("word1 AND (NOT word2)")));
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_phrase_empty() {
+        let mut lex = Lexer::new(r#""" word"#);
+
+        assert_eq!(lex.next(), Some(Token::Phrase("")));
+        assert_eq!(lex.next(), Some(Token::Word("word")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_phrase_unclosed() {
+        let mut lex = Lexer::new(r#"word1 "word2 word3"#);
+
+        assert_eq!(lex.next(), Some(Token::Word("word1")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word2 word3")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_proximity() {
+        let mut lex = Lexer::new(r#""word1 word2"~5 "word3"~0 word4"#);
+
+        assert_eq!(
+            lex.next(),
+            Some(Token::Proximity {
+                text: "word1 word2",
+                distance: 5
+            })
+        );
+        assert_eq!(
+            lex.next(),
+            Some(Token::Proximity {
+                text: "word3",
+                distance: 0
+            })
+        );
+        assert_eq!(lex.next(), Some(Token::Word("word4")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_proximity_without_spaces() {
+        let mut lex = Lexer::new(r#"("word1 word2"~12)"#);
+
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(
+            lex.next(),
+            Some(Token::Proximity {
+                text: "word1 word2",
+                distance: 12
+            })
+        );
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_proximity_invalid_distance() {
+        let mut lex =
+            Lexer::new(r#""word1 word2"~x "word3"~ "word4"~99999999999"#);
+
+        assert_eq!(lex.next(), Some(Token::Phrase("word1 word2")));
+        assert_eq!(lex.next(), Some(Token::Word("~x")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word3")));
+        assert_eq!(lex.next(), Some(Token::Word("~")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word4")));
+        assert_eq!(lex.next(), Some(Token::Word("~99999999999")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_prefix() {
+        let mut lex = Lexer::new("retriev* (inform*)");
+
+        assert_eq!(lex.next(), Some(Token::Prefix("retriev")));
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(lex.next(), Some(Token::Prefix("inform")));
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let mut lex = Lexer::new("wom?n inform*tion re*ev* wom?n?");
+
+        assert_eq!(lex.next(), Some(Token::Wildcard("wom?n")));
+        assert_eq!(lex.next(), Some(Token::Wildcard("inform*tion")));
+        assert_eq!(lex.next(), Some(Token::Wildcard("re*ev*")));
+        assert_eq!(lex.next(), Some(Token::Wildcard("wom?n")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_wildcard_punctuation() {
+        let mut lex = Lexer::new("retrieval? * ? *tion");
+
+        assert_eq!(lex.next(), Some(Token::Word("retrieval?")));
+        assert_eq!(lex.next(), Some(Token::Word("*")));
+        assert_eq!(lex.next(), Some(Token::Word("?")));
+        assert_eq!(lex.next(), Some(Token::Word("*tion")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        let mut lex = Lexer::new("retreival~1 (wrod~2) word~ word~x ~1");
+
+        assert_eq!(
+            lex.next(),
+            Some(Token::Fuzzy {
+                word: "retreival",
+                distance: 1
+            })
+        );
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(
+            lex.next(),
+            Some(Token::Fuzzy {
+                word: "wrod",
+                distance: 2
+            })
+        );
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), Some(Token::Word("word~")));
+        assert_eq!(lex.next(), Some(Token::Word("word~x")));
+        assert_eq!(lex.next(), Some(Token::Word("~1")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_field() {
+        let mut lex =
+            Lexer::new(r#"title:word1 title:(word2) text:"word3" a_1:wo*"#);
+
+        assert_eq!(lex.next(), Some(Token::Field("title")));
+        assert_eq!(lex.next(), Some(Token::Word("word1")));
+        assert_eq!(lex.next(), Some(Token::Field("title")));
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(lex.next(), Some(Token::Word("word2")));
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), Some(Token::Field("text")));
+        assert_eq!(lex.next(), Some(Token::Phrase("word3")));
+        assert_eq!(lex.next(), Some(Token::Field("a_1")));
+        assert_eq!(lex.next(), Some(Token::Prefix("wo")));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_field_invalid() {
+        let mut lex = Lexer::new("note: word1 :word2 ti-tle:word3 (title:)");
+
+        assert_eq!(lex.next(), Some(Token::Word("note:")));
+        assert_eq!(lex.next(), Some(Token::Word("word1")));
+        assert_eq!(lex.next(), Some(Token::Word(":word2")));
+        assert_eq!(lex.next(), Some(Token::Word("ti-tle:word3")));
+        assert_eq!(lex.next(), Some(Token::LParen));
+        assert_eq!(lex.next(), Some(Token::Word("title:")));
+        assert_eq!(lex.next(), Some(Token::RParen));
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut lex =
+            Lexer::new("date:[2020-01-01 TO 2021-01-01} count:{ 5 TO * ]");
+
+        assert_eq!(lex.next(), Some(Token::Field("date")));
+        assert_eq!(
+            lex.next(),
+            Some(Token::Range {
+                lower: Bound::Included("2020-01-01"),
+                upper: Bound::Excluded("2021-01-01"),
+            })
+        );
+        assert_eq!(lex.next(), Some(Token::Field("count")));
+        assert_eq!(
+            lex.next(),
+            Some(Token::Range {
+                lower: Bound::Excluded("5"),
+                upper: Bound::Unbounded,
+            })
+        );
+        assert_eq!(lex.next(), None);
+    }
+
+    #[test]
+    fn test_range_invalid() {
+        let mut lex = Lexer::new("[a TO b] count:[5 TO] note:[draft]");
+
+        assert_eq!(lex.next(), Some(Token::Word("[a")));
+        assert_eq!(lex.next(), Some(Token::Word("TO")));
+        assert_eq!(lex.next(), Some(Token::Word("b]")));
+        assert_eq!(lex.next(), Some(Token::Field("count")));
+        assert_eq!(lex.next(), Some(Token::Word("[5")));
+        assert_eq!(lex.next(), Some(Token::Word("TO]")));
+        assert_eq!(lex.next(), Some(Token::Field("note")));
+        assert_eq!(lex.next(), Some(Token::Word("[draft]")));
+        assert_eq!(lex.next(), None);
+    }
+
     #[test]
     fn test_complex_query() {
         let mut lex = Lexer::new("(rust OR c++) AND NOT (java OR python)");
@@ -2,9 +2,7 @@ mod ast;
 pub use ast::QueryAst;
 
 mod lexer;
-#[allow(unused_imports)] // TODO: remove unused code silencer
 pub use lexer::Lexer;
 
 mod parser;
-#[allow(unused_imports)] // TODO: remove unused code silencer
 pub use parser::Parser;
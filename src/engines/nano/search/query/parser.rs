@@ -24,7 +24,14 @@ use super::lexer::Token;
 ///   unary ::= "NOT" unary
 ///           | primary
 ///
-///   primary ::= WORD
+///   primary ::= FIELD primary
+///             | FIELD RANGE
+///             | WORD
+///             | PHRASE
+///             | PROXIMITY
+///             | PREFIX
+///             | WILDCARD
+///             | FUZZY
 ///             | "(" expression ")"
 ///   ```
 ///
@@ -37,6 +44,30 @@ use super::lexer::Token;
 ///   - "a b"     => "a OR b"
 ///   - "a (b c)" => "a OR (b OR c)"
 ///
+/// - Phrase is any text in double quotes, which is split into words by
+///   whitespaces. Unclosed quote lasts until the end of query.
+///   - "\"a b\" c" => "(a FOLLOWED BY b) OR c"
+///
+/// - Proximity is a phrase with `~N` suffix right after closing quote.
+///   - "\"a b\"~2" => "a NEAR b (within 2 positions, in any order)"
+///
+/// - Prefix is a word with trailing `*`, and wildcard is a word with `*` or `?`
+///   anywhere after its first character.
+///   - "retriev*" => any term starting with "retriev"
+///   - "wom?n"    => "woman", "women", etc.
+///
+/// - Fuzzy is a word with `~N` suffix.
+///   - "retreival~1" => any term within 1 edit from "retreival"
+///
+/// - Field is a name with `:` suffix, which restricts the next primary
+///   expression to that field. Words without field match in any field.
+///   - "title:a b"     => "(a in title) OR (b in any field)"
+///   - "title:(a OR b)" => "(a OR b) in title"
+///
+/// - Range goes right after field, with inclusive `[]` or exclusive `{}`
+///   bounds, where `*` means no bound.
+///   - "date:[2020-01-01 TO *]" => "date is 2020-01-01 or later"
+///
 /// - Restricting NOT to only appear within an AND expression is not the syntax
 ///   parser's job - that belongs to a later semantic analysis stage.
 ///   - "NOT a"      => valid syntax, at root
@@ -82,7 +113,17 @@ where
     fn starts_operand(&mut self) -> bool {
         matches!(
             self.tokens.peek(),
-            Some(Token::Word(_) | Token::LParen | Token::Not)
+            Some(
+                Token::Word(_)
+                    | Token::Phrase(_)
+                    | Token::Proximity { .. }
+                    | Token::Prefix(_)
+                    | Token::Wildcard(_)
+                    | Token::Fuzzy { .. }
+                    | Token::Field(_)
+                    | Token::LParen
+                    | Token::Not
+            )
         )
     }
 
@@ -134,19 +175,42 @@ where
     fn parse_primary(&mut self) -> Result<QueryAst<'a>> {
         match self.tokens.next() {
             Some(Token::Word(word)) => Ok(QueryAst::Word(word)),
+            Some(Token::Phrase(text)) => {
+                Ok(QueryAst::Phrase(text.split_whitespace().collect()))
+            }
+            Some(Token::Proximity { text, distance }) => {
+                Ok(QueryAst::Proximity {
+                    words: text.split_whitespace().collect(),
+                    distance,
+                })
+            }
+            Some(Token::Prefix(prefix)) => Ok(QueryAst::Prefix(prefix)),
+            Some(Token::Wildcard(pattern)) => Ok(QueryAst::Wildcard(pattern)),
+            Some(Token::Fuzzy { word, distance }) => {
+                Ok(QueryAst::Fuzzy { word, distance })
+            }
+            Some(Token::Field(field)) => Ok(QueryAst::Field {
+                field,
+                query: Box::new(self.parse_primary()?),
+            }),
+            Some(Token::Range { lower, upper }) => {
+                Ok(QueryAst::Range { lower, upper })
+            }
             Some(Token::LParen) => {
                 let ast = self.parse_expression()?;
                 self.expect_token(&Token::RParen, "should receive ')'")?;
                 Ok(ast)
             }
             // TODO: print offset position of problem in all errors
-            _ => bail!("should receive word or '('"),
+            _ => bail!("should receive word, phrase or '('"),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
     use super::super::lexer::Lexer;
     use super::*;
 
@@ -175,6 +239,134 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_phrase() -> Result<()> {
+        assert_eq!(
+            parse(r#""a b  c""#)?,
+            QueryAst::Phrase(vec!["a", "b", "c"])
+        );
+        assert_eq!(parse(r#""""#)?, QueryAst::Phrase(vec![]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_phrase_with_operators() -> Result<()> {
+        assert_eq!(
+            parse(r#"a AND NOT "b OR (c)""#)?,
+            QueryAst::And(vec![
+                QueryAst::Word("a"),
+                QueryAst::Not(Box::new(QueryAst::Phrase(vec![
+                    "b", "OR", "(c)"
+                ]))),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_proximity() -> Result<()> {
+        assert_eq!(
+            parse(r#"a AND "b c"~3"#)?,
+            QueryAst::And(vec![
+                QueryAst::Word("a"),
+                QueryAst::Proximity {
+                    words: vec!["b", "c"],
+                    distance: 3
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_and_wildcard() -> Result<()> {
+        assert_eq!(
+            parse("a* OR wom?n")?,
+            QueryAst::Or(vec![
+                QueryAst::Prefix("a"),
+                QueryAst::Wildcard("wom?n"),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fuzzy() -> Result<()> {
+        assert_eq!(
+            parse("a~1 AND b")?,
+            QueryAst::And(vec![
+                QueryAst::Fuzzy {
+                    word: "a",
+                    distance: 1
+                },
+                QueryAst::Word("b"),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_implicit_or_phrases() -> Result<()> {
+        assert_eq!(
+            parse(r#"a "b c" "d""#)?,
+            QueryAst::Or(vec![
+                QueryAst::Word("a"),
+                QueryAst::Phrase(vec!["b", "c"]),
+                QueryAst::Phrase(vec!["d"]),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_field() -> Result<()> {
+        assert_eq!(
+            parse("title:a b")?,
+            QueryAst::Or(vec![
+                QueryAst::Field {
+                    field: "title",
+                    query: Box::new(QueryAst::Word("a")),
+                },
+                QueryAst::Word("b"),
+            ])
+        );
+        assert_eq!(
+            parse(r#"title:(a OR "b c") AND NOT text:d*"#)?,
+            QueryAst::And(vec![
+                QueryAst::Field {
+                    field: "title",
+                    query: Box::new(QueryAst::Or(vec![
+                        QueryAst::Word("a"),
+                        QueryAst::Phrase(vec!["b", "c"]),
+                    ])),
+                },
+                QueryAst::Not(Box::new(QueryAst::Field {
+                    field: "text",
+                    query: Box::new(QueryAst::Prefix("d")),
+                })),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_range() -> Result<()> {
+        assert_eq!(
+            parse("cat AND date:[2020 TO *}")?,
+            QueryAst::And(vec![
+                QueryAst::Word("cat"),
+                QueryAst::Field {
+                    field: "date",
+                    query: Box::new(QueryAst::Range {
+                        lower: Bound::Included("2020"),
+                        upper: Bound::Unbounded,
+                    }),
+                },
+            ])
+        );
+        Ok(())
+    }
+
     // --- UNARY OPERATORS ---
 
     #[test]
@@ -365,21 +557,24 @@ mod tests {
 
     #[test]
     fn test_empty_input() -> Result<()> {
-        assert_eq!(err(parse(""))?, "should receive word or '('");
+        assert_eq!(err(parse(""))?, "should receive word, phrase or '('");
         Ok(())
     }
 
     #[test]
     fn test_missing_rhs_binary_operators() -> Result<()> {
-        assert_eq!(err(parse("a AND"))?, "should receive word or '('");
-        assert_eq!(err(parse("a OR"))?, "should receive word or '('");
+        assert_eq!(err(parse("a AND"))?, "should receive word, phrase or '('");
+        assert_eq!(err(parse("a OR"))?, "should receive word, phrase or '('");
         Ok(())
     }
 
     #[test]
     fn test_missing_rhs_unary_operator() -> Result<()> {
-        assert_eq!(err(parse("NOT"))?, "should receive word or '('");
-        assert_eq!(err(parse("a AND NOT"))?, "should receive word or '('");
+        assert_eq!(err(parse("NOT"))?, "should receive word, phrase or '('");
+        assert_eq!(
+            err(parse("a AND NOT"))?,
+            "should receive word, phrase or '('"
+        );
         Ok(())
     }
 
@@ -388,22 +583,32 @@ mod tests {
         assert_eq!(err(parse("(a"))?, "should receive ')'");
         assert_eq!(err(parse("((a AND b)"))?, "should receive ')'");
         assert_eq!(err(parse("a)"))?, "should consume all tokens");
-        assert_eq!(err(parse("()"))?, "should receive word or '('");
+        assert_eq!(err(parse("()"))?, "should receive word, phrase or '('");
+        assert_eq!(err(parse("title:(a"))?, "should receive ')'");
         Ok(())
     }
 
     #[test]
     fn test_unexpected_operators_at_start() -> Result<()> {
-        assert_eq!(err(parse("AND a"))?, "should receive word or '('");
-        assert_eq!(err(parse("OR a"))?, "should receive word or '('");
+        assert_eq!(err(parse("AND a"))?, "should receive word, phrase or '('");
+        assert_eq!(err(parse("OR a"))?, "should receive word, phrase or '('");
         Ok(())
     }
 
     #[test]
     fn test_consecutive_binary_operators() -> Result<()> {
-        assert_eq!(err(parse("a AND AND b"))?, "should receive word or '('");
-        assert_eq!(err(parse("a OR OR b"))?, "should receive word or '('");
-        assert_eq!(err(parse("a AND OR b"))?, "should receive word or '('");
+        assert_eq!(
+            err(parse("a AND AND b"))?,
+            "should receive word, phrase or '('"
+        );
+        assert_eq!(
+            err(parse("a OR OR b"))?,
+            "should receive word, phrase or '('"
+        );
+        assert_eq!(
+            err(parse("a AND OR b"))?,
+            "should receive word, phrase or '('"
+        );
         Ok(())
     }
 }
@@ -0,0 +1,400 @@
+use super::{FieldTermFreq, Scorer, TermStats};
+use crate::utils::TreeNode;
+
+/// Okapi BM25 scoring model.
+///
+/// BM25 improves upon basic TF-IDF by normalizing term frequency saturation
+/// and document length.
+///
+/// Term found in several fields is scored with BM25F: term frequencies of all
+/// fields are normalized by lengths of their fields, multiplied by field
+/// weights and summed first, and then saturated once. So term repeated in
+/// several fields can't score higher than in a single field with the same
+/// total frequency, and weights make occurrences in e.g. title count more.
+///
+/// # References
+/// - [Okapi BM25 on Wikipedia](https://en.wikipedia.org/wiki/Okapi_BM25)
+/// - Robertson, Zaragoza, Taylor (2004), "Simple BM25 Extension to Multiple
+///   Weighted Fields"
+#[derive(Debug, Clone, Copy)]
+pub struct Bm25 {
+    /// Term frequency saturation parameter, usually between [1.2, 2.0]
+    pub k1: f64,
+    /// Document length normalization parameter, between [0, 1]
+    pub b: f64,
+}
+
+impl Default for Bm25 {
+    fn default() -> Self {
+        Self { k1: 1.2, b: 0.75 }
+    }
+}
+
+impl Scorer for Bm25 {
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64 {
+        let term_frequency = calc_saturated_term_freq(
+            self.k1,
+            term_freq,
+            calc_length_normalization(
+                self.b,
+                doc_terms_count,
+                stats.terms_count_per_doc_avg,
+            ),
+        );
+
+        term_frequency
+            * calc_idf(stats.docs_with_term_count, stats.docs_total_count)
+    }
+
+    fn score_fields(&self, fields: &[FieldTermFreq], stats: &TermStats) -> f64 {
+        let tf = calc_fields_term_freq(self.b, fields);
+        let term_frequency = calc_saturated_term_freq(self.k1, tf, 1.0);
+
+        term_frequency
+            * calc_idf(stats.docs_with_term_count, stats.docs_total_count)
+    }
+
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("BM25")
+            .with_attr(
+                "score",
+                self.score(term_freq, doc_terms_count, stats).to_string(),
+            )
+            .with_attr("k1", self.k1.to_string())
+            .with_attr("b", self.b.to_string())
+            .with_child(explain_idf(stats))
+            .with_child(explain_term_freq(
+                self.k1,
+                self.b,
+                term_freq,
+                doc_terms_count,
+                stats,
+            ))
+    }
+
+    fn explain_fields(
+        &self,
+        fields: &[FieldTermFreq],
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("BM25F")
+            .with_attr("score", self.score_fields(fields, stats).to_string())
+            .with_attr("k1", self.k1.to_string())
+            .with_attr("b", self.b.to_string())
+            .with_child(explain_idf(stats))
+            .with_child(explain_fields_term_freq(self.k1, self.b, fields))
+    }
+}
+
+/// BM25+ scoring model.
+///
+/// BM25 over-penalizes long documents: term occurrence in very long document
+/// scores close to zero, i.e. almost as if the document didn't contain the
+/// term at all. BM25+ adds `delta` to saturated term frequency, so any
+/// occurrence scores at least `delta * idf`.
+///
+/// # References
+/// - Lv, Zhai (2011), "Lower-Bounding Term Frequency Normalization"
+#[derive(Debug, Clone, Copy)]
+pub struct Bm25Plus {
+    /// Term frequency saturation parameter, same as in [`Bm25`]
+    pub k1: f64,
+    /// Document length normalization parameter, same as in [`Bm25`]
+    pub b: f64,
+    /// Lower bound of saturated term frequency
+    pub delta: f64,
+}
+
+impl Default for Bm25Plus {
+    fn default() -> Self {
+        let bm25 = Bm25::default();
+        Self {
+            k1: bm25.k1,
+            b: bm25.b,
+            delta: 1.0,
+        }
+    }
+}
+
+impl Scorer for Bm25Plus {
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64 {
+        let term_frequency = calc_saturated_term_freq(
+            self.k1,
+            term_freq,
+            calc_length_normalization(
+                self.b,
+                doc_terms_count,
+                stats.terms_count_per_doc_avg,
+            ),
+        ) + self.delta;
+
+        term_frequency
+            * calc_idf(stats.docs_with_term_count, stats.docs_total_count)
+    }
+
+    fn score_fields(&self, fields: &[FieldTermFreq], stats: &TermStats) -> f64 {
+        let tf = calc_fields_term_freq(self.b, fields);
+        let term_frequency =
+            calc_saturated_term_freq(self.k1, tf, 1.0) + self.delta;
+
+        term_frequency
+            * calc_idf(stats.docs_with_term_count, stats.docs_total_count)
+    }
+
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("BM25+")
+            .with_attr(
+                "score",
+                self.score(term_freq, doc_terms_count, stats).to_string(),
+            )
+            .with_attr("k1", self.k1.to_string())
+            .with_attr("b", self.b.to_string())
+            .with_attr("delta", self.delta.to_string())
+            .with_child(explain_idf(stats))
+            .with_child(explain_term_freq(
+                self.k1,
+                self.b,
+                term_freq,
+                doc_terms_count,
+                stats,
+            ))
+    }
+
+    fn explain_fields(
+        &self,
+        fields: &[FieldTermFreq],
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("BM25F+")
+            .with_attr("score", self.score_fields(fields, stats).to_string())
+            .with_attr("k1", self.k1.to_string())
+            .with_attr("b", self.b.to_string())
+            .with_attr("delta", self.delta.to_string())
+            .with_child(explain_idf(stats))
+            .with_child(explain_fields_term_freq(self.k1, self.b, fields))
+    }
+}
+
+/// Saturates term frequency, so each next occurrence adds less to the score.
+/// `norm` is document length normalization factor, see
+/// [`calc_length_normalization`].
+fn calc_saturated_term_freq(k1: f64, term_freq: f64, norm: f64) -> f64 {
+    let num = term_freq * (k1 + 1.0);
+    let den = term_freq + (k1 * norm);
+
+    num / den
+}
+
+fn calc_length_normalization(
+    b: f64,
+    doc_terms_count: f64,
+    terms_count_per_doc_avg: f64,
+) -> f64 {
+    let doc_length_normalized = doc_terms_count / terms_count_per_doc_avg;
+
+    1.0 - b + (b * doc_length_normalized)
+}
+
+fn calc_idf(docs_with_term_count: u64, docs_total_count: u64) -> f64 {
+    let total_docs = docs_total_count as f64;
+    let matching_docs = docs_with_term_count as f64;
+
+    let num = total_docs - matching_docs + 0.5;
+    let den = matching_docs + 0.5;
+
+    f64::ln((num / den) + 1.0)
+}
+
+/// Sums weighted term frequencies of fields, normalized by lengths of their
+/// fields, into single term frequency of BM25F.
+fn calc_fields_term_freq(b: f64, fields: &[FieldTermFreq]) -> f64 {
+    fields
+        .iter()
+        .map(|field| {
+            field.weight * field.term_freq
+                / calc_length_normalization(
+                    b,
+                    f64::from(field.doc_terms_count),
+                    field.stats.terms_count_per_doc_avg,
+                )
+        })
+        .sum()
+}
+
+fn explain_idf(stats: &TermStats) -> TreeNode {
+    TreeNode::new("idf")
+        .with_attr(
+            "value",
+            calc_idf(stats.docs_with_term_count, stats.docs_total_count)
+                .to_string(),
+        )
+        .with_attr("df", stats.docs_with_term_count.to_string())
+        .with_attr("N", stats.docs_total_count.to_string())
+}
+
+fn explain_term_freq(
+    k1: f64,
+    b: f64,
+    term_freq: f64,
+    doc_terms_count: f64,
+    stats: &TermStats,
+) -> TreeNode {
+    let norm = calc_length_normalization(
+        b,
+        doc_terms_count,
+        stats.terms_count_per_doc_avg,
+    );
+
+    TreeNode::new("tf_norm")
+        .with_attr(
+            "value",
+            calc_saturated_term_freq(k1, term_freq, norm).to_string(),
+        )
+        .with_attr("tf", term_freq.to_string())
+        .with_attr("dl", doc_terms_count.to_string())
+        .with_attr("avgdl", stats.terms_count_per_doc_avg.to_string())
+        .with_attr("length_norm", norm.to_string())
+}
+
+fn explain_fields_term_freq(
+    k1: f64,
+    b: f64,
+    fields: &[FieldTermFreq],
+) -> TreeNode {
+    let tf = calc_fields_term_freq(b, fields);
+
+    let mut node = TreeNode::new("tf_norm")
+        .with_attr("value", calc_saturated_term_freq(k1, tf, 1.0).to_string())
+        .with_attr("tf", tf.to_string());
+
+    for field in fields {
+        let doc_terms_count = f64::from(field.doc_terms_count);
+        node.add_child(
+            TreeNode::new("field")
+                .with_attr("weight", field.weight.to_string())
+                .with_attr("tf", field.term_freq.to_string())
+                .with_attr("dl", doc_terms_count.to_string())
+                .with_attr(
+                    "avgdl",
+                    field.stats.terms_count_per_doc_avg.to_string(),
+                )
+                .with_attr(
+                    "length_norm",
+                    calc_length_normalization(
+                        b,
+                        doc_terms_count,
+                        field.stats.terms_count_per_doc_avg,
+                    )
+                    .to_string(),
+                ),
+        );
+    }
+
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_float_eq::assert_float_relative_eq;
+
+    use super::*;
+
+    const STATS: TermStats = TermStats {
+        docs_with_term_count: 5,
+        term_freq_total: 0,
+        docs_total_count: 1000,
+        terms_count_total: 150_000,
+        terms_count_per_doc_avg: 150.0,
+    };
+
+    fn field(
+        term_freq: f64,
+        doc_terms_count: u16,
+        terms_count_per_doc_avg: f64,
+        weight: f64,
+    ) -> FieldTermFreq {
+        FieldTermFreq {
+            term_freq,
+            doc_terms_count,
+            stats: TermStats {
+                terms_count_per_doc_avg,
+                ..STATS
+            },
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_bm25() {
+        let score = Bm25::default().score(2.0, 100.0, &STATS);
+        assert_eq!(score, 7.895_734_283_840_656);
+
+        // lower saturation parameter gives less to repeated occurrences
+        let scorer = Bm25 { k1: 0.5, b: 0.75 };
+        assert!(scorer.score(2.0, 100.0, &STATS) < score);
+
+        // without length normalization, length of document does not matter
+        let scorer = Bm25 { k1: 1.2, b: 0.0 };
+        assert_eq!(
+            scorer.score(2.0, 100.0, &STATS),
+            scorer.score(2.0, 1000.0, &STATS)
+        );
+    }
+
+    #[test]
+    fn test_bm25f() {
+        let title = field(1.0, 5, 10.0, 2.0);
+        let text = field(2.0, 100, 150.0, 1.0);
+
+        let score = Bm25::default().score_fields(&[title, text], &STATS);
+        assert_eq!(score, 9.504_676_364_321_393);
+
+        // single field with weight 1 scores same as plain BM25
+        let score = Bm25::default().score_fields(&[text], &STATS);
+        assert_float_relative_eq!(score, 7.895_734_283_840_656, 1e-12);
+    }
+
+    #[test]
+    fn test_bm25plus() {
+        let bm25 = Bm25::default();
+        let bm25plus = Bm25Plus::default();
+        let idf = calc_idf(STATS.docs_with_term_count, STATS.docs_total_count);
+
+        assert_float_relative_eq!(
+            bm25plus.score(2.0, 100.0, &STATS),
+            bm25.score(2.0, 100.0, &STATS) + idf,
+            1e-12
+        );
+
+        // occurrence in very long document still scores at least delta * idf
+        assert!(bm25.score(1.0, 60_000.0, &STATS) < 0.1 * idf);
+        assert!(bm25plus.score(1.0, 60_000.0, &STATS) > idf);
+
+        let text = field(2.0, 100, 150.0, 1.0);
+        assert_float_relative_eq!(
+            bm25plus.score_fields(&[text], &STATS),
+            bm25plus.score(2.0, 100.0, &STATS),
+            1e-12
+        );
+    }
+}
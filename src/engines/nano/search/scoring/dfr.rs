@@ -0,0 +1,152 @@
+use super::{Scorer, TermStats};
+use crate::utils::TreeNode;
+
+/// Divergence from randomness (DFR) scoring model.
+///
+/// Scores term by how much its frequency in the document diverges from
+/// frequency expected by chance. Uses `I(n)B2` combination of DFR components:
+/// - basic model `I(n)`: inverse document frequency, `tfn * log2((N+1) /
+///   (n+0.5))`
+/// - first normalization `B`: information gain of one more occurrence of the
+///   term, `(F+1) / (n * (tfn+1))`
+/// - term frequency normalization `H2`: `tfn = tf * log2(1 + c * avgl / l)`
+///
+/// where N is total number of documents, n is number of documents containing
+/// the term, F is total number of occurrences of the term, and `l`/`avgl` are
+/// document length and average document length.
+///
+/// Same as `DFRSimilarity(BasicModelIn, AfterEffectB, NormalizationH2)` in
+/// Lucene, which also smooths `F` and `n` by one to avoid division by zero.
+///
+/// # References
+/// - Amati, van Rijsbergen (2002), "Probabilistic Models of Information
+///   Retrieval Based on Measuring the Divergence from Randomness"
+#[derive(Debug, Clone, Copy)]
+pub struct Dfr {
+    /// Term frequency normalization parameter: the higher it is, the less
+    /// document length matters
+    pub c: f64,
+}
+
+impl Default for Dfr {
+    fn default() -> Self {
+        Self { c: 1.0 }
+    }
+}
+
+impl Scorer for Dfr {
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64 {
+        let tfn = calc_normalized_term_freq(
+            self.c,
+            term_freq,
+            doc_terms_count,
+            stats,
+        );
+
+        calc_basic_model(tfn, stats) * calc_after_effect(tfn, stats)
+    }
+
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode {
+        let tfn = calc_normalized_term_freq(
+            self.c,
+            term_freq,
+            doc_terms_count,
+            stats,
+        );
+
+        TreeNode::new("DFR I(n)B2")
+            .with_attr(
+                "score",
+                self.score(term_freq, doc_terms_count, stats).to_string(),
+            )
+            .with_attr("c", self.c.to_string())
+            .with_child(
+                TreeNode::new("tfn")
+                    .with_attr("value", tfn.to_string())
+                    .with_attr("tf", term_freq.to_string())
+                    .with_attr("dl", doc_terms_count.to_string())
+                    .with_attr(
+                        "avgdl",
+                        stats.terms_count_per_doc_avg.to_string(),
+                    ),
+            )
+            .with_child(
+                TreeNode::new("basic_model")
+                    .with_attr(
+                        "value",
+                        calc_basic_model(tfn, stats).to_string(),
+                    )
+                    .with_attr("df", stats.docs_with_term_count.to_string())
+                    .with_attr("N", stats.docs_total_count.to_string()),
+            )
+            .with_child(
+                TreeNode::new("after_effect")
+                    .with_attr(
+                        "value",
+                        calc_after_effect(tfn, stats).to_string(),
+                    )
+                    .with_attr("ctf", stats.term_freq_total.to_string())
+                    .with_attr("df", stats.docs_with_term_count.to_string()),
+            )
+    }
+}
+
+/// Normalizes term frequency by document length (`H2`).
+fn calc_normalized_term_freq(
+    c: f64,
+    term_freq: f64,
+    doc_terms_count: f64,
+    stats: &TermStats,
+) -> f64 {
+    term_freq
+        * f64::log2(1.0 + c * stats.terms_count_per_doc_avg / doc_terms_count)
+}
+
+/// Calculates basic model `I(n)`.
+fn calc_basic_model(tfn: f64, stats: &TermStats) -> f64 {
+    let docs_total_count = stats.docs_total_count as f64;
+    let docs_with_term_count = stats.docs_with_term_count as f64 + 1.0;
+
+    tfn * f64::log2((docs_total_count + 1.0) / (docs_with_term_count + 0.5))
+}
+
+/// Calculates first normalization `B`.
+fn calc_after_effect(tfn: f64, stats: &TermStats) -> f64 {
+    let docs_with_term_count = stats.docs_with_term_count as f64 + 1.0;
+    let term_freq_total = stats.term_freq_total as f64 + 1.0;
+
+    (term_freq_total + 1.0) / (docs_with_term_count * (tfn + 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dfr() {
+        let stats = TermStats {
+            docs_with_term_count: 5,
+            term_freq_total: 12,
+            docs_total_count: 1000,
+            terms_count_total: 150_000,
+            terms_count_per_doc_avg: 150.0,
+        };
+
+        let score = Dfr::default().score(2.0, 100.0, &stats);
+        assert_eq!(score, 12.302_568_398_565_105);
+
+        // higher normalization parameter gives more weight to term frequency
+        let scorer = Dfr { c: 2.0 };
+        assert!(scorer.score(2.0, 100.0, &stats) > score);
+    }
+}
@@ -0,0 +1,116 @@
+use super::{Scorer, TermStats};
+use crate::utils::TreeNode;
+
+/// Query likelihood language model with Dirichlet smoothing.
+///
+/// Ranks documents by probability, that language model of the document
+/// generates query terms. Document model is smoothed with model of the whole
+/// collection, so terms missing from the document do not zero the probability:
+/// `p(t|d) = (tf + mu * p(t|C)) / (len + mu)`, where `p(t|C)` is frequency of
+/// the term in the collection.
+///
+/// Score is the log of `p(t|d)` relative to probability of the term in a
+/// document, that does not contain it, which keeps it additive across query
+/// terms. Score is clamped at zero, since terms with high collection frequency
+/// in long documents may score below it.
+///
+/// Same as `LMDirichletSimilarity` in Lucene.
+///
+/// # References
+/// - Zhai, Lafferty (2001), "A Study of Smoothing Methods for Language Models
+///   Applied to Ad Hoc Information Retrieval"
+#[derive(Debug, Clone, Copy)]
+pub struct Dirichlet {
+    /// Smoothing parameter: the higher it is, the more document model is
+    /// smoothed with collection model. Usually around average document length
+    /// or above
+    pub mu: f64,
+}
+
+impl Default for Dirichlet {
+    fn default() -> Self {
+        Self { mu: 2000.0 }
+    }
+}
+
+impl Scorer for Dirichlet {
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64 {
+        let collection_probability = calc_collection_probability(stats);
+
+        let score =
+            f64::ln(1.0 + term_freq / (self.mu * collection_probability))
+                + f64::ln(self.mu / (doc_terms_count + self.mu));
+
+        score.max(0.0)
+    }
+
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("Dirichlet")
+            .with_attr(
+                "score",
+                self.score(term_freq, doc_terms_count, stats).to_string(),
+            )
+            .with_attr("mu", self.mu.to_string())
+            .with_attr("tf", term_freq.to_string())
+            .with_attr("dl", doc_terms_count.to_string())
+            .with_child(
+                TreeNode::new("collection_probability")
+                    .with_attr(
+                        "value",
+                        calc_collection_probability(stats).to_string(),
+                    )
+                    .with_attr("ctf", stats.term_freq_total.to_string())
+                    .with_attr(
+                        "terms_total",
+                        stats.terms_count_total.to_string(),
+                    ),
+            )
+    }
+}
+
+/// Gets probability of the term in the collection, i.e. `p(t|C)`.
+fn calc_collection_probability(stats: &TermStats) -> f64 {
+    // add one to avoid zero probability of terms missing from statistics
+    (stats.term_freq_total as f64 + 1.0)
+        / (stats.terms_count_total as f64 + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirichlet() {
+        let stats = TermStats {
+            docs_with_term_count: 5,
+            term_freq_total: 12,
+            docs_total_count: 1000,
+            terms_count_total: 150_000,
+            terms_count_per_doc_avg: 150.0,
+        };
+
+        let score = Dirichlet::default().score(2.0, 100.0, &stats);
+        assert_eq!(score, 2.480_016_814_126_299_7);
+
+        // less smoothing lets term frequency in the document weigh more
+        let scorer = Dirichlet { mu: 100.0 };
+        assert!(scorer.score(2.0, 100.0, &stats) > score);
+
+        // frequent term in long document is clamped at zero
+        let stats = TermStats {
+            term_freq_total: 100_000,
+            ..stats
+        };
+        assert_eq!(Dirichlet::default().score(1.0, 60_000.0, &stats), 0.0);
+    }
+}
@@ -0,0 +1,365 @@
+//! Scoring module implements various document scoring algorithms for search
+//! relevance.
+//!
+//! Search is configured with a [`Scorer`], so ranking functions can be
+//! compared on the same index without rebuilding it.
+
+mod bm25;
+mod dfr;
+mod dirichlet;
+mod tfidf;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail, ensure};
+pub use bm25::{Bm25, Bm25Plus};
+pub use dfr::Dfr;
+pub use dirichlet::Dirichlet;
+pub use tfidf::TfIdf;
+
+use crate::utils::TreeNode;
+
+/// Index-wide statistics of a term in a field
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TermStats {
+    /// Count of documents containing the term in the field
+    pub docs_with_term_count: u64,
+    /// Total number of occurrences of the term in the field of all documents
+    pub term_freq_total: u64,
+    /// Total number of documents in the index
+    pub docs_total_count: u64,
+    /// Total number of terms in the field of all documents
+    pub terms_count_total: u64,
+    /// Average number of terms in the field per document
+    pub terms_count_per_doc_avg: f64,
+}
+
+/// Occurrences of a term in one field of the document, for scoring the term
+/// across several fields
+#[derive(Debug, Clone, Copy)]
+pub struct FieldTermFreq {
+    /// Number of occurrences of the term in the field of the document
+    pub term_freq: f64,
+    /// Total count of terms in the field of the document
+    pub doc_terms_count: u16,
+    /// Index-wide statistics of the term in the field
+    pub stats: TermStats,
+    /// Multiplier of term occurrences in the field
+    pub weight: f64,
+}
+
+/// Scoring model, that scores a term in a document.
+///
+/// Scores of query terms are summed, so score should not be negative. It also
+/// should not decrease with term frequency and not increase with document
+/// length, since dynamic pruning takes score of highest frequency in shortest
+/// document of a block as upper bound of scores in the block.
+///
+/// It's called `Similarity` in Lucene.
+pub trait Scorer: Debug + Send + Sync {
+    /// Scores a term found in the field of a document.
+    ///
+    /// # Arguments
+    /// * `term_freq` - Number of occurrences of the term in the document. Can
+    ///   be fractional, e.g. for proximity matches, which are weighted by
+    ///   distance between terms
+    /// * `doc_terms_count` - Total count of terms in the field of the document
+    ///   (i.e. document length)
+    /// * `stats` - Index-wide statistics of the term
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64;
+
+    /// Scores a term found in several fields of a document, as if all fields
+    /// were one field.
+    ///
+    /// # Arguments
+    /// * `fields` - Occurrences of the term in fields of the document. Fields
+    ///   without occurrences can be omitted
+    /// * `stats` - Statistics of the term in all fields, see
+    ///   [`combine_term_stats`]
+    ///
+    /// By default weighted term frequencies and lengths of fields are summed
+    /// into a single pseudo field, which is scored with [`Scorer::score`].
+    fn score_fields(&self, fields: &[FieldTermFreq], stats: &TermStats) -> f64 {
+        let (term_freq, doc_terms_count) = combine_fields(fields);
+        self.score(term_freq, doc_terms_count, stats)
+    }
+
+    /// Explains components of the term score, see [`Scorer::score`].
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode;
+
+    /// Explains components of the term score in several fields, see
+    /// [`Scorer::score_fields`].
+    fn explain_fields(
+        &self,
+        fields: &[FieldTermFreq],
+        stats: &TermStats,
+    ) -> TreeNode {
+        let (term_freq, doc_terms_count) = combine_fields(fields);
+        self.explain(term_freq, doc_terms_count, stats)
+    }
+}
+
+/// Sums weighted term frequencies and lengths of fields of the document into
+/// term frequency and length of a single pseudo field.
+fn combine_fields(fields: &[FieldTermFreq]) -> (f64, f64) {
+    let term_freq = fields
+        .iter()
+        .map(|field| field.weight * field.term_freq)
+        .sum();
+    let doc_terms_count = fields
+        .iter()
+        .map(|field| field.weight * f64::from(field.doc_terms_count))
+        .sum();
+
+    (term_freq, doc_terms_count)
+}
+
+/// Merges statistics of a term in several fields into statistics of a single
+/// pseudo field.
+///
+/// Totals are summed, and average lengths are multiplied by field weights and
+/// summed, same as lengths of fields of the document in
+/// [`Scorer::score_fields`]. Term is considered found in as many documents as
+/// in the field where it's found most often, since actual number of documents
+/// containing the term in any field is unknown without merging posting lists.
+///
+/// Lucene's `CombinedFieldQuery` combines fields the same way.
+#[must_use]
+pub fn combine_term_stats(fields: &[(TermStats, f64)]) -> TermStats {
+    let mut combined = TermStats::default();
+
+    for (stats, weight) in fields {
+        combined.docs_with_term_count = combined
+            .docs_with_term_count
+            .max(stats.docs_with_term_count);
+        combined.docs_total_count =
+            combined.docs_total_count.max(stats.docs_total_count);
+        combined.term_freq_total += stats.term_freq_total;
+        combined.terms_count_total += stats.terms_count_total;
+        combined.terms_count_per_doc_avg +=
+            weight * stats.terms_count_per_doc_avg;
+    }
+
+    combined
+}
+
+/// Creates scorer from specification like `bm25` or `bm25:k1=1.5,b=0.6`,
+/// where name of the scoring model is followed by its parameters.
+///
+/// Supported scorers and their parameters (with defaults):
+/// - `bm25` - [`Bm25`]: `k1=1.2`, `b=0.75`
+/// - `bm25plus` - [`Bm25Plus`]: `k1=1.2`, `b=0.75`, `delta=1`
+/// - `tfidf` - [`TfIdf`]
+/// - `dirichlet` - [`Dirichlet`]: `mu=2000`
+/// - `dfr` - [`Dfr`]: `c=1`
+pub fn parse_scorer(spec: &str) -> Result<Arc<dyn Scorer>> {
+    let (name, params) = spec.split_once(':').unwrap_or((spec, ""));
+
+    let mut params = parse_scorer_params(params)?;
+    let mut param =
+        |key: &str, default: f64| params.remove(key).unwrap_or(default);
+
+    let scorer: Arc<dyn Scorer> = match name {
+        "bm25" => {
+            let default = Bm25::default();
+            Arc::new(Bm25 {
+                k1: param("k1", default.k1),
+                b: param("b", default.b),
+            })
+        }
+        "bm25plus" => {
+            let default = Bm25Plus::default();
+            Arc::new(Bm25Plus {
+                k1: param("k1", default.k1),
+                b: param("b", default.b),
+                delta: param("delta", default.delta),
+            })
+        }
+        "tfidf" => Arc::new(TfIdf),
+        "dirichlet" => Arc::new(Dirichlet {
+            mu: param("mu", Dirichlet::default().mu),
+        }),
+        "dfr" => Arc::new(Dfr {
+            c: param("c", Dfr::default().c),
+        }),
+        _ => bail!(
+            "scorer should be one of: bm25, bm25plus, tfidf, dirichlet, dfr"
+        ),
+    };
+
+    let mut unknown_params: Vec<&str> = params.into_keys().collect();
+    unknown_params.sort_unstable();
+    ensure!(
+        unknown_params.is_empty(),
+        "scorer '{name}' should not have parameters: {}",
+        unknown_params.join(", ")
+    );
+
+    Ok(scorer)
+}
+
+/// Parses comma-separated `key=value` pairs with numeric values.
+fn parse_scorer_params(params: &str) -> Result<HashMap<&str, f64>> {
+    params
+        .split(',')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (key, value) = param.split_once('=').with_context(|| {
+                format!("scorer parameter '{param}' should be key=value")
+            })?;
+            let value = value.parse().with_context(|| {
+                format!("scorer parameter '{key}' should be a number")
+            })?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(spec: &str) -> Result<String> {
+        Ok(format!("{:?}", parse_scorer(spec)?))
+    }
+
+    fn parse_err(spec: &str) -> Option<String> {
+        parse_scorer(spec).err().map(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_parse_scorer() -> Result<()> {
+        assert_eq!(parse("bm25")?, "Bm25 { k1: 1.2, b: 0.75 }");
+        assert_eq!(parse("bm25:k1=1.5,b=0.6")?, "Bm25 { k1: 1.5, b: 0.6 }");
+        assert_eq!(
+            parse("bm25plus:delta=0.5")?,
+            "Bm25Plus { k1: 1.2, b: 0.75, delta: 0.5 }"
+        );
+        assert_eq!(parse("tfidf")?, "TfIdf");
+        assert_eq!(parse("dirichlet:mu=1000")?, "Dirichlet { mu: 1000.0 }");
+        assert_eq!(parse("dfr")?, "Dfr { c: 1.0 }");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_scorer_errors() {
+        assert_eq!(
+            parse_err("lm").as_deref(),
+            Some(
+                "scorer should be one of: bm25, bm25plus, tfidf, dirichlet, \
+                 dfr"
+            )
+        );
+        assert_eq!(
+            parse_err("bm25:k=1,x=2").as_deref(),
+            Some("scorer 'bm25' should not have parameters: k, x")
+        );
+        assert_eq!(
+            parse_err("bm25:k1").as_deref(),
+            Some("scorer parameter 'k1' should be key=value")
+        );
+        assert_eq!(
+            parse_err("bm25:k1=high").as_deref(),
+            Some("scorer parameter 'k1' should be a number")
+        );
+    }
+
+    #[test]
+    fn test_combine_term_stats() {
+        let stats = |docs_with_term_count, avg| TermStats {
+            docs_with_term_count,
+            term_freq_total: docs_with_term_count * 2,
+            docs_total_count: 1000,
+            terms_count_total: 10_000,
+            terms_count_per_doc_avg: avg,
+        };
+
+        assert_eq!(
+            combine_term_stats(&[
+                (stats(5, 10.0), 2.0),
+                (stats(20, 150.0), 1.0)
+            ]),
+            TermStats {
+                docs_with_term_count: 20,
+                term_freq_total: 50,
+                docs_total_count: 1000,
+                terms_count_total: 20_000,
+                terms_count_per_doc_avg: 170.0,
+            }
+        );
+    }
+
+    /// Checks properties required from all scorers, see [`Scorer`]
+    #[test]
+    fn test_scorers_monotonic() -> Result<()> {
+        let stats = TermStats {
+            docs_with_term_count: 5,
+            term_freq_total: 12,
+            docs_total_count: 1000,
+            terms_count_total: 150_000,
+            terms_count_per_doc_avg: 150.0,
+        };
+
+        for spec in ["bm25", "bm25plus", "tfidf", "dirichlet", "dfr"] {
+            let scorer = parse_scorer(spec)?;
+            let score = |tf, len| scorer.score(tf, len, &stats);
+
+            assert!(score(1.0, 100.0) > 0.0, "{spec}");
+            assert!(score(2.0, 100.0) > score(1.0, 100.0), "{spec}");
+            assert!(score(2.0, 50.0) > score(2.0, 100.0), "{spec}");
+            assert!(score(1.0, 100_000.0) >= 0.0, "{spec}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scorers_explain_score() -> Result<()> {
+        let stats = TermStats {
+            docs_with_term_count: 5,
+            term_freq_total: 12,
+            docs_total_count: 1000,
+            terms_count_total: 150_000,
+            terms_count_per_doc_avg: 150.0,
+        };
+        let fields = [FieldTermFreq {
+            term_freq: 2.0,
+            doc_terms_count: 100,
+            stats,
+            weight: 2.0,
+        }];
+
+        for spec in ["bm25", "bm25plus", "tfidf", "dirichlet", "dfr"] {
+            let scorer = parse_scorer(spec)?;
+
+            let score = scorer.score(2.0, 100.0, &stats);
+            let explanation = scorer.explain(2.0, 100.0, &stats).to_string();
+            assert!(
+                explanation.contains(&format!("score = {score}")),
+                "{spec}"
+            );
+
+            let score = scorer.score_fields(&fields, &stats);
+            let explanation =
+                scorer.explain_fields(&fields, &stats).to_string();
+            assert!(
+                explanation.contains(&format!("score = {score}")),
+                "{spec}"
+            );
+        }
+
+        Ok(())
+    }
+}
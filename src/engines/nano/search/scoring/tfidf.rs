@@ -0,0 +1,79 @@
+use super::{Scorer, TermStats};
+use crate::utils::TreeNode;
+
+/// TF-IDF scoring model.
+///
+/// TF-IDF stands for Term Frequency-Inverse Document Frequency
+///
+/// Uses count-idf weighting scheme: tf * log(N/n)
+/// where:
+/// - tf = term frequency in document, normalized by document length
+/// - N = total number of documents
+/// - n = number of documents containing the term
+///
+/// # References
+/// - [TF-IDF on Wikipedia](https://en.wikipedia.org/wiki/Tf-idf)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TfIdf;
+
+impl Scorer for TfIdf {
+    fn score(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> f64 {
+        let term_frequency = term_freq / doc_terms_count;
+
+        term_frequency * calc_idf(stats)
+    }
+
+    fn explain(
+        &self,
+        term_freq: f64,
+        doc_terms_count: f64,
+        stats: &TermStats,
+    ) -> TreeNode {
+        TreeNode::new("TF-IDF")
+            .with_attr(
+                "score",
+                self.score(term_freq, doc_terms_count, stats).to_string(),
+            )
+            .with_child(
+                TreeNode::new("tf")
+                    .with_attr(
+                        "value",
+                        (term_freq / doc_terms_count).to_string(),
+                    )
+                    .with_attr("tf", term_freq.to_string())
+                    .with_attr("dl", doc_terms_count.to_string()),
+            )
+            .with_child(
+                TreeNode::new("idf")
+                    .with_attr("value", calc_idf(stats).to_string())
+                    .with_attr("df", stats.docs_with_term_count.to_string())
+                    .with_attr("N", stats.docs_total_count.to_string()),
+            )
+    }
+}
+
+fn calc_idf(stats: &TermStats) -> f64 {
+    f64::ln(stats.docs_total_count as f64 / stats.docs_with_term_count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tfidf() {
+        let stats = TermStats {
+            docs_with_term_count: 5,
+            docs_total_count: 1000,
+            ..TermStats::default()
+        };
+
+        let score = TfIdf.score(2.0, 100.0, &stats);
+        assert_eq!(score, 0.105_966_347_330_960_73);
+    }
+}
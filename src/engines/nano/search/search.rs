@@ -1,82 +1,385 @@
-use std::collections::HashMap;
-
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, ensure};
+use tracing::debug;
 
+use super::collectors::{
+    Collector, CountCollector, FacetCollector, SegmentCollector,
+    SortByFieldCollector, TopCollector,
+};
+use super::doc_lookup::DocLookup;
+use super::filter::{FilterCache, Filters};
+use super::highlight::highlight_doc;
+use super::iterators::{IntersectingDocIdIterator, ItDocId};
 use super::model::DocCandidate;
-use super::scoring;
-use super::stop_words::STOP_WORDS;
+use super::normalizer::normalize_query;
+use super::options::SearchOptions;
+use super::planner::plan_query_for_segment;
+use super::pool::SearchPool;
+use super::query::{Lexer, Parser, QueryAst};
+use super::stats::GlobalStats;
+use crate::engines::nano::analysis::Analyzer;
 use crate::engines::nano::index::model::{Index, IndexSegment, SegmentDocId};
-use crate::model::doc::ExternalDocId;
+use crate::model::doc::{Doc, DocValueField, ExternalDocId};
+use crate::model::highlight::{Fragment, HighlightOptions};
+use crate::model::search::{Facet, SearchHit, SearchRequest, SearchResponse};
+use crate::utils::TreeNode;
 
 pub fn search(
     query: &str,
     index: &dyn Index,
     limit: u64,
 ) -> Result<Vec<ExternalDocId>> {
-    let mut candidates = Vec::new();
+    search_with_options(query, index, limit, &SearchOptions::default())
+}
+
+/// Searches for best documents with options.
+///
+/// Segments are searched one by one on the calling thread, regardless of
+/// `search_threads` option, since search threads are started once by
+/// [`NanoSearchEngine`](crate::engines::nano::engine::NanoSearchEngine).
+pub fn search_with_options(
+    query: &str,
+    index: &dyn Index,
+    limit: u64,
+    options: &SearchOptions,
+) -> Result<Vec<ExternalDocId>> {
+    let request = SearchRequest::builder().query(query).limit(limit).build();
+
+    Ok(search_with_request(
+        &request,
+        index,
+        &Analyzer::default(),
+        options,
+        &FilterCache::default(),
+        &SearchPool::default(),
+    )?
+    .get_docids())
+}
+
+/// Searches for requested page of best documents, or documents sorted by
+/// field if requested, and counts all matching documents and aggregates their
+/// facets if requested.
+///
+/// Words of query and filters are turned into terms with `analyzer`, which
+/// should be the same the index was built with.
+///
+/// Only documents matching all filters of the request are found. Documents
+/// matching each filter are cached in `filter_cache`, which should only be
+/// used with this index.
+///
+/// Segments are searched in parallel on threads of `pool`.
+pub fn search_with_request(
+    request: &SearchRequest,
+    index: &dyn Index,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+    filter_cache: &FilterCache,
+    pool: &SearchPool,
+) -> Result<SearchResponse> {
+    let query_ast = parse_query(&request.query)?;
+    let filters = Filters::parse(&request.filters, filter_cache)?;
+    let limit = usize::try_from(request.limit)?;
+    let offset = usize::try_from(request.offset)?;
+
+    let Some(query_ast) = normalize_query(&query_ast, analyzer) else {
+        // nothing left from the query, so it cannot match any document
+        return Ok(SearchResponse {
+            hits: Vec::new(),
+            total_count: request.count_total.then_some(0),
+            facets: request
+                .facets
+                .iter()
+                .map(|facet| Facet {
+                    field: facet.get_field().to_string(),
+                    buckets: Vec::new(),
+                })
+                .collect(),
+        });
+    };
+
+    let (hits, total_count, facets) = if let Some(sort_by) = &request.sort_by {
+        ensure!(
+            request.search_after.is_none(),
+            "search_after should not be combined with sort_by"
+        );
+
+        let collector = SortByFieldCollector::new(sort_by.clone(), limit)
+            .with_offset(offset);
+        let (candidates, total_count, facets) = search_with_aggregations(
+            &query_ast, &filters, index, collector, request, analyzer, options,
+            pool,
+        )?;
+        let hits = candidates
+            .into_iter()
+            .map(|candidate| SearchHit::new(candidate.id, candidate.relevance))
+            .collect();
+        (hits, total_count, facets)
+    } else {
+        let collector = TopCollector::new(limit)
+            .with_offset(offset)
+            .with_search_after(request.search_after.map(|hit| DocCandidate {
+                id: hit.docid,
+                relevance: hit.score,
+            }));
+        let (candidates, total_count, facets) = search_with_aggregations(
+            &query_ast, &filters, index, collector, request, analyzer, options,
+            pool,
+        )?;
+        let hits = candidates
+            .into_iter()
+            .map(|candidate| SearchHit::new(candidate.id, candidate.relevance))
+            .collect();
+        (hits, total_count, facets)
+    };
 
-    for segment in index.get_segments() {
-        let mut segment_candidates = search_segment(query, segment)?;
-        candidates.append(&mut segment_candidates);
+    Ok(SearchResponse {
+        hits,
+        total_count,
+        facets,
+    })
+}
+
+/// Searches with passed collector, and in the same iteration counts all
+/// matching documents and aggregates facets of them if requested.
+#[expect(clippy::too_many_arguments)]
+fn search_with_aggregations<'a, C>(
+    query_ast: &QueryAst,
+    filters: &Filters,
+    index: &'a dyn Index,
+    collector: C,
+    request: &SearchRequest,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+    pool: &SearchPool,
+) -> Result<(C::Output, Option<u64>, Vec<Facet>)>
+where
+    C: Collector<'a> + Sync,
+    C::SegmentOutput: Send,
+{
+    let facet_collectors: Vec<_> =
+        request.facets.iter().map(FacetCollector::new).collect();
+
+    // counting and facets disable skipping of documents not competitive for
+    // top ones, so they're only added when requested
+    match (request.count_total, facet_collectors.is_empty()) {
+        (false, true) => {
+            let output = search_with_collector(
+                query_ast, filters, index, &collector, analyzer, options, pool,
+            )?;
+            Ok((output, None, Vec::new()))
+        }
+        (true, true) => {
+            let (output, count) = search_with_collector(
+                query_ast,
+                filters,
+                index,
+                &(collector, CountCollector),
+                analyzer,
+                options,
+                pool,
+            )?;
+            Ok((output, Some(count as u64), Vec::new()))
+        }
+        (false, false) => {
+            let (output, facets) = search_with_collector(
+                query_ast,
+                filters,
+                index,
+                &(collector, facet_collectors),
+                analyzer,
+                options,
+                pool,
+            )?;
+            Ok((output, None, facets))
+        }
+        (true, false) => {
+            let ((output, count), facets) = search_with_collector(
+                query_ast,
+                filters,
+                index,
+                &((collector, CountCollector), facet_collectors),
+                analyzer,
+                options,
+                pool,
+            )?;
+            Ok((output, Some(count as u64), facets))
+        }
     }
+}
+
+/// Explains how the document is scored for the query: which query terms
+/// matched it, how scores of the terms were combined, and which statistics
+/// and parameters the scorer used for each of them.
+///
+/// Document which doesn't match the query is explained with a node without
+/// children.
+pub fn explain(
+    query: &str,
+    index: &dyn Index,
+    external_docid: ExternalDocId,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+    doc_lookup: &DocLookup,
+) -> Result<TreeNode> {
+    let query_ast = parse_query(query)?;
+
+    let (segment, docid) = doc_lookup
+        .find(index, external_docid)
+        .context("doc should exist in index")?;
+
+    let tree =
+        TreeNode::new("Document").with_attr("id", external_docid.to_string());
 
-    if candidates.len() > limit as usize {
-        candidates.select_nth_unstable(limit as usize);
-        candidates.truncate(limit as usize);
+    let Some(query_ast) = normalize_query(&query_ast, analyzer) else {
+        return Ok(tree.with_attr("matched", "false"));
+    };
+
+    let stats = GlobalStats::collect(&query_ast, index, analyzer, options)?;
+    let mut it =
+        plan_query_for_segment(&query_ast, segment, &stats, analyzer, options)?;
+
+    it.advance_to(docid)?;
+
+    if it.current_docid()? != ItDocId::Active(docid) {
+        return Ok(tree.with_attr("matched", "false"));
     }
 
-    candidates.sort();
+    Ok(tree
+        .with_attr("score", it.current_score()?.expect_val()?.to_string())
+        .with_child(it.explain_score()?))
+}
 
-    let docids = candidates.iter().map(|c| c.id).collect();
+/// Gets the document with original fields and typed values, as it was stored
+/// in the index.
+///
+/// Returns `None` if fields were not stored while building the index.
+pub fn get_document(
+    index: &dyn Index,
+    external_docid: ExternalDocId,
+    doc_lookup: &DocLookup,
+) -> Result<Option<Doc>> {
+    let (segment, docid) = doc_lookup
+        .find(index, external_docid)
+        .context("doc should exist in index")?;
 
-    Ok(docids)
+    get_segment_document(segment, docid)
 }
 
-fn search_segment(
-    query: &str,
+/// Gets the document of the segment by its segment docid, with original
+/// fields and typed values, as it was stored in the segment.
+///
+/// Returns `None` if fields were not stored while building the index.
+pub fn get_segment_document(
     segment: &dyn IndexSegment,
-) -> Result<Vec<DocCandidate<ExternalDocId>>> {
-    let words: Vec<&str> = query.split_whitespace().collect();
+    docid: SegmentDocId,
+) -> Result<Option<Doc>> {
+    let Some(fields) = segment.get_stored_fields(docid)? else {
+        return Ok(None);
+    };
 
-    let mut candidates: HashMap<SegmentDocId, DocCandidate<ExternalDocId>> =
-        HashMap::new();
+    let mut values = Vec::new();
+    for column in segment.get_doc_values_columns()? {
+        if let Some(value) = column.get_value(docid)? {
+            values.push(DocValueField::new(&column.info.name, value));
+        }
+    }
 
-    for word in words {
-        let term = crate::utils::normalize_word(word);
+    Ok(Some(Doc {
+        id: segment.get_stored_doc(docid)?.docid,
+        fields,
+        values,
+    }))
+}
 
-        if STOP_WORDS.contains(&term) {
-            continue;
-        }
+/// Picks fragments of the document best matching the query, with matching
+/// words highlighted, to show as a snippet in search results.
+pub fn highlight(
+    query: &str,
+    doc: &Doc,
+    analyzer: &Analyzer,
+    options: &HighlightOptions,
+) -> Result<Vec<Fragment>> {
+    highlight_doc(&parse_query(query)?, doc, analyzer, options)
+}
+
+fn parse_query(query: &str) -> Result<QueryAst<'_>> {
+    Parser::new(Lexer::new(query))
+        .parse()
+        .context("query should be parsed")
+}
+
+/// Searches all segments of the index on threads of the pool and merges
+/// per-segment results with passed collector.
+fn search_with_collector<'a, C>(
+    query_ast: &QueryAst,
+    filters: &Filters,
+    index: &'a dyn Index,
+    collector: &C,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+    pool: &SearchPool,
+) -> Result<C::Output>
+where
+    C: Collector<'a> + Sync,
+    C::SegmentOutput: Send,
+{
+    let stats = GlobalStats::collect(query_ast, index, analyzer, options)?;
 
-        if let Some(postings) = segment.get_doc_postings_for_term(&term)? {
-            for posting in postings.iterator {
-                let posting = posting?;
-                let relevance = scoring::calc_bm25(
-                    scoring::ScoringParams {
-                        doc_term_freq: posting.term_freq,
-                        doc_total_terms_count: *segment
-                            .get_doc_terms_count(posting.docid)?,
-                        docs_with_term_count: postings.count as u64,
-                        docs_total_count: segment
-                            .get_stats()
-                            .indexed_docs_count,
-                    },
-                    segment.get_stats().terms_count_per_doc_avg,
-                );
-
-                candidates
-                    .entry(posting.docid)
-                    .and_modify(|c| c.relevance += relevance)
-                    .or_insert(DocCandidate {
-                        id: segment
-                            .get_stored_doc(posting.docid)
-                            .context("stored doc should exist")?
-                            .docid,
-                        relevance,
-                    });
+    let outputs = pool.map(&index.get_segments(), |segment| {
+        search_segment(
+            query_ast, filters, *segment, &stats, collector, analyzer, options,
+        )
+    })?;
+
+    collector.merge_segment_outputs(outputs)
+}
+
+/// Drives document-at-a-time iteration over matching documents of a segment
+/// and passes each of them to the segment collector.
+fn search_segment<'a, C: Collector<'a>>(
+    query_ast: &QueryAst,
+    filters: &Filters,
+    segment: &'a dyn IndexSegment,
+    stats: &GlobalStats,
+    collector: &C,
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+) -> Result<C::SegmentOutput> {
+    let mut it =
+        plan_query_for_segment(query_ast, segment, stats, analyzer, options)?;
+
+    // filters score nothing, so intersecting with them keeps scores of query
+    let filter_its = filters.plan_for_segment(segment, stats, analyzer)?;
+    if !filter_its.is_empty() {
+        let mut inputs = vec![it];
+        inputs.extend(filter_its);
+        it = Box::new(IntersectingDocIdIterator::new(inputs));
+    }
+    debug!("segment query plan:\n{}", it.explain());
+
+    let mut segment_collector = collector.create_segment_collector(segment)?;
+
+    let requires_score = segment_collector.requires_score();
+
+    it.advance()?;
+
+    while let ItDocId::Active(docid) = it.current_docid()? {
+        if requires_score {
+            let score = it.current_score()?.expect_val()?;
+            segment_collector.add_docid_and_score(docid, score)?;
+
+            // let iterator skip documents, which cannot affect the output
+            if options.pruning
+                && let Some(score) =
+                    segment_collector.get_min_competitive_score()
+            {
+                it.set_min_competitive_score(score);
             }
+        } else {
+            segment_collector.add_docid(docid)?;
         }
+
+        it.advance()?;
     }
 
-    Ok(candidates.values().cloned().collect())
+    segment_collector.extract_output()
 }
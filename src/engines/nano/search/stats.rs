@@ -0,0 +1,483 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+
+use super::expansion::{ExpandedTerm, TermExpansion};
+use super::options::SearchOptions;
+use super::query::QueryAst;
+use super::scoring::{Scorer, TermStats};
+use crate::engines::nano::analysis::Analyzer;
+use crate::engines::nano::index::model::{
+    Index, IndexSegment, Term, make_field_term_key,
+};
+
+/// Index-wide statistics required for scoring documents matching a query.
+///
+/// Segments maintain only local statistics, so on each search they are
+/// aggregated across all segments for query terms on the fly. Then all
+/// segments score their documents using these global numbers, which makes
+/// scores independent of how documents are distributed across segments.
+///
+/// Lucene and Tantivy use the same approach: `IndexSearcher` provides
+/// `CollectionStatistics`/`TermStatistics` in Lucene, and `Searcher` provides
+/// doc frequencies for `Bm25Weight` in Tantivy.
+#[derive(Debug)]
+pub struct GlobalStats {
+    /// Scoring model, that scores documents using these statistics
+    pub scorer: Arc<dyn Scorer>,
+
+    /// Number of documents indexed in all segments
+    pub indexed_docs_count: u64,
+
+    /// Names of fields indexed in any segment, in order they first appear.
+    /// Query words without field are searched in all of them
+    pub fields: Vec<String>,
+
+    /// Number of terms in the field of all documents across all segments
+    terms_count_totals: HashMap<String, u64>,
+
+    /// Average number of terms in the field per document across all segments
+    terms_count_per_doc_avg: HashMap<String, f64>,
+
+    /// Number of documents containing the term in the field in all segments,
+    /// for each term of the query, keyed with [`make_field_term_key`]
+    docs_with_term_counts: HashMap<String, u64>,
+
+    /// Number of occurrences of the term in the field in all segments, for
+    /// each term of the query
+    term_freq_totals: HashMap<String, u64>,
+
+    /// Terms of all segments, that each prefix, wildcard or fuzzy word of the
+    /// query expands into in the field. Expansion is capped once for the whole
+    /// index, so each segment searches the same terms
+    expanded_terms: HashMap<(String, TermExpansion), Vec<ExpandedTerm>>,
+}
+
+impl Default for GlobalStats {
+    fn default() -> Self {
+        Self {
+            scorer: SearchOptions::default().scorer,
+            indexed_docs_count: 0,
+            fields: Vec::new(),
+            terms_count_totals: HashMap::new(),
+            terms_count_per_doc_avg: HashMap::new(),
+            docs_with_term_counts: HashMap::new(),
+            term_freq_totals: HashMap::new(),
+            expanded_terms: HashMap::new(),
+        }
+    }
+}
+
+impl GlobalStats {
+    pub fn collect(
+        query_ast: &QueryAst,
+        index: &dyn Index,
+        analyzer: &Analyzer,
+        options: &SearchOptions,
+    ) -> Result<Self> {
+        let segments = index.get_segments();
+
+        let mut stats = Self {
+            scorer: options.scorer.clone(),
+            ..Self::default()
+        };
+
+        for segment in &segments {
+            let segment_stats = segment.get_stats();
+
+            stats.indexed_docs_count += segment_stats.indexed_docs_count;
+
+            for field in &segment_stats.fields {
+                if !stats.fields.contains(&field.name) {
+                    stats.fields.push(field.name.clone());
+                }
+                *stats
+                    .terms_count_totals
+                    .entry(field.name.clone())
+                    .or_default() += field.terms_count_total;
+            }
+        }
+
+        // derive averages from integer totals, instead of averaging segment
+        // averages, so they exactly match averages of single-segment index
+        stats.terms_count_per_doc_avg = stats
+            .terms_count_totals
+            .iter()
+            .map(|(field, total)| {
+                (
+                    field.clone(),
+                    *total as f64 / stats.indexed_docs_count as f64,
+                )
+            })
+            .collect();
+
+        stats.expand_terms(query_ast, &segments, analyzer, options)?;
+
+        let fields: Vec<&str> =
+            stats.fields.iter().map(String::as_str).collect();
+
+        let mut terms = HashSet::new();
+        let mut expansions = Vec::new();
+        collect_scoring_terms(
+            query_ast,
+            &fields,
+            analyzer,
+            options,
+            &mut terms,
+            &mut expansions,
+        )?;
+
+        for (field, expansion) in &expansions {
+            let expanded_terms = stats.get_expanded_terms(field, expansion)?;
+            terms.extend(
+                expanded_terms
+                    .iter()
+                    .map(|t| (field.clone(), t.term.clone())),
+            );
+        }
+
+        for segment in &segments {
+            for (field, term) in &terms {
+                let Some(postings) =
+                    segment.get_doc_postings_for_term(field, term)?
+                else {
+                    continue;
+                };
+
+                let key = make_field_term_key(field, term);
+
+                *stats.docs_with_term_counts.entry(key.clone()).or_default() +=
+                    postings.count as u64;
+                *stats.term_freq_totals.entry(key).or_default() +=
+                    postings.term_freq_total;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Gets statistics of the term in the field for scoring.
+    pub fn get_term_stats(&self, field: &str, term: &str) -> TermStats {
+        let key = make_field_term_key(field, term);
+
+        TermStats {
+            docs_with_term_count: self
+                .docs_with_term_counts
+                .get(&key)
+                .copied()
+                .unwrap_or_default(),
+            term_freq_total: self
+                .term_freq_totals
+                .get(&key)
+                .copied()
+                .unwrap_or_default(),
+            docs_total_count: self.indexed_docs_count,
+            terms_count_total: self
+                .terms_count_totals
+                .get(field)
+                .copied()
+                .unwrap_or_default(),
+            terms_count_per_doc_avg: self.get_terms_count_per_doc_avg(field),
+        }
+    }
+
+    /// Gets terms of all segments, that query node expands into in the field.
+    pub fn get_expanded_terms(
+        &self,
+        field: &str,
+        expansion: &TermExpansion,
+    ) -> Result<&[ExpandedTerm]> {
+        self.expanded_terms
+            .get(&(field.to_string(), expansion.clone()))
+            .map(Vec::as_slice)
+            .context("expanded terms should be collected")
+    }
+
+    /// Expands prefixes, wildcards and fuzzy words of the query in fields of
+    /// the index they are searched in, including ones under `NOT`.
+    pub(super) fn expand_terms(
+        &mut self,
+        query_ast: &QueryAst,
+        segments: &[&dyn IndexSegment],
+        analyzer: &Analyzer,
+        options: &SearchOptions,
+    ) -> Result<()> {
+        let fields: Vec<&str> =
+            self.fields.iter().map(String::as_str).collect();
+
+        let mut expansions = Vec::new();
+        collect_expansions(query_ast, &fields, analyzer, &mut expansions)?;
+
+        for key in expansions {
+            if let Entry::Vacant(entry) = self.expanded_terms.entry(key) {
+                let (field, expansion) = entry.key();
+                let terms = expansion.expand(
+                    segments,
+                    field,
+                    options.max_expansions,
+                )?;
+                entry.insert(terms);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_terms_count_per_doc_avg(&self, field: &str) -> f64 {
+        self.terms_count_per_doc_avg
+            .get(field)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Collects terms and term expansions, that contribute to the score of matching
+/// documents, along with fields they are searched in.
+///
+/// Terms under `NOT` are skipped, since excluding has no impact on scoring.
+/// Expansions are skipped if expanded terms are not scored.
+fn collect_scoring_terms(
+    query_ast: &QueryAst,
+    fields: &[&str],
+    analyzer: &Analyzer,
+    options: &SearchOptions,
+    terms: &mut HashSet<(String, Term)>,
+    expansions: &mut Vec<(String, TermExpansion)>,
+) -> Result<()> {
+    let mut add_term = |word: &str| -> Result<()> {
+        let term = analyzer.analyze_word(word)?;
+        for field in fields {
+            terms.insert(((*field).to_string(), term.clone()));
+        }
+        Ok(())
+    };
+
+    match query_ast {
+        QueryAst::Word(word) => add_term(word)?,
+        QueryAst::Phrase(words) | QueryAst::Proximity { words, .. } => {
+            for word in words {
+                add_term(word)?;
+            }
+        }
+        QueryAst::Prefix(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Fuzzy { .. } => {
+            for field in fields {
+                if let Some(expansion) =
+                    TermExpansion::from_query_ast(query_ast, analyzer)?
+                    && expansion.is_scored(options)
+                {
+                    expansions.push(((*field).to_string(), expansion));
+                }
+            }
+        }
+        QueryAst::Field { field, query } => {
+            collect_scoring_terms(
+                query,
+                &[field],
+                analyzer,
+                options,
+                terms,
+                expansions,
+            )?;
+        }
+        QueryAst::And(operands) | QueryAst::Or(operands) => {
+            for operand in operands {
+                collect_scoring_terms(
+                    operand, fields, analyzer, options, terms, expansions,
+                )?;
+            }
+        }
+        QueryAst::Range { .. } | QueryAst::Not(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Collects all prefixes, wildcards and fuzzy words of the query along with
+/// fields they are searched in.
+fn collect_expansions(
+    query_ast: &QueryAst,
+    fields: &[&str],
+    analyzer: &Analyzer,
+    expansions: &mut Vec<(String, TermExpansion)>,
+) -> Result<()> {
+    match query_ast {
+        QueryAst::Prefix(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Fuzzy { .. } => {
+            if let Some(expansion) =
+                TermExpansion::from_query_ast(query_ast, analyzer)?
+            {
+                for field in fields {
+                    expansions.push(((*field).to_string(), expansion.clone()));
+                }
+            }
+        }
+        QueryAst::Field { field, query } => {
+            collect_expansions(query, &[field], analyzer, expansions)?;
+        }
+        QueryAst::And(operands) | QueryAst::Or(operands) => {
+            for operand in operands {
+                collect_expansions(operand, fields, analyzer, expansions)?;
+            }
+        }
+        QueryAst::Not(operand) => {
+            collect_expansions(operand, fields, analyzer, expansions)?;
+        }
+        QueryAst::Word(_)
+        | QueryAst::Phrase(_)
+        | QueryAst::Proximity { .. }
+        | QueryAst::Range { .. } => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::engines::nano::index::model::IndexMedium;
+    use crate::engines::nano::index::{DiskIndexOptions, build_index};
+    use crate::engines::nano::search::options::ExpansionScoring;
+    use crate::engines::nano::search::query::{Lexer, Parser};
+    use crate::utils::test_docs::create_cat_mouse_docs_iterator;
+
+    fn collect_stats(query: &str, index: &dyn Index) -> Result<GlobalStats> {
+        collect_stats_with_options(query, index, &SearchOptions::default())
+    }
+
+    fn collect_stats_with_options(
+        query: &str,
+        index: &dyn Index,
+        options: &SearchOptions,
+    ) -> Result<GlobalStats> {
+        let query_ast = Parser::new(Lexer::new(query)).parse()?;
+        GlobalStats::collect(&query_ast, index, &Analyzer::default(), options)
+    }
+
+    #[test]
+    fn test_collect_from_multiple_segments() -> Result<()> {
+        let dir = TempDir::new()?;
+        let medium = IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .max_segment_docs(4)
+                .build(),
+        );
+        let index =
+            build_index(&medium, &mut create_cat_mouse_docs_iterator())?;
+        assert_eq!(index.get_segments().len(), 2);
+
+        let stats = collect_stats(
+            r#"Cat OR mouse AND NOT dog OR "mouse cat""#,
+            index.as_ref(),
+        )?;
+
+        assert_eq!(stats.indexed_docs_count, 7);
+        assert_eq!(stats.fields, &["text"]);
+        assert_eq!(stats.get_terms_count_per_doc_avg("text"), 12.0 / 7.0);
+        assert_eq!(stats.get_term_stats("text", "cat").docs_with_term_count, 4);
+        assert_eq!(
+            stats.get_term_stats("text", "mouse").docs_with_term_count,
+            4
+        );
+
+        // excluded terms are not collected
+        assert_eq!(stats.get_term_stats("text", "dog").docs_with_term_count, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_unknown_term() -> Result<()> {
+        let index = build_index(
+            &IndexMedium::Memory,
+            &mut create_cat_mouse_docs_iterator(),
+        )?;
+
+        let stats = collect_stats("unknown", index.as_ref())?;
+
+        assert_eq!(
+            stats.get_term_stats("text", "unknown").docs_with_term_count,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_expanded_terms() -> Result<()> {
+        let dir = TempDir::new()?;
+        let medium = IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .max_segment_docs(4)
+                .build(),
+        );
+        let index =
+            build_index(&medium, &mut create_cat_mouse_docs_iterator())?;
+
+        // constant scoring does not need statistics of expanded terms
+        let stats = collect_stats("mo*", index.as_ref())?;
+        assert_eq!(
+            stats.get_term_stats("text", "mouse").docs_with_term_count,
+            0
+        );
+
+        let options = SearchOptions::builder()
+            .expansion_scoring(ExpansionScoring::Summed)
+            .build();
+        let stats =
+            collect_stats_with_options("mo* d?g", index.as_ref(), &options)?;
+        assert_eq!(
+            stats.get_term_stats("text", "mouse").docs_with_term_count,
+            4
+        );
+        assert_eq!(stats.get_term_stats("text", "dog").docs_with_term_count, 3);
+
+        // fuzzy terms are always scored
+        let stats = collect_stats("mose~1", index.as_ref())?;
+        assert_eq!(
+            stats.get_term_stats("text", "mouse").docs_with_term_count,
+            4
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_term_freq_total() -> Result<()> {
+        let dir = TempDir::new()?;
+        let medium = IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .max_segment_docs(4)
+                .build(),
+        );
+        let index =
+            build_index(&medium, &mut create_cat_mouse_docs_iterator())?;
+
+        let stats = collect_stats("cat", index.as_ref())?;
+        assert_eq!(
+            stats.get_term_stats("text", "cat"),
+            TermStats {
+                docs_with_term_count: 4,
+                term_freq_total: 5,
+                docs_total_count: 7,
+                terms_count_total: 12,
+                terms_count_per_doc_avg: 12.0 / 7.0,
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -1,10 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use itertools::Itertools;
 use tempfile::TempDir;
 
+use crate::engines::nano::analysis::{
+    Analyzer, LowercaseFilter, StopWordsFilter, WhitespaceTokenizer,
+};
 use crate::engines::nano::engine::NanoSearchEngine;
+use crate::engines::nano::index::build_index;
+use crate::engines::nano::index::doc_values::DocValueType;
+use crate::engines::nano::index::model::IndexMedium;
+use crate::engines::nano::search::{
+    ExpansionScoring, FieldScoring, SearchOptions, parse_scorer,
+    search_with_options,
+};
+use crate::model::doc::{Doc, DocField, DocValue, DocValueField};
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::highlight::{Fragment, HighlightOptions};
+use crate::model::search::{
+    Facet, FacetBucket, FacetRange, FacetRequest, HistogramInterval, SearchHit,
+    SearchRequest, SortBy,
+};
 use crate::utils::test_docs::create_cat_mouse_docs_iterator;
 use crate::utils::test_docs::docs::*;
+use crate::utils::test_docs_iterator::TestDocsIterator;
 
 #[test]
 fn test_search_fails_on_uninitialized_index() -> Result<()> {
@@ -155,3 +173,1056 @@ fn test_search_with_multiple_words_query() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_search_with_boolean_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute & assert
+    assert_eq!(
+        engine.search("cat AND mouse", 10)?,
+        vec![
+            CAT_MOUSE_CAT.id, // 1st - contains both terms (one twice)
+            CAT_MOUSE.id,     // 2nd - contains both terms
+        ]
+    );
+    assert_eq!(
+        engine.search("cat AND NOT mouse", 10)?,
+        vec![CAT.id, CAT_DOG.id]
+    );
+    assert_eq!(
+        engine.search("(cat OR dog) AND NOT mouse", 10)?,
+        vec![
+            CAT_DOG.id, // 1st - contains both terms
+            DOG.id,     // 2nd - contains rarer term
+            CAT.id,     // 3rd - contains more frequent term
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_search_with_phrase_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute & assert
+    assert_eq!(
+        engine.search(r#""cat mouse""#, 10)?,
+        vec![
+            CAT_MOUSE.id,     // 1st - contains phrase in shorter doc
+            CAT_MOUSE_CAT.id, // 2nd - contains phrase in longer doc
+        ]
+    );
+    assert_eq!(engine.search(r#""mouse cat""#, 10)?, vec![CAT_MOUSE_CAT.id]);
+    assert!(engine.search(r#""dog cat""#, 10)?.is_empty());
+    assert_eq!(
+        engine.search(r#""cat dog" OR mouse"#, 10)?,
+        vec![
+            CAT_DOG.id,       // 1st - contains phrase with rarer term
+            MOUSE.id,         // 2nd - full match of single term
+            DOG_MOUSE.id,     // 3rd - contains single term
+            CAT_MOUSE.id,     // 4th - contains single term
+            CAT_MOUSE_CAT.id, // 5th - contains single term in longer doc
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_search_with_proximity_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute & assert
+    assert_eq!(
+        engine.search(r#""mouse cat"~0"#, 10)?,
+        vec![
+            CAT_MOUSE_CAT.id, // 1st - contains words next to each other twice
+            CAT_MOUSE.id,     // 2nd - contains words next to each other once
+        ]
+    );
+    assert_eq!(
+        engine.search(r#""cat mouse cat"~0"#, 10)?,
+        vec![CAT_MOUSE_CAT.id]
+    );
+    assert!(engine.search(r#""dog cat mouse"~5"#, 10)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_with_prefix_and_wildcard_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute & assert
+    assert_eq!(
+        engine.search("mou*", 10)?,
+        vec![
+            // all matching docs get the same score, so go in index order
+            MOUSE.id,
+            DOG_MOUSE.id,
+            CAT_MOUSE.id,
+            CAT_MOUSE_CAT.id,
+        ]
+    );
+    assert_eq!(engine.search("d?g AND c*t", 10)?, vec![CAT_DOG.id]);
+    assert!(engine.search("mo?", 10)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_with_prefix_query_and_summed_scoring() -> Result<()> {
+    // setup
+    let index = build_index(
+        &IndexMedium::Memory,
+        &mut create_cat_mouse_docs_iterator(),
+    )?;
+    let options = SearchOptions::builder()
+        .expansion_scoring(ExpansionScoring::Summed)
+        .build();
+
+    // execute
+    let docids = search_with_options("mou*", index.as_ref(), 10, &options)?;
+
+    // assert
+    assert_eq!(
+        docids,
+        vec![
+            MOUSE.id,         // 1st - full match of single term
+            DOG_MOUSE.id,     // 2nd - shorter doc
+            CAT_MOUSE.id,     // 3rd - shorter doc, but later in index
+            CAT_MOUSE_CAT.id, // 4th - longer doc
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_search_with_fuzzy_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute & assert
+    assert_eq!(
+        engine.search("mose~1", 10)?,
+        vec![
+            MOUSE.id,         // 1st - full match of single term
+            DOG_MOUSE.id,     // 2nd - shorter doc
+            CAT_MOUSE.id,     // 3rd - shorter doc, but later in index
+            CAT_MOUSE_CAT.id, // 4th - longer doc
+        ]
+    );
+
+    // transposition is a single edit
+    assert_eq!(engine.search("dgo~1 AND cta~1", 10)?, vec![CAT_DOG.id]);
+    assert!(engine.search("mose~0", 10)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_with_field_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields([
+        [("title", "cat"), ("text", "dog mouse")],
+        [("title", "dog"), ("text", "cat")],
+        [("title", "mouse"), ("text", "cat dog")],
+    ]);
+    engine.index_docs(&mut docs)?;
+
+    // execute & assert
+    assert_eq!(engine.search("mouse", 10)?, vec![2, 0]);
+    assert_eq!(engine.search("title:cat", 10)?, vec![0]);
+    assert_eq!(
+        engine.search("text:cat", 10)?,
+        vec![
+            1, // 1st - shorter text
+            2, // 2nd - longer text
+        ]
+    );
+    assert_eq!(engine.search("title:(dog OR mouse)", 10)?, vec![1, 2]);
+    assert_eq!(engine.search("title:cat AND text:dog", 10)?, vec![0]);
+    assert_eq!(engine.search(r#"text:"cat dog""#, 10)?, vec![2]);
+    assert_eq!(engine.search("cat AND NOT title:cat", 10)?, vec![1, 2]);
+    assert!(engine.search("author:cat", 10)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_search_with_field_weights() -> Result<()> {
+    // setup
+    let mut docs = TestDocsIterator::from_enumerated_fields([
+        [("title", "cat"), ("text", "dog")],
+        [("title", "dog"), ("text", "cat")],
+    ]);
+    let index = build_index(&IndexMedium::Memory, &mut docs)?;
+
+    for field_scoring in [FieldScoring::Combined, FieldScoring::Summed] {
+        let search = |field_weights: &[(&str, f64)]| {
+            let options = SearchOptions::builder()
+                .field_scoring(field_scoring)
+                .field_weights(
+                    field_weights
+                        .iter()
+                        .map(|(field, weight)| (field.to_string(), *weight))
+                        .collect(),
+                )
+                .build();
+            search_with_options("cat", index.as_ref(), 10, &options)
+        };
+
+        // execute & assert
+        assert_eq!(search(&[])?, vec![0, 1]);
+        assert_eq!(search(&[("text", 2.0)])?, vec![1, 0]);
+        assert_eq!(search(&[("title", 2.0)])?, vec![0, 1]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_search_with_scorer() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["cat", "cat cat dog dog dog dog dog dog"].map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    let mut search = |scorer: &str| {
+        engine.set_search_options(
+            SearchOptions::builder()
+                .scorer(parse_scorer(scorer)?)
+                .build(),
+        )?;
+        engine.search("cat", 10)
+    };
+
+    // execute & assert
+    for scorer in ["bm25", "bm25plus", "tfidf", "dirichlet", "dfr"] {
+        let mut docids = search(scorer)?;
+        docids.sort_unstable();
+        assert_eq!(docids, vec![0, 1], "{scorer}");
+    }
+
+    // without length normalization more occurrences win, with full
+    // normalization shorter document wins
+    assert_eq!(search("bm25:b=0")?, vec![1, 0]);
+    assert_eq!(search("bm25:b=1")?, vec![0, 1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_stop_words_only_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute
+    let docids = engine.search("the AND of", 10)?;
+
+    // assert
+    assert_eq!(docids.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_search_with_invalid_query() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.index_docs(&mut create_cat_mouse_docs_iterator())?;
+
+    // execute
+    let res = engine.search("cat AND (mouse", 10);
+
+    // assert
+    assert!(res.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["cat dog", "cat cat mouse", "mouse"].map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    let explain = |docid| {
+        engine
+            .explain("cat dog", docid)?
+            .context("nano engine should explain")
+    };
+
+    // execute & assert
+    assert_eq!(
+        explain(0)?.to_string(),
+        "\
+Document (id = 0, score = 1.4508328822574619)
+└── Union (score = 1.4508328822574619, combine = sum of matching inputs)
+    ├── Term (field = text, term = cat, score = 0.47000362924573563)
+    │   └── BM25 (score = 0.47000362924573563, k1 = 1.2, b = 0.75)
+    │       ├── idf (value = 0.47000362924573563, df = 2, N = 3)
+    │       └── tf_norm (value = 1, tf = 1, dl = 2, avgdl = 2, length_norm = 1)
+    └── Term (field = text, term = dog, score = 0.9808292530117263)
+        └── BM25 (score = 0.9808292530117263, k1 = 1.2, b = 0.75)
+            ├── idf (value = 0.9808292530117263, df = 1, N = 3)
+            └── tf_norm (value = 1, tf = 1, dl = 2, avgdl = 2, length_norm = 1)
+"
+    );
+
+    // only matching inputs are explained
+    let explanation = explain(1)?.to_string();
+    assert!(explanation.contains("term = cat"));
+    assert!(!explanation.contains("term = dog"));
+
+    assert_eq!(
+        explain(2)?.to_string(),
+        "Document (id = 2, matched = false)\n"
+    );
+    assert!(explain(3).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_get_document() -> Result<()> {
+    // setup
+    let dir = TempDir::new()?;
+    let mut engine = NanoSearchEngine::create_on_disk(
+        CreateOnDiskOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .build(),
+    )?;
+    // last doc has no values
+    let dates = [Some(0), Some(1_700_000_000), None];
+    let docs = ["cat dog", "cat cat mouse", "mouse"]
+        .into_iter()
+        .enumerate()
+        .map(|(id, text)| Doc {
+            id: id as u64,
+            fields: vec![
+                DocField::new("title", format!("doc {id}")),
+                DocField::new("text", text),
+            ],
+            values: dates[id]
+                .map(|date| DocValueField::new("date", DocValue::Date(date)))
+                .into_iter()
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    engine.index_docs(&mut docs.clone().into_iter().map(Ok))?;
+
+    let engine = NanoSearchEngine::open_from_disk(dir.path())?;
+
+    // execute & assert
+    for doc in &docs {
+        assert_eq!(engine.get_document(doc.id)?.as_ref(), Some(doc));
+    }
+    assert!(engine.get_document(3).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_highlight() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["The cat chased a mouse.", "Dogs sleep."].map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    let options = HighlightOptions::default();
+    let highlight = |docid| {
+        let doc = engine
+            .get_document(docid)?
+            .context("nano engine should store docs")?;
+        engine
+            .highlight("cat OR mouse", &doc, &options)?
+            .context("nano engine should highlight")
+    };
+
+    // execute & assert
+    assert_eq!(
+        highlight(0)?,
+        &[Fragment {
+            field: "text".to_string(),
+            text: "The cat chased a mouse.".to_string(),
+            highlights: vec![4..7, 17..22],
+            score: 2.0,
+        }]
+    );
+    assert!(highlight(1)?.is_empty());
+    assert!(highlight(2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_punctuation_and_digits() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        [
+            "State-of-the-art retrieval",
+            "Spread of COVID-19 in 2020.",
+            "Don't panic",
+            "Patents of the U.S.",
+        ]
+        .map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    // execute & assert
+    // hyphenated words are searched as phrases of their parts
+    assert_eq!(engine.search("state-of-the-art", 10)?, vec![0]);
+    assert_eq!(engine.search("\"state of the art\"", 10)?, vec![0]);
+    assert_eq!(engine.search("covid-19", 10)?, vec![1]);
+    assert!(engine.search("covid-20", 10)?.is_empty());
+
+    // numbers are indexed
+    assert_eq!(engine.search("2020", 10)?, vec![1]);
+
+    // apostrophes are removed, dots between letters are kept
+    assert_eq!(engine.search("don't", 10)?, vec![2]);
+    assert_eq!(engine.search("dont", 10)?, vec![2]);
+    assert_eq!(engine.search("(u.s.)", 10)?, vec![3]);
+
+    // patterns are split into words same way, so they match the same terms
+    assert_eq!(engine.search("U.S*", 10)?, vec![3]);
+    assert_eq!(engine.search("Don't*", 10)?, vec![2]);
+    assert!(engine.search("covid-1*", 10).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_analyzer() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.set_analyzer(
+        Analyzer::new(WhitespaceTokenizer)
+            .with_filter(LowercaseFilter)
+            .with_filter(StopWordsFilter::new(["cat"])),
+    );
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["The cat chased a mouse", "Cat,", "cat"].map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    // execute & assert
+    // punctuation is kept, so "cat," is indexed, while "cat" is removed
+    assert_eq!(engine.search("cat,", 10)?, vec![1]);
+    assert!(engine.search("cat", 10)?.is_empty());
+
+    // removed words do not take positions, so words around them are adjacent
+    assert_eq!(engine.search("\"the chased\"", 10)?, vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_patterns_and_custom_analyzer() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    engine.set_analyzer(Analyzer::new(WhitespaceTokenizer));
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["Cat", "cat"].map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    // execute & assert
+    // analyzer doesn't lowercase, so patterns keep case too
+    assert_eq!(engine.search("Ca*", 10)?, vec![0]);
+    assert_eq!(engine.search("c?t", 10)?, vec![1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_request() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["cat", "cat dog", "dog", "cat", "cat cat mouse"]
+            .map(|text| [("text", text)]),
+    );
+    engine.index_docs(&mut docs)?;
+
+    let search = |request: SearchRequest| engine.search_with_request(&request);
+
+    // execute & assert
+    let response = search(
+        SearchRequest::builder()
+            .query("cat")
+            .limit(2)
+            .count_total(true)
+            .build(),
+    )?;
+    assert_eq!(response.get_docids(), vec![0, 3]);
+    assert_eq!(response.total_count, Some(4));
+
+    // hits with equal scores are ranked by docid
+    let score = response.hits[0].score;
+    assert_eq!(
+        response.hits,
+        vec![SearchHit::new(0, score), SearchHit::new(3, score)]
+    );
+
+    // count is not returned unless requested
+    let response =
+        search(SearchRequest::builder().query("cat").limit(2).build())?;
+    assert_eq!(response.total_count, None);
+
+    // paging with offset and with cursor gives the same pages
+    let all_hits =
+        search(SearchRequest::builder().query("cat").limit(10).build())?.hits;
+    assert_eq!(all_hits.len(), 4);
+
+    let mut search_after = None;
+    for page in 0..3 {
+        let page_by_offset = search(
+            SearchRequest::builder()
+                .query("cat")
+                .limit(2)
+                .offset(page * 2)
+                .build(),
+        )?;
+        let page_by_cursor = search(
+            SearchRequest::builder()
+                .query("cat")
+                .limit(2)
+                .maybe_search_after(search_after)
+                .build(),
+        )?;
+
+        let expected: Vec<_> = all_hits
+            .iter()
+            .copied()
+            .skip(page as usize * 2)
+            .take(2)
+            .collect();
+        assert_eq!(page_by_offset.hits, expected);
+        assert_eq!(page_by_cursor.hits, expected);
+
+        search_after = page_by_cursor.hits.last().copied();
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_search_ties_ranked_by_docid_in_any_indexing_order() -> Result<()> {
+    // setup
+    // docs are indexed in descending order of their IDs, and all get the same
+    // score, so skipping non-competitive docs should not drop ties
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = (0..6).rev().map(|id| {
+        Ok(Doc {
+            id,
+            fields: vec![DocField::new("text", "cat dog")],
+            values: Vec::new(),
+        })
+    });
+    engine.index_docs(&mut docs)?;
+
+    // execute & assert
+    // counting all docs disables skipping, so both ways should give same pages
+    for count_total in [false, true] {
+        let mut search_after = None;
+        for page in 0..3 {
+            let response = engine.search_with_request(
+                &SearchRequest::builder()
+                    .query("cat dog")
+                    .limit(2)
+                    .count_total(count_total)
+                    .maybe_search_after(search_after)
+                    .build(),
+            )?;
+            assert_eq!(response.get_docids(), vec![page * 2, page * 2 + 1]);
+
+            let response = engine.search_with_request(
+                &SearchRequest::builder()
+                    .query("cat dog")
+                    .limit(2)
+                    .offset(page * 2)
+                    .count_total(count_total)
+                    .build(),
+            )?;
+            assert_eq!(response.get_docids(), vec![page * 2, page * 2 + 1]);
+
+            search_after = response.hits.last().copied();
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_search_sorted_by_field() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["cat", "cat dog", "dog", "cat cat mouse", "cat"]
+            .map(|text| [("text", text)]),
+    )
+    .with_values([Some(30), None, Some(10), Some(10), Some(20)].map(|count| {
+        count
+            .map(|count| DocValueField::new("count", DocValue::U64(count)))
+            .into_iter()
+            .collect()
+    }));
+    engine.index_docs(&mut docs)?;
+
+    let search = |sort_by: &str, offset| -> Result<Vec<u64>> {
+        Ok(engine
+            .search_with_request(
+                &SearchRequest::builder()
+                    .query("cat")
+                    .limit(2)
+                    .offset(offset)
+                    .sort_by(sort_by.parse()?)
+                    .build(),
+            )?
+            .get_docids())
+    };
+
+    // execute & assert
+    // docs without value go last in both orders
+    assert_eq!(search("count", 0)?, vec![3, 4]);
+    assert_eq!(search("count", 2)?, vec![0, 1]);
+    assert_eq!(search("count:desc", 0)?, vec![0, 4]);
+    assert_eq!(search("count:desc", 2)?, vec![3, 1]);
+    assert_eq!(search("unknown", 0)?, vec![0, 1]);
+
+    // hits keep their scores, and all matching docs are counted
+    let response = engine.search_with_request(
+        &SearchRequest::builder()
+            .query("cat")
+            .sort_by("count:desc".parse()?)
+            .count_total(true)
+            .build(),
+    )?;
+    assert_eq!(response.total_count, Some(4));
+
+    let mut relevance_hits = engine
+        .search_with_request(&SearchRequest::builder().query("cat").build())?
+        .hits;
+    relevance_hits.sort_by_key(|hit| hit.docid);
+    let mut sorted_hits = response.hits;
+    sorted_hits.sort_by_key(|hit| hit.docid);
+    assert_eq!(sorted_hits, relevance_hits);
+
+    // cursor holds score, so it cannot be used for sorted pages
+    assert!(
+        engine
+            .search_with_request(
+                &SearchRequest::builder()
+                    .query("cat")
+                    .sort_by("count".parse()?)
+                    .search_after(SearchHit::new(3, 1.0))
+                    .build(),
+            )
+            .is_err()
+    );
+    assert!("count:up".parse::<SortBy>().is_err());
+    assert!(":desc".parse::<SortBy>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_range() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let values = [
+        Some("2019-06-01"),
+        Some("2020-03-01"),
+        Some("2020-06-01"),
+        Some("2021-01-01"),
+        None,
+    ]
+    .into_iter()
+    .map(|date| {
+        date.map(|date| {
+            Ok(DocValueField::new(
+                "date",
+                DocValueType::Date.parse_value(date)?,
+            ))
+        })
+        .into_iter()
+        .collect()
+    })
+    .collect::<Result<Vec<_>>>()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        ["cat", "cat cat dog", "dog", "cat", "cat mouse"]
+            .map(|text| [("text", text)]),
+    )
+    .with_values(values);
+    engine.index_docs(&mut docs)?;
+
+    let search = |query: &str| -> Result<Vec<SearchHit>> {
+        Ok(engine
+            .search_with_request(
+                &SearchRequest::builder().query(query).build(),
+            )?
+            .hits)
+    };
+    // ranking is checked separately, so docids are sorted
+    let docids = |query: &str| -> Result<Vec<u64>> {
+        Ok(search(query)?
+            .iter()
+            .map(|hit| hit.docid)
+            .sorted()
+            .collect())
+    };
+
+    // execute & assert
+    // range filters documents, but keeps their scores
+    let hits = search("cat AND date:[2020-01-01 TO 2021-01-01]")?;
+    let cat_hits = search("cat")?;
+    assert_eq!(
+        hits,
+        cat_hits
+            .iter()
+            .copied()
+            .filter(|hit| [1, 3].contains(&hit.docid))
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(docids("cat AND date:[2020-01-01 TO 2021-01-01}")?, vec![1]);
+    assert_eq!(docids("cat AND NOT date:[* TO 2020-01-01}")?, vec![1, 3, 4]);
+    assert_eq!(docids("date:{2020-03-01 TO *]")?, vec![2, 3]);
+
+    // documents matching only the range get zero score
+    let hits = search("dog OR date:[2021-01-01T00:00:00Z TO *]")?;
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[2], SearchHit::new(3, 0.0));
+
+    assert!(docids("date:[* TO 2019-01-01]")?.is_empty());
+    assert!(docids("rating:[1 TO 2]")?.is_empty());
+    assert!(search("date:[last TO week]").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_facets() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let docs = [
+        ("cat", Some("pet"), 3),
+        ("cat dog", Some("pet"), 1),
+        ("dog", Some("pet"), 2),
+        ("cat mouse", Some("pest"), 5),
+        ("cat", None, 4),
+        ("cat lion", Some("wild"), 6),
+    ];
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        docs.map(|(text, _, _)| [("text", text)]),
+    )
+    .with_values(docs.map(|(_, kind, count)| {
+        let mut values =
+            vec![DocValueField::new("count", DocValue::U64(count))];
+        values.extend(kind.map(|kind| {
+            DocValueField::new("kind", DocValue::Keyword(kind.into()))
+        }));
+        values
+    }));
+    engine.index_docs(&mut docs)?;
+
+    let terms = |field: &str, limit| FacetRequest::Terms {
+        field: field.to_string(),
+        limit,
+    };
+
+    // execute & assert
+    // facets count all matching documents, not only the requested page
+    let response = engine.search_with_request(
+        &SearchRequest::builder()
+            .query("cat")
+            .limit(1)
+            .count_total(true)
+            .facets(vec![terms("kind", 10), terms("unknown", 10)])
+            .build(),
+    )?;
+    assert_eq!(response.hits.len(), 1);
+    assert_eq!(response.total_count, Some(5));
+    assert_eq!(
+        response.facets,
+        vec![
+            Facet {
+                field: "kind".to_string(),
+                // ties go in order of values
+                buckets: vec![
+                    FacetBucket::new("pet", 2),
+                    FacetBucket::new("pest", 1),
+                    FacetBucket::new("wild", 1),
+                ],
+            },
+            Facet {
+                field: "unknown".to_string(),
+                buckets: Vec::new(),
+            },
+        ]
+    );
+
+    // facets don't change hits, and go along with sorting by field
+    let sorted_request = SearchRequest::builder()
+        .query("cat")
+        .limit(2)
+        .sort_by("count:desc".parse()?);
+    let response = engine.search_with_request(
+        &sorted_request.facets(vec![terms("kind", 1)]).build(),
+    )?;
+    assert_eq!(response.get_docids(), vec![5, 3]);
+    assert_eq!(response.total_count, None);
+    assert_eq!(
+        response.facets,
+        vec![Facet {
+            field: "kind".to_string(),
+            buckets: vec![FacetBucket::new("pet", 2)],
+        }]
+    );
+
+    // query with nothing to search still returns requested facets
+    let response = engine.search_with_request(
+        &SearchRequest::builder()
+            .query("the")
+            .facets(vec![terms("kind", 10)])
+            .build(),
+    )?;
+    assert!(response.hits.is_empty());
+    assert_eq!(
+        response.facets,
+        vec![Facet {
+            field: "kind".to_string(),
+            buckets: Vec::new(),
+        }]
+    );
+
+    assert!(
+        engine
+            .search_with_request(
+                &SearchRequest::builder()
+                    .query("cat")
+                    .facets(vec![terms("count", 10)])
+                    .build(),
+            )
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_range_and_histogram_facets() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let docs = [
+        ("cat", "2019-06-01", 120),
+        ("cat dog", "2020-03-01", 80),
+        ("dog", "2020-06-01", 300),
+        ("cat mouse", "2020-12-31", 450),
+        ("cat", "2022-01-01", 20),
+    ];
+    let values = docs
+        .into_iter()
+        .map(|(_, date, len)| {
+            Ok(vec![
+                DocValueField::new(
+                    "date",
+                    DocValueType::Date.parse_value(date)?,
+                ),
+                DocValueField::new("len", DocValue::U64(len)),
+            ])
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        docs.map(|(text, _, _)| [("text", text)]),
+    )
+    .with_values(values);
+    engine.index_docs(&mut docs)?;
+
+    let facets =
+        |facets: Vec<FacetRequest>| -> Result<Vec<Vec<(String, u64)>>> {
+            Ok(engine
+                .search_with_request(
+                    &SearchRequest::builder()
+                        .query("cat")
+                        .limit(1)
+                        .facets(facets)
+                        .build(),
+                )?
+                .facets
+                .into_iter()
+                .map(|facet| {
+                    facet
+                        .buckets
+                        .into_iter()
+                        .map(|bucket| (bucket.key, bucket.count))
+                        .collect()
+                })
+                .collect())
+        };
+    let buckets = |buckets: &[(&str, u64)]| -> Vec<(String, u64)> {
+        buckets
+            .iter()
+            .map(|(key, count)| ((*key).to_string(), *count))
+            .collect()
+    };
+
+    // execute & assert
+    // facets of different kinds are aggregated in the same search
+    assert_eq!(
+        facets(vec![
+            FacetRequest::Histogram {
+                field: "date".to_string(),
+                interval: "year".parse()?,
+            },
+            FacetRequest::Ranges {
+                field: "len".to_string(),
+                ranges: vec!["*..100".parse()?, "100..*".parse()?],
+            },
+            FacetRequest::Ranges {
+                field: "date".to_string(),
+                ranges: vec![FacetRange::new(
+                    Some("2020-01-01"),
+                    Some("2021-01-01")
+                )],
+            },
+            FacetRequest::Histogram {
+                field: "len".to_string(),
+                interval: HistogramInterval::Width(100.0),
+            },
+        ])?,
+        vec![
+            buckets(&[("2019", 1), ("2020", 2), ("2022", 1)]),
+            buckets(&[("*..100", 2), ("100..*", 2)]),
+            buckets(&[("2020-01-01..2021-01-01", 2)]),
+            buckets(&[("0", 2), ("100", 1), ("400", 1)]),
+        ]
+    );
+
+    assert!(
+        facets(vec![FacetRequest::Histogram {
+            field: "len".to_string(),
+            interval: HistogramInterval::Month,
+        }])
+        .is_err()
+    );
+    assert!(
+        facets(vec![FacetRequest::Ranges {
+            field: "date".to_string(),
+            ranges: vec!["yesterday..*".parse()?],
+        }])
+        .is_err()
+    );
+    assert!("2020-01-01".parse::<FacetRange>().is_err());
+    assert!("week".parse::<HistogramInterval>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_filters() -> Result<()> {
+    // setup
+    let mut engine = NanoSearchEngine::create_in_memory()?;
+    let docs = [
+        ("cat", "pet", 3),
+        ("cat cat dog", "pet", 1),
+        ("dog", "pet", 2),
+        ("cat mouse", "pest", 5),
+        ("cat", "draft", 4),
+    ];
+    let mut docs = TestDocsIterator::from_enumerated_fields(
+        docs.map(|(text, tag, _)| [("text", text), ("tag", tag)]),
+    )
+    .with_values(docs.map(|(_, _, count)| {
+        vec![DocValueField::new("count", DocValue::U64(count))]
+    }));
+    engine.index_docs(&mut docs)?;
+
+    let search = |filters: &[&str]| {
+        engine.search_with_request(
+            &SearchRequest::builder()
+                .query("text:cat")
+                .count_total(true)
+                .filters(filters.iter().map(ToString::to_string).collect())
+                .build(),
+        )
+    };
+
+    // execute & assert
+    let unfiltered = search(&[])?;
+    assert_eq!(unfiltered.get_docids(), vec![0, 4, 1, 3]);
+
+    // filters restrict hits without changing their scores
+    let filtered = search(&["tag:pet"])?;
+    assert_eq!(filtered.get_docids(), vec![0, 1]);
+    assert_eq!(filtered.hits, vec![unfiltered.hits[0], unfiltered.hits[2]]);
+    assert_eq!(filtered.total_count, Some(2));
+
+    // multiple filters should all match
+    assert_eq!(
+        search(&["NOT tag:draft", "count:[3 TO *]"])?.get_docids(),
+        [0, 3]
+    );
+    assert_eq!(search(&["tag:(pet OR pest)", "mouse"])?.get_docids(), [3]);
+    assert!(search(&["tag:unknown"])?.hits.is_empty());
+
+    // repeated filters are taken from cache
+    assert_eq!(search(&["tag:pet"])?, filtered);
+
+    assert!(search(&["\"cat dog\""]).is_err());
+    assert!(search(&["tag:(pet"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_search_with_filters_after_set_analyzer() -> Result<()> {
+    // setup
+    let dir = TempDir::new()?;
+    let mut engine = NanoSearchEngine::create_on_disk(
+        CreateOnDiskOptions::builder()
+            .index_dir(dir.path())
+            .index_threads(1)
+            .build(),
+    )?;
+    let mut docs = TestDocsIterator::from_enumerated_fields([
+        [("text", "cat")],
+        [("text", "dog")],
+    ]);
+    engine.index_docs(&mut docs)?;
+
+    let search = |engine: &NanoSearchEngine| -> Result<Vec<u64>> {
+        Ok(engine
+            .search_with_request(
+                &SearchRequest::builder()
+                    .query("cat OR dog")
+                    .filters(vec!["Cat".to_string()])
+                    .build(),
+            )?
+            .get_docids())
+    };
+
+    // execute & assert
+    assert_eq!(search(&engine)?, vec![0]);
+
+    // filter is no longer lowercased, so cached documents should not be used
+    engine.set_analyzer(Analyzer::new(WhitespaceTokenizer));
+    assert!(search(&engine)?.is_empty());
+
+    Ok(())
+}
@@ -1,12 +1,21 @@
 use anyhow::Result;
+use itertools::Itertools;
 use tempfile::TempDir;
 
 use crate::dataset_readers::cisi::CisiDatasetReader;
+use crate::engines::nano::analysis::Analyzer;
 use crate::engines::nano::engine::NanoSearchEngine;
+use crate::engines::nano::index::model::IndexMedium;
+use crate::engines::nano::index::{DiskIndexOptions, build_index};
+use crate::engines::nano::search::{
+    FilterCache, SearchOptions, SearchPool, search, search_with_options,
+    search_with_request,
+};
 use crate::eval::evaluate_search_quality;
 use crate::eval::model::QueriesSource;
 use crate::model::doc::DocsSource;
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::search::SearchRequest;
 use crate::utils::GetPercentile;
 
 #[test]
@@ -61,6 +70,161 @@ fn test_eval_create_on_disk_and_open() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_multiple_segments_rank_same_as_single_segment() -> Result<()> {
+    let dataset = CisiDatasetReader::new("datasets/cisi");
+    let dir = TempDir::new()?;
+
+    let memory_index = build_index(&IndexMedium::Memory, &mut dataset.docs()?)?;
+
+    // docs are distributed randomly across ~10 segments of different sizes
+    let disk_index = build_index(
+        &IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(3)
+                .max_segment_docs(150)
+                .build(),
+        ),
+        &mut dataset.docs()?,
+    )?;
+    assert!(disk_index.get_segments().len() >= 10);
+
+    for query in dataset.queries()? {
+        let query = query?;
+        assert_eq!(
+            search(&query.text, disk_index.as_ref(), 10)?,
+            search(&query.text, memory_index.as_ref(), 10)?,
+            "query {} should get same results",
+            query.id
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parallel_search_same_as_sequential() -> Result<()> {
+    let dataset = CisiDatasetReader::new("datasets/cisi");
+    let dir = TempDir::new()?;
+
+    let index = build_index(
+        &IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(3)
+                .max_segment_docs(150)
+                .build(),
+        ),
+        &mut dataset.docs()?,
+    )?;
+
+    let parallel_pool = SearchPool::new(4)?;
+    let sequential_pool = SearchPool::default();
+
+    let search = |query: &str, pool: &SearchPool| {
+        search_with_request(
+            &SearchRequest::builder()
+                .query(query)
+                .limit(10)
+                .count_total(true)
+                .build(),
+            index.as_ref(),
+            &Analyzer::default(),
+            &SearchOptions::default(),
+            &FilterCache::default(),
+            pool,
+        )
+    };
+
+    for query in dataset.queries()? {
+        let query = query?;
+        assert_eq!(
+            search(&query.text, &parallel_pool)?,
+            search(&query.text, &sequential_pool)?,
+            "query {} should get same results",
+            query.id
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_pruned_top_results_same_as_exhaustive() -> Result<()> {
+    let dataset = CisiDatasetReader::new("datasets/cisi");
+    let dir = TempDir::new()?;
+
+    let index = build_index(
+        &IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .build(),
+        ),
+        &mut dataset.docs()?,
+    )?;
+
+    let docs_count = index.get_segments()[0].get_stats().indexed_docs_count;
+
+    for query in dataset.queries()? {
+        let query = query?;
+
+        // top collector never gets full when asked for all docs, so it never
+        // lets iterators skip any document
+        let mut exhaustive = search(&query.text, index.as_ref(), docs_count)?;
+        exhaustive.truncate(10);
+
+        assert_eq!(
+            search(&query.text, index.as_ref(), 10)?,
+            exhaustive,
+            "query {} should get same results",
+            query.id
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_short_queries_without_pruning_get_same_results() -> Result<()> {
+    let dataset = CisiDatasetReader::new("datasets/cisi");
+    let dir = TempDir::new()?;
+
+    let index = build_index(
+        &IndexMedium::Disk(
+            DiskIndexOptions::builder()
+                .index_dir(dir.path())
+                .index_threads(1)
+                .build(),
+        ),
+        &mut dataset.docs()?,
+    )?;
+
+    let without_pruning = SearchOptions::builder().pruning(false).build();
+
+    for query in dataset.queries()? {
+        let query = query?;
+        // punctuation is dropped, so words can't make invalid query syntax
+        let text = query
+            .text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(3)
+            .map(str::to_lowercase)
+            .join(" ");
+
+        assert_eq!(
+            search(&text, index.as_ref(), 10)?,
+            search_with_options(&text, index.as_ref(), 10, &without_pruning)?,
+            "query {} should get same results",
+            query.id
+        );
+    }
+
+    Ok(())
+}
+
 fn assert_search_quality(engine: &impl SearchEngine) -> Result<()> {
     let dataset = CisiDatasetReader::new("datasets/cisi");
     let quality = evaluate_search_quality(&mut dataset.queries()?, engine, 10)?;
@@ -68,19 +232,19 @@ fn assert_search_quality(engine: &impl SearchEngine) -> Result<()> {
     assert_eq!(quality.queries_count, 112);
 
     // assert precision
-    assert_eq!(quality.precision_avg, 0.188_392_857_142_857_22);
+    assert_eq!(quality.precision_avg, 0.200_892_857_142_857_23);
     assert_eq!(quality.precisions.perc(0.5)?, 0.1);
-    assert_eq!(quality.precisions.perc(0.9)?, 0.590_000_000_000_000_5);
+    assert_eq!(quality.precisions.perc(0.9)?, 0.6);
     assert_eq!(quality.precisions.perc(1.0)?, 0.9);
 
     // assert recall
-    assert_eq!(quality.recall_avg, 0.395_092_405_402_701_9);
-    assert_eq!(quality.recalls.perc(0.5)?, 0.115_966_386_554_621_85);
+    assert_eq!(quality.recall_avg, 0.403_021_479_822_992_75);
+    assert_eq!(quality.recalls.perc(0.5)?, 0.121_323_529_411_764_7);
     assert_eq!(quality.recalls.perc(0.9)?, 1.0);
     assert_eq!(quality.recalls.perc(1.0)?, 1.0);
 
     // assert NDCG
-    assert_eq!(quality.ndcg_avg, 0.220_955_568_892_424_04);
+    assert_eq!(quality.ndcg_avg, 0.234_667_685_136_598_9);
 
     Ok(())
 }
@@ -1,7 +1,8 @@
-use std::path::Path;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
-use tantivy::collector::TopDocs;
+use anyhow::{Context, Result, anyhow};
+use tantivy::collector::{Count, TopDocs};
 use tantivy::query::QueryParser;
 use tantivy::schema::{
     Field, IndexRecordOption, NumericOptions, Schema, TextFieldIndexing,
@@ -9,19 +10,36 @@ use tantivy::schema::{
 };
 use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument};
 
-use crate::model::doc::{Doc, ExternalDocId};
+use crate::engines::utils::search_top_hits_page;
+use crate::model::doc::Doc;
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::search::{SearchHit, SearchRequest, SearchResponse};
+
+const ID_FIELD: &str = "id";
 
 pub struct TantivySearchEngine {
+    /// Where to create new index
+    index_dir: Option<PathBuf>,
+    index_threads: Option<usize>,
+
+    /// Tantivy requires schema up front, while document fields are known only
+    /// when first document comes, so new index is created on first indexing.
+    ///
+    /// Schema can't be changed once index is created, so all documents should
+    /// have fields of the first document, as [`Doc::fields`] requires
+    index: Option<TantivyIndex>,
+}
+
+struct TantivyIndex {
     index: Index,
     index_writer: IndexWriter,
     index_reader: IndexReader,
 
     id_field: Field,
-    text_field: Field,
+    text_fields: Vec<Field>,
 }
 
-impl TantivySearchEngine {
+impl TantivyIndex {
     fn new(index: Index, index_threads: Option<usize>) -> Result<Self> {
         const MEMORY_BUDGET_BYTES: usize = 50_000_000;
 
@@ -38,44 +56,78 @@ impl TantivySearchEngine {
             .try_into()
             .context("should get index reader")?;
 
-        let id_field = index
-            .schema()
-            .get_field("id")
+        let schema = index.schema();
+
+        let id_field = schema
+            .get_field(ID_FIELD)
             .context("id field should be created")?;
-        let text_field = index
-            .schema()
-            .get_field("text")
-            .context("text field should be created")?;
+        let text_fields = schema
+            .fields()
+            .map(|(field, _)| field)
+            .filter(|field| *field != id_field)
+            .collect();
 
         Ok(Self {
             index,
             index_writer,
             index_reader,
             id_field,
-            text_field,
+            text_fields,
         })
     }
 }
 
-fn create_schema() -> Schema {
+/// Creates schema with text field for each field of the document.
+fn create_schema(doc: &Doc) -> Schema {
     let mut schema_builder = Schema::builder();
-    schema_builder.add_u64_field("id", NumericOptions::default().set_stored());
-    schema_builder.add_text_field(
-        "text",
-        // explicitly configure text field instead of using `TEXT` preset.
-        // this switches record format from `WithFreqsAndPositions` to
-        // `WithFreqs`, which removes positional indexing and disables
-        // phrase query support in `QueryParser`, to match nano behavior
-        TextOptions::default().set_indexing_options(
-            TextFieldIndexing::default()
-                .set_tokenizer("default")
-                .set_fieldnorms(true)
-                .set_index_option(IndexRecordOption::WithFreqs),
-        ),
-    );
+    schema_builder
+        .add_u64_field(ID_FIELD, NumericOptions::default().set_stored());
+    for field in &doc.fields {
+        schema_builder.add_text_field(
+            &field.name,
+            // explicitly configure text field instead of using `TEXT` preset,
+            // to keep record format in sync with nano: it records positions,
+            // so `QueryParser` supports phrase queries too
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("default")
+                    .set_fieldnorms(true)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+    }
     schema_builder.build()
 }
 
+impl TantivySearchEngine {
+    /// Gets existing index, or creates new one with schema of the first
+    /// document.
+    fn get_or_create_index(
+        &mut self,
+        docs: &mut Peekable<&mut dyn Iterator<Item = Result<Doc>>>,
+    ) -> Result<Option<&mut TantivyIndex>> {
+        if self.index.is_none() {
+            let Some(doc) = docs.peek() else {
+                return Ok(None);
+            };
+            let doc = doc
+                .as_ref()
+                .map_err(|err| anyhow!("doc should be valid: {err:#}"))?;
+            let schema = create_schema(doc);
+
+            let index = match &self.index_dir {
+                Some(index_dir) => Index::create_in_dir(index_dir, schema)
+                    .context("index should be created in dir")?,
+                None => Index::create_in_ram(schema),
+            };
+
+            self.index = Some(TantivyIndex::new(index, self.index_threads)?);
+        }
+
+        Ok(self.index.as_mut())
+    }
+}
+
 impl SearchEngine for TantivySearchEngine {
     fn name() -> &'static str {
         "tantivy"
@@ -89,8 +141,11 @@ impl SearchEngine for TantivySearchEngine {
     where
         Self: Sized,
     {
-        let index = Index::create_in_ram(create_schema());
-        Self::new(index, None)
+        Ok(Self {
+            index_dir: None,
+            index_threads: None,
+            index: None,
+        })
     }
 
     fn create_on_disk(opts: CreateOnDiskOptions) -> Result<Self> {
@@ -101,46 +156,77 @@ impl SearchEngine for TantivySearchEngine {
         std::fs::create_dir_all(&opts.index_dir)
             .context("index dir should be created")?;
 
-        let index = Index::create_in_dir(&opts.index_dir, create_schema())
-            .context("index should be created in dir")?;
-
-        Self::new(index, opts.index_threads)
+        Ok(Self {
+            index_dir: Some(opts.index_dir),
+            index_threads: opts.index_threads,
+            index: None,
+        })
     }
 
     fn open_from_disk(index_dir: impl AsRef<Path>) -> Result<Self> {
-        let index = Index::open_in_dir(index_dir)
+        let index = Index::open_in_dir(&index_dir)
             .context("index should be opened from dir")?;
 
-        Self::new(index, None)
+        Ok(Self {
+            index_dir: Some(index_dir.as_ref().to_path_buf()),
+            index_threads: None,
+            index: Some(TantivyIndex::new(index, None)?),
+        })
     }
 
     fn index_docs(
         &mut self,
         docs: &mut dyn Iterator<Item = Result<Doc>>,
     ) -> Result<()> {
+        let mut docs = docs.peekable();
+
+        let Some(index) = self.get_or_create_index(&mut docs)? else {
+            // nothing to index
+            return Ok(());
+        };
+
+        let schema = index.index.schema();
+
         for doc in docs {
             let doc = doc.context("doc should be valid")?;
 
             let mut tantivy_doc = TantivyDocument::default();
-            tantivy_doc.add_u64(self.id_field, doc.id);
-            tantivy_doc.add_text(self.text_field, doc.text);
-
-            self.index_writer
+            tantivy_doc.add_u64(index.id_field, doc.id);
+            for field in doc.fields {
+                let text_field =
+                    schema.get_field(&field.name).map_err(|_| {
+                        anyhow!(
+                            "doc {} has field '{}', which first indexed doc \
+                             does not have, but all docs should have the same \
+                             fields, since tantivy schema is created from \
+                             fields of the first doc",
+                            doc.id,
+                            field.name
+                        )
+                    })?;
+                tantivy_doc.add_text(text_field, field.text);
+            }
+
+            index
+                .index_writer
                 .add_document(tantivy_doc)
                 .context("doc should be added to index")?;
         }
 
-        self.index_writer
+        index
+            .index_writer
             .commit()
             .context("indexer_writer should commit documents to index")?;
 
-        self.index_reader
+        index
+            .index_reader
             .reload()
             .context("index reader should be reloaded after writer commit")?;
 
         // garbage collect unused index files after switching reader to new
         // segments, and thus releasing old segments
-        self.index_writer
+        index
+            .index_writer
             .garbage_collect_files()
             .wait()
             .context("index_writer should garbage collect index files")?;
@@ -148,33 +234,62 @@ impl SearchEngine for TantivySearchEngine {
         Ok(())
     }
 
-    fn search(&self, query: &str, limit: u64) -> Result<Vec<ExternalDocId>> {
-        let searcher = self.index_reader.searcher();
-
-        let query_parser =
-            QueryParser::for_index(&self.index, vec![self.text_field]);
+    fn search_with_request(
+        &self,
+        request: &SearchRequest,
+    ) -> Result<SearchResponse> {
+        let index = self
+            .index
+            .as_ref()
+            .context("index should be initialized before search")?;
 
-        let (query, _) = query_parser.parse_query_lenient(query);
+        let searcher = index.index_reader.searcher();
 
-        let top_docs = searcher
-            .search(
-                &query,
-                &TopDocs::with_limit(limit as usize).order_by_score(),
-            )
-            .context("should search")?;
-
-        let mut result = Vec::new();
-
-        for (_score, doc_address) in top_docs {
-            let retrieved_doc: TantivyDocument =
-                searcher.doc(doc_address).context("document")?;
-            let id = retrieved_doc
-                .get_first(self.id_field)
-                .context("should get id from found document")?;
-
-            result.push(id.as_u64().context("id should be integer")?);
-        }
-
-        Ok(result)
+        // words without field are searched in all text fields, same as in nano
+        let query_parser =
+            QueryParser::for_index(&index.index, index.text_fields.clone());
+
+        let (query, _) = query_parser.parse_query_lenient(&request.query);
+
+        let hits = search_top_hits_page(request, |limit| {
+            let top_docs = searcher
+                .search(
+                    &query,
+                    &TopDocs::with_limit(usize::try_from(limit)?)
+                        .order_by_score(),
+                )
+                .context("should search")?;
+
+            let mut hits = Vec::with_capacity(top_docs.len());
+
+            for (score, doc_address) in top_docs {
+                let retrieved_doc: TantivyDocument =
+                    searcher.doc(doc_address).context("document")?;
+                let id = retrieved_doc
+                    .get_first(index.id_field)
+                    .context("should get id from found document")?;
+
+                hits.push(SearchHit::new(
+                    id.as_u64().context("id should be integer")?,
+                    score.into(),
+                ));
+            }
+
+            Ok(hits)
+        })?;
+
+        let total_count = if request.count_total {
+            let count =
+                searcher.search(&query, &Count).context("should count")?;
+            Some(count as u64)
+        } else {
+            None
+        };
+
+        Ok(SearchResponse {
+            hits,
+            total_count,
+            facets: Vec::new(),
+        })
     }
 }
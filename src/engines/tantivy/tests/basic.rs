@@ -0,0 +1,42 @@
+use anyhow::Result;
+
+use crate::engines::tantivy::engine::TantivySearchEngine;
+use crate::model::doc::{Doc, DocField};
+use crate::model::engine::SearchEngine;
+
+#[test]
+fn test_index_doc_with_field_missing_in_first_doc() -> Result<()> {
+    let mut engine = TantivySearchEngine::create_in_memory()?;
+
+    let docs = [
+        Doc {
+            id: 0,
+            fields: vec![DocField::new("title", "cat")],
+            values: Vec::new(),
+        },
+        Doc {
+            id: 1,
+            fields: vec![
+                DocField::new("title", "dog"),
+                DocField::new("text", "mouse"),
+            ],
+            values: Vec::new(),
+        },
+    ];
+
+    let err = engine
+        .index_docs(&mut docs.into_iter().map(Ok))
+        .err()
+        .map(|err| err.to_string());
+
+    assert_eq!(
+        err.as_deref(),
+        Some(
+            "doc 1 has field 'text', which first indexed doc does not have, \
+             but all docs should have the same fields, since tantivy schema \
+             is created from fields of the first doc"
+        )
+    );
+
+    Ok(())
+}
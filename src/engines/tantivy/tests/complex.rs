@@ -8,6 +8,7 @@ use crate::eval::evaluate_search_quality;
 use crate::eval::model::QueriesSource;
 use crate::model::doc::DocsSource;
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::search::SearchRequest;
 use crate::utils::GetPercentile;
 
 #[test]
@@ -62,6 +63,59 @@ fn test_eval_create_on_disk_and_open() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_search_with_request_paging() -> Result<()> {
+    let dataset = CisiDatasetReader::new("datasets/cisi");
+
+    let mut engine = TantivySearchEngine::create_in_memory()?;
+    engine.index_docs(&mut dataset.docs()?)?;
+
+    let request = |offset, search_after| {
+        SearchRequest::builder()
+            .query("information retrieval")
+            .limit(10)
+            .offset(offset)
+            .maybe_search_after(search_after)
+            .count_total(true)
+            .build()
+    };
+
+    let all_hits = engine
+        .search_with_request(
+            &SearchRequest::builder()
+                .query("information retrieval")
+                .limit(30)
+                .build(),
+        )?
+        .hits;
+    assert_eq!(all_hits.len(), 30);
+    assert!(all_hits.is_sorted());
+
+    let mut search_after = None;
+    for page in 0..3 {
+        let page_by_offset =
+            engine.search_with_request(&request(page * 10, None))?;
+        let page_by_cursor =
+            engine.search_with_request(&request(0, search_after))?;
+
+        let expected = &all_hits[page as usize * 10..][..10];
+        assert_eq!(page_by_offset.hits, expected);
+        assert_eq!(page_by_cursor.hits, expected);
+        assert_eq!(page_by_offset.total_count, Some(702));
+
+        search_after = page_by_cursor.hits.last().copied();
+    }
+
+    Ok(())
+}
+
+/// Asserts quality of tantivy search over CISI dataset in `datasets/cisi`.
+///
+/// Expected numbers depend on tantivy version, its default tokenizer and
+/// scoring, so they have to be regenerated on tantivy upgrade, or when indexed
+/// fields or query syntax change, e.g. with
+/// `cargo test --lib engines::tantivy::tests::complex` and copying actual
+/// values from failed assertions.
 fn assert_search_quality(engine: &impl SearchEngine) -> Result<()> {
     let dataset = CisiDatasetReader::new("datasets/cisi");
     let quality = evaluate_search_quality(&mut dataset.queries()?, engine, 10)?;
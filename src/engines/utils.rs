@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 
-use anyhow::{Result, bail};
+use anyhow::{Result, bail, ensure};
+use itertools::Itertools;
 
 use crate::engines::nano::engine::NanoSearchEngine;
+use crate::engines::nano::search::SearchOptions;
 use crate::engines::tantivy::engine::TantivySearchEngine;
 use crate::engines::vector::engine::VectorSearchEngine;
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::search::{SearchHit, SearchRequest};
 
 #[must_use]
 pub fn get_all_engine_names() -> Vec<String> {
@@ -59,15 +62,22 @@ pub fn engine_create_on_disk_by_name(
     Ok(engine)
 }
 
+/// Opens engine index from disk.
+///
+/// `nano_search_options` apply to nano engine only, e.g. other engines ignore
+/// scorer.
 pub fn engine_open_from_disk_by_name(
     engine_name: &str,
     parent_index_dir: &str,
+    nano_search_options: &SearchOptions,
 ) -> Result<Box<dyn SearchEngine>> {
     let index_dir = get_engine_index_dir(parent_index_dir, engine_name);
 
     let engine: Box<dyn SearchEngine> = match engine_name {
         n if n == NanoSearchEngine::name() => {
-            Box::new(NanoSearchEngine::open_from_disk(index_dir)?)
+            let mut engine = NanoSearchEngine::open_from_disk(index_dir)?;
+            engine.set_search_options(nano_search_options.clone())?;
+            Box::new(engine)
         }
         n if n == TantivySearchEngine::name() => {
             Box::new(TantivySearchEngine::open_from_disk(index_dir)?)
@@ -100,11 +110,154 @@ pub fn engine_create_on_disk_by_names(
 pub fn engine_open_from_disk_by_names(
     engine_names: &[String],
     parent_index_dir: &str,
+    nano_search_options: &SearchOptions,
 ) -> Result<Vec<Box<dyn SearchEngine>>> {
     engine_names
         .iter()
         .map(|engine_name| {
-            engine_open_from_disk_by_name(engine_name, parent_index_dir)
+            engine_open_from_disk_by_name(
+                engine_name,
+                parent_index_dir,
+                nano_search_options,
+            )
         })
         .collect::<Result<Vec<_>>>()
 }
+
+/// Gets requested page of hits for engines, which can only search for top hits
+/// by score.
+///
+/// `search_top_hits` should return passed number of best hits. Fetched hits
+/// are re-ranked with [`SearchHit`] ordering, so ties are broken by docid the
+/// same way in all engines. If `search_after` cursor is set, number of fetched
+/// hits grows until enough of them go after the cursor. Sorting by field and
+/// facets are not supported, since such engines have no doc values, and
+/// neither are filters.
+pub fn search_top_hits_page(
+    request: &SearchRequest,
+    mut search_top_hits: impl FnMut(u64) -> Result<Vec<SearchHit>>,
+) -> Result<Vec<SearchHit>> {
+    ensure!(
+        request.sort_by.is_none(),
+        "sorting by field should be supported by engine"
+    );
+    ensure!(
+        request.facets.is_empty(),
+        "facets should be supported by engine"
+    );
+    ensure!(
+        request.filters.is_empty(),
+        "filters should be supported by engine"
+    );
+
+    let required_count = request.offset.saturating_add(request.limit);
+    let mut fetch_count = required_count;
+
+    loop {
+        let hits = search_top_hits(fetch_count)?;
+        let is_exhausted = (hits.len() as u64) < fetch_count;
+
+        let hits: Vec<SearchHit> = hits
+            .into_iter()
+            .sorted()
+            .filter(|hit| {
+                request.search_after.is_none_or(|cursor| *hit > cursor)
+            })
+            .collect();
+
+        if hits.len() as u64 >= required_count || is_exhausted {
+            return Ok(hits
+                .into_iter()
+                .skip(usize::try_from(request.offset)?)
+                .take(usize::try_from(request.limit)?)
+                .collect());
+        }
+
+        fetch_count = fetch_count.saturating_mul(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::search::FacetRequest;
+
+    #[test]
+    fn test_search_top_hits_page() -> Result<()> {
+        // engine ranks hits with equal scores in its own order
+        let all_hits = [(1, 2.0), (2, 1.0), (0, 1.0), (3, 0.5), (4, 0.1)]
+            .map(|(docid, score)| SearchHit::new(docid, score));
+
+        let mut fetch_counts = Vec::new();
+        let mut search = |request: SearchRequest| {
+            search_top_hits_page(&request, |count| {
+                fetch_counts.push(count);
+                Ok(all_hits.iter().copied().take(count as usize).collect())
+            })
+            .map(|hits| hits.iter().map(|hit| hit.docid).collect::<Vec<_>>())
+        };
+
+        // hits with equal scores are re-ranked by docid
+        assert_eq!(
+            search(SearchRequest::builder().query("").limit(10).build())?,
+            vec![1, 0, 2, 3, 4]
+        );
+        assert_eq!(
+            search(
+                SearchRequest::builder()
+                    .query("")
+                    .limit(2)
+                    .offset(1)
+                    .build()
+            )?,
+            vec![0, 2]
+        );
+
+        // sorting by field, facets and filters fail without fetching hits
+        assert!(
+            search(
+                SearchRequest::builder()
+                    .query("")
+                    .sort_by("timestamp".parse()?)
+                    .build()
+            )
+            .is_err()
+        );
+        assert!(
+            search(
+                SearchRequest::builder()
+                    .query("")
+                    .facets(vec![FacetRequest::Terms {
+                        field: "tag".to_string(),
+                        limit: 10,
+                    }])
+                    .build()
+            )
+            .is_err()
+        );
+        assert!(
+            search(
+                SearchRequest::builder()
+                    .query("")
+                    .filters(vec!["tag:cat".to_string()])
+                    .build()
+            )
+            .is_err()
+        );
+
+        // fetches more hits, until enough of them go after the cursor
+        assert_eq!(
+            search(
+                SearchRequest::builder()
+                    .query("")
+                    .limit(1)
+                    .search_after(SearchHit::new(0, 1.0))
+                    .build()
+            )?,
+            vec![2]
+        );
+        assert_eq!(fetch_counts[2..], [1, 2]);
+
+        Ok(())
+    }
+}
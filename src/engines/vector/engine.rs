@@ -10,8 +10,10 @@ use sqlite_vec::sqlite3_vec_init;
 use tracing::debug;
 use zerocopy::IntoBytes;
 
-use crate::model::doc::{Doc, ExternalDocId};
+use crate::engines::utils::search_top_hits_page;
+use crate::model::doc::Doc;
 use crate::model::engine::{CreateOnDiskOptions, SearchEngine};
+use crate::model::search::{SearchHit, SearchRequest, SearchResponse};
 use crate::utils::HF_CACHE_DIR;
 
 pub struct VectorSearchEngine {
@@ -92,10 +94,13 @@ impl SearchEngine for VectorSearchEngine {
             let texts_batch = docs_batch
                 .iter()
                 .filter_map(|doc| doc.as_ref().ok())
-                .map(|d| d.text.as_str())
-                .collect::<Vec<&str>>();
+                .map(Doc::join_fields_text)
+                .collect::<Vec<String>>();
 
-            let vectors = embed(&self.model, texts_batch)?;
+            let vectors = embed(
+                &self.model,
+                texts_batch.iter().map(String::as_str).collect(),
+            )?;
 
             for (idx, doc) in docs_batch.into_iter().enumerate() {
                 let doc = doc.context("doc should be valid")?;
@@ -111,35 +116,50 @@ impl SearchEngine for VectorSearchEngine {
         Ok(())
     }
 
-    fn search(&self, query: &str, limit: u64) -> Result<Vec<ExternalDocId>> {
-        let vectors = embed(&self.model, vec![query])?;
+    /// Ranks documents by distance between embeddings of the document and the
+    /// query, with `1 / (1 + distance)` as score.
+    ///
+    /// Every document is a neighbor of the query at some distance, so there's
+    /// no count of matching documents.
+    fn search_with_request(
+        &self,
+        request: &SearchRequest,
+    ) -> Result<SearchResponse> {
+        let vectors = embed(&self.model, vec![&request.query])?;
         let vector = &vectors[0];
 
-        let result: Vec<u64> = self
-            .db
-            .prepare_cached(
-                r"
-                SELECT docid
-                FROM docs
-                WHERE embedding MATCH ?1
-                ORDER BY distance
-                LIMIT ?2
-                ",
-            )?
-            .query_map((vector.as_bytes(), limit), |row| {
-                let docid: String = row.get(0)?;
-                Ok(docid)
-            })?
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .map(|docid_str| {
-                docid_str.parse::<u64>().context(format!(
-                    "numeric docid should be parsed from {docid_str}"
-                ))
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        Ok(result)
+        let hits = search_top_hits_page(request, |limit| {
+            self.db
+                .prepare_cached(
+                    r"
+                    SELECT docid, distance
+                    FROM docs
+                    WHERE embedding MATCH ?1
+                    ORDER BY distance
+                    LIMIT ?2
+                    ",
+                )?
+                .query_map((vector.as_bytes(), limit), |row| {
+                    let docid: String = row.get(0)?;
+                    let distance: f64 = row.get(1)?;
+                    Ok((docid, distance))
+                })?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .map(|(docid_str, distance)| {
+                    let docid = docid_str.parse::<u64>().context(format!(
+                        "numeric docid should be parsed from {docid_str}"
+                    ))?;
+                    Ok(SearchHit::new(docid, 1.0 / (1.0 + distance)))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        Ok(SearchResponse {
+            hits,
+            total_count: None,
+            facets: Vec::new(),
+        })
     }
 }
 
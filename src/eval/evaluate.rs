@@ -8,12 +8,31 @@ use crate::eval::metrics::ndcg;
 use crate::eval::model::{Query, Relevance};
 use crate::model::doc::ExternalDocId;
 use crate::model::engine::SearchEngine;
+use crate::model::search::SearchRequest;
 
 pub fn evaluate_search_quality(
     queries: &mut dyn Iterator<Item = Result<Query>>,
     engine: &dyn SearchEngine,
     search_limit: u64,
 ) -> Result<SearchQuality> {
+    let request = SearchRequest::builder()
+        .query(String::new())
+        .limit(search_limit)
+        .build();
+
+    evaluate_search_quality_with_request(queries, engine, &request)
+}
+
+/// Evaluates search quality of each query searched with given request.
+///
+/// Same as [`evaluate_search_quality`], but e.g. to count all matching
+/// documents. Query of the request is replaced with text of each query.
+pub fn evaluate_search_quality_with_request(
+    queries: &mut dyn Iterator<Item = Result<Query>>,
+    engine: &dyn SearchEngine,
+    request: &SearchRequest,
+) -> Result<SearchQuality> {
+    let search_limit = request.limit;
     let mut precision_sum: f64 = 0.0;
     let mut recall_sum: f64 = 0.0;
     let mut ndcg_sum: f64 = 0.0;
@@ -26,7 +45,11 @@ pub fn evaluate_search_quality(
     for query in queries {
         let query = query.context("query should be valid")?;
 
-        let found_docids = engine.search(&query.text, search_limit)?;
+        let request = SearchRequest {
+            query: query.text.clone(),
+            ..request.clone()
+        };
+        let found_docids = engine.search_with_request(&request)?.get_docids();
 
         let quality = evaluate_search_quality_for_query(
             &found_docids,
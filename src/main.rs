@@ -2,6 +2,7 @@ use anyhow::Result;
 use clap::Parser;
 use nano_search::cli::args::{Args, Command};
 use nano_search::cli::commands;
+use nano_search::engines::nano::search::SearchOptions;
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
@@ -17,15 +18,49 @@ fn main() -> Result<()> {
                 threads,
             )?;
         }
-        Command::Eval => commands::eval(
+        Command::Eval {
+            scorer,
+            search_threads,
+            count_total,
+            no_pruning,
+            max_query_words,
+        } => commands::eval(
             &args.engines,
             &args.dataset,
             &args.parent_index_dir,
+            &SearchOptions::builder()
+                .scorer(scorer)
+                .maybe_search_threads(search_threads)
+                .pruning(!no_pruning)
+                .build(),
+            count_total,
+            max_query_words,
         )?,
-        Command::Search => commands::search(
+        Command::Search {
+            scorer,
+            search_threads,
+            explain,
+            limit,
+            offset,
+            sort_by,
+            term_facets,
+            histogram_facets,
+            filters,
+        } => commands::search(
             &args.engines,
             &args.dataset,
             &args.parent_index_dir,
+            &SearchOptions::builder()
+                .scorer(scorer)
+                .maybe_search_threads(search_threads)
+                .build(),
+            explain,
+            limit,
+            offset,
+            sort_by.as_ref(),
+            &term_facets,
+            &histogram_facets,
+            &filters,
         )?,
     }
 
@@ -1,4 +1,8 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
 use anyhow::Result;
+use itertools::Itertools;
 
 /// Unique identifier of a document in the input dataset and the whole index.
 ///
@@ -14,12 +18,130 @@ pub type ExternalDocId = u64;
 /// "field should be stored inside the index". Such a custom document is not
 /// required to have any ID field, unless the client wants to update or delete
 /// documents using an ID later.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Doc {
     pub id: ExternalDocId,
+
+    /// Named text fields of the document, e.g. title and body.
+    ///
+    /// All documents of a dataset are expected to have the same fields in the
+    /// same order, which makes up the schema of the dataset. Missing values
+    /// should be passed as empty text.
+    pub fields: Vec<DocField>,
+
+    /// Named typed values of the document, e.g. publication date or category.
+    ///
+    /// Values are not searched as text, but stored in columns, so results can
+    /// be sorted by them. Documents may have no value of some field, but each
+    /// field should have values of the same type in all documents.
+    pub values: Vec<DocValueField>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocField {
+    pub name: String,
     pub text: String,
 }
 
+impl DocField {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocValueField {
+    pub name: String,
+    pub value: DocValue,
+}
+
+impl DocValueField {
+    pub fn new(name: impl Into<String>, value: DocValue) -> Self {
+        Self {
+            name: name.into(),
+            value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DocValue {
+    U64(u64),
+    F64(f64),
+
+    /// Unix timestamp in seconds
+    Date(i64),
+
+    /// Exact string, which is not split into words, e.g. category or tag
+    Keyword(String),
+}
+
+impl DocValue {
+    /// Compares values of the same type by their natural order. Values of
+    /// different types are ordered by type, in order of enum variants.
+    #[must_use]
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::U64(a), Self::U64(b)) => a.cmp(b),
+            (Self::F64(a), Self::F64(b)) => a.total_cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Keyword(a), Self::Keyword(b)) => a.cmp(b),
+            _ => self.get_type_ordinal().cmp(&other.get_type_ordinal()),
+        }
+    }
+
+    const fn get_type_ordinal(&self) -> u8 {
+        match self {
+            Self::U64(_) => 0,
+            Self::F64(_) => 1,
+            Self::Date(_) => 2,
+            Self::Keyword(_) => 3,
+        }
+    }
+}
+
+impl Display for DocValue {
+    /// Formats dates in RFC 3339, e.g. "2024-01-31T12:00:00Z".
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::U64(value) => write!(f, "{value}"),
+            Self::F64(value) => write!(f, "{value}"),
+            Self::Date(value) => {
+                match chrono::DateTime::from_timestamp(*value, 0) {
+                    Some(date) => write!(
+                        f,
+                        "{}",
+                        date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                    ),
+                    None => write!(f, "{value}"),
+                }
+            }
+            Self::Keyword(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl Doc {
+    /// Gets total length of texts of all fields in bytes.
+    #[must_use]
+    pub fn get_text_len(&self) -> usize {
+        self.fields.iter().map(|field| field.text.len()).sum()
+    }
+
+    /// Joins texts of all fields into single text, for engines which do not
+    /// distinguish fields.
+    #[must_use]
+    pub fn join_fields_text(&self) -> String {
+        self.fields
+            .iter()
+            .map(|field| field.text.as_str())
+            .join(" ")
+    }
+}
+
 pub trait DocsSource {
     fn docs(&self) -> Result<Box<dyn Iterator<Item = Result<Doc>>>>;
     fn docs_count(&self) -> Result<Option<usize>>;
@@ -4,6 +4,9 @@ use anyhow::Result;
 
 use super::doc::Doc;
 use crate::model::doc::ExternalDocId;
+use crate::model::highlight::{Fragment, HighlightOptions};
+use crate::model::search::{SearchRequest, SearchResponse};
+use crate::utils::TreeNode;
 
 pub trait SearchEngine {
     /// Gets search engine name for debug logging purposes.
@@ -33,8 +36,55 @@ pub trait SearchEngine {
         docs: &mut dyn Iterator<Item = Result<Doc>>,
     ) -> Result<()>;
 
-    /// Searches for documents relevant to passed query in the index.
-    fn search(&self, query: &str, limit: u64) -> Result<Vec<ExternalDocId>>;
+    /// Searches for documents relevant to the request query in the index, and
+    /// returns requested page of them with scores.
+    fn search_with_request(
+        &self,
+        request: &SearchRequest,
+    ) -> Result<SearchResponse>;
+
+    /// Searches for `limit` documents most relevant to passed query in the
+    /// index.
+    fn search(&self, query: &str, limit: u64) -> Result<Vec<ExternalDocId>> {
+        let request =
+            SearchRequest::builder().query(query).limit(limit).build();
+        Ok(self.search_with_request(&request)?.get_docids())
+    }
+
+    /// Explains how the document is scored for passed query, e.g. which query
+    /// terms matched it and how their scores were combined.
+    ///
+    /// Returns `None` if the engine cannot explain scores.
+    fn explain(
+        &self,
+        _query: &str,
+        _docid: ExternalDocId,
+    ) -> Result<Option<TreeNode>> {
+        Ok(None)
+    }
+
+    /// Gets the document with original fields, as it was stored in the index,
+    /// e.g. to show what was actually found by a search query.
+    ///
+    /// Returns `None` if the engine does not store documents.
+    fn get_document(&self, _docid: ExternalDocId) -> Result<Option<Doc>> {
+        Ok(None)
+    }
+
+    /// Picks fragments of the document text best matching the query, with
+    /// matching words highlighted, to show as a snippet in search results.
+    /// Document is passed as got with [`Self::get_document`], so it's fetched
+    /// once for both snippet and other fields.
+    ///
+    /// Returns `None` if the engine cannot highlight documents.
+    fn highlight(
+        &self,
+        _query: &str,
+        _doc: &Doc,
+        _options: &HighlightOptions,
+    ) -> Result<Option<Vec<Fragment>>> {
+        Ok(None)
+    }
 }
 
 #[derive(bon::Builder)]
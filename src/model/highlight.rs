@@ -0,0 +1,74 @@
+use std::ops::Range;
+
+/// Options of highlighter, which picks fragments of document text matching
+/// search query to show as a snippet.
+#[derive(bon::Builder, Clone, Debug)]
+pub struct HighlightOptions {
+    /// Max length of each fragment in characters. Fragments are cut at word
+    /// boundaries, so they are usually a bit shorter. A word longer than that
+    /// makes a fragment of its own.
+    #[builder(default = 150)]
+    pub fragment_chars: usize,
+
+    /// Max number of best fragments to return for a document.
+    #[builder(default = 2)]
+    pub max_fragments: usize,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Piece of document field text with words matching search query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fragment {
+    pub field: String,
+    pub text: String,
+
+    /// Sorted byte ranges of matching words in `text`.
+    pub highlights: Vec<Range<usize>>,
+
+    /// How well the fragment matches the query. The larger, the better.
+    /// Scores are only comparable between fragments of the same query.
+    pub score: f64,
+}
+
+impl Fragment {
+    /// Gets fragment text with matching words marked, e.g. wrapped into
+    /// `<b>` tags or colored for terminal.
+    pub fn mark(&self, mark: impl Fn(&str) -> String) -> String {
+        let mut marked = String::with_capacity(self.text.len());
+        let mut last_end = 0;
+
+        for highlight in &self.highlights {
+            marked.push_str(&self.text[last_end..highlight.start]);
+            marked.push_str(&mark(&self.text[highlight.clone()]));
+            last_end = highlight.end;
+        }
+        marked.push_str(&self.text[last_end..]);
+
+        marked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_mark() {
+        let fragment = Fragment {
+            field: "text".to_string(),
+            text: "cat and dog".to_string(),
+            highlights: vec![0..3, 8..11],
+            score: 2.0,
+        };
+
+        assert_eq!(
+            fragment.mark(|word| format!("<b>{word}</b>")),
+            "<b>cat</b> and <b>dog</b>"
+        );
+    }
+}
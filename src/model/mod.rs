@@ -1,2 +1,4 @@
 pub mod doc;
 pub mod engine;
+pub mod highlight;
+pub mod search;
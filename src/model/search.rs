@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow, bail, ensure};
+
+use crate::model::doc::ExternalDocId;
+
+/// Search query along with the part of results it asks for.
+#[derive(bon::Builder, Clone, Debug)]
+pub struct SearchRequest {
+    #[builder(into)]
+    pub query: String,
+
+    /// Max number of hits to return.
+    #[builder(default = 10)]
+    pub limit: u64,
+
+    /// Number of best hits to skip, e.g. `limit * 2` for the third page.
+    #[builder(default)]
+    pub offset: u64,
+
+    /// Last hit of the previous page. Only hits ranked after it are returned,
+    /// and `offset` is counted from it.
+    ///
+    /// Unlike offset, which makes engine rank all skipped hits again, the
+    /// cursor lets engine skip hits ranked higher, so it's better suited for
+    /// deep paging.
+    pub search_after: Option<SearchHit>,
+
+    /// Whether to count all documents matching the query, see
+    /// [`SearchResponse::total_count`]. Counting requires visiting every
+    /// matching document, so it makes search slower.
+    #[builder(default)]
+    pub count_total: bool,
+
+    /// Field of typed document values to order hits by, instead of relevance.
+    /// Hits without value go last, and hits with equal values go in ascending
+    /// order of docid.
+    ///
+    /// Cannot be combined with `search_after`, since cursor holds score of the
+    /// hit rather than its value.
+    pub sort_by: Option<SortBy>,
+
+    /// Facets to aggregate over all documents matching the query, rather than
+    /// only over the requested page. Same as counting, it requires visiting
+    /// every matching document.
+    #[builder(default)]
+    pub facets: Vec<FacetRequest>,
+
+    /// Queries, which documents should match to be found, but which don't
+    /// affect their scores, e.g. "category:news AND date:[2024-01-01 TO *]".
+    /// Filters support words, ranges and boolean operators.
+    ///
+    /// Engine may cache documents matching each filter, so filters repeated
+    /// across many queries are cheaper than the same clauses in the query.
+    #[builder(default)]
+    pub filters: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortBy {
+    pub field: String,
+    pub order: SortOrder,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    /// Parses field name with optional order, e.g. "timestamp:desc".
+    fn from_str(value: &str) -> Result<Self> {
+        let (field, order) = match value.split_once(':') {
+            None => (value, SortOrder::Asc),
+            Some((field, "asc")) => (field, SortOrder::Asc),
+            Some((field, "desc")) => (field, SortOrder::Desc),
+            Some((_, order)) => {
+                bail!("sort order should be 'asc' or 'desc', got '{order}'")
+            }
+        };
+        ensure!(!field.is_empty(), "sort field should not be empty");
+
+        Ok(Self {
+            field: field.to_string(),
+            order,
+        })
+    }
+}
+
+/// Request to aggregate documents matching the query by values of a field,
+/// e.g. to show number of hits in each category next to search results.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FacetRequest {
+    /// Counts documents per value of a keyword field, and returns `limit`
+    /// values with the most documents.
+    Terms { field: String, limit: usize },
+
+    /// Counts documents per range of values of a number or date field, e.g.
+    /// pages by length band. Ranges may overlap or leave gaps.
+    Ranges {
+        field: String,
+        ranges: Vec<FacetRange>,
+    },
+
+    /// Counts documents per interval of values of a number or date field,
+    /// e.g. pages by year of last revision.
+    Histogram {
+        field: String,
+        interval: HistogramInterval,
+    },
+}
+
+impl FacetRequest {
+    #[must_use]
+    pub fn get_field(&self) -> &str {
+        match self {
+            Self::Terms { field, .. }
+            | Self::Ranges { field, .. }
+            | Self::Histogram { field, .. } => field,
+        }
+    }
+}
+
+/// Range of values of [`FacetRequest::Ranges`], which includes `from` and
+/// excludes `to`. Bounds are parsed as values of the field, e.g. "2024-01-31"
+/// for dates, and `None` means unbounded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FacetRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl FacetRange {
+    pub fn new(from: Option<&str>, to: Option<&str>) -> Self {
+        Self {
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+        }
+    }
+
+    /// Gets key of the bucket of the range, e.g. "10..100" or "*..100".
+    #[must_use]
+    pub fn get_key(&self) -> String {
+        format!(
+            "{}..{}",
+            self.from.as_deref().unwrap_or("*"),
+            self.to.as_deref().unwrap_or("*")
+        )
+    }
+}
+
+impl FromStr for FacetRange {
+    type Err = anyhow::Error;
+
+    /// Parses range in the same form as its key, e.g. "10..100" or "*..100".
+    fn from_str(value: &str) -> Result<Self> {
+        let Some((from, to)) = value.split_once("..") else {
+            bail!("facet range '{value}' should have form \"from..to\"");
+        };
+        let parse_bound = |bound: &str| {
+            (!bound.is_empty() && bound != "*").then(|| bound.to_string())
+        };
+
+        Ok(Self {
+            from: parse_bound(from),
+            to: parse_bound(to),
+        })
+    }
+}
+
+/// Interval of values of [`FacetRequest::Histogram`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HistogramInterval {
+    /// Fixed width, in seconds for dates. Intervals start at multiples of it,
+    /// e.g. 0, 100, 200 for width 100.
+    Width(f64),
+
+    /// Calendar year of a date, in UTC.
+    Year,
+
+    /// Calendar month of a date, in UTC.
+    Month,
+}
+
+impl FromStr for HistogramInterval {
+    type Err = anyhow::Error;
+
+    /// Parses calendar interval, i.e. "year" or "month", or fixed width, e.g.
+    /// "100".
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "year" => Self::Year,
+            "month" => Self::Month,
+            _ => Self::Width(value.parse().map_err(|_| {
+                anyhow!(
+                    "histogram interval should be year, month or width, got \
+                     '{value}'"
+                )
+            })?),
+        })
+    }
+}
+
+/// Number of documents matching the query per value of a field, see
+/// [`FacetRequest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Facet {
+    pub field: String,
+
+    /// Buckets of term facet go in descending order of count, and buckets
+    /// with equal counts in ascending order of key. Buckets of range facet go
+    /// in requested order, and buckets of histogram in ascending order of
+    /// values, without intervals that have no documents.
+    pub buckets: Vec<FacetBucket>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FacetBucket {
+    /// Value of the field, which documents of the bucket have.
+    pub key: String,
+    pub count: u64,
+}
+
+impl FacetBucket {
+    pub fn new(key: impl Into<String>, count: u64) -> Self {
+        Self {
+            key: key.into(),
+            count,
+        }
+    }
+}
+
+/// Document found by a search query.
+///
+/// Hits are ranked in descending order of score, and hits with equal scores
+/// go in ascending order of docid, so ranking is deterministic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchHit {
+    pub docid: ExternalDocId,
+
+    /// Relevance of the document to the query. The larger, the more relevant.
+    /// Scores are only comparable within the same engine.
+    pub score: f64,
+}
+
+impl SearchHit {
+    #[must_use]
+    pub const fn new(docid: ExternalDocId, score: f64) -> Self {
+        Self { docid, score }
+    }
+}
+
+impl Ord for SearchHit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .score
+            .total_cmp(&self.score)
+            .then_with(|| self.docid.cmp(&other.docid))
+    }
+}
+
+impl PartialOrd for SearchHit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for SearchHit {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchResponse {
+    /// Requested page of hits, from the best one.
+    pub hits: Vec<SearchHit>,
+
+    /// Total number of documents matching the query, if it was requested and
+    /// engine is able to count them.
+    pub total_count: Option<u64>,
+
+    /// Facets in the same order as requested.
+    pub facets: Vec<Facet>,
+}
+
+impl SearchResponse {
+    #[must_use]
+    pub fn get_docids(&self) -> Vec<ExternalDocId> {
+        self.hits.iter().map(|hit| hit.docid).collect()
+    }
+}
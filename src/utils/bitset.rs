@@ -0,0 +1,157 @@
+/// Fixed-size set of integers from `0` to `len - 1`, stored as one bit per
+/// integer, e.g. set of matching documents of a segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Creates empty set of integers less than `len`.
+    #[must_use]
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Creates set of all integers less than `len`.
+    #[must_use]
+    pub fn full(len: usize) -> Self {
+        let mut set = Self::new(len);
+        set.invert();
+        set
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Gets number of integers in the set.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        assert!(value < self.len, "value should be less than length of set");
+        self.words[value / 64] |= 1 << (value % 64);
+    }
+
+    #[must_use]
+    pub fn contains(&self, value: usize) -> bool {
+        value < self.len && self.words[value / 64] & (1 << (value % 64)) != 0
+    }
+
+    /// Gets the smallest integer of the set, which is equal to or greater
+    /// than `value`.
+    #[must_use]
+    pub fn next_from(&self, value: usize) -> Option<usize> {
+        if value >= self.len {
+            return None;
+        }
+
+        let mut word_idx = value / 64;
+        // drop bits of integers lower than value
+        let mut word = self.words[word_idx] & (u64::MAX << (value % 64));
+
+        loop {
+            if word != 0 {
+                return Some(word_idx * 64 + word.trailing_zeros() as usize);
+            }
+            word_idx += 1;
+            word = *self.words.get(word_idx)?;
+        }
+    }
+
+    /// Keeps only integers, which are in both sets.
+    pub fn intersect_with(&mut self, other: &Self) {
+        debug_assert_eq!(self.len, other.len, "sets should have same length");
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+    }
+
+    /// Adds integers of the other set.
+    pub fn union_with(&mut self, other: &Self) {
+        debug_assert_eq!(self.len, other.len, "sets should have same length");
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Replaces the set with integers less than `len`, which are not in it.
+    pub fn invert(&mut self) {
+        for word in &mut self.words {
+            *word = !*word;
+        }
+
+        // keep bits beyond length unset, so they are never found
+        if !self.len.is_multiple_of(64)
+            && let Some(last) = self.words.last_mut()
+        {
+            *last &= (1 << (self.len % 64)) - 1;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.next_from(0), |value| {
+            self.next_from(value + 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset() {
+        let mut set = BitSet::new(130);
+        assert!(set.is_empty());
+        assert_eq!(set.next_from(0), None);
+
+        for value in [0, 5, 63, 64, 129] {
+            set.insert(value);
+        }
+        assert_eq!(set.count(), 5);
+        assert!(set.contains(63));
+        assert!(!set.contains(62));
+        assert!(!set.contains(500));
+        assert_eq!(set.next_from(6), Some(63));
+        assert_eq!(set.next_from(65), Some(129));
+        assert_eq!(set.next_from(130), None);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 5, 63, 64, 129]);
+
+        let mut other = BitSet::new(130);
+        other.insert(5);
+        other.insert(100);
+
+        let mut intersection = set.clone();
+        intersection.intersect_with(&other);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), vec![5]);
+
+        let mut union = set.clone();
+        union.union_with(&other);
+        assert_eq!(
+            union.iter().collect::<Vec<_>>(),
+            vec![0, 5, 63, 64, 100, 129]
+        );
+
+        set.invert();
+        assert_eq!(set.count(), 125);
+        assert_eq!(set.next_from(128), Some(128));
+        assert_eq!(set.next_from(129), None);
+        assert_eq!(BitSet::full(130).count(), 130);
+    }
+}
@@ -3,9 +3,6 @@ pub mod wikidump;
 mod percentile;
 pub use percentile::GetPercentile;
 
-mod normalize;
-pub use normalize::*;
-
 mod panic_on_error;
 pub use panic_on_error::*;
 
@@ -30,6 +27,9 @@ pub use counting_writer::CountingWriter;
 mod tree;
 pub use tree::TreeNode;
 
+mod bitset;
+pub use bitset::BitSet;
+
 #[cfg(test)]
 pub mod test_docs_iterator;
 
@@ -3,7 +3,7 @@ use std::vec::IntoIter;
 use anyhow::Result;
 use itertools::Itertools;
 
-use crate::model::doc::Doc;
+use crate::model::doc::{Doc, DocField, DocValueField};
 use crate::utils::test_docs::TestDoc;
 
 pub struct TestDocsIterator {
@@ -20,13 +20,58 @@ impl TestDocsIterator {
                 .map(|doc| {
                     Ok(Doc {
                         id: doc.id,
-                        text: doc.text.to_owned(),
+                        fields: vec![DocField::new("text", doc.text)],
+                        values: Vec::new(),
                     })
                 })
                 .collect::<Vec<Result<Doc>>>()
                 .into_iter(),
         }
     }
+
+    /// Creates docs with given `(name, text)` fields, with docids equal to
+    /// positions of docs, e.g. `[[("title", "cat"), ("text", "dog")]]`.
+    #[must_use]
+    pub fn from_enumerated_fields<'a, F>(
+        docs: impl IntoIterator<Item = F>,
+    ) -> Self
+    where
+        F: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        Self {
+            docs: docs
+                .into_iter()
+                .enumerate()
+                .map(|(id, fields)| {
+                    Ok(Doc {
+                        id: id as u64,
+                        fields: fields
+                            .into_iter()
+                            .map(|(name, text)| DocField::new(name, text))
+                            .collect(),
+                        values: Vec::new(),
+                    })
+                })
+                .collect::<Vec<Result<Doc>>>()
+                .into_iter(),
+        }
+    }
+
+    /// Sets typed values of docs in the same order as docs go.
+    #[must_use]
+    pub fn with_values(
+        self,
+        values: impl IntoIterator<Item = Vec<DocValueField>>,
+    ) -> Self {
+        Self {
+            docs: self
+                .docs
+                .zip(values)
+                .map(|(doc, values)| doc.map(|doc| Doc { values, ..doc }))
+                .collect::<Vec<Result<Doc>>>()
+                .into_iter(),
+        }
+    }
 }
 
 impl Iterator for TestDocsIterator {